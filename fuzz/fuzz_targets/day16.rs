@@ -0,0 +1,19 @@
+#![no_main]
+
+use advent_of_code_2021::day16;
+use libfuzzer_sys::fuzz_target;
+
+// any hex either decodes to a packet or is an error, and a packet that decodes encodes back to itself
+fuzz_target!(|data: &[u8]| {
+    if let Ok(input) = std::str::from_utf8(data) {
+        if let Ok(packet) = input.parse::<day16::Packet>() {
+            let _ = packet.value();
+            if let Ok(hex) = packet.to_hex() {
+                assert_eq!(hex.parse::<day16::Packet>().as_ref(), Ok(&packet));
+            }
+        }
+        if let Ok(mut transmission) = input.parse::<day16::Transmission>() {
+            let _ = transmission.packets();
+        }
+    }
+});
@@ -0,0 +1,21 @@
+#![no_main]
+
+use advent_of_code_2021::day18;
+use libfuzzer_sys::fuzz_target;
+
+// a number that parses prints the way it would be written, which parses to the same number, and
+// adding it to itself reduces the same way as a tree and as a flat list of tokens
+fuzz_target!(|data: &[u8]| {
+    if let Ok(input) = std::str::from_utf8(data) {
+        if let Ok(number) = input.parse::<day18::Element>() {
+            assert_eq!(number.to_string().parse::<day18::Element>().as_ref(), Ok(&number));
+            let _ = number.magnitude();
+
+            let flat = day18::FlatNumber::from(&number);
+            let sum = day18::Element::add(number.clone(), number);
+            let flat_sum = day18::FlatNumber::add(flat.clone(), flat);
+            assert_eq!(day18::Element::from(&flat_sum), sum);
+            assert_eq!(flat_sum.magnitude(), sum.magnitude());
+        }
+    }
+});
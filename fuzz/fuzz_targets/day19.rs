@@ -0,0 +1,13 @@
+#![no_main]
+
+use advent_of_code_2021::day19;
+use libfuzzer_sys::fuzz_target;
+
+// reports that parse can be put together, or not, without panicking
+fuzz_target!(|data: &[u8]| {
+    if let Ok(input) = std::str::from_utf8(data) {
+        if let Ok(game) = input.parse::<day19::Game>() {
+            let _ = game.find_probes_and_scanners();
+        }
+    }
+});
@@ -0,0 +1,21 @@
+#![no_main]
+
+use advent_of_code_2021::{day22, error};
+use libfuzzer_sys::fuzz_target;
+
+// parsing a grid also plays the steps, so this covers the cuboid arithmetic too. every strategy has to
+// count the same lit cubes, and a step that parses has a volume
+fuzz_target!(|data: &[u8]| {
+    if let Ok(input) = std::str::from_utf8(data) {
+        if let Ok(grid) = input.parse::<day22::Grid>() {
+            let _ = grid.num_lit();
+            let _ = grid.num_lit_in_initialization_region();
+        }
+        if let Err(error::Error::General(message)) = day22::cross_check(input) {
+            panic!("{}", message);
+        }
+        if let Ok(step) = input.parse::<day22::RebootStep>() {
+            let _ = step.range.volume();
+        }
+    }
+});
@@ -0,0 +1,171 @@
+use crate::day18;
+use crate::error;
+use crate::input;
+use crate::puzzle;
+
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Stats {
+    pub min: Duration,
+    pub mean: Duration,
+    pub median: Duration,
+}
+
+impl Stats {
+    pub fn from_samples(samples: &[Duration]) -> Option<Stats> {
+        let mut sorted = samples.to_vec();
+        sorted.sort_unstable();
+        let min = *sorted.first()?;
+        let mean = sorted.iter().sum::<Duration>() / sorted.len() as u32;
+        // the same sample twice for an odd count
+        let median = (sorted[(sorted.len() - 1) / 2] + sorted[sorted.len() / 2]) / 2;
+        Some(Stats { min, mean, median })
+    }
+}
+
+pub struct DayBench {
+    pub day: usize,
+    pub runs: usize,
+    pub parse: Stats,
+    pub part1: Stats,
+    pub part2: Stats,
+}
+
+fn time<T>(samples: &mut Vec<Duration>, f: impl FnOnce() -> Result<T, error::Error>) -> Result<T, error::Error> {
+    let start = Instant::now();
+    let result = f()?;
+    samples.push(start.elapsed());
+    Ok(result)
+}
+
+// parses the input and solves both parts from scratch, runs times over
+pub fn bench_day(day: usize, input: &str, runs: usize) -> Result<DayBench, error::Error> {
+    if runs == 0 {
+        return Err(error::Error::General("need at least one run to benchmark".to_string()));
+    }
+
+    let (mut parse, mut part1, mut part2) = (vec![], vec![], vec![]);
+    for _ in 0..runs {
+        let solver = time(&mut parse, || puzzle::solver(day, input))?;
+        time(&mut part1, || solver.part1())?;
+        time(&mut part2, || solver.part2())?;
+    }
+
+    let stats = |samples: &[Duration]| Stats::from_samples(samples).expect("at least one run");
+    Ok(DayBench {
+        day,
+        runs,
+        parse: stats(&parse),
+        part1: stats(&part1),
+        part2: stats(&part2),
+    })
+}
+
+pub struct StrategyBench {
+    pub strategy: day18::Strategy,
+    pub runs: usize,
+    pub part1: Stats,
+    pub part2: Stats,
+}
+
+// both parts of day 18 with the numbers reduced as a tree and as a flat list of tokens, runs times over
+pub fn bench_day18_strategies(input: &str, runs: usize) -> Result<Vec<StrategyBench>, error::Error> {
+    if runs == 0 {
+        return Err(error::Error::General("need at least one run to benchmark".to_string()));
+    }
+
+    let stats = |samples: &[Duration]| Stats::from_samples(samples).expect("at least one run");
+    [day18::Strategy::Tree, day18::Strategy::Flat]
+        .into_iter()
+        .map(|strategy| {
+            let (mut part1, mut part2) = (vec![], vec![]);
+            for _ in 0..runs {
+                time(&mut part1, || day18::Element::new_with(input, strategy).map(|sum| sum.magnitude()))?;
+                time(&mut part2, || day18::find_max_magnitude_with(input, strategy))?;
+            }
+            Ok(StrategyBench {
+                strategy,
+                runs,
+                part1: stats(&part1),
+                part2: stats(&part2),
+            })
+        })
+        .collect()
+}
+
+// every day in order, each with its own result so a missing input doesn't hide the rest
+pub fn bench_all(inputs: &input::Inputs, runs: usize) -> Vec<Result<DayBench, error::Error>> {
+    puzzle::DAYS.map(|day| bench_day(day, &inputs.load(day)?, runs)).collect()
+}
+
+// one row per day and step, and the medians added up at the bottom
+pub fn summary(benches: &[DayBench]) -> String {
+    let mut table = format!("{:>4} {:>6} {:>5} {:>14} {:>14} {:>14}\n", "day", "step", "runs", "min", "mean", "median");
+    let mut total = Duration::ZERO;
+    for bench in benches {
+        for (step, stats) in [("parse", &bench.parse), ("part1", &bench.part1), ("part2", &bench.part2)] {
+            table += &format!("{:>4} {:>6} {:>5} {}\n", bench.day, step, bench.runs, columns(stats));
+            total += stats.median;
+        }
+    }
+    table += &format!("total of medians: {:.2?}\n", total);
+    table
+}
+
+// one row per strategy and part
+pub fn strategy_summary(benches: &[StrategyBench]) -> String {
+    let mut table = format!("{:>8} {:>6} {:>5} {:>14} {:>14} {:>14}\n", "strategy", "step", "runs", "min", "mean", "median");
+    for bench in benches {
+        for (step, stats) in [("part1", &bench.part1), ("part2", &bench.part2)] {
+            table += &format!("{:>8} {:>6} {:>5} {}\n", format!("{:?}", bench.strategy), step, bench.runs, columns(stats));
+        }
+    }
+    table
+}
+
+fn columns(stats: &Stats) -> String {
+    format!("{:>14} {:>14} {:>14}", format!("{:.2?}", stats.min), format!("{:.2?}", stats.mean), format!("{:.2?}", stats.median))
+}
+
+#[test]
+fn test_stats() {
+    let ms = Duration::from_millis;
+    assert_eq!(Stats::from_samples(&[]), None);
+    assert_eq!(
+        Stats::from_samples(&[ms(5), ms(1), ms(3)]),
+        Some(Stats {
+            min: ms(1),
+            mean: ms(3),
+            median: ms(3)
+        })
+    );
+    assert_eq!(Stats::from_samples(&[ms(4), ms(1), ms(2), ms(9)]).map(|s| s.median), Some(ms(3)));
+}
+
+#[test]
+fn test_bench_day() -> Result<(), error::Error> {
+    let bench = bench_day(1, &std::fs::read_to_string("input_day1")?, 3)?;
+    assert_eq!((bench.day, bench.runs), (1, 3));
+    assert!(bench.part1.min <= bench.part1.median);
+    assert!(bench_day(1, "1\n2\n", 0).is_err());
+    assert!(bench_day(26, "", 1).is_err());
+
+    let table = summary(&[bench]);
+    assert_eq!(table.lines().count(), 5);
+    assert!(table.lines().nth(2).unwrap().trim_start().starts_with("1  part1     3"));
+    Ok(())
+}
+
+#[test]
+fn test_bench_day18_strategies() -> Result<(), error::Error> {
+    let benches = bench_day18_strategies(&std::fs::read_to_string("input_day18")?, 1)?;
+    assert_eq!(benches.iter().map(|bench| bench.strategy).collect::<Vec<_>>(), vec![day18::Strategy::Tree, day18::Strategy::Flat]);
+    assert!(bench_day18_strategies("[1,2]", 0).is_err());
+    assert!(bench_day18_strategies("[1,", 1).is_err());
+
+    let table = strategy_summary(&benches);
+    assert_eq!(table.lines().count(), 5);
+    assert!(table.lines().nth(3).unwrap().trim_start().starts_with("Flat  part1     1"));
+    Ok(())
+}
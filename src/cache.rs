@@ -0,0 +1,158 @@
+use crate::error;
+use crate::input;
+use crate::puzzle;
+
+use std::path::PathBuf;
+use std::time::Instant;
+
+// answers that were worked out before, one file per day, part and input under
+// <cache_dir>/.answers/dayN. another input hashes differently and simply misses, but a changed
+// solution doesn't, so clear a day after changing how it is solved.
+pub struct AnswerCache {
+    dir: PathBuf,
+}
+
+// fnv-1a, which unlike the std hashers is the same from one build to the next
+pub fn hash(input: &str) -> u64 {
+    input.bytes().fold(0xcbf29ce484222325, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x100000001b3))
+}
+
+// the kind of answer on the first line and the answer itself after it
+fn encode(answer: &puzzle::Answer) -> String {
+    match answer {
+        puzzle::Answer::Int(value) => format!("int\n{}", value),
+        puzzle::Answer::UInt(value) => format!("uint\n{}", value),
+        puzzle::Answer::Text(s) => format!("text\n{}", s),
+        puzzle::Answer::Grid(s) => format!("grid\n{}", s),
+    }
+}
+
+fn decode(s: &str) -> Option<puzzle::Answer> {
+    let (kind, value) = s.split_once('\n')?;
+    match kind {
+        "int" => value.parse().ok().map(puzzle::Answer::Int),
+        "uint" => value.parse().ok().map(puzzle::Answer::UInt),
+        "text" => Some(puzzle::Answer::Text(value.to_string())),
+        "grid" => Some(puzzle::Answer::Grid(value.to_string())),
+        _ => None,
+    }
+}
+
+impl AnswerCache {
+    pub fn new<P: Into<PathBuf>>(cache_dir: P) -> Self {
+        AnswerCache {
+            dir: cache_dir.into().join(".answers"),
+        }
+    }
+
+    // next to the inputs
+    pub fn from_env() -> Self {
+        AnswerCache::new(std::env::var(input::CACHE_DIR_VAR).unwrap_or_else(|_| ".".to_string()))
+    }
+
+    fn day_dir(&self, day: usize) -> PathBuf {
+        self.dir.join(format!("day{}", day))
+    }
+
+    pub fn path(&self, day: usize, part: usize, input: &str) -> PathBuf {
+        self.day_dir(day).join(format!("part{}-{:016x}", part, hash(input)))
+    }
+
+    // a file that can't be read or makes no sense is a miss, it gets written over next time
+    pub fn get(&self, day: usize, part: usize, input: &str) -> Option<puzzle::Answer> {
+        decode(&std::fs::read_to_string(self.path(day, part, input)).ok()?)
+    }
+
+    pub fn put(&self, day: usize, part: usize, input: &str, answer: &puzzle::Answer) -> Result<(), error::Error> {
+        std::fs::create_dir_all(self.day_dir(day))?;
+        std::fs::write(self.path(day, part, input), encode(answer))?;
+        Ok(())
+    }
+
+    // all the parts, or None if any of them is missing
+    pub fn lookup(&self, day: usize, parts: &[usize], input: &str) -> Option<Vec<puzzle::PartResult>> {
+        parts
+            .iter()
+            .map(|&part| {
+                let start = Instant::now();
+                let answer = self.get(day, part, input)?;
+                Some(puzzle::PartResult {
+                    day,
+                    part,
+                    answer,
+                    micros: start.elapsed().as_micros(),
+                    cached: true,
+                })
+            })
+            .collect()
+    }
+
+    // not being able to write the cache doesn't make the answers any less right, so that is only
+    // a warning
+    pub fn store(&self, input: &str, results: &[puzzle::PartResult]) {
+        for result in results {
+            if let Err(e) = self.put(result.day, result.part, input, &result.answer) {
+                tracing::warn!(day = result.day, part = result.part, error = %e, "could not cache the answer");
+            }
+        }
+    }
+
+    // forgets the answers for one day, or for every day
+    pub fn clear(&self, day: Option<usize>) -> Result<(), error::Error> {
+        let dir = match day {
+            Some(day) => self.day_dir(day),
+            None => self.dir.clone(),
+        };
+        if dir.exists() {
+            std::fs::remove_dir_all(dir)?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_answer_cache() -> Result<(), error::Error> {
+    assert_eq!(hash(""), 0xcbf29ce484222325);
+    assert_eq!(hash("a"), 0xaf63dc4c8601ec8c);
+    assert_ne!(hash("1\n2\n"), hash("1\n2"));
+
+    let answers = [
+        puzzle::Answer::Int(-5151),
+        puzzle::Answer::UInt(u128::MAX),
+        puzzle::Answer::Text("HECRZKPR".to_string()),
+        puzzle::Answer::Grid("X X\n X\n".to_string()),
+    ];
+    for answer in &answers {
+        assert_eq!(decode(&encode(answer)).as_ref(), Some(answer));
+    }
+    assert_eq!(decode("uint\n-1"), None);
+    assert_eq!(decode("float\n1.5"), None);
+
+    let cache_dir = std::env::temp_dir().join(format!("aoc2021-answers-{}", std::process::id()));
+    let cache = AnswerCache::new(&cache_dir);
+    assert_eq!(cache.get(1, 1, "1\n2"), None);
+    cache.put(1, 1, "1\n2", &puzzle::Answer::UInt(1))?;
+    cache.put(1, 2, "1\n2", &puzzle::Answer::UInt(0))?;
+    cache.put(2, 1, "forward 1", &puzzle::Answer::UInt(0))?;
+    assert_eq!(cache.get(1, 1, "1\n2"), Some(puzzle::Answer::UInt(1)));
+    assert_eq!(cache.get(1, 1, "1\n3"), None);
+    assert_eq!(cache.get(1, 2, "1\n2"), Some(puzzle::Answer::UInt(0)));
+
+    let results = cache.lookup(1, &[1, 2], "1\n2").ok_or_else(|| error::Error::General("not cached".to_string()))?;
+    assert_eq!(results.iter().map(|r| (r.part, r.cached)).collect::<Vec<_>>(), vec![(1, true), (2, true)]);
+    assert!(cache.lookup(2, &[1, 2], "forward 1").is_none());
+
+    // garbage in the cache is a miss
+    std::fs::write(cache.path(1, 1, "1\n2"), "uint\nlots")?;
+    assert_eq!(cache.get(1, 1, "1\n2"), None);
+
+    cache.clear(Some(1))?;
+    assert_eq!(cache.get(1, 2, "1\n2"), None);
+    assert_eq!(cache.get(2, 1, "forward 1"), Some(puzzle::Answer::UInt(0)));
+    cache.clear(None)?;
+    assert_eq!(cache.get(2, 1, "forward 1"), None);
+    cache.clear(None)?;
+
+    std::fs::remove_dir_all(&cache_dir)?;
+    Ok(())
+}
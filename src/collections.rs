@@ -0,0 +1,10 @@
+// std::collections when there is a std. without one the same names come from alloc, and the
+// hash maps and sets from hashbrown, which is what std's are built on anyway.
+
+#[cfg(feature = "std")]
+pub use std::collections::*;
+
+#[cfg(not(feature = "std"))]
+pub use alloc::collections::*;
+#[cfg(not(feature = "std"))]
+pub use hashbrown::{HashMap, HashSet};
@@ -1,10 +1,11 @@
 use crate::error;
+use crate::puzzle;
 
-pub fn load_input(path: &str) -> Result<Vec<u64>, error::Error> {
-    let data = std::fs::read_to_string(path)?;
-    let lines: Vec<&str> = data.lines().collect();
-    let mut values = Vec::with_capacity(lines.len());
-    for line in lines {
+use alloc::vec::Vec;
+
+pub fn parse_input(input: &str) -> Result<Vec<u64>, error::Error> {
+    let mut values = vec![];
+    for line in input.lines().map(|l| l.trim()).filter(|l| !l.is_empty()) {
         values.push(line.parse()?);
     }
     Ok(values)
@@ -41,9 +42,43 @@ pub fn num_increased_measurements_window(input: &Vec<u64>) -> u64 {
     num_increased
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Measurements(pub Vec<u64>);
+
+const EXAMPLE: &str = r#"
+199
+200
+208
+210
+200
+207
+240
+269
+260
+263
+"#;
+
+impl puzzle::Puzzle for Measurements {
+    fn parse(input: &str) -> Result<Self, error::Error> {
+        Ok(Measurements(parse_input(input)?))
+    }
+
+    fn part1(&self) -> Result<puzzle::Answer, error::Error> {
+        Ok(num_increased_measurements(&self.0).into())
+    }
+
+    fn part2(&self) -> Result<puzzle::Answer, error::Error> {
+        Ok(num_increased_measurements_window(&self.0).into())
+    }
+
+    fn examples() -> Vec<puzzle::Example> {
+        vec![(EXAMPLE, Some("7"), Some("5"))]
+    }
+}
+
 #[test]
-fn test_load_file() -> Result<(), error::Error> {
-    let input: Vec<u64> = load_input("input_day1")?;
+fn test_parse_file() -> Result<(), error::Error> {
+    let input: Vec<u64> = parse_input(&std::fs::read_to_string("input_day1")?)?;
     assert_eq!(input.len(), 2000);
     Ok(())
 }
@@ -62,14 +97,14 @@ fn test_num_increased_measurements_window() {
 
 #[test]
 fn test_num_increased_measurements_file() -> Result<(), error::Error> {
-    let input: Vec<u64> = load_input("input_day1")?;
+    let input: Vec<u64> = parse_input(&std::fs::read_to_string("input_day1")?)?;
     assert_eq!(num_increased_measurements(&input), 1759);
     Ok(())
 }
 
 #[test]
 fn test_num_increased_measurements_window_file() -> Result<(), error::Error> {
-    let input: Vec<u64> = load_input("input_day1")?;
+    let input: Vec<u64> = parse_input(&std::fs::read_to_string("input_day1")?)?;
     assert_eq!(num_increased_measurements_window(&input), 1805);
     Ok(())
 }
@@ -1,4 +1,8 @@
 use crate::error;
+use crate::puzzle;
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
 
 #[derive(PartialEq, Debug)]
 pub enum ParserError {
@@ -6,15 +10,12 @@ pub enum ParserError {
     InvalidChar(char),
 }
 
+#[derive(Default)]
 pub struct Parser {
     pub stack: Vec<char>,
 }
 
 impl Parser {
-    pub fn default() -> Self {
-        Parser { stack: vec![] }
-    }
-
     fn is_open(c: char) -> bool {
         c == '(' || c == '[' || c == '{' || c == '<'
     }
@@ -49,6 +50,7 @@ impl Parser {
     }
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Line {
     pub line: String,
 }
@@ -109,20 +111,28 @@ impl Line {
     }
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Lines {
     pub lines: Vec<Line>,
 }
 
-impl std::str::FromStr for Lines {
+impl core::str::FromStr for Lines {
     type Err = error::Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let lines = s
+        let lines: Vec<Line> = s
             .lines()
-            .filter(|line| !line.trim_start().trim_end().is_empty())
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
             .map(str::to_string)
             .map(|line| Line { line })
             .collect();
+        if lines.is_empty() {
+            return Err(error::Error::Parse("no lines".to_string()));
+        }
+        if let Some(c) = lines.iter().flat_map(|line| line.line.chars()).find(|&c| !Parser::is_open(c) && !Parser::is_close(c)) {
+            return Err(error::Error::Parse(format!("invalid char: {}", c)));
+        }
         Ok(Lines { lines })
     }
 }
@@ -132,13 +142,43 @@ impl Lines {
         self.lines.iter().map(|line| line.score_corrupt()).sum()
     }
 
-    pub fn score_middle_incomplete(&self) -> u64 {
+    pub fn score_middle_incomplete(&self) -> Result<u64, error::Error> {
         let mut scores: Vec<u64> = self.lines.iter().map(|line| line.score_incomplete()).filter(|&score| score != 0).collect();
         if scores.len() % 2 != 1 {
-            panic!("invalid number of incomplete scores");
+            return Err(error::Error::General(format!("no middle score among {} incomplete lines", scores.len())));
         }
         scores.sort_unstable();
-        scores[(scores.len() - 1) / 2]
+        Ok(scores[(scores.len() - 1) / 2])
+    }
+}
+
+const EXAMPLE: &str = r#"
+[({(<(())[]>[[{[]{<()<>>
+[(()[<>])]({[<{<<[]>>(
+{([(<{}[<>[]}>{[]{[(<()>
+(((({<>}<{<{<>}{[]{[]{}
+[[<[([]))<([[{}[[()]]]
+[{[{({}]{}}([{[{{{}}([]
+{<[[]]>}<{[{[{[]{()[[[]
+[<(<(<(<{}))><([]([]()
+<{([([[(<>()){}]>(<<{{
+<{([{{}}[<[[[<>{}]]]>[]]"#;
+
+impl puzzle::Puzzle for Lines {
+    fn parse(input: &str) -> Result<Self, error::Error> {
+        input.parse()
+    }
+
+    fn part1(&self) -> Result<puzzle::Answer, error::Error> {
+        Ok(self.total_score_corrupt().into())
+    }
+
+    fn part2(&self) -> Result<puzzle::Answer, error::Error> {
+        Ok(self.score_middle_incomplete()?.into())
+    }
+
+    fn examples() -> Vec<puzzle::Example> {
+        vec![(EXAMPLE, Some("26397"), Some("288957"))]
     }
 }
 
@@ -161,19 +201,20 @@ fn test_parser() -> Result<(), error::Error> {
     Ok(())
 }
 
+#[test]
+fn test_parse_errors() -> Result<(), error::Error> {
+    assert_eq!("".parse::<Lines>().err(), Some(error::Error::Parse("no lines".to_string())));
+    assert_eq!("(x)".parse::<Lines>().err(), Some(error::Error::Parse("invalid char: x".to_string())));
+    assert_eq!(
+        "()\n(\n[".parse::<Lines>()?.score_middle_incomplete(),
+        Err(error::Error::General("no middle score among 2 incomplete lines".to_string()))
+    );
+    Ok(())
+}
+
 #[test]
 fn test_day10() -> Result<(), error::Error> {
-    let input = r#"
-[({(<(())[]>[[{[]{<()<>>
-[(()[<>])]({[<{<<[]>>(
-{([(<{}[<>[]}>{[]{[(<()>
-(((({<>}<{<{<>}{[]{[]{}
-[[<[([]))<([[{}[[()]]]
-[{[{({}]{}}([{[{{{}}([]
-{<[[]]>}<{[{[{[]{()[[[]
-[<(<(<(<{}))><([]([]()
-<{([([[(<>()){}]>(<<{{
-<{([{{}}[<[[[<>{}]]]>[]]"#;
+    let input = EXAMPLE;
     let lines: Lines = input.parse()?;
     assert_eq!(lines.lines.len(), 10);
     assert_eq!(lines.lines[2].score_corrupt(), 1197);
@@ -189,13 +230,13 @@ fn test_day10() -> Result<(), error::Error> {
     assert_eq!(lines.lines[3].score_incomplete(), 1480781);
     assert_eq!(lines.lines[6].score_incomplete(), 995444);
     assert_eq!(lines.lines[9].score_incomplete(), 294);
-    assert_eq!(lines.score_middle_incomplete(), 288957);
+    assert_eq!(lines.score_middle_incomplete()?, 288957);
 
     let input = std::fs::read_to_string("input_day10")?;
     let lines: Lines = input.parse()?;
     assert_eq!(lines.lines.len(), 102);
     assert_eq!(lines.total_score_corrupt(), 288291);
-    assert_eq!(lines.score_middle_incomplete(), 820045242);
+    assert_eq!(lines.score_middle_incomplete()?, 820045242);
 
     Ok(())
 }
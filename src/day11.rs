@@ -1,27 +1,28 @@
 use crate::error;
+use crate::grid;
+use crate::puzzle;
 
-use std::collections::HashSet;
+use crate::collections::HashSet;
+use alloc::string::ToString;
+use alloc::vec::Vec;
 
 #[derive(PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GameState {
-    pub grid: Vec<Vec<u64>>,
+    pub grid: grid::Grid<u64>,
 }
 
 #[derive(Eq, Hash, PartialEq, Debug, Clone)]
 struct Flash {
-    x: i32,
-    y: i32,
+    x: i64,
+    y: i64,
 }
 
-impl std::str::FromStr for GameState {
+impl core::str::FromStr for GameState {
     type Err = error::Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let grid: Vec<Vec<u64>> = s
-            .lines()
-            .filter(|line| !line.trim_start().trim_end().is_empty())
-            .map(|line| line.chars().filter(|&c| c != ' ').map(|c| c.to_digit(10).unwrap() as u64).collect())
-            .collect();
+        let grid = grid::Grid::parse_digits(s)?.map(|&energy| energy as u64);
         Ok(GameState { grid })
     }
 }
@@ -33,12 +34,12 @@ pub struct SimulationResult {
 }
 
 impl GameState {
-    pub fn width(&self) -> i32 {
-        self.grid[0].len() as i32
+    pub fn width(&self) -> usize {
+        self.grid.width()
     }
 
-    pub fn height(&self) -> i32 {
-        self.grid.len() as i32
+    pub fn height(&self) -> usize {
+        self.grid.height()
     }
 
     pub fn simulate(&self, num_steps: usize) -> SimulationResult {
@@ -48,8 +49,9 @@ impl GameState {
 
         for iteration in 0..num_steps {
             let flashes = game_state.simulate_one_step();
+            tracing::debug!(step = iteration + 1, flashes, "stepped");
             total_flashes += flashes;
-            if flashes as i32 == (self.width() * self.height()) {
+            if flashes == self.grid.cells().len() {
                 mega_flashes.push(iteration + 1);
             }
         }
@@ -61,47 +63,20 @@ impl GameState {
         }
     }
 
-    fn _dump(&self) {
-        for y in 0..self.height() {
-            for x in 0..self.width() {
-                print!("{:3} ", self.grid[x as usize][y as usize]);
-            }
-            println!();
-        }
-        println!();
-    }
-
-    fn should_flash(&self, x: i32, y: i32) -> bool {
-        self.grid[x as usize][y as usize] > 9
-    }
-
-    fn increase_by_one_unless_oob(&mut self, x: i32, y: i32) {
-        let width = self.width();
-        let height = self.height();
-        if x < 0 || x >= width || y < 0 || y >= height {
-            return;
-        }
-        self.grid[x as usize][y as usize] += 1
+    fn should_flash(&self, x: i64, y: i64) -> bool {
+        self.grid[(x, y)] > 9
     }
 
     fn apply_flash(&mut self, flash: &Flash) {
-        let x = flash.x;
-        let y = flash.y;
-        self.increase_by_one_unless_oob(x, y - 1);
-        self.increase_by_one_unless_oob(x + 1, y - 1);
-        self.increase_by_one_unless_oob(x + 1, y);
-        self.increase_by_one_unless_oob(x + 1, y + 1);
-        self.increase_by_one_unless_oob(x, y + 1);
-        self.increase_by_one_unless_oob(x - 1, y + 1);
-        self.increase_by_one_unless_oob(x - 1, y);
-        self.increase_by_one_unless_oob(x - 1, y - 1);
+        let neighbors: Vec<(i64, i64)> = self.grid.neighbors8(flash.x, flash.y).collect();
+        for position in neighbors {
+            self.grid[position] += 1;
+        }
     }
 
     fn simulate_one_step(&mut self) -> usize {
-        for y in 0..self.height() {
-            for x in 0..self.width() {
-                self.increase_by_one_unless_oob(x, y);
-            }
+        for energy in self.grid.cells_mut() {
+            *energy += 1;
         }
 
         let mut all_flashes: HashSet<Flash> = HashSet::new();
@@ -109,14 +84,12 @@ impl GameState {
         loop {
             let mut new_flashes: HashSet<Flash> = HashSet::new();
 
-            for y in 0..self.height() {
-                for x in 0..self.width() {
-                    if self.should_flash(x, y) {
-                        let flash = Flash { x, y };
-                        if !all_flashes.contains(&flash) {
-                            new_flashes.insert(flash.clone());
-                            all_flashes.insert(flash);
-                        }
+            for (x, y) in self.grid.positions() {
+                if self.should_flash(x, y) {
+                    let flash = Flash { x, y };
+                    if !all_flashes.contains(&flash) {
+                        new_flashes.insert(flash.clone());
+                        all_flashes.insert(flash);
                     }
                 }
             }
@@ -131,10 +104,49 @@ impl GameState {
         }
 
         for flash in &all_flashes {
-            self.grid[flash.x as usize][flash.y as usize] = 0;
+            self.grid[(flash.x, flash.y)] = 0;
         }
 
-        all_flashes.len() as usize
+        all_flashes.len()
+    }
+}
+
+const EXAMPLE: &str = r#"
+5483143223
+2745854711
+5264556173
+6141336146
+6357385478
+4167524645
+2176841721
+6882881134
+4846848554
+5283751526
+"#;
+
+impl puzzle::Puzzle for GameState {
+    fn parse(input: &str) -> Result<Self, error::Error> {
+        input.parse()
+    }
+
+    fn part1(&self) -> Result<puzzle::Answer, error::Error> {
+        Ok(self.simulate(100).total_flashes.into())
+    }
+
+    fn part2(&self) -> Result<puzzle::Answer, error::Error> {
+        // keep simulating further until every octopus has flashed at once
+        let mut num_steps = 128;
+        while num_steps <= 1 << 20 {
+            if let Some(step) = self.simulate(num_steps).mega_flashes.first() {
+                return Ok((*step).into());
+            }
+            num_steps *= 2;
+        }
+        Err(error::Error::General("the octopuses never flash at the same time".to_string()))
+    }
+
+    fn examples() -> Vec<puzzle::Example> {
+        vec![(EXAMPLE, Some("1656"), Some("195"))]
     }
 }
 
@@ -167,19 +179,7 @@ fn test_day11_mini() -> Result<(), error::Error> {
 
 #[test]
 fn test_day11() -> Result<(), error::Error> {
-    let initial_state: GameState = r#"
-5483143223
-2745854711
-5264556173
-6141336146
-6357385478
-4167524645
-2176841721
-6882881134
-4846848554
-5283751526
-"#
-    .parse()?;
+    let initial_state: GameState = EXAMPLE.parse()?;
     assert_eq!(initial_state.width(), 10);
     assert_eq!(initial_state.height(), 10);
 
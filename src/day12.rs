@@ -1,13 +1,19 @@
 use crate::error;
+use crate::puzzle;
 
-use std::collections::HashMap;
+use crate::collections::HashMap;
+use alloc::borrow::ToOwned;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
 
 pub enum GraphRules {
     FirstPart,
     SecondPart,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Graph {
+    #[cfg_attr(feature = "serde", serde(serialize_with = "crate::snapshot::sorted_map"))]
     connection_map: HashMap<String, Vec<String>>,
 }
 
@@ -18,22 +24,26 @@ pub struct Path {
     any_small_duplicates: bool,
 }
 
-impl std::str::FromStr for Graph {
+impl core::str::FromStr for Graph {
     type Err = error::Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let mut connection_map: HashMap<String, Vec<String>> = HashMap::new();
 
-        let connection_iter = s.lines().filter(|line| !line.trim_start().trim_end().is_empty()).map(|line| {
-            let mut tokens = line.split('-');
-            (tokens.next().unwrap().to_owned(), tokens.next().unwrap().to_owned())
-        });
-
-        for (from, to) in connection_iter {
+        for line in s.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            let (from, to) = match line.split_once('-') {
+                Some((from, to)) if !from.is_empty() && !to.is_empty() && !to.contains('-') => (from.to_owned(), to.to_owned()),
+                _ => return Err(error::Error::Parse(format!("invalid connection: {}", line))),
+            };
             connection_map.entry(from.to_string()).or_default().push(to.to_string());
             connection_map.entry(to).or_default().push(from);
         }
 
+        // every path is built out from the start cave
+        if !connection_map.contains_key("start") {
+            return Err(error::Error::Parse("no start cave".to_string()));
+        }
+
         Ok(Graph { connection_map })
     }
 }
@@ -80,7 +90,7 @@ impl Graph {
     }
 }
 
-impl std::str::FromStr for Path {
+impl core::str::FromStr for Path {
     type Err = error::Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
@@ -140,6 +150,77 @@ impl Path {
     }
 }
 
+const EXAMPLE_SMALL: &str = r#"
+start-A
+start-b
+A-c
+A-b
+b-d
+A-end
+b-end"#;
+
+const EXAMPLE_MEDIUM: &str = r#"
+dc-end
+HN-start
+start-kj
+dc-start
+dc-HN
+LN-dc
+HN-end
+kj-sa
+kj-HN
+kj-dc"#;
+
+const EXAMPLE_LARGE: &str = r#"
+fs-end
+he-DX
+fs-he
+start-DX
+pj-DX
+end-zg
+zg-sl
+zg-pj
+pj-he
+RW-he
+fs-DX
+pj-RW
+zg-RW
+start-pj
+he-WI
+zg-he
+pj-fs
+start-RW"#;
+
+impl puzzle::Puzzle for Graph {
+    fn parse(input: &str) -> Result<Self, error::Error> {
+        input.parse()
+    }
+
+    fn part1(&self) -> Result<puzzle::Answer, error::Error> {
+        Ok(self.generate_paths(GraphRules::FirstPart).len().into())
+    }
+
+    fn part2(&self) -> Result<puzzle::Answer, error::Error> {
+        Ok(self.generate_paths(GraphRules::SecondPart).len().into())
+    }
+
+    fn examples() -> Vec<puzzle::Example> {
+        vec![
+            (EXAMPLE_SMALL, Some("10"), Some("36")),
+            (EXAMPLE_MEDIUM, Some("19"), Some("103")),
+            (EXAMPLE_LARGE, Some("226"), Some("3509")),
+        ]
+    }
+}
+
+#[test]
+fn test_parse_errors() {
+    assert_eq!("".parse::<Graph>().err(), Some(error::Error::Parse("no start cave".to_string())));
+    assert_eq!("x".parse::<Graph>().err(), Some(error::Error::Parse("invalid connection: x".to_string())));
+    assert_eq!("start-".parse::<Graph>().err(), Some(error::Error::Parse("invalid connection: start-".to_string())));
+    assert_eq!("a-b".parse::<Graph>().err(), Some(error::Error::Parse("no start cave".to_string())));
+}
+
 #[test]
 fn test_utils() -> Result<(), error::Error> {
     assert!(is_small_cave(""));
@@ -190,53 +271,15 @@ fn test_path_can_add() -> Result<(), error::Error> {
 
 #[test]
 fn test_day12() -> Result<(), error::Error> {
-    let graph: Graph = r#"
-start-A
-start-b
-A-c
-A-b
-b-d
-A-end
-b-end"#
-        .parse()?;
+    let graph: Graph = EXAMPLE_SMALL.parse()?;
     assert_eq!(graph.generate_paths(GraphRules::FirstPart).len(), 10);
     assert_eq!(graph.generate_paths(GraphRules::SecondPart).len(), 36);
 
-    let graph: Graph = r#"
-dc-end
-HN-start
-start-kj
-dc-start
-dc-HN
-LN-dc
-HN-end
-kj-sa
-kj-HN
-kj-dc"#
-        .parse()?;
+    let graph: Graph = EXAMPLE_MEDIUM.parse()?;
     assert_eq!(graph.generate_paths(GraphRules::FirstPart).len(), 19);
     assert_eq!(graph.generate_paths(GraphRules::SecondPart).len(), 103);
 
-    let graph: Graph = r#"
-fs-end
-he-DX
-fs-he
-start-DX
-pj-DX
-end-zg
-zg-sl
-zg-pj
-pj-he
-RW-he
-fs-DX
-pj-RW
-zg-RW
-start-pj
-he-WI
-zg-he
-pj-fs
-start-RW"#
-        .parse()?;
+    let graph: Graph = EXAMPLE_LARGE.parse()?;
     assert_eq!(graph.generate_paths(GraphRules::FirstPart).len(), 226);
     assert_eq!(graph.generate_paths(GraphRules::SecondPart).len(), 3509);
 
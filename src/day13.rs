@@ -1,63 +1,198 @@
 use crate::error;
+use crate::ocr;
+use crate::puzzle;
 
-use itertools::Itertools;
+use crate::collections::HashSet;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
 
-#[derive(Clone)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum FoldType {
     Vertical,
     Horizontal,
 }
 
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FoldInstruction {
     fold_at_line: usize,
     fold_type: FoldType,
 }
 
+impl FoldInstruction {
+    pub fn new(fold_type: FoldType, fold_at_line: usize) -> Self {
+        Self { fold_at_line, fold_type }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FoldValidation {
+    Error,
+    Clamp,
+    Drop,
+}
+
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Paper {
-    points: Vec<(usize, usize)>,
+    #[cfg_attr(feature = "serde", serde(serialize_with = "crate::snapshot::sorted_set"))]
+    points: HashSet<(usize, usize)>,
     instructions: Vec<FoldInstruction>,
+    fold_validation: FoldValidation,
+}
+
+// the paper after each fold, folding one copy of it in place
+pub struct Folds {
+    paper: Paper,
+    failed: bool,
+}
+
+impl Folds {
+    // lends the folded paper out instead of copying it, for callers that only look at each fold
+    pub fn next_fold(&mut self) -> Option<Result<&Paper, error::Error>> {
+        if self.failed || self.paper.instructions.is_empty() {
+            return None;
+        }
+        match self.paper.fold_in_place() {
+            Ok(()) => Some(Ok(&self.paper)),
+            Err(e) => {
+                self.failed = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+impl Iterator for Folds {
+    type Item = Result<Paper, error::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_fold().map(|folded| folded.cloned())
+    }
+}
+
+// Points on the fold line, or farther away from it than the part being folded onto, have no valid
+// mirrored position.
+fn fold_coordinate(c: usize, line: usize, fold_validation: FoldValidation) -> Result<Option<usize>, error::Error> {
+    if c < line {
+        return Ok(Some(c));
+    }
+    match (2 * line).checked_sub(c) {
+        Some(folded) if c != line => Ok(Some(folded)),
+        _ => match fold_validation {
+            FoldValidation::Error => Err(error::Error::General(format!("coordinate {} cannot be folded along line {}", c, line))),
+            FoldValidation::Clamp if line > 0 => Ok(Some((2 * line).saturating_sub(c).min(line - 1))),
+            FoldValidation::Clamp => Err(error::Error::General(format!("coordinate {} cannot be clamped when folding along line 0", c))),
+            FoldValidation::Drop => Ok(None),
+        },
+    }
+}
+
+impl Default for Paper {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Paper {
+    pub fn new() -> Self {
+        Paper {
+            points: HashSet::new(),
+            instructions: vec![],
+            fold_validation: FoldValidation::Error,
+        }
+    }
+
+    pub fn with_points<I: IntoIterator<Item = (usize, usize)>>(mut self, points: I) -> Self {
+        self.points.extend(points);
+        self
+    }
+
+    pub fn with_instruction(mut self, instruction: FoldInstruction) -> Self {
+        self.instructions.push(instruction);
+        self
+    }
+
+    pub fn with_instructions<I: IntoIterator<Item = FoldInstruction>>(mut self, instructions: I) -> Self {
+        self.instructions.extend(instructions);
+        self
+    }
+
+    pub fn with_fold_validation(mut self, fold_validation: FoldValidation) -> Self {
+        self.fold_validation = fold_validation;
+        self
+    }
+
     fn width(&self) -> usize {
-        self.points.iter().map(|&p| p.0).max().unwrap() as usize + 1
+        self.points.iter().map(|&p| p.0).max().map_or(0, |x| x + 1)
     }
 
     fn height(&self) -> usize {
-        self.points.iter().map(|&p| p.1).max().unwrap() as usize + 1
+        self.points.iter().map(|&p| p.1).max().map_or(0, |y| y + 1)
     }
 
-    pub fn fold_once(&self) -> Paper {
-        let instruction = &self.instructions[0];
-        Paper {
-            points: self
-                .points
-                .iter()
-                .map(|&p| match instruction.fold_type {
-                    FoldType::Vertical => {
-                        let x = if p.0 < instruction.fold_at_line {
-                            p.0
-                        } else {
-                            (instruction.fold_at_line - 1) - (p.0 - instruction.fold_at_line - 1)
-                        };
-                        let y = p.1;
-                        (x, y)
-                    }
-                    FoldType::Horizontal => {
-                        let x = p.0;
-                        let y = if p.1 < instruction.fold_at_line {
-                            p.1
-                        } else {
-                            (instruction.fold_at_line - 1) - (p.1 - instruction.fold_at_line - 1)
-                        };
-                        (x, y)
-                    }
-                })
-                .unique()
-                .collect(),
-            instructions: self.instructions[1..].to_vec(),
+    pub fn fold_once(&self) -> Result<Paper, error::Error> {
+        let mut paper = self.clone();
+        paper.fold_in_place()?;
+        Ok(paper)
+    }
+
+    pub fn fold_in_place(&mut self) -> Result<(), error::Error> {
+        let instruction = self.instructions.first().cloned().ok_or_else(|| error::Error::General("no fold instructions left".to_string()))?;
+        self.fold_points(&instruction)?;
+        self.instructions.remove(0);
+        Ok(())
+    }
+
+    pub fn apply(&self, instruction: &FoldInstruction) -> Result<Paper, error::Error> {
+        let mut paper = self.clone();
+        paper.fold_points(instruction)?;
+        Ok(paper)
+    }
+
+    fn fold_points(&mut self, instruction: &FoldInstruction) -> Result<(), error::Error> {
+        let line = instruction.fold_at_line;
+
+        // resolve every moving point before touching the set, so a validation error leaves the paper untouched
+        let mut moves = vec![];
+        for &(x, y) in &self.points {
+            let moved = match instruction.fold_type {
+                FoldType::Vertical if x >= line => fold_coordinate(x, line, self.fold_validation)?.map(|x| (x, y)),
+                FoldType::Horizontal if y >= line => fold_coordinate(y, line, self.fold_validation)?.map(|y| (x, y)),
+                _ => continue,
+            };
+            moves.push(((x, y), moved));
+        }
+
+        for (from, to) in moves {
+            self.points.remove(&from);
+            if let Some(to) = to {
+                self.points.insert(to);
+            }
         }
+
+        tracing::debug!(fold = ?instruction, points = self.points.len(), "folded");
+        tracing::trace!("\n{}", self.render());
+        Ok(())
+    }
+
+    // folds along every instruction and reads the letters left on the paper
+    pub fn fold_all(&self) -> Result<String, error::Error> {
+        ocr::decode(&self.folded()?.render())
+    }
+
+    fn folded(&self) -> Result<Paper, error::Error> {
+        let mut paper = self.clone();
+        while !paper.instructions.is_empty() {
+            paper.fold_in_place()?;
+        }
+        Ok(paper)
+    }
+
+    pub fn folds(&self) -> Folds {
+        Folds { paper: self.clone(), failed: false }
     }
 
     fn plot(&self) -> Vec<Vec<u8>> {
@@ -68,50 +203,103 @@ impl Paper {
         map
     }
 
-    pub fn dump(&self) {
-        let map: Vec<Vec<u8>> = self.plot();
-        for y in 0..map.len() {
-            for x in 0..map[0].len() {
-                print!("{}", if map[y][x] == 0 { ' ' } else { 'X' });
+    pub fn render(&self) -> String {
+        let mut s = String::new();
+        for row in self.plot() {
+            s.extend(row.iter().map(|&count| if count == 0 { ' ' } else { 'X' }));
+            s.push('\n');
+        }
+        s
+    }
+}
+
+#[cfg(feature = "image")]
+impl Paper {
+    pub fn to_svg(&self, scale: usize) -> String {
+        let mut points: Vec<&(usize, usize)> = self.points.iter().collect();
+        points.sort_unstable_by_key(|&&(x, y)| (y, x));
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\">\n<rect width=\"100%\" height=\"100%\" fill=\"white\"/>\n",
+            self.width() * scale,
+            self.height() * scale
+        );
+        for (x, y) in points {
+            svg += &format!("<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"black\"/>\n", x * scale, y * scale, scale, scale);
+        }
+        svg += "</svg>\n";
+        svg
+    }
+
+    pub fn write_png<W: std::io::Write>(&self, writer: W, scale: usize) -> Result<(), error::Error> {
+        let (width, height) = (self.width() * scale, self.height() * scale);
+        if width == 0 || height == 0 {
+            return Err(error::Error::General("cannot export an empty paper".to_string()));
+        }
+        let mut data = vec![255u8; width * height];
+        for &(x, y) in &self.points {
+            for row in y * scale..(y + 1) * scale {
+                data[row * width + x * scale..row * width + (x + 1) * scale].fill(0);
             }
-            println!();
         }
-        println!();
-        println!();
+        let mut encoder = png::Encoder::new(writer, width as u32, height as u32);
+        encoder.set_color(png::ColorType::Grayscale);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.write_header()?.write_image_data(&data)?;
+        Ok(())
     }
 }
 
-impl std::str::FromStr for FoldInstruction {
+impl core::str::FromStr for FoldInstruction {
     type Err = error::Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         // fold along x=655
-        let s = &s[11..];
-        let mut tokens = s.split('=');
-        let x_or_y = tokens.next().unwrap();
-        let line: usize = tokens.next().unwrap().parse()?;
+        let (x_or_y, line) = s
+            .trim()
+            .strip_prefix("fold along ")
+            .and_then(|s| s.split_once('='))
+            .ok_or_else(|| error::Error::Parse(format!("invalid fold instruction: {}", s)))?;
+        let fold_type = match x_or_y {
+            "x" => FoldType::Vertical,
+            "y" => FoldType::Horizontal,
+            _ => return Err(error::Error::Parse(format!("invalid fold axis: {}", x_or_y))),
+        };
         Ok(Self {
-            fold_at_line: line,
-            fold_type: if x_or_y == "x" { FoldType::Vertical } else { FoldType::Horizontal },
+            fold_at_line: line.parse()?,
+            fold_type,
         })
     }
 }
 
-impl std::str::FromStr for Paper {
+fn parse_point(line: &str) -> Result<(usize, usize), error::Error> {
+    // 1288,245
+    let (x, y) = line.trim().split_once(',').ok_or_else(|| error::Error::Parse(format!("invalid point: {}", line)))?;
+    Ok((x.parse()?, y.parse()?))
+}
+
+fn non_empty_lines(s: &str) -> impl Iterator<Item = &str> {
+    s.lines().filter(|l| !l.trim_start().trim_end().is_empty())
+}
+
+pub fn parse_points(s: &str) -> Result<Vec<(usize, usize)>, error::Error> {
+    non_empty_lines(s).map(parse_point).collect()
+}
+
+pub fn parse_instructions(s: &str) -> Result<Vec<FoldInstruction>, error::Error> {
+    non_empty_lines(s).map(|line| line.parse()).collect()
+}
+
+impl core::str::FromStr for Paper {
     type Err = error::Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut paper = Paper { points: vec![], instructions: vec![] };
+        let mut paper = Paper::new();
 
-        for line in s.lines().filter(|l| !l.trim_start().trim_end().is_empty()) {
+        for line in non_empty_lines(s) {
             if line.starts_with("fold along") {
                 paper.instructions.push(line.parse()?);
             } else {
-                // 1288,245
-                let mut tokens = line.split(',');
-                let x: usize = tokens.next().unwrap().parse()?;
-                let y: usize = tokens.next().unwrap().parse()?;
-                paper.points.push((x, y));
+                paper.points.insert(parse_point(line)?);
             }
         }
 
@@ -119,9 +307,7 @@ impl std::str::FromStr for Paper {
     }
 }
 
-#[test]
-fn test_day13() -> Result<(), error::Error> {
-    let input = r#"
+const EXAMPLE: &str = r#"
 6,10
 0,14
 9,10
@@ -142,29 +328,235 @@ fn test_day13() -> Result<(), error::Error> {
 9,0
 
 fold along y=7
+fold along x=5"#;
+
+impl puzzle::Puzzle for Paper {
+    fn parse(input: &str) -> Result<Self, error::Error> {
+        input.parse()
+    }
+
+    fn part1(&self) -> Result<puzzle::Answer, error::Error> {
+        Ok(self.fold_once()?.points.len().into())
+    }
+
+    // the drawing itself when it doesn't spell anything, like the square the example folds into
+    fn part2(&self) -> Result<puzzle::Answer, error::Error> {
+        let paper = self.folded()?;
+        match ocr::decode(&paper.render()) {
+            Ok(letters) => Ok(letters.into()),
+            Err(_) => Ok(puzzle::Answer::Grid(paper.render())),
+        }
+    }
+
+    fn examples() -> Vec<puzzle::Example> {
+        // the example folds into a square
+        vec![(EXAMPLE, Some("17"), Some("XXXXX\nX   X\nX   X\nX   X\nXXXXX\n"))]
+    }
+}
+
+#[test]
+fn test_day13() -> Result<(), error::Error> {
+    let input = EXAMPLE;
+
+    let paper: Paper = input.parse()?;
+    let paper = paper.fold_once()?;
+    assert_eq!(paper.points.len(), 17);
+    let paper = paper.fold_once()?;
+    assert!(paper.fold_once().is_err());
+
+    let paper: Paper = std::fs::read_to_string("input_day13")?.parse()?;
+    let paper = paper.fold_once()?;
+    assert_eq!(paper.points.len(), 759);
+    let paper = paper.fold_once()?;
+    let paper = paper.fold_once()?;
+    let paper = paper.fold_once()?;
+    let paper = paper.fold_once()?;
+    let paper = paper.fold_once()?;
+    let paper = paper.fold_once()?;
+    let paper = paper.fold_once()?;
+    let paper = paper.fold_once()?;
+    let paper = paper.fold_once()?;
+    let paper = paper.fold_once()?;
+    let paper = paper.fold_once()?;
+    assert_eq!(ocr::decode(&paper.render())?, "HECRZKPR");
+
+    Ok(())
+}
+
+#[test]
+fn test_day13_folds() -> Result<(), error::Error> {
+    let input = EXAMPLE;
+
+    let paper: Paper = input.parse()?;
+    let stages = paper.folds().collect::<Result<Vec<Paper>, error::Error>>()?;
+    assert_eq!(stages.len(), 2);
+    assert_eq!(stages[0].points.len(), 17);
+    assert_eq!(stages[1].points.len(), 16);
+    assert_eq!(stages[0].points, paper.fold_once()?.points);
+    let mut folds = paper.folds();
+    assert_eq!(folds.next_fold().unwrap()?.instructions.len(), 1);
+    assert_eq!(folds.next_fold().unwrap()?.points, stages[1].points);
+    assert!(folds.next_fold().is_none());
+    // a square is no letter
+    assert!(paper.fold_all().is_err());
+
+    let paper: Paper = std::fs::read_to_string("input_day13")?.parse()?;
+    assert_eq!(paper.folds().count(), 12);
+    assert_eq!(paper.folds().next().unwrap()?.points.len(), 759);
+    assert_eq!(paper.fold_all()?, "HECRZKPR");
+
+    Ok(())
+}
+
+#[test]
+fn test_day13_fold_validation() -> Result<(), error::Error> {
+    let input = r#"
+0,0
+4,0
+7,1
+20,2
+
+fold along x=5"#;
+
+    let paper: Paper = input.parse()?;
+    assert!(paper.fold_once().is_err());
+    // the fold that fails is the last one
+    let mut folds = paper.clone().with_instruction(FoldInstruction::new(FoldType::Vertical, 50)).folds();
+    assert!(matches!(folds.next(), Some(Err(_))));
+    assert!(folds.next().is_none());
+
+    let folded = paper.clone().with_fold_validation(FoldValidation::Drop).fold_once()?;
+    assert_eq!(folded.points, HashSet::from([(0, 0), (4, 0), (3, 1)]));
+
+    let folded = paper.clone().with_fold_validation(FoldValidation::Clamp).fold_once()?;
+    assert_eq!(folded.points, HashSet::from([(0, 0), (4, 0), (3, 1), (0, 2)]));
+
+    let input = r#"
+5,3
+fold along x=5"#;
+    let paper: Paper = input.parse()?;
+    assert!(paper.fold_once().is_err());
+    assert!(paper.clone().with_fold_validation(FoldValidation::Drop).fold_once()?.points.is_empty());
+    assert_eq!(paper.with_fold_validation(FoldValidation::Clamp).fold_once()?.points, HashSet::from([(4, 3)]));
+
+    let input = r#"
+0,3
+fold along x=0"#;
+    let paper: Paper = input.parse()?;
+    assert!(paper.clone().with_fold_validation(FoldValidation::Clamp).fold_once().is_err());
+    assert!(paper.with_fold_validation(FoldValidation::Drop).fold_once()?.points.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_day13_fold_in_place() -> Result<(), error::Error> {
+    let input = r#"
+0,0
+6,0
+8,1
+2,1
+20,2
+
+fold along x=4"#;
+
+    let mut paper: Paper = input.parse()?;
+    assert!(paper.fold_in_place().is_err());
+    assert_eq!(paper.points.len(), 5);
+    assert_eq!(paper.instructions.len(), 1);
+
+    let mut paper = paper.with_fold_validation(FoldValidation::Drop);
+    paper.fold_in_place()?;
+    assert_eq!(paper.points, HashSet::from([(0, 0), (2, 0), (0, 1), (2, 1)]));
+    assert!(paper.instructions.is_empty());
+    assert!(paper.fold_in_place().is_err());
+
+    Ok(())
+}
+
+#[cfg(feature = "image")]
+#[test]
+fn test_day13_export() -> Result<(), error::Error> {
+    let input = r#"
+0,0
+2,1
 fold along x=5"#;
 
     let paper: Paper = input.parse()?;
-    let paper = paper.fold_once();
-    assert_eq!(paper.points.iter().count(), 17);
-    let paper = paper.fold_once();
-    paper.dump();
+    let svg = paper.to_svg(10);
+    assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"30\" height=\"20\">"));
+    assert!(svg.contains("<rect x=\"0\" y=\"0\" width=\"10\" height=\"10\" fill=\"black\"/>"));
+    assert!(svg.contains("<rect x=\"20\" y=\"10\" width=\"10\" height=\"10\" fill=\"black\"/>"));
+
+    let mut png = vec![];
+    paper.write_png(&mut png, 4)?;
+    assert_eq!(&png[1..4], b"PNG");
 
     let paper: Paper = std::fs::read_to_string("input_day13")?.parse()?;
-    let paper = paper.fold_once();
-    assert_eq!(paper.points.iter().count(), 759);
-    let paper = paper.fold_once();
-    let paper = paper.fold_once();
-    let paper = paper.fold_once();
-    let paper = paper.fold_once();
-    let paper = paper.fold_once();
-    let paper = paper.fold_once();
-    let paper = paper.fold_once();
-    let paper = paper.fold_once();
-    let paper = paper.fold_once();
-    let paper = paper.fold_once();
-    let paper = paper.fold_once();
-    paper.dump();
+    let mut folds = paper.folds();
+    while let Some(folded) = folds.next_fold() {
+        folded?.write_png(std::io::sink(), 2)?;
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_day13_apply() -> Result<(), error::Error> {
+    let input = EXAMPLE;
+
+    let paper: Paper = input.parse()?;
+    let in_order = paper.apply(&FoldInstruction::new(FoldType::Horizontal, 7))?.apply(&FoldInstruction::new(FoldType::Vertical, 5))?;
+    let reversed = paper.apply(&FoldInstruction::new(FoldType::Vertical, 5))?.apply(&FoldInstruction::new(FoldType::Horizontal, 7))?;
+    assert_eq!(in_order.points.len(), 16);
+    assert_eq!(in_order.points, reversed.points);
+    assert_eq!(in_order.instructions, paper.instructions);
+
+    let folded = paper.apply(&FoldInstruction::new(FoldType::Horizontal, 7))?;
+    assert!(folded.apply(&FoldInstruction::new(FoldType::Horizontal, 3)).is_err());
+    let synthetic = folded.with_fold_validation(FoldValidation::Drop).apply(&FoldInstruction::new(FoldType::Horizontal, 3))?;
+    assert_eq!(synthetic.height(), 3);
+
+    Ok(())
+}
+
+#[test]
+fn test_day13_builder() -> Result<(), error::Error> {
+    let points = r#"
+6,10
+0,14
+9,10
+0,3
+10,4
+4,11
+6,0
+6,12
+4,1
+0,13
+10,12
+3,4
+3,0
+8,4
+1,10
+2,14
+8,10
+9,0"#;
+    let instructions = r#"
+fold along y=7
+fold along x=5"#;
+
+    let paper = Paper::new().with_points(parse_points(points)?).with_instructions(parse_instructions(instructions)?);
+    let parsed: Paper = format!("{}\n{}", points, instructions).parse()?;
+    assert_eq!(paper.points, parsed.points);
+    assert_eq!(paper.instructions, parsed.instructions);
+    assert_eq!(paper.folds().last().unwrap()?.points.len(), 16);
+
+    let paper = Paper::new().with_points(vec![(0, 0), (0, 4)]).with_instruction(FoldInstruction::new(FoldType::Horizontal, 2));
+    assert_eq!(paper.fold_once()?.points, HashSet::from([(0, 0)]));
+
+    assert!(parse_points("1,2\n3").is_err());
+    assert!(parse_instructions("fold along z=3").is_err());
+    assert!(parse_instructions("fold").is_err());
 
     Ok(())
 }
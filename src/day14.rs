@@ -1,142 +1,424 @@
 use crate::error;
+use crate::puzzle;
 
-use std::collections::HashMap;
+use crate::collections::HashMap;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
 
-fn get_two_chars_from_pair(pair: &str) -> Option<(char, char)> {
-    let mut chars = pair.chars();
-    let char1 = chars.next();
-    let char2 = chars.next();
-    let char3 = chars.next();
-    if char1.is_none() || char2.is_none() || char3.is_some() {
-        return None;
+pub type Pair = (u8, u8);
+
+pub trait Count: Clone + Ord + core::fmt::Debug {
+    fn zero() -> Self;
+    fn one() -> Self;
+    fn checked_add(&self, rhs: &Self) -> Option<Self>;
+    fn checked_mul(&self, rhs: &Self) -> Option<Self>;
+    fn checked_sub(&self, rhs: &Self) -> Option<Self>;
+}
+
+macro_rules! impl_count {
+    ($($t:ty),*) => {
+        $(impl Count for $t {
+            fn zero() -> Self {
+                0
+            }
+
+            fn one() -> Self {
+                1
+            }
+
+            fn checked_add(&self, rhs: &Self) -> Option<Self> {
+                <$t>::checked_add(*self, *rhs)
+            }
+
+            fn checked_mul(&self, rhs: &Self) -> Option<Self> {
+                <$t>::checked_mul(*self, *rhs)
+            }
+
+            fn checked_sub(&self, rhs: &Self) -> Option<Self> {
+                <$t>::checked_sub(*self, *rhs)
+            }
+        })*
+    };
+}
+
+impl_count!(u64, u128, usize);
+
+#[cfg(feature = "bignum")]
+impl Count for num_bigint::BigUint {
+    fn zero() -> Self {
+        num_bigint::BigUint::from(0u8)
+    }
+
+    fn one() -> Self {
+        num_bigint::BigUint::from(1u8)
+    }
+
+    fn checked_add(&self, rhs: &Self) -> Option<Self> {
+        Some(self + rhs)
     }
-    Some((char1.unwrap(), char2.unwrap()))
+
+    fn checked_mul(&self, rhs: &Self) -> Option<Self> {
+        Some(self * rhs)
+    }
+
+    fn checked_sub(&self, rhs: &Self) -> Option<Self> {
+        (self >= rhs).then(|| self - rhs)
+    }
+}
+
+fn overflow_error() -> error::Error {
+    error::Error::General("pair count overflow".to_string())
 }
 
-fn template_to_pair_counter(s: &str) -> HashMap<String, usize> {
+fn add_count<C: Count>(counter: &mut HashMap<Pair, C>, pair: Pair, v: &C) -> Result<(), error::Error> {
+    let count = counter.entry(pair).or_insert_with(C::zero);
+    *count = count.checked_add(v).ok_or_else(overflow_error)?;
+    Ok(())
+}
+
+fn template_to_pair_counter<C: Count>(s: &str) -> Result<HashMap<Pair, C>, error::Error> {
     let mut pair_counter = HashMap::new();
-    for i in 0..s.len() - 1 {
-        let from = &s[i..=(i + 1)];
-        *pair_counter.entry(from.to_string()).or_default() += 1
+    for pair in s.as_bytes().windows(2) {
+        add_count(&mut pair_counter, (pair[0], pair[1]), &C::one())?;
     }
-    pair_counter
+    Ok(pair_counter)
 }
 
+type Matrix<C> = Vec<Vec<C>>;
+
+fn matrix_identity<C: Count>(size: usize) -> Matrix<C> {
+    (0..size).map(|i| (0..size).map(|j| if i == j { C::one() } else { C::zero() }).collect()).collect()
+}
+
+fn matrix_multiply<C: Count>(a: &Matrix<C>, b: &Matrix<C>) -> Result<Matrix<C>, error::Error> {
+    let size = a.len();
+    let mut product = vec![vec![C::zero(); size]; size];
+    for i in 0..size {
+        for k in 0..size {
+            if a[i][k] == C::zero() {
+                continue;
+            }
+            for j in 0..size {
+                product[i][j] = a[i][k].checked_mul(&b[k][j]).and_then(|v| v.checked_add(&product[i][j])).ok_or_else(overflow_error)?;
+            }
+        }
+    }
+    Ok(product)
+}
+
+fn matrix_power<C: Count>(matrix: &Matrix<C>, mut exponent: usize) -> Result<Matrix<C>, error::Error> {
+    let mut result = matrix_identity(matrix.len());
+    let mut base = matrix.clone();
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            result = matrix_multiply(&result, &base)?;
+        }
+        exponent >>= 1;
+        if exponent > 0 {
+            base = matrix_multiply(&base, &base)?;
+        }
+    }
+    Ok(result)
+}
+
+pub const MAX_EXPANDED_LEN: usize = 1 << 24;
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Game {
     pub template: String,
-    pub instructions: HashMap<String, char>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::snapshot::map_as_list"))]
+    pub instructions: HashMap<Pair, u8>,
+}
+
+fn parse_rule(line: &str) -> Result<(Pair, u8), error::Error> {
+    // CH -> B
+    let (from, to) = line.split_once("->").ok_or_else(|| error::Error::Parse(format!("invalid rule: {}", line)))?;
+    match (from.trim().as_bytes(), to.trim().as_bytes()) {
+        (&[char1, char2], &[to]) => Ok(((char1, char2), to)),
+        _ => Err(error::Error::Parse(format!("invalid rule: {}", line))),
+    }
 }
 
-impl std::str::FromStr for Game {
+impl core::str::FromStr for Game {
     type Err = error::Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let mut lines = s.lines().filter(|t| !t.trim_start().trim_end().is_empty());
-        Ok(Game {
-            template: lines.next().unwrap().to_string(),
-            instructions: lines.fold(HashMap::new(), |mut acc, l| {
-                let mut tokens = l.split(&[' ', '-', '>'][..]).filter(|t| !t.trim_start().trim_end().is_empty());
-                let from = tokens.next().unwrap();
-                let to = tokens.next().unwrap().chars().next().unwrap();
-                acc.entry(from.to_string()).or_insert(to);
-                acc
-            }),
-        })
+        let template = lines.next().ok_or_else(|| error::Error::Parse("missing polymer template".to_string()))?.trim().to_string();
+        let mut instructions = HashMap::new();
+        for line in lines {
+            let (from, to) = parse_rule(line)?;
+            if instructions.insert(from, to).filter(|&previous| previous != to).is_some() {
+                return Err(error::Error::Parse(format!("conflicting rules for {}{}", from.0 as char, from.1 as char)));
+            }
+        }
+        Ok(Game { template, instructions })
     }
 }
 
-pub struct GameResult {
-    pair_counter: HashMap<String, usize>,
+pub struct GameResult<C = usize> {
+    pair_counter: HashMap<Pair, C>,
     template: String,
 }
 
-impl GameResult {
-    pub fn score(&self) -> usize {
-        let mut char_counter: HashMap<char, usize> = HashMap::new();
-        for (k, v) in &self.pair_counter {
-            let mut chars = k.chars();
-            let char1 = chars.next().unwrap();
-            let char2 = chars.next().unwrap();
-            *char_counter.entry(char1).or_default() += v;
-            *char_counter.entry(char2).or_default() += v;
+impl<C: Count> GameResult<C> {
+    fn new(pair_counter: HashMap<Pair, C>, template: &str) -> Result<Self, error::Error> {
+        // the polymer length bounds every element count, so once it fits score() cannot overflow
+        pair_counter.values().try_fold(C::one(), |acc, v| acc.checked_add(v)).ok_or_else(overflow_error)?;
+        Ok(GameResult {
+            pair_counter,
+            template: template.to_string(),
+        })
+    }
+
+    pub fn element_counts(&self) -> HashMap<u8, C> {
+        // every element except the last one starts exactly one pair
+        let mut char_counter: HashMap<u8, C> = HashMap::new();
+        for (&(char1, _), v) in &self.pair_counter {
+            let count = char_counter.entry(char1).or_insert_with(C::zero);
+            *count = count.checked_add(v).unwrap();
         }
+        if let Some(&last_template_char) = self.template.as_bytes().last() {
+            let count = char_counter.entry(last_template_char).or_insert_with(C::zero);
+            *count = count.checked_add(&C::one()).unwrap();
+        }
+        char_counter
+    }
 
-        let first_template_char = self.template.chars().next().unwrap();
-        let last_template_char = self.template.chars().last().unwrap();
+    pub fn count_of(&self, element: char) -> C {
+        u8::try_from(element).ok().and_then(|element| self.element_counts().remove(&element)).unwrap_or_else(C::zero)
+    }
 
-        for (&k, v) in char_counter.iter_mut() {
-            if k == first_template_char {
-                *v -= 1;
+    pub fn score(&self) -> C {
+        let char_counter = self.element_counts();
+        let max = char_counter.values().max().unwrap();
+        let min = char_counter.values().min().unwrap();
+        max.checked_sub(min).unwrap()
+    }
+}
+
+impl Game {
+    // pairs without a rule pass through unchanged
+    fn generate_two_pairs_from_pair(&self, from: Pair) -> Option<(Pair, Pair)> {
+        let to = *self.instructions.get(&from)?;
+        Some(((from.0, to), (to, from.1)))
+    }
+
+    fn validate(&self) -> Result<(), error::Error> {
+        if self.template.is_empty() {
+            return Err(error::Error::General("empty polymer template".to_string()));
+        }
+        if let Some(c) = self.template.bytes().find(|c| !c.is_ascii_alphabetic()) {
+            return Err(error::Error::General(format!("invalid element {:?} in template", c as char)));
+        }
+        for (&(char1, char2), &to) in &self.instructions {
+            if ![char1, char2, to].iter().all(u8::is_ascii_alphabetic) {
+                return Err(error::Error::General(format!("invalid rule {}{} -> {}", char1 as char, char2 as char, to as char)));
             }
-            if k == last_template_char {
-                *v -= 1;
+        }
+        Ok(())
+    }
+
+    pub fn expand(&self, times: usize) -> Result<String, error::Error> {
+        let len = self.template.len();
+        let expanded_len = u32::try_from(times)
+            .ok()
+            .and_then(|times| len.saturating_sub(1).checked_mul(1usize.checked_shl(times)?))
+            .and_then(|len| len.checked_add(1))
+            .filter(|&expanded_len| expanded_len <= MAX_EXPANDED_LEN)
+            .ok_or_else(|| error::Error::General(format!("polymer after {} steps would exceed {} elements", times, MAX_EXPANDED_LEN)))?;
+
+        self.validate()?;
+
+        let mut polymer: Vec<u8> = self.template.as_bytes().to_vec();
+        for _iteration in 0..times {
+            let mut next = Vec::with_capacity(expanded_len);
+            for pair in polymer.windows(2) {
+                next.push(pair[0]);
+                next.extend(self.instructions.get(&(pair[0], pair[1])));
             }
-            *v /= 2;
+            next.extend(polymer.last());
+            polymer = next;
+        }
+
+        Ok(String::from_utf8(polymer)?)
+    }
+
+    pub fn step(&self, times: usize) -> Result<GameResult, error::Error> {
+        self.step_counting(times)
+    }
+
+    pub fn step_counting<C: Count>(&self, times: usize) -> Result<GameResult<C>, error::Error> {
+        self.validate()?;
 
-            if k == first_template_char {
-                *v += 1;
+        let mut pair_counter_current: HashMap<Pair, C> = template_to_pair_counter(&self.template)?;
+
+        for _iteration in 0..times {
+            let mut pair_counter_next: HashMap<Pair, C> = HashMap::new();
+
+            for (&k, v) in &pair_counter_current {
+                match self.generate_two_pairs_from_pair(k) {
+                    Some((pair1, pair2)) => {
+                        add_count(&mut pair_counter_next, pair1, v)?;
+                        add_count(&mut pair_counter_next, pair2, v)?;
+                    }
+                    None => add_count(&mut pair_counter_next, k, v)?,
+                }
             }
-            if k == last_template_char {
-                *v += 1;
+
+            pair_counter_current = pair_counter_next;
+        }
+
+        GameResult::new(pair_counter_current, &self.template)
+    }
+
+    // every pair that can ever appear, starting from the template pairs
+    fn reachable_pairs(&self) -> Vec<Pair> {
+        let mut pairs: Vec<Pair> = self.template.as_bytes().windows(2).map(|pair| (pair[0], pair[1])).collect();
+        pairs.sort_unstable();
+        pairs.dedup();
+        let mut i = 0;
+        while i < pairs.len() {
+            if let Some((pair1, pair2)) = self.generate_two_pairs_from_pair(pairs[i]) {
+                for pair in [pair1, pair2] {
+                    if !pairs.contains(&pair) {
+                        pairs.push(pair);
+                    }
+                }
             }
+            i += 1;
         }
+        pairs
+    }
 
-        let numbers: Vec<usize> = char_counter.into_iter().map(|(_, y)| y).collect();
-        let max = numbers.iter().max().unwrap().to_owned();
-        let min = numbers.iter().min().unwrap().to_owned();
-        max - min
+    fn transition_targets(&self, pairs: &[Pair], index: &HashMap<Pair, usize>) -> Vec<Vec<usize>> {
+        pairs
+            .iter()
+            .enumerate()
+            .map(|(from, &pair)| match self.generate_two_pairs_from_pair(pair) {
+                Some((pair1, pair2)) => vec![index[&pair1], index[&pair2]],
+                None => vec![from],
+            })
+            .collect()
     }
-}
 
-impl Game {
-    fn generate_two_pairs_from_pair(&self, from: &str) -> (String, String) {
-        let to = self.instructions.get(from).unwrap();
-        let (char1, char2) = get_two_chars_from_pair(from).unwrap();
+    pub fn step_matrix(&self, times: usize) -> Result<GameResult, error::Error> {
+        self.step_matrix_counting(times)
+    }
+
+    pub fn step_matrix_counting<C: Count>(&self, times: usize) -> Result<GameResult<C>, error::Error> {
+        self.validate()?;
+
+        let pairs = self.reachable_pairs();
+        let index: HashMap<Pair, usize> = pairs.iter().enumerate().map(|(i, &pair)| (pair, i)).collect();
+
+        let mut transition: Matrix<C> = vec![vec![C::zero(); pairs.len()]; pairs.len()];
+        for (from, targets) in self.transition_targets(&pairs, &index).into_iter().enumerate() {
+            for to in targets {
+                transition[to][from] = transition[to][from].checked_add(&C::one()).ok_or_else(overflow_error)?;
+            }
+        }
+        let transition = matrix_power(&transition, times)?;
 
-        (format!("{}{}", char1, to), format!("{}{}", to, char2))
+        let initial: HashMap<Pair, C> = template_to_pair_counter(&self.template)?;
+        let mut pair_counter = HashMap::new();
+        for (to, row) in transition.iter().enumerate() {
+            let mut count = C::zero();
+            for (pair, v) in &initial {
+                count = row[index[pair]].checked_mul(v).and_then(|c| c.checked_add(&count)).ok_or_else(overflow_error)?;
+            }
+            if count != C::zero() {
+                pair_counter.insert(pairs[to], count);
+            }
+        }
+
+        GameResult::new(pair_counter, &self.template)
     }
 
-    pub fn step(&self, times: usize) -> GameResult {
-        let mut pair_counter_current = template_to_pair_counter(&self.template);
+    pub fn count_of(&self, element: char, times: usize) -> Result<usize, error::Error> {
+        Ok(self.step_matrix(times)?.count_of(element))
+    }
 
-        for _iteration in 0..times {
-            let mut pair_counter_next: HashMap<String, usize> = HashMap::new();
+    // Asymptotic ratio count(n + 1) / count(n) for the element: the largest Perron root among the strongly
+    // connected pair components that are reachable from the template and feed pairs starting with the element.
+    pub fn growth_rate(&self, element: char) -> Result<f64, error::Error> {
+        self.validate()?;
 
-            for (k, v) in &pair_counter_current {
-                let (pair1, pair2) = self.generate_two_pairs_from_pair(k);
-                *pair_counter_next.entry(pair1).or_default() += v;
-                *pair_counter_next.entry(pair2).or_default() += v;
+        let pairs = self.reachable_pairs();
+        let index: HashMap<Pair, usize> = pairs.iter().enumerate().map(|(i, &pair)| (pair, i)).collect();
+        let targets = self.transition_targets(&pairs, &index);
+
+        let mut graph = petgraph::graph::DiGraph::<(), ()>::new();
+        let nodes: Vec<_> = pairs.iter().map(|_| graph.add_node(())).collect();
+        for (from, to) in targets.iter().enumerate() {
+            for &to in to {
+                graph.add_edge(nodes[from], nodes[to], ());
+            }
+        }
+        let mut feeds_element: Vec<bool> = pairs.iter().map(|&(char1, _)| char1 as char == element).collect();
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (from, to) in targets.iter().enumerate() {
+                if !feeds_element[from] && to.iter().any(|&to| feeds_element[to]) {
+                    feeds_element[from] = true;
+                    changed = true;
+                }
             }
+        }
 
-            pair_counter_current = pair_counter_next;
+        let mut rate: f64 = 0.0;
+        for component in petgraph::algo::tarjan_scc(&graph) {
+            let members: Vec<usize> = component.iter().map(|node| node.index()).collect();
+            if feeds_element[members[0]] {
+                rate = rate.max(spectral_radius(&members, &targets));
+            }
         }
 
-        GameResult {
-            pair_counter: pair_counter_current,
-            template: self.template.to_string(),
+        if rate > 0.0 {
+            Ok(rate)
+        } else if self.template.ends_with(element) {
+            Ok(1.0)
+        } else {
+            Err(error::Error::General(format!("element {} does not keep appearing", element)))
         }
     }
 }
 
-#[test]
-fn test_day14() -> Result<(), error::Error> {
-    let input = r#"
-NN
+// Perron root of an irreducible component. Power iteration runs on M + I, which is primitive and therefore
+// converges even for periodic components, and has the root shifted by exactly one.
+fn spectral_radius(members: &[usize], targets: &[Vec<usize>]) -> f64 {
+    const MAX_ITERATIONS: usize = 100_000;
+    const EPSILON: f64 = 1e-13;
 
-CC -> N
-NN -> C
-NC -> C
-CN -> N
-"#;
-    let game: Game = input.parse()?;
-    assert_eq!(game.template, "NN");
-    assert_eq!(game.instructions.len(), 4);
-    assert_eq!(game.instructions.get("CC").unwrap(), &'N');
-    assert_eq!(game.step(0).score(), 0);
-    assert_eq!(game.step(1).score(), 1);
-    assert_eq!(game.step(2).score(), 1);
+    let position: HashMap<usize, usize> = members.iter().enumerate().map(|(i, &m)| (m, i)).collect();
+    let has_edges = members.iter().any(|m| targets[*m].iter().any(|to| position.contains_key(to)));
+    if !has_edges {
+        return 0.0;
+    }
 
-    let input = r#"
+    let mut v = vec![1.0 / members.len() as f64; members.len()];
+    let mut previous_ratio = f64::NAN;
+    for _iteration in 0..MAX_ITERATIONS {
+        let mut next = v.clone();
+        for (i, &from) in members.iter().enumerate() {
+            for to in &targets[from] {
+                if let Some(&j) = position.get(to) {
+                    next[j] += v[i];
+                }
+            }
+        }
+        let ratio = next.iter().sum::<f64>();
+        v = next.into_iter().map(|c| c / ratio).collect();
+        if (ratio - previous_ratio).abs() < EPSILON {
+            break;
+        }
+        previous_ratio = ratio;
+    }
+    previous_ratio - 1.0
+}
+
+const EXAMPLE: &str = r#"
 NNCB
 
 CH -> B
@@ -155,17 +437,195 @@ BB -> N
 BC -> B
 CC -> N
 CN -> C"#;
+
+impl puzzle::Puzzle for Game {
+    fn parse(input: &str) -> Result<Self, error::Error> {
+        input.parse()
+    }
+
+    fn part1(&self) -> Result<puzzle::Answer, error::Error> {
+        Ok(self.step(10)?.score().into())
+    }
+
+    fn part2(&self) -> Result<puzzle::Answer, error::Error> {
+        Ok(self.step(40)?.score().into())
+    }
+
+    fn examples() -> Vec<puzzle::Example> {
+        vec![(EXAMPLE, Some("1588"), Some("2188189693529"))]
+    }
+}
+
+#[test]
+fn test_day14() -> Result<(), error::Error> {
+    let input = r#"
+NN
+
+CC -> N
+NN -> C
+NC -> C
+CN -> N
+"#;
+    let game: Game = input.parse()?;
+    assert_eq!(game.template, "NN");
+    assert_eq!(game.instructions.len(), 4);
+    assert_eq!(game.instructions.get(&(b'C', b'C')).unwrap(), &b'N');
+    assert_eq!(game.step(0)?.score(), 0);
+    assert_eq!(game.step(1)?.score(), 1);
+    assert_eq!(game.step(2)?.score(), 1);
+
+    let input = EXAMPLE;
     let game: Game = input.parse()?;
     assert_eq!(game.template, "NNCB");
     assert_eq!(game.instructions.len(), 16);
-    assert_eq!(game.instructions.get("NB").unwrap(), &'B');
-    assert_eq!(game.step(1).score(), 1);
-    assert_eq!(game.step(10).score(), 1588);
-    assert_eq!(game.step(40).score(), 2188189693529);
+    assert_eq!(game.instructions.get(&(b'N', b'B')).unwrap(), &b'B');
+    assert_eq!(game.step(1)?.score(), 1);
+    assert_eq!(game.step(10)?.score(), 1588);
+    assert_eq!(game.step(40)?.score(), 2188189693529);
 
     let game: Game = std::fs::read_to_string("input_day14")?.parse()?;
-    assert_eq!(game.step(10).score(), 3259);
-    assert_eq!(game.step(40).score(), 3459174981021);
+    assert_eq!(game.step(10)?.score(), 3259);
+    assert_eq!(game.step(40)?.score(), 3459174981021);
+
+    Ok(())
+}
+
+#[test]
+fn test_day14_expand() -> Result<(), error::Error> {
+    let input = EXAMPLE;
+    let game: Game = input.parse()?;
+    assert_eq!(game.expand(0)?, "NNCB");
+    assert_eq!(game.expand(1)?, "NCNBCHB");
+    assert_eq!(game.expand(2)?, "NBCCNBBBCBHCB");
+    assert_eq!(game.expand(3)?, "NBBBCNCCNBBNBNBBCHBHHBCHB");
+    assert_eq!(game.expand(4)?, "NBBNBNBBCCNBCNCCNBBNBBNBBBNBBNBBCBHCBHHNHCBBCBHCB");
+
+    let polymer = game.expand(10)?;
+    assert_eq!(polymer.len(), 3073);
+    let counts = polymer.chars().fold(HashMap::new(), |mut acc: HashMap<char, usize>, c| {
+        *acc.entry(c).or_default() += 1;
+        acc
+    });
+    assert_eq!(counts.values().max().unwrap() - counts.values().min().unwrap(), game.step(10)?.score());
+
+    assert!(game.expand(40).is_err());
+    assert!(game.expand(usize::MAX).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_day14_unmatched_pairs() -> Result<(), error::Error> {
+    let game: Game = "NXN\n\nNX -> C".parse()?;
+    assert_eq!(game.expand(1)?, "NCXN");
+    assert_eq!(game.expand(2)?, "NCXN");
+    assert_eq!(game.step(1)?.score(), 1);
+    assert_eq!(game.step(5)?.score(), game.step(1)?.score());
+
+    let game: Game = "NN\n".parse()?;
+    assert_eq!(game.expand(3)?, "NN");
+    assert_eq!(game.step(3)?.score(), 0);
+
+    assert!("NN\n\nNN -> C\nNN -> B".parse::<Game>().is_err());
+    assert!("NN\n\nNN -> C\nNN -> C".parse::<Game>().is_ok());
+    assert!("NN\n\nNNN -> C".parse::<Game>().is_err());
+    assert!("NN\n\nNN C".parse::<Game>().is_err());
+    assert!("".parse::<Game>().is_err());
+
+    let game = Game {
+        template: "NN".to_string(),
+        instructions: HashMap::from([((b'N', b'N'), b'-')]),
+    };
+    assert!(game.step(1).is_err());
+    assert!(game.expand(1).is_err());
+    let game = Game {
+        template: "".to_string(),
+        instructions: HashMap::new(),
+    };
+    assert!(game.step(1).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_day14_step_matrix() -> Result<(), error::Error> {
+    let input = EXAMPLE;
+    let game: Game = input.parse()?;
+    for times in [0, 1, 2, 10, 40] {
+        assert_eq!(game.step_matrix(times)?.pair_counter, game.step(times)?.pair_counter);
+    }
+    assert_eq!(game.step_matrix(40)?.score(), 2188189693529);
+    assert!(game.step_matrix(1_000_000).is_err());
+
+    let game: Game = std::fs::read_to_string("input_day14")?.parse()?;
+    assert_eq!(game.step_matrix(10)?.score(), 3259);
+    assert_eq!(game.step_matrix(40)?.score(), 3459174981021);
+
+    let game: Game = "NXNB\n\nXN -> B\nBB -> Q".parse()?;
+    assert_eq!(game.step_matrix(1)?.pair_counter, game.step(1)?.pair_counter);
+    assert_eq!(game.step_matrix(1_000_000_000)?.pair_counter, game.step(1)?.pair_counter);
+    assert_eq!(game.step_matrix(usize::MAX)?.score(), 1);
+
+    Ok(())
+}
+
+#[test]
+fn test_day14_overflow() -> Result<(), error::Error> {
+    let input = EXAMPLE;
+    let game: Game = input.parse()?;
+    assert_eq!(game.step_counting::<u64>(40)?.score(), 2188189693529);
+    assert!(game.step(63).is_err());
+    assert!(game.step_matrix(63).is_err());
+    assert!(game.step_counting::<u64>(63).is_err());
+
+    let score = game.step_counting::<u128>(100)?.score();
+    assert_eq!(game.step_matrix_counting::<u128>(100)?.score(), score);
+    assert!(game.step_counting::<u128>(127).is_err());
+
+    let game: Game = "N".parse()?;
+    assert_eq!(game.step(10)?.score(), 0);
+
+    Ok(())
+}
+
+#[cfg(feature = "bignum")]
+#[test]
+fn test_day14_bignum() -> Result<(), error::Error> {
+    let input = EXAMPLE;
+    let game: Game = input.parse()?;
+    assert_eq!(game.step_counting::<num_bigint::BigUint>(40)?.score(), num_bigint::BigUint::from(2188189693529u64));
+    assert_eq!(
+        game.step_counting::<num_bigint::BigUint>(100)?.score(),
+        num_bigint::BigUint::from(game.step_counting::<u128>(100)?.score())
+    );
+    assert_eq!(game.step_counting::<num_bigint::BigUint>(200)?.score(), game.step_matrix_counting::<num_bigint::BigUint>(200)?.score());
+
+    Ok(())
+}
+
+#[test]
+fn test_day14_element_queries() -> Result<(), error::Error> {
+    let input = EXAMPLE;
+    let game: Game = input.parse()?;
+    assert_eq!(game.count_of('B', 10)?, 1749);
+    assert_eq!(game.count_of('C', 10)?, 298);
+    assert_eq!(game.count_of('H', 10)?, 161);
+    assert_eq!(game.count_of('N', 10)?, 865);
+    assert_eq!(game.count_of('Z', 10)?, 0);
+    assert_eq!(game.count_of('B', 40)?, 2192039569602);
+    assert_eq!(game.count_of('H', 40)?, 3849876073);
+    assert_eq!(game.step(10)?.count_of('B'), 1749);
+
+    for element in ['B', 'C', 'H', 'N'] {
+        let ratio = game.step_counting::<u128>(121)?.count_of(element) as f64 / game.step_counting::<u128>(120)?.count_of(element) as f64;
+        assert!((game.growth_rate(element)? - ratio).abs() < 1e-3);
+    }
+    assert!((game.growth_rate('N')? - 2.0).abs() < 1e-9);
+    assert!(game.growth_rate('H')? < 1.8);
+    assert!(game.growth_rate('Z').is_err());
+
+    let game: Game = "NXNB\n\nXN -> B\nBB -> Q".parse()?;
+    assert!((game.growth_rate('N')? - 1.0).abs() < 1e-9);
 
     Ok(())
 }
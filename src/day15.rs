@@ -1,99 +1,479 @@
 use crate::error;
+use crate::grid;
+use crate::puzzle;
 
-#[derive(Debug)]
+use crate::collections::BinaryHeap;
+use alloc::vec::Vec;
+use core::cmp::Reverse;
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Board {
-    positions: Vec<Vec<u8>>,
+    positions: grid::Grid<u8>,
+    // the five-by-five tiled board, materialized once so the tiled searches are plain lookups
+    quintupled: grid::Grid<u8>,
+    movement: Movement,
 }
 
-impl std::str::FromStr for Board {
+impl core::str::FromStr for Board {
     type Err = error::Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(Board {
-            positions: s
-                .lines()
-                .filter(|l| !l.trim_start().trim_end().is_empty())
-                .map(|l| l.chars().map(|c| c.to_digit(10).unwrap() as u8).collect())
-                .collect(),
-        })
+        let positions = grid::Grid::parse_digits(s)?;
+        let mut board = Board {
+            quintupled: grid::Grid::new(positions.width() * 5, positions.height() * 5, 0),
+            positions,
+            movement: Movement::Orthogonal,
+        };
+        for y in 0..board.height() {
+            for x in 0..board.width() {
+                board.update_tiles(x, y);
+            }
+        }
+        Ok(board)
     }
 }
 
-impl Board {
-    pub fn lowest_total_risk(&self) -> i32 {
-        let width = || self.positions[0].len() as i32;
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Movement {
+    Orthogonal,
+    Diagonal,
+    // successors of a position; positions outside the board are ignored. a board with custom
+    // movement can't be serialized.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    Custom(fn(Pos) -> Vec<Pos>),
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum Heuristic {
+    None,
+    // remaining step count (manhattan, chebyshev with diagonals, zero for custom moves) scaled by the
+    // cheapest risk on the board, which keeps it admissible
+    GridDistance,
+    // estimate from a position to the goal; must never overestimate for the result to be optimal
+    Custom(fn((i32, i32), (i32, i32)) -> i32),
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum Strategy {
+    AStar(Heuristic),
+    BucketDijkstra,
+}
+
+pub type Pos = (i32, i32);
+
+impl Movement {
+    fn neighbors(&self, (x, y): Pos) -> Vec<Pos> {
+        match self {
+            Movement::Orthogonal => vec![(x, y - 1), (x + 1, y), (x, y + 1), (x - 1, y)],
+            Movement::Diagonal => vec![(x, y - 1), (x + 1, y - 1), (x + 1, y), (x + 1, y + 1), (x, y + 1), (x - 1, y + 1), (x - 1, y), (x - 1, y - 1)],
+            Movement::Custom(successors) => successors((x, y)),
+        }
+    }
+}
+
+fn no_path_error(start: Pos, goal: Pos) -> error::Error {
+    error::Error::General(format!("no path from {:?} to {:?}", start, goal))
+}
 
-        let height = || self.positions.len() as i32;
+// risks are single digits, so distances within reach of the current one fit in a ring of ten buckets
+const NUM_BUCKETS: usize = 10;
 
-        let is_oob = |x, y| -> bool { x < 0 || x >= width() || y < 0 || y >= height() };
+struct RiskMap<F: Fn(i32, i32) -> i32> {
+    width: i32,
+    height: i32,
+    at: F,
+    min_risk: i32,
+    movement: Movement,
+}
 
-        let at = |x, y| self.positions[y as usize][x as usize] as i32;
+impl<F: Fn(i32, i32) -> i32> RiskMap<F> {
+    fn is_oob(&self, (x, y): Pos) -> bool {
+        x < 0 || x >= self.width || y < 0 || y >= self.height
+    }
 
-        let cost_to = |x, y| {
-            if is_oob(x, y) {
-                return 999;
+    fn neighbors(&self, pos: Pos) -> impl Iterator<Item = Pos> + '_ {
+        self.movement.neighbors(pos).into_iter().filter(move |&p| !self.is_oob(p))
+    }
+
+    fn grid_distance(&self, (x, y): Pos, goal: Pos) -> i32 {
+        let (dx, dy) = ((goal.0 - x).abs(), (goal.1 - y).abs());
+        match self.movement {
+            Movement::Orthogonal => dx + dy,
+            Movement::Diagonal => dx.max(dy),
+            Movement::Custom(_) => 0,
+        }
+    }
+
+    fn find_path(&self, start: Pos, goal: Pos, strategy: Strategy) -> Result<(Vec<Pos>, i32), error::Error> {
+        if self.is_oob(start) || self.is_oob(goal) {
+            return Err(error::Error::General(format!("{:?} or {:?} is outside the {}x{} board", start, goal, self.width, self.height)));
+        }
+        match strategy {
+            Strategy::AStar(heuristic) => self.find_path_astar(start, goal, heuristic),
+            Strategy::BucketDijkstra => self.find_path_bucket_dijkstra(start, goal),
+        }
+    }
+
+    fn find_path_bucket_dijkstra(&self, start: Pos, goal: Pos) -> Result<(Vec<Pos>, i32), error::Error> {
+        let index = |(x, y): Pos| (y * self.width + x) as usize;
+
+        let mut distances = vec![i32::MAX; (self.width * self.height) as usize];
+        let mut previous = vec![usize::MAX; (self.width * self.height) as usize];
+        let mut buckets: Vec<Vec<Pos>> = vec![vec![]; NUM_BUCKETS];
+        let mut num_queued = 1;
+        distances[index(start)] = 0;
+        buckets[0].push(start);
+
+        let mut distance = 0;
+        while num_queued > 0 {
+            while let Some(pos) = buckets[distance as usize % NUM_BUCKETS].pop() {
+                num_queued -= 1;
+                let current = index(pos);
+                if distances[current] != distance {
+                    continue;
+                }
+                if pos == goal {
+                    return Ok((self.path_to(&previous, current), distance));
+                }
+                for next_pos in self.neighbors(pos) {
+                    let next = index(next_pos);
+                    let next_distance = distance + (self.at)(next_pos.0, next_pos.1);
+                    if next_distance < distances[next] {
+                        distances[next] = next_distance;
+                        previous[next] = current;
+                        buckets[next_distance as usize % NUM_BUCKETS].push(next_pos);
+                        num_queued += 1;
+                    }
+                }
             }
-            at(x, y)
+            distance += 1;
+        }
+
+        Err(no_path_error(start, goal))
+    }
+
+    // the queue is ordered by the risk so far plus the estimate of what is left. a position can be
+    // queued again when a cheaper way to it turns up, the entries that were beaten are skipped.
+    fn find_path_astar(&self, start: Pos, goal: Pos, heuristic: Heuristic) -> Result<(Vec<Pos>, i32), error::Error> {
+        let index = |(x, y): Pos| (y * self.width + x) as usize;
+        let estimate = |pos: Pos| match heuristic {
+            Heuristic::None => 0,
+            Heuristic::GridDistance => self.grid_distance(pos, goal) * self.min_risk,
+            Heuristic::Custom(estimate) => estimate(pos, goal),
         };
 
-        pathfinding::directed::astar::astar(
-            &(0, 0),
-            |&(x, y)| vec![(x, y - 1), (x + 1, y), (x, y + 1), (x - 1, y)].into_iter().map(|p| (p, cost_to(p.0, p.1))),
-            |&(x, y)| (height() - y) + (width() - x),
-            |&p| p.0 == width() - 1 && p.1 == height() - 1,
-        )
-        .unwrap()
-        .1
+        let mut distances = vec![i32::MAX; (self.width * self.height) as usize];
+        let mut previous = vec![usize::MAX; (self.width * self.height) as usize];
+        let mut queue = BinaryHeap::from([Reverse((estimate(start), 0, start))]);
+        distances[index(start)] = 0;
+
+        while let Some(Reverse((_, distance, pos))) = queue.pop() {
+            let current = index(pos);
+            if distance > distances[current] {
+                continue;
+            }
+            if pos == goal {
+                return Ok((self.path_to(&previous, current), distance));
+            }
+            for next_pos in self.neighbors(pos) {
+                let next = index(next_pos);
+                let next_distance = distance + (self.at)(next_pos.0, next_pos.1);
+                if next_distance < distances[next] {
+                    distances[next] = next_distance;
+                    previous[next] = current;
+                    queue.push(Reverse((next_distance + estimate(next_pos), next_distance, next_pos)));
+                }
+            }
+        }
+
+        Err(no_path_error(start, goal))
     }
 
-    pub fn lowest_total_risk_quintupled(&self) -> i32 {
-        let width = || (self.positions[0].len() * 5) as i32;
+    // from the start to the cell, following the previous cell of each one back
+    fn path_to(&self, previous: &[usize], mut node: usize) -> Vec<Pos> {
+        let mut path = vec![(node as i32 % self.width, node as i32 / self.width)];
+        while previous[node] != usize::MAX {
+            node = previous[node];
+            path.push((node as i32 % self.width, node as i32 / self.width));
+        }
+        path.reverse();
+        path
+    }
+}
+
+// Shortest path tree from a fixed start over a materialized risk map, repaired locally when a cell changes
+// instead of being recomputed from scratch.
+pub struct Planner {
+    width: i32,
+    height: i32,
+    risks: Vec<u8>,
+    start: usize,
+    successors: Vec<Vec<usize>>,
+    predecessors: Vec<Vec<usize>>,
+    distances: Vec<i32>,
+    previous: Vec<usize>,
+}
+
+impl Planner {
+    fn new(width: i32, height: i32, risks: Vec<u8>, start: Pos, movement: Movement) -> Result<Self, error::Error> {
+        let is_oob = |(x, y): Pos| x < 0 || x >= width || y < 0 || y >= height;
+        if is_oob(start) {
+            return Err(error::Error::General(format!("{:?} is outside the {}x{} board", start, width, height)));
+        }
+        let size = risks.len();
+        let mut successors = vec![vec![]; size];
+        let mut predecessors = vec![vec![]; size];
+        for (index, successors) in successors.iter_mut().enumerate() {
+            let pos = (index as i32 % width, index as i32 / width);
+            for next in movement.neighbors(pos).into_iter().filter(|&p| !is_oob(p)) {
+                let next = (next.1 * width + next.0) as usize;
+                successors.push(next);
+                predecessors[next].push(index);
+            }
+        }
+        let mut planner = Planner {
+            width,
+            height,
+            risks,
+            start: (start.1 * width + start.0) as usize,
+            successors,
+            predecessors,
+            distances: vec![i32::MAX; size],
+            previous: vec![usize::MAX; size],
+        };
+        planner.distances[planner.start] = 0;
+        planner.propagate(BinaryHeap::from([Reverse((0, planner.start))]));
+        Ok(planner)
+    }
+
+    fn index(&self, (x, y): Pos) -> Result<usize, error::Error> {
+        if x < 0 || x >= self.width || y < 0 || y >= self.height {
+            return Err(error::Error::General(format!("{:?} is outside the {}x{} board", (x, y), self.width, self.height)));
+        }
+        Ok((y * self.width + x) as usize)
+    }
 
-        let height = || (self.positions.len() * 5) as i32;
+    fn propagate(&mut self, mut queue: BinaryHeap<Reverse<(i32, usize)>>) {
+        while let Some(Reverse((distance, current))) = queue.pop() {
+            if distance > self.distances[current] {
+                continue;
+            }
+            for &next in &self.successors[current] {
+                let next_distance = distance + self.risks[next] as i32;
+                if next_distance < self.distances[next] {
+                    self.distances[next] = next_distance;
+                    self.previous[next] = current;
+                    queue.push(Reverse((next_distance, next)));
+                }
+            }
+        }
+    }
 
-        let is_oob = |x, y| -> bool { x < 0 || x >= width() || y < 0 || y >= height() };
+    // cheapest way into the cell from any predecessor that is still reachable
+    fn best_entry(&self, index: usize) -> Option<(i32, usize)> {
+        self.predecessors[index]
+            .iter()
+            .filter(|&&from| self.distances[from] != i32::MAX)
+            .map(|&from| (self.distances[from] + self.risks[index] as i32, from))
+            .min()
+    }
 
-        let at = |x, y| {
-            let base_width = self.positions[0].len() as i32;
-            let base_height = self.positions.len() as i32;
-            let tile_x = x / base_width;
-            let tile_y = y / base_height;
-            let base_x = x % base_width;
-            let base_y = y % base_height;
+    pub fn risk(&self, pos: Pos) -> Result<u8, error::Error> {
+        Ok(self.risks[self.index(pos)?])
+    }
 
-            let base_risk = self.positions[base_y as usize][base_x as usize] as i32;
+    pub fn set_risk(&mut self, pos: Pos, risk: u8) -> Result<(), error::Error> {
+        let index = self.index(pos)?;
+        if risk > 9 {
+            return Err(error::Error::General(format!("risk level {} is not a single digit", risk)));
+        }
+        let old_risk = core::mem::replace(&mut self.risks[index], risk);
+        if index == self.start || self.distances[index] == i32::MAX || risk == old_risk {
+            return Ok(());
+        }
 
-            let mut new_risk = base_risk + tile_x + tile_y;
+        if risk < old_risk {
+            if let Some((distance, from)) = self.best_entry(index).filter(|&(distance, _)| distance < self.distances[index]) {
+                self.distances[index] = distance;
+                self.previous[index] = from;
+                self.propagate(BinaryHeap::from([Reverse((distance, index))]));
+            }
+            return Ok(());
+        }
 
-            if new_risk > 9 {
-                new_risk -= 9;
+        // every cell whose shortest path ran through the changed cell has to be settled again
+        let mut invalidated = vec![index];
+        let mut i = 0;
+        while i < invalidated.len() {
+            let current = invalidated[i];
+            for &next in &self.successors[current] {
+                if self.previous[next] == current {
+                    invalidated.push(next);
+                }
             }
+            i += 1;
+        }
+        for &cell in &invalidated {
+            self.distances[cell] = i32::MAX;
+            self.previous[cell] = usize::MAX;
+        }
+        let mut queue = BinaryHeap::new();
+        for &cell in &invalidated {
+            if let Some((distance, from)) = self.best_entry(cell) {
+                self.distances[cell] = distance;
+                self.previous[cell] = from;
+                queue.push(Reverse((distance, cell)));
+            }
+        }
+        self.propagate(queue);
 
-            new_risk
-        };
+        Ok(())
+    }
+
+    pub fn lowest_total_risk(&self, goal: Pos) -> Result<i32, error::Error> {
+        Ok(self.lowest_risk_path(goal)?.1)
+    }
 
-        let cost_to = |x, y| {
-            if is_oob(x, y) {
-                return 999;
+    pub fn lowest_risk_path(&self, goal: Pos) -> Result<(Vec<Pos>, i32), error::Error> {
+        let mut node = self.index(goal)?;
+        let start = (self.start as i32 % self.width, self.start as i32 / self.width);
+        if self.distances[node] == i32::MAX {
+            return Err(no_path_error(start, goal));
+        }
+        let mut path = vec![goal];
+        while self.previous[node] != usize::MAX {
+            node = self.previous[node];
+            path.push((node as i32 % self.width, node as i32 / self.width));
+        }
+        path.reverse();
+        Ok((path, self.distances[self.index(goal)?]))
+    }
+}
+
+impl Board {
+    pub fn with_movement(mut self, movement: Movement) -> Self {
+        self.movement = movement;
+        self
+    }
+
+    fn width(&self) -> i32 {
+        self.positions.width() as i32
+    }
+
+    fn height(&self) -> i32 {
+        self.positions.height() as i32
+    }
+
+    fn at(&self, x: i32, y: i32) -> i32 {
+        self.positions[(x as i64, y as i64)] as i32
+    }
+
+    fn at_quintupled(&self, x: i32, y: i32) -> i32 {
+        self.quintupled[(x as i64, y as i64)] as i32
+    }
+
+    // copies a base cell into its 25 tiles, raising the risk by the tile distance and wrapping 10+ back to 1
+    fn update_tiles(&mut self, x: i32, y: i32) {
+        let base_risk = self.at(x, y);
+        for tile_y in 0..5 {
+            for tile_x in 0..5 {
+                let mut new_risk = base_risk + tile_x + tile_y;
+
+                if new_risk > 9 {
+                    new_risk -= 9;
+                }
+
+                let (qx, qy) = (tile_x * self.width() + x, tile_y * self.height() + y);
+                self.quintupled[(qx as i64, qy as i64)] = new_risk as u8;
             }
+        }
+    }
 
-            at(x, y)
-        };
+    pub fn set_risk(&mut self, (x, y): Pos, risk: u8) -> Result<(), error::Error> {
+        if !self.positions.contains(x as i64, y as i64) {
+            return Err(error::Error::General(format!("{:?} is outside the {}x{} board", (x, y), self.width(), self.height())));
+        }
+        if risk > 9 {
+            return Err(error::Error::General(format!("risk level {} is not a single digit", risk)));
+        }
+        self.positions[(x as i64, y as i64)] = risk;
+        self.update_tiles(x, y);
+        Ok(())
+    }
+
+    fn min_risk(&self) -> i32 {
+        self.positions.cells().iter().copied().min().unwrap_or(0) as i32
+    }
+
+    fn risk_map(&self) -> RiskMap<impl Fn(i32, i32) -> i32 + '_> {
+        RiskMap {
+            width: self.width(),
+            height: self.height(),
+            at: move |x, y| self.at(x, y),
+            min_risk: self.min_risk(),
+            movement: self.movement,
+        }
+    }
+
+    fn risk_map_quintupled(&self) -> RiskMap<impl Fn(i32, i32) -> i32 + '_> {
+        RiskMap {
+            width: self.width() * 5,
+            height: self.height() * 5,
+            at: move |x, y| self.at_quintupled(x, y),
+            // wrapped tiles can drop below the base minimum, but never below 1
+            min_risk: self.min_risk().min(1),
+            movement: self.movement,
+        }
+    }
+
+    pub fn lowest_total_risk(&self) -> Result<i32, error::Error> {
+        Ok(self.lowest_risk_path()?.1)
+    }
+
+    pub fn lowest_risk_path(&self) -> Result<(Vec<Pos>, i32), error::Error> {
+        self.lowest_risk_path_with(Strategy::AStar(Heuristic::GridDistance))
+    }
+
+    pub fn lowest_risk_path_with(&self, strategy: Strategy) -> Result<(Vec<Pos>, i32), error::Error> {
+        self.lowest_risk_path_between((0, 0), (self.width() - 1, self.height() - 1), strategy)
+    }
+
+    pub fn lowest_total_risk_between(&self, start: Pos, goal: Pos) -> Result<i32, error::Error> {
+        Ok(self.lowest_risk_path_between(start, goal, Strategy::AStar(Heuristic::GridDistance))?.1)
+    }
+
+    pub fn lowest_risk_path_between(&self, start: Pos, goal: Pos, strategy: Strategy) -> Result<(Vec<Pos>, i32), error::Error> {
+        self.risk_map().find_path(start, goal, strategy)
+    }
+
+    pub fn lowest_total_risk_quintupled(&self) -> Result<i32, error::Error> {
+        Ok(self.lowest_risk_path_quintupled()?.1)
+    }
+
+    pub fn lowest_risk_path_quintupled(&self) -> Result<(Vec<Pos>, i32), error::Error> {
+        self.lowest_risk_path_quintupled_with(Strategy::BucketDijkstra)
+    }
+
+    pub fn lowest_risk_path_quintupled_with(&self, strategy: Strategy) -> Result<(Vec<Pos>, i32), error::Error> {
+        self.lowest_risk_path_quintupled_between((0, 0), (self.width() * 5 - 1, self.height() * 5 - 1), strategy)
+    }
+
+    pub fn planner(&self, start: Pos) -> Result<Planner, error::Error> {
+        Planner::new(self.width(), self.height(), self.positions.cells().to_vec(), start, self.movement)
+    }
 
-        pathfinding::directed::astar::astar(
-            &(0, 0),
-            |&(x, y)| vec![(x, y - 1), (x + 1, y), (x, y + 1), (x - 1, y)].into_iter().map(|p| (p, cost_to(p.0, p.1))),
-            |&(x, y)| (height() - y) + (width() - x),
-            |&p| p.0 == width() - 1 && p.1 == height() - 1,
-        )
-        .unwrap()
-        .1
+    pub fn planner_quintupled(&self, start: Pos) -> Result<Planner, error::Error> {
+        Planner::new(self.width() * 5, self.height() * 5, self.quintupled.cells().to_vec(), start, self.movement)
+    }
+
+    pub fn lowest_risk_path_quintupled_between(&self, start: Pos, goal: Pos, strategy: Strategy) -> Result<(Vec<Pos>, i32), error::Error> {
+        self.risk_map_quintupled().find_path(start, goal, strategy)
     }
 }
 
-#[test]
-fn test_day15() -> Result<(), error::Error> {
-    let board: Board = r#"
+const EXAMPLE: &str = r#"
 1163751742
 1381373672
 2136511328
@@ -104,14 +484,222 @@ fn test_day15() -> Result<(), error::Error> {
 3125421639
 1293138521
 2311944581
+"#;
+
+impl puzzle::Puzzle for Board {
+    fn parse(input: &str) -> Result<Self, error::Error> {
+        input.parse()
+    }
+
+    fn part1(&self) -> Result<puzzle::Answer, error::Error> {
+        Ok(self.lowest_total_risk()?.into())
+    }
+
+    fn part2(&self) -> Result<puzzle::Answer, error::Error> {
+        Ok(self.lowest_total_risk_quintupled()?.into())
+    }
+
+    fn examples() -> Vec<puzzle::Example> {
+        vec![(EXAMPLE, Some("40"), Some("315"))]
+    }
+}
+
+#[test]
+fn test_day15() -> Result<(), error::Error> {
+    let board: Board = EXAMPLE.parse()?;
+    assert_eq!(board.lowest_total_risk()?, 40);
+    assert_eq!(board.lowest_total_risk_quintupled()?, 315);
+
+    let board: Board = std::fs::read_to_string("input_day15")?.parse()?;
+    assert_eq!(board.lowest_total_risk()?, 696);
+    assert_eq!(board.lowest_total_risk_quintupled()?, 2952);
+
+    Ok(())
+}
+
+#[test]
+fn test_day15_path() -> Result<(), error::Error> {
+    let board: Board = EXAMPLE.parse()?;
+    let (path, risk) = board.lowest_risk_path()?;
+    assert_eq!(risk, 40);
+    assert_eq!(path.first(), Some(&(0, 0)));
+    assert_eq!(path.last(), Some(&(9, 9)));
+    assert!(path.windows(2).all(|w| (w[0].0 - w[1].0).abs() + (w[0].1 - w[1].1).abs() == 1));
+    assert_eq!(path[1..].iter().map(|&(x, y)| board.at(x, y)).sum::<i32>(), risk);
+
+    let (path, risk) = board.lowest_risk_path_quintupled()?;
+    assert_eq!(risk, 315);
+    assert_eq!(path.last(), Some(&(49, 49)));
+    assert_eq!(path[1..].iter().map(|&(x, y)| board.at_quintupled(x, y)).sum::<i32>(), risk);
+
+    Ok(())
+}
+
+#[test]
+fn test_day15_strategies() -> Result<(), error::Error> {
+    let board: Board = std::fs::read_to_string("input_day15")?.parse()?;
+    for strategy in [Strategy::AStar(Heuristic::GridDistance), Strategy::AStar(Heuristic::None), Strategy::BucketDijkstra] {
+        let (path, risk) = board.lowest_risk_path_with(strategy)?;
+        assert_eq!(risk, 696);
+        assert_eq!(path[1..].iter().map(|&(x, y)| board.at(x, y)).sum::<i32>(), risk);
+
+        let (path, risk) = board.lowest_risk_path_quintupled_with(strategy)?;
+        assert_eq!(risk, 2952);
+        assert_eq!(path[1..].iter().map(|&(x, y)| board.at_quintupled(x, y)).sum::<i32>(), risk);
+    }
+
+    let board: Board = "0".parse()?;
+    assert_eq!(board.lowest_risk_path_with(Strategy::BucketDijkstra)?, (vec![(0, 0)], 0));
+
+    Ok(())
+}
+
+#[test]
+fn test_day15_heuristic() -> Result<(), error::Error> {
+    // a free corridor along the edges that the old, overestimating heuristic would wander away from
+    let board: Board = r#"
+10000
+19990
+19990
+19990
+00000
 "#
     .parse()?;
-    assert_eq!(board.lowest_total_risk(), 40);
-    assert_eq!(board.lowest_total_risk_quintupled(), 315);
+    for strategy in [
+        Strategy::AStar(Heuristic::GridDistance),
+        Strategy::AStar(Heuristic::None),
+        Strategy::AStar(Heuristic::Custom(|_, _| 0)),
+        Strategy::BucketDijkstra,
+    ] {
+        assert_eq!(board.lowest_risk_path_with(strategy)?.1, 0);
+    }
+    assert_eq!(board.min_risk(), 0);
 
-    let board: Board = std::fs::read_to_string("input_day15")?.parse()?;
-    assert_eq!(board.lowest_total_risk(), 696);
-    assert_eq!(board.lowest_total_risk_quintupled(), 2952);
+    assert!("12\n3".parse::<Board>().is_err());
+    assert!("1a".parse::<Board>().is_err());
+    assert!("".parse::<Board>().is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_day15_between() -> Result<(), error::Error> {
+    let board: Board = EXAMPLE.parse()?;
+    assert_eq!(board.lowest_total_risk_between((0, 0), (9, 9))?, 40);
+    assert_eq!(board.lowest_total_risk_between((3, 3), (3, 3))?, 0);
+    assert_eq!(board.lowest_total_risk_between((0, 0), (1, 0))?, 1);
+    assert_eq!(board.lowest_total_risk_between((1, 0), (0, 0))?, 1);
+
+    // legs through a waypoint cost at least as much as the direct route
+    let direct = board.lowest_total_risk_between((9, 0), (0, 9))?;
+    let legs = board.lowest_total_risk_between((9, 0), (5, 5))? + board.lowest_total_risk_between((5, 5), (0, 9))?;
+    assert!(legs >= direct);
+
+    for strategy in [Strategy::AStar(Heuristic::GridDistance), Strategy::BucketDijkstra] {
+        let (path, risk) = board.lowest_risk_path_between((9, 0), (0, 9), strategy)?;
+        assert_eq!(risk, direct);
+        assert_eq!(path.first(), Some(&(9, 0)));
+        assert_eq!(path.last(), Some(&(0, 9)));
+        assert_eq!(
+            board.lowest_risk_path_quintupled_between((10, 10), (49, 49), strategy)?.1,
+            board.lowest_risk_path_quintupled_between((10, 10), (49, 49), Strategy::AStar(Heuristic::None))?.1
+        );
+    }
+
+    assert!(board.lowest_total_risk_between((0, 0), (10, 0)).is_err());
+    assert!(board.lowest_total_risk_between((-1, 0), (1, 0)).is_err());
+    assert!(board.lowest_risk_path_quintupled_between((0, 0), (49, 49), Strategy::BucketDijkstra).is_ok());
+
+    Ok(())
+}
+
+#[test]
+fn test_day15_movement() -> Result<(), error::Error> {
+    let board: Board = EXAMPLE.parse()?;
+
+    let diagonal = board.clone().with_movement(Movement::Diagonal);
+    let (path, risk) = diagonal.lowest_risk_path()?;
+    assert!(risk < 40);
+    assert!(path.windows(2).all(|w| (w[0].0 - w[1].0).abs().max((w[0].1 - w[1].1).abs()) == 1));
+    assert_eq!(path[1..].iter().map(|&(x, y)| board.at(x, y)).sum::<i32>(), risk);
+    for strategy in [Strategy::AStar(Heuristic::None), Strategy::BucketDijkstra] {
+        assert_eq!(diagonal.lowest_risk_path_with(strategy)?.1, risk);
+    }
+    assert_eq!(
+        diagonal.lowest_total_risk_quintupled()?,
+        diagonal.lowest_risk_path_quintupled_with(Strategy::AStar(Heuristic::GridDistance))?.1
+    );
+
+    let right_or_down = board.clone().with_movement(Movement::Custom(|(x, y)| vec![(x + 1, y), (x, y + 1)]));
+    assert_eq!(right_or_down.lowest_total_risk()?, 40);
+    assert!(right_or_down.lowest_total_risk_between((9, 9), (0, 0)).is_err());
+
+    let knight = board.with_movement(Movement::Custom(|(x, y)| vec![(x + 1, y + 2), (x + 2, y + 1), (x - 1, y + 2), (x + 2, y - 1)]));
+    let (path, _) = knight.lowest_risk_path_with(Strategy::BucketDijkstra)?;
+    assert!(path.windows(2).all(|w| (w[0].0 - w[1].0).abs() * (w[0].1 - w[1].1).abs() == 2));
+
+    Ok(())
+}
+
+#[test]
+fn test_day15_planner() -> Result<(), error::Error> {
+    let input = EXAMPLE;
+    let mut board: Board = input.parse()?;
+    let mut planner = board.planner((0, 0))?;
+    assert_eq!(planner.lowest_total_risk((9, 9))?, 40);
+    assert_eq!(planner.lowest_risk_path((9, 9))?.0.first(), Some(&(0, 0)));
+
+    // pseudo random edits, each cross-checked against a search from scratch
+    let mut seed: u64 = 15;
+    for _ in 0..300 {
+        seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        let (x, y, risk) = ((seed >> 33) as i32 % 10, (seed >> 41) as i32 % 10, (seed >> 49) as u8 % 10);
+        planner.set_risk((x, y), risk)?;
+        board.set_risk((x, y), risk)?;
+        assert_eq!(planner.risk((x, y))?, risk);
+        for goal in [(9, 9), (0, 9), (5, 4)] {
+            let (path, total) = planner.lowest_risk_path(goal)?;
+            assert_eq!(total, board.lowest_risk_path_between((0, 0), goal, Strategy::AStar(Heuristic::None))?.1);
+            assert_eq!(path[1..].iter().map(|&(x, y)| board.at(x, y)).sum::<i32>(), total);
+        }
+    }
+
+    let board: Board = input.parse()?;
+    let mut planner = board.planner_quintupled((0, 0))?;
+    assert_eq!(planner.lowest_total_risk((49, 49))?, 315);
+    planner.set_risk((49, 48), 9)?;
+    planner.set_risk((48, 49), 9)?;
+    assert!(planner.lowest_total_risk((49, 49))? > 315);
+    planner.set_risk((49, 48), 1)?;
+    planner.set_risk((48, 49), 1)?;
+    assert!(planner.lowest_total_risk((49, 49))? <= 315);
+
+    let board = board.with_movement(Movement::Custom(|(x, y)| vec![(x + 1, y), (x, y + 1)]));
+    let mut planner = board.planner((0, 0))?;
+    assert_eq!(planner.lowest_total_risk((9, 9))?, 40);
+    assert!(planner.lowest_total_risk((0, 0)).is_ok());
+    assert!(board.planner((5, 5))?.lowest_total_risk((0, 0)).is_err());
+    assert!(planner.set_risk((10, 0), 1).is_err());
+    assert!(planner.set_risk((0, 0), 10).is_err());
+    assert!(board.planner((-1, 0)).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_day15_quintupled_board() -> Result<(), error::Error> {
+    let board: Board = "8".parse()?;
+    let tiled: Vec<i32> = (0..5).flat_map(|y| (0..5).map(move |x| (x, y))).map(|(x, y)| board.at_quintupled(x, y)).collect();
+    assert_eq!(tiled, vec![8, 9, 1, 2, 3, 9, 1, 2, 3, 4, 1, 2, 3, 4, 5, 2, 3, 4, 5, 6, 3, 4, 5, 6, 7]);
+
+    let mut board: Board = "12\n34".parse()?;
+    assert_eq!(board.at_quintupled(3, 1), 5);
+    assert_eq!(board.at_quintupled(9, 9), 3);
+    board.set_risk((1, 1), 9)?;
+    assert_eq!(board.at(1, 1), 9);
+    assert_eq!(board.at_quintupled(3, 1), 1);
+    assert_eq!(board.at_quintupled(9, 9), 8);
+    assert!(board.set_risk((2, 0), 1).is_err());
 
     Ok(())
 }
@@ -1,8 +1,12 @@
 use crate::error;
+use crate::puzzle;
 
-#[derive(PartialEq, Debug)]
-pub enum TypeId {
-    Literal,
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Operator {
     Sum,
     Product,
     Minimum,
@@ -12,302 +16,1113 @@ pub enum TypeId {
     EqualTo,
 }
 
-impl TypeId {
-    fn from_type_id(type_id: usize) -> Self {
+const LITERAL_TYPE_ID: usize = 4;
+
+impl Operator {
+    fn from_type_id(type_id: usize) -> Option<Self> {
         match type_id {
-            0 => TypeId::Sum,
-            1 => TypeId::Product,
-            2 => TypeId::Minimum,
-            3 => TypeId::Maximum,
-            4 => TypeId::Literal,
-            5 => TypeId::GreaterThan,
-            6 => TypeId::LessThan,
-            7 => TypeId::EqualTo,
-            _ => {
-                panic!("invalid type id: {}", type_id);
-            }
+            0 => Some(Operator::Sum),
+            1 => Some(Operator::Product),
+            2 => Some(Operator::Minimum),
+            3 => Some(Operator::Maximum),
+            5 => Some(Operator::GreaterThan),
+            6 => Some(Operator::LessThan),
+            7 => Some(Operator::EqualTo),
+            _ => None,
+        }
+    }
+
+    fn type_id(&self) -> u64 {
+        match self {
+            Operator::Sum => 0,
+            Operator::Product => 1,
+            Operator::Minimum => 2,
+            Operator::Maximum => 3,
+            Operator::GreaterThan => 5,
+            Operator::LessThan => 6,
+            Operator::EqualTo => 7,
         }
     }
+
+    fn is_comparison(&self) -> bool {
+        matches!(self, Operator::GreaterThan | Operator::LessThan | Operator::EqualTo)
+    }
+
+    pub fn apply(&self, values: &[u64]) -> Result<u64, error::Error> {
+        if values.is_empty() || (self.is_comparison() && values.len() != 2) {
+            return Err(error::Error::General(format!("{:?} cannot be applied to {} values", self, values.len())));
+        }
+
+        let result = match self {
+            Operator::Sum => values.iter().try_fold(0u64, |acc, &v| acc.checked_add(v)).ok_or_else(overflow_error)?,
+            Operator::Product => values.iter().try_fold(1u64, |acc, &v| acc.checked_mul(v)).ok_or_else(overflow_error)?,
+            Operator::Minimum => *values.iter().min().unwrap(),
+            Operator::Maximum => *values.iter().max().unwrap(),
+            Operator::GreaterThan => (values[0] > values[1]) as u64,
+            Operator::LessThan => (values[0] < values[1]) as u64,
+            Operator::EqualTo => (values[0] == values[1]) as u64,
+        };
+
+        Ok(result)
+    }
 }
 
-#[derive(Debug)]
-pub struct Transmission {
-    pub digits: String,
-    left: Vec<char>,
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Payload {
+    Literal(u64),
+    Operator { op: Operator, children: Vec<Packet> },
 }
 
-#[derive(Debug)]
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Packet {
-    pub version: usize,
-    type_id: TypeId,
-    value: usize,
-    num_sub_packet_bits: usize,
-    num_sub_packets: usize,
+    pub version: u8,
+    pub payload: Payload,
 }
 
-impl std::str::FromStr for Transmission {
-    type Err = error::Error;
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum LengthType {
+    // pick whichever header is shorter and still fits
+    Compact,
+    TotalBits,
+    SubPacketCount,
+}
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let digits: String = s
-            .trim_start()
-            .trim_end()
-            .chars()
-            .map(|c| format!("{:04b}", u64::from_str_radix(&format!("{}", c), 16).unwrap()))
-            .collect();
+const MAX_TOTAL_BITS: usize = (1 << 15) - 1;
+const MAX_SUB_PACKETS: usize = (1 << 11) - 1;
+// a few bits are enough for another level of operator, so without a limit a long transmission could
+// nest deep enough to run decoding out of stack. real ones are nowhere near this deep.
+pub const MAX_DEPTH: usize = 256;
 
-        Ok(Transmission {
-            digits: digits.to_string(),
-            left: digits.chars().collect(),
-        })
+#[derive(Default)]
+struct BitWriter {
+    bits: Vec<bool>,
+}
+
+impl BitWriter {
+    fn push(&mut self, value: u64, num_bits: usize) {
+        for i in (0..num_bits).rev() {
+            self.bits.push((value >> i) & 1 == 1);
+        }
+    }
+
+    fn append(&mut self, other: BitWriter) {
+        self.bits.extend(other.bits);
+    }
+
+    fn to_hex(&self) -> String {
+        // transmissions are padded with zeros to a whole number of bytes
+        self.bits
+            .chunks(8)
+            .map(|byte| {
+                let value = byte.iter().enumerate().fold(0u8, |acc, (i, &bit)| acc | ((bit as u8) << (7 - i)));
+                format!("{:02X}", value)
+            })
+            .collect()
     }
 }
 
-fn process_operation(packet: &Packet, value_packets: &Vec<Packet>) -> Packet {
-    let values: Vec<usize> = value_packets.iter().map(|p| p.value).collect();
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum SubPacketLength {
+    TotalBits(usize),
+    Count(usize),
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Span {
+    pub offset: usize,
+    pub bit_len: usize,
+    pub depth: usize,
+    // what the operator header declared, None for literals
+    pub declared: Option<SubPacketLength>,
+    pub sub_packet_bits: usize,
+    pub num_sub_packets: usize,
+}
 
-    let result = match packet.type_id {
-        TypeId::Sum => values.iter().sum(),
-        TypeId::Product => values.iter().product(),
-        TypeId::Minimum => *values.iter().min().unwrap(),
-        TypeId::Maximum => *values.iter().max().unwrap(),
-        TypeId::GreaterThan => {
-            if values[0] > values[1] {
-                1
-            } else {
-                0
+#[derive(Debug)]
+pub struct Diagnostics {
+    pub packet: Packet,
+    // one per packet, in transmission order
+    pub spans: Vec<Span>,
+    pub padding_bits: usize,
+    pub padding_is_zero: bool,
+}
+
+impl Diagnostics {
+    pub fn validate(&self) -> Result<(), error::Error> {
+        for span in &self.spans {
+            match span.declared {
+                Some(SubPacketLength::TotalBits(bits)) if bits != span.sub_packet_bits => {
+                    return Err(error::Error::Parse(format!(
+                        "operator at bit {} declares {} bits of sub-packets but they span {}",
+                        span.offset, bits, span.sub_packet_bits
+                    )));
+                }
+                Some(SubPacketLength::Count(count)) if count != span.num_sub_packets => {
+                    return Err(error::Error::Parse(format!(
+                        "operator at bit {} declares {} sub-packets but has {}",
+                        span.offset, count, span.num_sub_packets
+                    )));
+                }
+                _ => {}
             }
         }
-        TypeId::LessThan => {
-            if values[0] < values[1] {
-                1
-            } else {
-                0
+        if !self.padding_is_zero {
+            return Err(error::Error::Parse(format!("the {} padding bits at the end are not all zero", self.padding_bits)));
+        }
+        Ok(())
+    }
+}
+
+// anything the recursive packet parser can pull bits from
+trait BitSource {
+    fn read_bits(&mut self, num_bits: usize) -> Result<u64, error::Error>;
+
+    fn bit_position(&self) -> usize;
+
+    // a lenient source lets sub-packets overrun their declared length so it can be reported later
+    fn strict(&self) -> bool {
+        true
+    }
+
+    fn record(&mut self, _span: Span) {}
+
+    fn read_operator_children(&mut self, offset: usize, depth: usize) -> Result<(Vec<Packet>, SubPacketLength), error::Error> {
+        let mut children = vec![];
+        let declared = match self.read_bits(1)? {
+            0 => {
+                let num_sub_packet_bits = self.read_bits(15)? as usize;
+                let end = self.bit_position() + num_sub_packet_bits;
+                while self.bit_position() < end {
+                    children.push(self.read_packet_at(depth + 1)?);
+                }
+                if self.strict() && self.bit_position() != end {
+                    return Err(error::Error::Parse(format!(
+                        "sub-packets of the operator at bit {} overran their declared {} bits",
+                        offset, num_sub_packet_bits
+                    )));
+                }
+                SubPacketLength::TotalBits(num_sub_packet_bits)
             }
+            _ => {
+                let num_sub_packets = self.read_bits(11)? as usize;
+                for _ in 0..num_sub_packets {
+                    children.push(self.read_packet_at(depth + 1)?);
+                }
+                SubPacketLength::Count(num_sub_packets)
+            }
+        };
+        if children.is_empty() {
+            return Err(error::Error::Parse(format!("operator at bit {} has no sub-packets", offset)));
         }
-        TypeId::EqualTo => {
-            if values[0] == values[1] {
-                1
-            } else {
-                0
+        Ok((children, declared))
+    }
+
+    fn read_literal(&mut self, offset: usize) -> Result<u64, error::Error> {
+        let mut value: u64 = 0;
+
+        loop {
+            let not_last_bit = self.read_bits(1)?;
+            let group = self.read_bits(4)?;
+            value = value.checked_mul(16).ok_or_else(|| error::Error::Parse(format!("literal at bit {} does not fit in 64 bits", offset)))? | group;
+            if not_last_bit == 0 {
+                break;
             }
         }
-        _ => panic!("invalid operation: {:?}", packet.type_id),
-    };
 
-    let mut num_sub_packet_bits = value_packets.iter().map(|p| p.num_sub_packet_bits).sum::<usize>() + 3 + 3 + 1;
-    if packet.num_sub_packet_bits > 0 {
-        num_sub_packet_bits += 15;
-    } else {
-        num_sub_packet_bits += 11;
+        Ok(value)
     }
 
-    Packet {
-        version: 0,
-        type_id: TypeId::Literal,
-        value: result,
-        num_sub_packet_bits,
-        num_sub_packets: 1,
+    fn read_packet(&mut self) -> Result<Packet, error::Error> {
+        self.read_packet_at(0)
     }
-}
 
-pub fn process_packets(mut packets: Vec<Packet>) -> usize {
-    let mut stack: Vec<Packet> = Vec::new();
+    fn read_packet_at(&mut self, depth: usize) -> Result<Packet, error::Error> {
+        let offset = self.bit_position();
+        if depth > MAX_DEPTH {
+            return Err(error::Error::Parse(format!("packet at bit {} is nested more than {} deep", offset, MAX_DEPTH)));
+        }
+        let version = self.read_bits(3)? as u8;
+        let type_id = self.read_bits(3)? as usize;
 
-    loop {
-        let packet = packets.pop().unwrap();
-        if packet.type_id == TypeId::Literal {
-            stack.push(packet)
+        let (payload, declared) = if type_id == LITERAL_TYPE_ID {
+            (Payload::Literal(self.read_literal(offset)?), None)
         } else {
-            let mut operation_values: Vec<Packet> = Vec::new();
-            loop {
-                if (packet.num_sub_packet_bits > 0 && operation_values.iter().map(|p| p.num_sub_packet_bits).sum::<usize>() == packet.num_sub_packet_bits)
-                    || (packet.num_sub_packets > 0 && operation_values.iter().map(|p| p.num_sub_packets).sum::<usize>() == packet.num_sub_packets)
-                {
-                    break;
-                }
-                operation_values.push(stack.pop().unwrap());
-            }
-            stack.push(process_operation(&packet, &operation_values));
+            let op = Operator::from_type_id(type_id).ok_or_else(|| error::Error::Parse(format!("invalid type id {} at bit {}", type_id, offset + 3)))?;
+            let (children, declared) = self.read_operator_children(offset, depth)?;
+            (Payload::Operator { op, children }, Some(declared))
+        };
+
+        let packet = Packet { version, payload };
+        let bit_len = self.bit_position() - offset;
+        let header_len = match declared {
+            None => bit_len,
+            Some(SubPacketLength::TotalBits(_)) => 6 + 1 + 15,
+            Some(SubPacketLength::Count(_)) => 6 + 1 + 11,
+        };
+        self.record(Span {
+            offset,
+            bit_len,
+            depth,
+            declared,
+            sub_packet_bits: bit_len - header_len,
+            num_sub_packets: packet.children().len(),
+        });
+
+        Ok(packet)
+    }
+}
+
+#[derive(Debug)]
+pub struct BitReader<'a> {
+    bytes: &'a [u8],
+    len: usize,
+    pos: usize,
+}
+
+impl<'a> BitReader<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        BitReader { bytes, len: bytes.len() * 8, pos: 0 }
+    }
+
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.len - self.pos
+    }
+
+    pub fn read(&mut self, num_bits: usize) -> Result<u64, error::Error> {
+        if num_bits > 64 {
+            return Err(error::Error::General(format!("cannot read {} bits at once", num_bits)));
+        }
+        if self.remaining() < num_bits {
+            return Err(error::Error::Parse(format!("transmission ended at bit {} in the middle of a packet", self.len)));
         }
 
-        if packets.is_empty() {
-            break;
+        let mut value: u64 = 0;
+        let mut left = num_bits;
+        while left > 0 {
+            let offset = self.pos % 8;
+            let take = (8 - offset).min(left);
+            let bits = (self.bytes[self.pos / 8] >> (8 - offset - take)) as u64 & ((1 << take) - 1);
+            value = (value << take) | bits;
+            self.pos += take;
+            left -= take;
         }
+
+        Ok(value)
+    }
+
+    pub fn packet(&mut self) -> Result<Packet, error::Error> {
+        self.read_packet()
+    }
+
+    // bits up to the next byte boundary, where the next top-level packet starts
+    fn padding_bits(&self) -> usize {
+        ((8 - self.pos % 8) % 8).min(self.remaining())
+    }
+
+    fn rest_is_zero(&self) -> bool {
+        let mut reader = BitReader { ..*self };
+        while reader.remaining() > 0 {
+            if reader.read(reader.remaining().min(64)).unwrap() != 0 {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+impl BitSource for BitReader<'_> {
+    fn read_bits(&mut self, num_bits: usize) -> Result<u64, error::Error> {
+        self.read(num_bits)
     }
 
-    if stack.len() != 1 {
-        panic!("problem with algorithm");
+    fn bit_position(&self) -> usize {
+        self.pos
     }
+}
 
-    stack[0].value
+#[derive(Debug)]
+pub struct Transmission {
+    bytes: Vec<u8>,
+    len: usize,
+    pos: usize,
+}
+
+impl core::str::FromStr for Transmission {
+    type Err = error::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let nibbles = s
+            .trim()
+            .chars()
+            .map(|c| c.to_digit(16).map(|d| d as u8).ok_or_else(|| error::Error::Parse(format!("invalid hex digit: {:?}", c))))
+            .collect::<Result<Vec<u8>, _>>()?;
+        let bytes = nibbles.chunks(2).map(|pair| pair[0] << 4 | pair.get(1).unwrap_or(&0)).collect();
+
+        Ok(Transmission {
+            bytes,
+            len: nibbles.len() * 4,
+            pos: 0,
+        })
+    }
 }
 
 impl Transmission {
-    fn consume_bits_to_int(&mut self, num_bits: usize) -> Option<usize> {
-        self.consume_bits_to_string(num_bits).map(|binary_string| usize::from_str_radix(&binary_string, 2).unwrap())
+    pub fn from_bytes(bytes: Vec<u8>) -> Self {
+        let len = bytes.len() * 8;
+        Transmission { bytes, len, pos: 0 }
     }
 
-    fn consume_bits_to_string(&mut self, num_bits: usize) -> Option<String> {
-        if self.left.len() < num_bits {
-            return None;
+    pub fn concat<I: IntoIterator<Item = Transmission>>(transmissions: I) -> Self {
+        let mut result = Transmission::from_bytes(vec![]);
+        for transmission in transmissions {
+            result.append(transmission);
         }
+        result
+    }
 
-        let substring = self.left.drain(0..num_bits).collect();
+    // appends another transmission, starting it on the next byte boundary
+    pub fn append(&mut self, other: Transmission) {
+        self.len = self.bytes.len() * 8 + other.len;
+        self.bytes.extend(other.bytes);
+    }
 
-        Some(substring)
+    // the transmission as '0'/'1' characters, mostly for debugging
+    pub fn digits(&self) -> String {
+        let mut reader = BitReader {
+            bytes: &self.bytes,
+            len: self.len,
+            pos: 0,
+        };
+        (0..self.len).map(|_| if reader.read(1).unwrap() == 1 { '1' } else { '0' }).collect()
     }
 
-    fn consume_packet_type_operator(&mut self, packet: &mut Packet) {
-        let length_type_id = self.consume_bits_to_int(1).unwrap();
-        match length_type_id {
-            0 => {
-                packet.num_sub_packet_bits = self.consume_bits_to_int(15).unwrap();
-            }
-            1 => {
-                packet.num_sub_packets = self.consume_bits_to_int(11).unwrap();
-            }
-            _ => {
-                panic!("invalid length type id: {}", length_type_id);
-            }
+    fn reader(&self) -> BitReader<'_> {
+        BitReader {
+            bytes: &self.bytes,
+            len: self.len,
+            pos: self.pos,
         }
     }
 
-    fn consume_packet_type_literal(&mut self, packet: &mut Packet) {
-        let mut binary_string = String::new();
+    pub fn packet(&mut self) -> Result<Packet, error::Error> {
+        let mut reader = self.reader();
+        let packet = reader.packet()?;
+        self.pos = reader.pos;
+        Ok(packet)
+    }
 
+    // all remaining top-level packets, each padded to a byte boundary, until only zeros are left
+    pub fn packets(&mut self) -> Result<Vec<Packet>, error::Error> {
+        let mut packets = vec![];
         loop {
-            let not_last_bit = self.consume_bits_to_int(1).unwrap();
-            binary_string += &self.consume_bits_to_string(4).unwrap();
-            if not_last_bit == 0 {
+            let mut reader = self.reader();
+            reader.pos += reader.padding_bits();
+            if reader.rest_is_zero() {
+                self.pos = self.len;
                 break;
             }
+            packets.push(reader.packet()?);
+            self.pos = reader.pos;
+        }
+        Ok(packets)
+    }
+
+    // decodes the next packet without consuming it, keeping track of where every packet sits
+    pub fn diagnose(&self) -> Result<Diagnostics, error::Error> {
+        let mut recorder = Recorder { reader: self.reader(), spans: vec![] };
+        let packet = recorder.read_packet()?;
+        // spans are recorded as packets complete, children before parents
+        let mut spans = recorder.spans;
+        spans.sort_by_key(|span| span.offset);
+
+        // the padding runs to the next byte boundary, or to the end if nothing follows
+        let mut reader = recorder.reader;
+        let padding_bits = if reader.rest_is_zero() { reader.remaining() } else { reader.padding_bits() };
+        let padding_is_zero = reader.read(padding_bits.min(64))? == 0;
+
+        Ok(Diagnostics {
+            packet,
+            spans,
+            padding_bits,
+            padding_is_zero,
+        })
+    }
+}
+
+struct Recorder<'a> {
+    reader: BitReader<'a>,
+    spans: Vec<Span>,
+}
+
+impl BitSource for Recorder<'_> {
+    fn read_bits(&mut self, num_bits: usize) -> Result<u64, error::Error> {
+        self.reader.read(num_bits)
+    }
+
+    fn bit_position(&self) -> usize {
+        self.reader.pos
+    }
+
+    fn strict(&self) -> bool {
+        false
+    }
+
+    fn record(&mut self, span: Span) {
+        self.spans.push(span);
+    }
+}
+
+// packets straight from a reader, as the hex comes in. readers are std::io, so this needs std.
+#[cfg(feature = "std")]
+pub struct Decoder<R> {
+    input: R,
+    buffer: u64,
+    buffered: usize,
+    pos: usize,
+    eof: bool,
+    nonzero: bool,
+    done: bool,
+}
+
+#[cfg(feature = "std")]
+impl<R: std::io::Read> Decoder<std::io::BufReader<R>> {
+    pub fn from_reader(reader: R) -> Self {
+        Decoder::new(std::io::BufReader::new(reader))
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: std::io::BufRead> Decoder<R> {
+    pub fn new(input: R) -> Self {
+        Decoder {
+            input,
+            buffer: 0,
+            buffered: 0,
+            pos: 0,
+            eof: false,
+            nonzero: false,
+            done: false,
+        }
+    }
+
+    fn next_nibble(&mut self) -> Result<Option<u64>, error::Error> {
+        loop {
+            let c = match self.input.fill_buf()?.first() {
+                Some(&c) => c,
+                None => return Ok(None),
+            };
+            self.input.consume(1);
+            if c.is_ascii_whitespace() {
+                continue;
+            }
+            return match (c as char).to_digit(16) {
+                Some(nibble) => Ok(Some(nibble as u64)),
+                None => Err(error::Error::Parse(format!("invalid hex digit: {:?}", c as char))),
+            };
+        }
+    }
+
+    fn rest_is_zero(&mut self) -> bool {
+        if self.buffer != 0 {
+            return false;
+        }
+        loop {
+            match self.next_nibble() {
+                Ok(Some(0)) => continue,
+                Ok(None) => return true,
+                _ => return false,
+            }
         }
+    }
+}
 
-        packet.num_sub_packets = 1;
-        packet.num_sub_packet_bits = ((binary_string.len() / 4) * 5) + 6;
-        packet.value = usize::from_str_radix(&binary_string, 2).unwrap();
+#[cfg(feature = "std")]
+impl<R: std::io::BufRead> BitSource for Decoder<R> {
+    fn read_bits(&mut self, num_bits: usize) -> Result<u64, error::Error> {
+        if num_bits > 60 {
+            return Err(error::Error::General(format!("cannot read {} bits at once", num_bits)));
+        }
+        while self.buffered < num_bits {
+            match self.next_nibble()? {
+                Some(nibble) => {
+                    self.buffer = self.buffer << 4 | nibble;
+                    self.buffered += 4;
+                }
+                None => {
+                    self.eof = true;
+                    return Err(error::Error::Parse(format!("transmission ended at bit {} in the middle of a packet", self.pos + self.buffered)));
+                }
+            }
+        }
+
+        self.buffered -= num_bits;
+        let value = self.buffer >> self.buffered;
+        self.buffer &= (1 << self.buffered) - 1;
+        self.pos += num_bits;
+        self.nonzero |= value != 0;
+
+        Ok(value)
+    }
+
+    fn bit_position(&self) -> usize {
+        self.pos
     }
 }
 
-impl Iterator for Transmission {
-    type Item = Packet;
+#[cfg(feature = "std")]
+impl<R: std::io::BufRead> Iterator for Decoder<R> {
+    type Item = Result<Packet, error::Error>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.left.len() < 8 {
+        if self.done {
             return None;
         }
 
-        let version = self.consume_bits_to_int(3)?;
-        let type_id = self.consume_bits_to_int(3)?;
+        // every top-level packet is padded with zeros to a byte boundary
+        let padding = (8 - self.pos % 8) % 8;
+        if let Err(e) = self.read_bits(padding) {
+            self.done = true;
+            return if self.eof { None } else { Some(Err(e)) };
+        }
+
+        // a run of zeros up to the end of input is trailing padding, not a packet
+        self.nonzero = false;
+        match self.read_packet() {
+            Ok(packet) => Some(Ok(packet)),
+            Err(e) => {
+                self.done = true;
+                if !self.nonzero && self.rest_is_zero() {
+                    None
+                } else {
+                    Some(Err(e))
+                }
+            }
+        }
+    }
+}
+
+impl core::str::FromStr for Packet {
+    type Err = error::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse::<Transmission>()?.packet()
+    }
+}
 
-        let mut packet = Packet {
+fn overflow_error() -> error::Error {
+    error::Error::General("packet value overflow".to_string())
+}
+
+impl Packet {
+    pub fn literal(version: u8, value: u64) -> Result<Packet, error::Error> {
+        Self::check_version(version)?;
+        Ok(Packet {
             version,
-            type_id: TypeId::from_type_id(type_id),
-            value: 0,
-            num_sub_packet_bits: 0,
-            num_sub_packets: 0,
-        };
+            payload: Payload::Literal(value),
+        })
+    }
+
+    pub fn operator(version: u8, op: Operator, children: Vec<Packet>) -> Result<Packet, error::Error> {
+        Self::check_version(version)?;
+        if children.is_empty() || (op.is_comparison() && children.len() != 2) {
+            return Err(error::Error::General(format!("{:?} cannot have {} sub-packets", op, children.len())));
+        }
+        Ok(Packet {
+            version,
+            payload: Payload::Operator { op, children },
+        })
+    }
 
-        match packet.type_id {
-            TypeId::Literal => self.consume_packet_type_literal(&mut packet),
-            _ => self.consume_packet_type_operator(&mut packet),
+    fn check_version(version: u8) -> Result<(), error::Error> {
+        if version > 7 {
+            return Err(error::Error::General(format!("version does not fit in 3 bits: {}", version)));
         }
+        Ok(())
+    }
+
+    pub fn to_hex(&self) -> Result<String, error::Error> {
+        self.to_hex_with(LengthType::Compact)
+    }
+
+    pub fn to_hex_with(&self, length_type: LengthType) -> Result<String, error::Error> {
+        let mut writer = BitWriter::default();
+        self.encode(length_type, &mut writer)?;
+        Ok(writer.to_hex())
+    }
+
+    fn encode(&self, length_type: LengthType, writer: &mut BitWriter) -> Result<(), error::Error> {
+        Self::check_version(self.version)?;
+        writer.push(self.version as u64, 3);
+        match &self.payload {
+            Payload::Literal(value) => {
+                writer.push(LITERAL_TYPE_ID as u64, 3);
+                let num_groups = (64 - value.leading_zeros() as usize).div_ceil(4);
+                for group in (0..num_groups.max(1)).rev() {
+                    writer.push((group > 0) as u64, 1);
+                    writer.push((value >> (group * 4)) & 0xf, 4);
+                }
+            }
+            Payload::Operator { op, children } => {
+                writer.push(op.type_id(), 3);
+                let mut sub_packets = BitWriter::default();
+                for child in children {
+                    child.encode(length_type, &mut sub_packets)?;
+                }
+                let fits_count = children.len() <= MAX_SUB_PACKETS;
+                let fits_bits = sub_packets.bits.len() <= MAX_TOTAL_BITS;
+                let use_count = match length_type {
+                    LengthType::Compact => fits_count,
+                    LengthType::TotalBits => false,
+                    LengthType::SubPacketCount => true,
+                };
+                if use_count && fits_count {
+                    writer.push(1, 1);
+                    writer.push(children.len() as u64, 11);
+                } else if !use_count && fits_bits {
+                    writer.push(0, 1);
+                    writer.push(sub_packets.bits.len() as u64, 15);
+                } else {
+                    return Err(error::Error::General(format!(
+                        "{} sub-packets in {} bits cannot be encoded as {:?}",
+                        children.len(),
+                        sub_packets.bits.len(),
+                        length_type
+                    )));
+                }
+                writer.append(sub_packets);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn children(&self) -> &[Packet] {
+        match &self.payload {
+            Payload::Literal(_) => &[],
+            Payload::Operator { children, .. } => children,
+        }
+    }
+
+    // calls f on every packet, parents before children, with its depth below self
+    pub fn visit<F: FnMut(&Packet, usize)>(&self, mut f: F) {
+        self.visit_at(0, &mut f);
+    }
+
+    fn visit_at<F: FnMut(&Packet, usize)>(&self, depth: usize, f: &mut F) {
+        f(self, depth);
+        for child in self.children() {
+            child.visit_at(depth + 1, f);
+        }
+    }
+
+    // bottom-up: f gets each packet together with the results of its children
+    pub fn fold<T, F: FnMut(&Packet, Vec<T>) -> T>(&self, mut f: F) -> T {
+        self.fold_with(&mut f)
+    }
+
+    fn fold_with<T, F: FnMut(&Packet, Vec<T>) -> T>(&self, f: &mut F) -> T {
+        let results = self.children().iter().map(|child| child.fold_with(f)).collect();
+        f(self, results)
+    }
+
+    pub fn version_sum(&self) -> u64 {
+        self.fold(|packet, sums: Vec<u64>| packet.version as u64 + sums.iter().sum::<u64>())
+    }
+
+    pub fn num_packets(&self) -> usize {
+        self.fold(|_, counts: Vec<usize>| 1 + counts.iter().sum::<usize>())
+    }
+
+    pub fn max_depth(&self) -> usize {
+        self.fold(|_, depths: Vec<usize>| depths.into_iter().max().map_or(0, |depth| depth + 1))
+    }
+
+    pub fn value(&self) -> Result<u64, error::Error> {
+        self.fold(|packet, values: Vec<Result<u64, error::Error>>| match &packet.payload {
+            Payload::Literal(value) => Ok(*value),
+            Payload::Operator { op, .. } => op.apply(&values.into_iter().collect::<Result<Vec<u64>, _>>()?),
+        })
+    }
+}
+
+impl puzzle::Puzzle for Packet {
+    fn parse(input: &str) -> Result<Self, error::Error> {
+        input.parse()
+    }
+
+    fn part1(&self) -> Result<puzzle::Answer, error::Error> {
+        Ok(self.version_sum().into())
+    }
+
+    fn part2(&self) -> Result<puzzle::Answer, error::Error> {
+        Ok(self.value()?.into())
+    }
 
-        Some(packet)
+    fn examples() -> Vec<puzzle::Example> {
+        // each example in the text only gives one of the answers
+        vec![
+            ("8A004A801A8002F478", Some("16"), None),
+            ("620080001611562C8802118E34", Some("12"), None),
+            ("C0015000016115A2E0802F182340", Some("23"), None),
+            ("A0016C880162017C3686B18A3D4780", Some("31"), None),
+            ("C200B40A82", None, Some("3")),
+            ("04005AC33890", None, Some("54")),
+            ("880086C3E88112", None, Some("7")),
+            ("CE00C43D881120", None, Some("9")),
+            ("D8005AC2A8F0", None, Some("1")),
+            ("F600BC2D8F", None, Some("0")),
+            ("9C005AC2F8F0", None, Some("0")),
+            ("9C0141080250320F1802104A08", None, Some("1")),
+        ]
     }
 }
 
 #[test]
-fn test_day16_utils() {
+fn test_day16_utils() -> Result<(), error::Error> {
     assert_eq!(format!("{:04b}", 7), "0111");
+
+    let bytes = [0b1010_1100, 0b0101_1111, 0xff];
+    let mut reader = BitReader::new(&bytes);
+    assert_eq!(reader.read(3)?, 0b101);
+    assert_eq!(reader.read(7)?, 0b011_0001);
+    assert_eq!(reader.position(), 10);
+    assert_eq!(reader.read(14)?, 0b01_1111_1111_1111);
+    assert_eq!(reader.remaining(), 0);
+    assert!(reader.read(1).is_err());
+    assert_eq!(BitReader::new(&[0xff; 9]).read(64)?, u64::MAX);
+
+    let transmission: Transmission = "a7f".parse()?;
+    assert_eq!(transmission.digits(), "101001111111");
+    let mut transmission = Transmission::from_bytes(vec![0xD2, 0xFE, 0x28]);
+    assert_eq!(transmission.packet()?, Packet::literal(6, 2021)?);
+
+    Ok(())
 }
 
 #[test]
 fn test_day16_part1() -> Result<(), error::Error> {
-    let transmission: Transmission = "D2FE28".parse()?;
-    assert_eq!(transmission.digits, "110100101111111000101000");
-    let packets: Vec<Packet> = transmission.collect();
-    assert_eq!(packets.len(), 1);
-    assert_eq!(packets[0].version, 6);
-    assert_eq!(packets[0].type_id, TypeId::Literal);
-    assert_eq!(packets[0].value, 2021);
+    let mut transmission: Transmission = "D2FE28".parse()?;
+    assert_eq!(transmission.digits(), "110100101111111000101000");
+    let packet = transmission.packet()?;
+    assert_eq!(packet.num_packets(), 1);
+    assert_eq!(packet.version, 6);
+    assert_eq!(packet.payload, Payload::Literal(2021));
 
-    let transmission: Transmission = "38006F45291200".parse()?;
-    assert_eq!(transmission.digits, "00111000000000000110111101000101001010010001001000000000");
-    let packets: Vec<Packet> = transmission.collect();
-    assert_eq!(packets.len(), 3);
-
-    let transmission: Transmission = "EE00D40C823060".parse()?;
-    assert_eq!(transmission.digits, "11101110000000001101010000001100100000100011000001100000");
-    let packets: Vec<Packet> = transmission.collect();
-    assert_eq!(packets.len(), 4);
-
-    let transmission: Transmission = "8A004A801A8002F478".parse()?;
-    let packets: Vec<Packet> = transmission.collect();
-    assert_eq!(packets.len(), 4);
-    assert_eq!(packets.iter().map(|p| p.version).sum::<usize>(), 16);
-
-    let transmission: Transmission = "620080001611562C8802118E34".parse()?;
-    let packets: Vec<Packet> = transmission.collect();
-    assert_eq!(packets.len(), 7);
-    assert_eq!(packets.iter().map(|p| p.version).sum::<usize>(), 12);
-
-    let transmission: Transmission = "C0015000016115A2E0802F182340".parse()?;
-    let packets: Vec<Packet> = transmission.collect();
-    assert_eq!(packets.len(), 7);
-    assert_eq!(packets.iter().map(|p| p.version).sum::<usize>(), 23);
-
-    let transmission: Transmission = "A0016C880162017C3686B18A3D4780".parse()?;
-    let packets: Vec<Packet> = transmission.collect();
-    assert_eq!(packets.len(), 8);
-    assert_eq!(packets.iter().map(|p| p.version).sum::<usize>(), 31);
+    let mut transmission: Transmission = "38006F45291200".parse()?;
+    assert_eq!(transmission.digits(), "00111000000000000110111101000101001010010001001000000000");
+    let packet = transmission.packet()?;
+    assert_eq!(packet.num_packets(), 3);
+    assert_eq!(
+        packet.payload,
+        Payload::Operator {
+            op: Operator::LessThan,
+            children: vec![
+                Packet {
+                    version: 6,
+                    payload: Payload::Literal(10)
+                },
+                Packet {
+                    version: 2,
+                    payload: Payload::Literal(20)
+                }
+            ],
+        }
+    );
 
-    let transmission: Transmission = std::fs::read_to_string("input_day16")?.parse()?;
-    let packets: Vec<Packet> = transmission.collect();
-    assert_eq!(packets.len(), 268);
-    assert_eq!(packets.iter().map(|p| p.version).sum::<usize>(), 999);
+    let mut transmission: Transmission = "EE00D40C823060".parse()?;
+    assert_eq!(transmission.digits(), "11101110000000001101010000001100100000100011000001100000");
+    let packet = transmission.packet()?;
+    assert_eq!(packet.num_packets(), 4);
+
+    let packet: Packet = "8A004A801A8002F478".parse()?;
+    assert_eq!(packet.num_packets(), 4);
+    assert_eq!(packet.version_sum(), 16);
+
+    let packet: Packet = "620080001611562C8802118E34".parse()?;
+    assert_eq!(packet.num_packets(), 7);
+    assert_eq!(packet.version_sum(), 12);
+
+    let packet: Packet = "C0015000016115A2E0802F182340".parse()?;
+    assert_eq!(packet.num_packets(), 7);
+    assert_eq!(packet.version_sum(), 23);
+
+    let packet: Packet = "A0016C880162017C3686B18A3D4780".parse()?;
+    assert_eq!(packet.num_packets(), 8);
+    assert_eq!(packet.version_sum(), 31);
+
+    let packet: Packet = std::fs::read_to_string("input_day16")?.parse()?;
+    assert_eq!(packet.num_packets(), 268);
+    assert_eq!(packet.version_sum(), 999);
 
     Ok(())
 }
 
 #[test]
 fn test_day16_part2() -> Result<(), error::Error> {
-    let transmission: Transmission = "D2FE28".parse()?;
-    assert_eq!(process_packets(transmission.collect()), 2021);
+    let packet: Packet = "D2FE28".parse()?;
+    assert_eq!(packet.value()?, 2021);
+
+    let packet: Packet = "EE00D40C823060".parse()?;
+    assert_eq!(packet.value()?, 3);
+
+    let packet: Packet = "620080001611562C8802118E34".parse()?;
+    assert_eq!(packet.value()?, 46);
+
+    let packet: Packet = "C200B40A82".parse()?;
+    assert_eq!(packet.value()?, 3);
+
+    let packet: Packet = "04005AC33890".parse()?;
+    assert_eq!(packet.value()?, 54);
+
+    let packet: Packet = "880086C3E88112".parse()?;
+    assert_eq!(packet.value()?, 7);
+
+    let packet: Packet = "CE00C43D881120".parse()?;
+    assert_eq!(packet.value()?, 9);
+
+    let packet: Packet = "D8005AC2A8F0".parse()?;
+    assert_eq!(packet.value()?, 1);
+
+    let packet: Packet = "F600BC2D8F".parse()?;
+    assert_eq!(packet.value()?, 0);
+
+    let packet: Packet = "9C005AC2F8F0".parse()?;
+    assert_eq!(packet.value()?, 0);
+
+    let packet: Packet = "9C0141080250320F1802104A08".parse()?;
+    assert_eq!(packet.value()?, 1);
+
+    let packet: Packet = std::fs::read_to_string("input_day16")?.parse()?;
+    assert_eq!(packet.value()?, 3408662834145);
+
+    Ok(())
+}
+
+#[test]
+fn test_day16_errors() -> Result<(), error::Error> {
+    assert!("D2FE2".parse::<Packet>().is_err());
+    assert!("XYZ".parse::<Packet>().is_err());
+    assert!("".parse::<Packet>().is_err());
+    // operator with a single sub-packet cannot be compared
+    let packet = Packet {
+        version: 0,
+        payload: Payload::Operator {
+            op: Operator::EqualTo,
+            children: vec![Packet {
+                version: 0,
+                payload: Payload::Literal(1),
+            }],
+        },
+    };
+    assert!(packet.value().is_err());
+
+    let mut nested = Packet::literal(0, 1)?;
+    for _ in 0..MAX_DEPTH {
+        nested = Packet::operator(0, Operator::Sum, vec![nested])?;
+    }
+    assert_eq!(nested.to_hex()?.parse::<Packet>()?.value()?, 1);
+    let too_deep = Packet::operator(0, Operator::Sum, vec![nested])?;
+    assert_eq!(
+        too_deep.to_hex()?.parse::<Packet>(),
+        Err(error::Error::Parse(format!("packet at bit {} is nested more than {} deep", 18 * (MAX_DEPTH + 1), MAX_DEPTH)))
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_day16_encode() -> Result<(), error::Error> {
+    assert_eq!(Packet::literal(6, 2021)?.to_hex()?, "D2FE28");
+    assert_eq!("38006F45291200".parse::<Packet>()?.to_hex_with(LengthType::TotalBits)?, "38006F45291200");
+    assert_eq!("EE00D40C823060".parse::<Packet>()?.to_hex_with(LengthType::SubPacketCount)?, "EE00D40C823060");
+
+    for hex in ["8A004A801A8002F478", "620080001611562C8802118E34", "9C0141080250320F1802104A08"] {
+        let packet: Packet = hex.parse()?;
+        for length_type in [LengthType::Compact, LengthType::TotalBits, LengthType::SubPacketCount] {
+            assert_eq!(packet.to_hex_with(length_type)?.parse::<Packet>()?, packet);
+        }
+    }
+    let packet: Packet = std::fs::read_to_string("input_day16")?.parse()?;
+    assert_eq!(packet.to_hex()?.parse::<Packet>()?, packet);
 
-    let transmission: Transmission = "EE00D40C823060".parse()?;
-    assert_eq!(process_packets(transmission.collect()), 3);
+    let expression = Packet::operator(
+        1,
+        Operator::Sum,
+        vec![
+            Packet::literal(2, 0)?,
+            Packet::operator(3, Operator::Product, vec![Packet::literal(4, 6)?, Packet::literal(5, u64::MAX / 6)?])?,
+        ],
+    )?;
+    let decoded: Packet = expression.to_hex()?.parse()?;
+    assert_eq!(decoded, expression);
+    assert_eq!(decoded.value()?, u64::MAX / 6 * 6);
+    assert_eq!(decoded.version_sum(), 15);
 
-    let transmission: Transmission = "620080001611562C8802118E34".parse()?;
-    assert_eq!(process_packets(transmission.collect()), 46);
+    assert!(Packet::literal(8, 1).is_err());
+    assert!(Packet::operator(0, Operator::LessThan, vec![Packet::literal(0, 1)?]).is_err());
+    assert!(Packet::operator(0, Operator::Maximum, vec![]).is_err());
+    let wide = Packet::operator(0, Operator::Sum, vec![Packet::literal(0, 1)?; MAX_SUB_PACKETS + 1])?;
+    assert!(wide.to_hex_with(LengthType::SubPacketCount).is_err());
+    assert_eq!(wide.to_hex()?.parse::<Packet>()?.value()?, MAX_SUB_PACKETS as u64 + 1);
 
-    let transmission: Transmission = "C200B40A82".parse()?;
-    assert_eq!(process_packets(transmission.collect()), 3);
+    Ok(())
+}
 
-    let transmission: Transmission = "04005AC33890".parse()?;
-    assert_eq!(process_packets(transmission.collect()), 54);
+#[test]
+fn test_day16_decoder() -> Result<(), error::Error> {
+    let packets = Decoder::from_reader(std::fs::File::open("input_day16")?).collect::<Result<Vec<Packet>, _>>()?;
+    assert_eq!(packets, vec![std::fs::read_to_string("input_day16")?.parse::<Packet>()?]);
 
-    let transmission: Transmission = "880086C3E88112".parse()?;
-    assert_eq!(process_packets(transmission.collect()), 7);
+    let expected: Vec<Packet> = ["D2FE28", "38006F45291200", "EE00D40C823060", "9C0141080250320F1802104A08"]
+        .iter()
+        .map(|hex| hex.parse())
+        .collect::<Result<_, _>>()?;
+    let input = expected.iter().map(Packet::to_hex).collect::<Result<Vec<String>, _>>()?.join("\n") + "0000\n";
+    let packets = Decoder::new(input.as_bytes()).collect::<Result<Vec<Packet>, _>>()?;
+    assert_eq!(packets, expected);
 
-    let transmission: Transmission = "CE00C43D881120".parse()?;
-    assert_eq!(process_packets(transmission.collect()), 9);
+    let mut decoder = Decoder::new("D2FE28D2FE".as_bytes());
+    assert_eq!(decoder.next().transpose()?, Some(Packet::literal(6, 2021)?));
+    assert!(decoder.next().unwrap().is_err());
+    assert!(decoder.next().is_none());
 
-    let transmission: Transmission = "D8005AC2A8F0".parse()?;
-    assert_eq!(process_packets(transmission.collect()), 1);
+    assert!(Decoder::new("D2FE28XX".as_bytes()).nth(1).unwrap().is_err());
+    assert!(Decoder::new("".as_bytes()).next().is_none());
+
+    Ok(())
+}
+
+#[test]
+fn test_day16_visit() -> Result<(), error::Error> {
+    let packet: Packet = "9C0141080250320F1802104A08".parse()?;
+    assert_eq!(packet.max_depth(), 2);
+    assert_eq!(Packet::literal(0, 1)?.max_depth(), 0);
+
+    let mut literals = vec![];
+    let mut deepest = 0;
+    packet.visit(|p, depth| {
+        if let Payload::Literal(value) = p.payload {
+            literals.push(value);
+        }
+        deepest = deepest.max(depth);
+    });
+    assert_eq!(literals, vec![1, 3, 2, 2]);
+    assert_eq!(deepest, packet.max_depth());
+
+    // version sum, value and depth in a single traversal
+    let packet: Packet = std::fs::read_to_string("input_day16")?.parse()?;
+    let (version_sum, value, depth) = packet.fold(|p, children: Vec<(u64, Result<u64, error::Error>, usize)>| {
+        let version_sum = p.version as u64 + children.iter().map(|c| c.0).sum::<u64>();
+        let depth = children.iter().map(|c| c.2 + 1).max().unwrap_or(0);
+        let value = match &p.payload {
+            Payload::Literal(value) => Ok(*value),
+            Payload::Operator { op, .. } => children.into_iter().map(|c| c.1).collect::<Result<Vec<u64>, _>>().and_then(|values| op.apply(&values)),
+        };
+        (version_sum, value, depth)
+    });
+    assert_eq!(version_sum, 999);
+    assert_eq!(value?, 3408662834145);
+    assert_eq!(depth, packet.max_depth());
 
-    let transmission: Transmission = "F600BC2D8F".parse()?;
-    assert_eq!(process_packets(transmission.collect()), 0);
+    Ok(())
+}
 
-    let transmission: Transmission = "9C005AC2F8F0".parse()?;
-    assert_eq!(process_packets(transmission.collect()), 0);
+#[test]
+fn test_day16_diagnostics() -> Result<(), error::Error> {
+    let transmission: Transmission = "38006F45291200".parse()?;
+    let diagnostics = transmission.diagnose()?;
+    assert_eq!(
+        diagnostics.spans,
+        vec![
+            Span {
+                offset: 0,
+                bit_len: 49,
+                depth: 0,
+                declared: Some(SubPacketLength::TotalBits(27)),
+                sub_packet_bits: 27,
+                num_sub_packets: 2,
+            },
+            Span {
+                offset: 22,
+                bit_len: 11,
+                depth: 1,
+                declared: None,
+                sub_packet_bits: 0,
+                num_sub_packets: 0,
+            },
+            Span {
+                offset: 33,
+                bit_len: 16,
+                depth: 1,
+                declared: None,
+                sub_packet_bits: 0,
+                num_sub_packets: 0,
+            },
+        ]
+    );
+    assert_eq!(diagnostics.padding_bits, 7);
+    diagnostics.validate()?;
 
-    let transmission: Transmission = "9C0141080250320F1802104A08".parse()?;
-    assert_eq!(process_packets(transmission.collect()), 1);
+    let diagnostics = "EE00D40C823060".parse::<Transmission>()?.diagnose()?;
+    assert_eq!(diagnostics.spans[0].declared, Some(SubPacketLength::Count(3)));
+    assert_eq!(diagnostics.spans.len(), diagnostics.packet.num_packets());
+    diagnostics.validate()?;
 
     let transmission: Transmission = std::fs::read_to_string("input_day16")?.parse()?;
-    assert_eq!(process_packets(transmission.collect()), 3408662834145);
+    let diagnostics = transmission.diagnose()?;
+    assert_eq!(diagnostics.spans.len(), 268);
+    assert_eq!(diagnostics.spans[0].bit_len + diagnostics.padding_bits, transmission.digits().len());
+    diagnostics.validate()?;
+
+    // the operator declares 26 bits but its sub-packets take 27
+    assert!("38006B45291200".parse::<Packet>().is_err());
+    let diagnostics = "38006B45291200".parse::<Transmission>()?.diagnose()?;
+    assert_eq!(diagnostics.spans[0].declared, Some(SubPacketLength::TotalBits(26)));
+    assert_eq!(diagnostics.spans[0].sub_packet_bits, 27);
+    assert!(diagnostics.validate().is_err());
+
+    let diagnostics = "D2FE29".parse::<Transmission>()?.diagnose()?;
+    assert_eq!((diagnostics.padding_bits, diagnostics.padding_is_zero), (3, false));
+    assert!(diagnostics.validate().is_err());
+
+    assert_eq!("D2FE".parse::<Packet>(), Err(error::Error::Parse("transmission ended at bit 16 in the middle of a packet".to_string())));
+
+    Ok(())
+}
+
+#[test]
+fn test_day16_multiple_packets() -> Result<(), error::Error> {
+    let mut transmission: Transmission = std::fs::read_to_string("input_day16")?.parse()?;
+    let packets = transmission.packets()?;
+    assert_eq!(packets.len(), 1);
+    assert_eq!(packets[0].version_sum(), 999);
+    assert!(transmission.packets()?.is_empty());
+
+    let hex = ["102", "D2FE28", "38006F45291200", "EE00D40C823060"];
+    let expected: Vec<Packet> = hex.iter().map(|hex| hex.parse()).collect::<Result<_, _>>()?;
+    assert_eq!(expected[0], Packet::literal(0, 1)?);
+
+    let mut transmission = Transmission::concat(hex.iter().map(|hex| hex.parse()).collect::<Result<Vec<Transmission>, _>>()?);
+    assert_eq!(transmission.packets()?, expected);
+
+    let mut transmission: Transmission = hex[1..].concat().parse()?;
+    assert_eq!(transmission.packets()?, expected[1..]);
+
+    let mut transmission: Transmission = "D2FE28".parse()?;
+    assert_eq!(transmission.packet()?, expected[1]);
+    transmission.append("102".parse()?);
+    assert_eq!(transmission.packets()?, expected[..1]);
+
+    let diagnostics = "D2FE28102".parse::<Transmission>()?.diagnose()?;
+    assert_eq!(diagnostics.padding_bits, 3);
+    diagnostics.validate()?;
+
+    assert!("D2FE28D2FE".parse::<Transmission>()?.packets().is_err());
 
     Ok(())
 }
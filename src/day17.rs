@@ -1,83 +1,243 @@
 use crate::error;
+use crate::parse;
+use crate::puzzle;
 
-use scan_fmt;
+use crate::par::*;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
 
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Force {
+    // constant velocity
+    None,
+    // velocity moves one step towards zero
+    Drag,
+    // velocity decreases by one every step
+    Gravity,
+}
+
+impl Force {
+    fn next_velocity(&self, velocity: i64) -> i64 {
+        match self {
+            Force::None => velocity,
+            Force::Drag => velocity - velocity.signum(),
+            Force::Gravity => velocity - 1,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TargetArea {
     x_begin: i64,
     x_end: i64,
     y_begin: i64,
     y_end: i64,
+    z_begin: i64,
+    z_end: i64,
+    is_3d: bool,
+    forces: [Force; 3],
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Strategy {
+    // fire every candidate velocity and check the trajectory
+    Simulate,
+    // max height by formula where possible, counts by intersecting per-axis step windows
+    ClosedForm,
+}
+
+// inclusive ranges of steps at which one axis is inside the target, usize::MAX meaning forever
+type StepWindows = Vec<(usize, usize)>;
+
+fn push_step(windows: &mut StepWindows, step: usize) {
+    if let Some(last) = windows.last_mut() {
+        if last.1 + 1 == step {
+            last.1 = step;
+            return;
+        }
+    }
+    windows.push((step, step));
+}
+
+fn intersect_windows(a: &StepWindows, b: &StepWindows) -> StepWindows {
+    let mut windows = StepWindows::new();
+    for &(a_begin, a_end) in a {
+        for &(b_begin, b_end) in b {
+            let (begin, end) = (a_begin.max(b_begin), a_end.min(b_end));
+            if begin <= end {
+                windows.push((begin, end));
+            }
+        }
+    }
+    windows
+}
+
+fn windows_overlap(a: &StepWindows, b: &StepWindows) -> bool {
+    !intersect_windows(a, b).is_empty()
 }
 
 #[derive(Clone, Copy, PartialEq, Debug)]
 pub struct Pos {
     x: i64,
     y: i64,
+    z: i64,
 }
 
 impl Pos {
     pub fn new(x: i64, y: i64) -> Self {
-        Self { x, y }
+        Self { x, y, z: 0 }
+    }
+
+    pub fn new_3d(x: i64, y: i64, z: i64) -> Self {
+        Self { x, y, z }
+    }
+
+    fn axis(&self, axis: usize) -> i64 {
+        [self.x, self.y, self.z][axis]
+    }
+
+    fn axis_mut(&mut self, axis: usize) -> &mut i64 {
+        match axis {
+            0 => &mut self.x,
+            1 => &mut self.y,
+            _ => &mut self.z,
+        }
     }
 }
 
-impl std::str::FromStr for TargetArea {
+#[derive(Clone, PartialEq, Debug)]
+pub struct TrajectoryResult {
+    pub positions: Vec<Pos>,
+    // the first position inside the target and the step it was reached at
+    pub hit: Option<(Pos, usize)>,
+    // the highest position along the gravity axis
+    pub apex: Pos,
+}
+
+impl core::str::FromStr for TargetArea {
     type Err = error::Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let s = s.trim_start().trim_end();
-        let (x_begin, x_end, y_begin, y_end) = scan_fmt::scan_fmt!(s, "target area: x={d}..{d}, y={d}..{d}", i64, i64, i64, i64)?;
-        Ok(Self {
-            x_begin: std::cmp::min(x_begin, x_end),
-            x_end: std::cmp::max(x_begin, x_end),
-            y_begin: std::cmp::min(y_begin, y_end),
-            y_end: std::cmp::max(y_begin, y_end),
-        })
+        if let Ok([x_begin, x_end, y_begin, y_end, z_begin, z_end]) = parse::scan(s, "target area: x={}..{}, y={}..{}, z={}..{}") {
+            return Ok(Self::new_3d((x_begin, x_end), (y_begin, y_end), (z_begin, z_end)));
+        }
+        let [x_begin, x_end, y_begin, y_end] = parse::scan(s, "target area: x={}..{}, y={}..{}")?;
+        Ok(Self::new((x_begin, x_end), (y_begin, y_end)))
     }
 }
 
 impl TargetArea {
-    fn simulate_trajectory(&self, initial_position: &Pos, mut velocity_x: i64, mut velocity_y: i64) -> Vec<Pos> {
-        let mut position = *initial_position;
+    pub fn new(x: (i64, i64), y: (i64, i64)) -> Self {
+        Self {
+            x_begin: core::cmp::min(x.0, x.1),
+            x_end: core::cmp::max(x.0, x.1),
+            y_begin: core::cmp::min(y.0, y.1),
+            y_end: core::cmp::max(y.0, y.1),
+            z_begin: 0,
+            z_end: 0,
+            is_3d: false,
+            forces: [Force::Drag, Force::Gravity, Force::None],
+        }
+    }
+
+    // in three dimensions y drifts freely and gravity pulls along z
+    pub fn new_3d(x: (i64, i64), y: (i64, i64), z: (i64, i64)) -> Self {
+        Self {
+            z_begin: core::cmp::min(z.0, z.1),
+            z_end: core::cmp::max(z.0, z.1),
+            is_3d: true,
+            forces: [Force::Drag, Force::None, Force::Gravity],
+            ..Self::new(x, y)
+        }
+    }
+
+    pub fn with_forces(mut self, x: Force, y: Force, z: Force) -> Self {
+        self.forces = [x, y, z];
+        self
+    }
+
+    pub fn is_3d(&self) -> bool {
+        self.is_3d
+    }
+
+    fn num_axes(&self) -> usize {
+        if self.is_3d {
+            3
+        } else {
+            2
+        }
+    }
+
+    fn range(&self, axis: usize) -> (i64, i64) {
+        match axis {
+            0 => (self.x_begin, self.x_end),
+            1 => (self.y_begin, self.y_end),
+            _ => (self.z_begin, self.z_end),
+        }
+    }
+
+    fn gravity_axis(&self) -> Option<usize> {
+        (0..self.num_axes()).find(|&axis| self.forces[axis] == Force::Gravity)
+    }
+
+    // what "up" means for apexes and plots
+    fn height_axis(&self) -> usize {
+        self.gravity_axis().unwrap_or(1)
+    }
+
+    fn inside_axis(&self, axis: usize, pos: i64) -> bool {
+        let (begin, end) = self.range(axis);
+        begin <= pos && pos <= end
+    }
+
+    // true once the axis can never be inside the target again
+    fn axis_missed(&self, axis: usize, pos: i64, velocity: i64) -> bool {
+        let (begin, end) = self.range(axis);
+        match self.forces[axis] {
+            Force::Gravity => velocity <= 0 && pos < begin,
+            Force::None | Force::Drag => (velocity == 0 && !self.inside_axis(axis, pos)) || (velocity > 0 && pos > end) || (velocity < 0 && pos < begin),
+        }
+    }
+
+    pub fn simulate(&self, initial_position: Pos, initial_velocity: Pos) -> TrajectoryResult {
+        let mut position = initial_position;
+        let mut velocity = initial_velocity;
         let mut positions: Vec<Pos> = vec![position];
+        // without gravity a probe can come to rest inside the target's span on every axis, where
+        // no axis ever counts as missed
+        let can_rest = self.gravity_axis().is_none();
         loop {
-            position.x += velocity_x;
-            position.y += velocity_y;
+            for axis in 0..self.num_axes() {
+                *position.axis_mut(axis) += velocity.axis(axis);
+                *velocity.axis_mut(axis) = self.forces[axis].next_velocity(velocity.axis(axis));
+            }
 
             positions.push(position);
 
-            if velocity_x == 0 && (position.x < self.x_begin || position.x > self.x_end) {
+            if (0..self.num_axes()).any(|axis| self.axis_missed(axis, position.axis(axis), velocity.axis(axis))) {
                 break;
             }
-
-            if position.y < std::cmp::min(self.y_end, self.y_begin) {
+            if can_rest && (0..self.num_axes()).all(|axis| velocity.axis(axis) == 0) {
                 break;
             }
-
-            // modify velocities
-
-            velocity_x += match velocity_x.cmp(&0) {
-                std::cmp::Ordering::Less => 1,
-                std::cmp::Ordering::Greater => -1,
-                _ => 0,
-            };
-
-            velocity_y -= 1;
         }
 
-        positions
-    }
+        let hit = positions.iter().enumerate().find(|(_, pos)| self.inside_target_area(pos)).map(|(step, pos)| (*pos, step));
+        let height_axis = self.height_axis();
+        let apex = *positions.iter().rev().max_by_key(|pos| pos.axis(height_axis)).unwrap();
 
-    fn inside_target_area(&self, pos: &Pos) -> bool {
-        self.x_begin <= pos.x && pos.x <= self.x_end && self.y_begin <= pos.y && pos.y <= self.y_end
+        TrajectoryResult { positions, hit, apex }
     }
 
-    fn inside_target_area_x(&self, pos: i64) -> bool {
-        self.x_begin <= pos && pos <= self.x_end
+    pub fn simulate_trajectory(&self, initial_position: &Pos, velocity_x: i64, velocity_y: i64) -> TrajectoryResult {
+        self.simulate(*initial_position, Pos::new(velocity_x, velocity_y))
     }
 
-    fn inside_target_area_y(&self, pos: i64) -> bool {
-        self.y_begin <= pos && pos <= self.y_end
+    fn inside_target_area(&self, pos: &Pos) -> bool {
+        (0..self.num_axes()).all(|axis| self.inside_axis(axis, pos.axis(axis)))
     }
 
     pub fn hits_target(&self, trajectory: &Vec<Pos>) -> Option<Pos> {
@@ -90,99 +250,259 @@ impl TargetArea {
         None
     }
 
-    fn would_hit_x(&self, initial_position: i64, mut velocity: i64) -> bool {
-        let mut position = initial_position;
-        loop {
-            if self.inside_target_area_x(position) {
-                return true;
+    fn possible_velocities(&self, axis: usize, initial_position: i64) -> Vec<i64> {
+        let (begin, end) = self.range(axis);
+        let (min_velocity, max_velocity) = match self.forces[axis] {
+            // whatever goes up comes back down through the same heights, so the speed is bounded by the farthest edge
+            Force::Gravity => {
+                let farthest = core::cmp::max((end - initial_position).abs(), (begin - initial_position).abs());
+                (-farthest, farthest)
             }
+            // the first step must not jump past the far edge, whichever side of the launcher the target is on
+            Force::None | Force::Drag => (core::cmp::min(begin - initial_position, 0), core::cmp::max(end - initial_position, 0)),
+        };
+        (min_velocity..=max_velocity)
+            .filter(|&velocity| !self.step_windows_axis(axis, initial_position, velocity).is_empty())
+            .collect()
+    }
 
-            position += velocity;
-
-            if velocity > 0 {
-                velocity -= 1;
+    fn step_windows_axis(&self, axis: usize, initial_position: i64, mut velocity: i64) -> StepWindows {
+        let mut windows = StepWindows::new();
+        let mut position = initial_position;
+        let mut step = 0;
+        loop {
+            let inside = self.inside_axis(axis, position);
+            if inside {
+                push_step(&mut windows, step);
             }
-
-            if velocity < 0 {
-                velocity += 1;
+            if velocity == 0 && self.forces[axis] != Force::Gravity {
+                // nothing moves the probe along this axis any more
+                if inside {
+                    windows.last_mut().unwrap().1 = usize::MAX;
+                }
+                break;
             }
-
-            if velocity == 0 {
+            if self.axis_missed(axis, position, velocity) {
                 break;
             }
+            position += velocity;
+            velocity = self.forces[axis].next_velocity(velocity);
+            step += 1;
         }
-        false
+        windows
     }
 
-    fn would_hit_y(&self, initial_position: i64, mut velocity: i64) -> bool {
-        let mut position = initial_position;
-        loop {
-            if self.inside_target_area_y(position) {
-                return true;
+    // per axis, every velocity that can reach the target together with the steps at which it is inside
+    fn step_windows(&self, initial_position: Pos) -> Vec<Vec<(i64, StepWindows)>> {
+        (0..3)
+            .map(|axis| {
+                if axis >= self.num_axes() {
+                    return vec![(0, vec![(0, usize::MAX)])];
+                }
+                self.possible_velocities(axis, initial_position.axis(axis))
+                    .into_iter()
+                    .map(|velocity| (velocity, self.step_windows_axis(axis, initial_position.axis(axis), velocity)))
+                    .collect()
+            })
+            .collect()
+    }
+
+    // the per-axis pruned candidates, grouped by the velocity along the gravity axis
+    // (fastest upwards first) so the grid can be scanned one group per task
+    fn candidate_velocities(&self, initial_position: Pos) -> (Vec<i64>, impl Fn(i64) -> Vec<Pos> + Sync) {
+        let outer = self.height_axis();
+        let velocities = |axis: usize| {
+            if axis < self.num_axes() {
+                self.possible_velocities(axis, initial_position.axis(axis))
+            } else {
+                vec![0]
             }
+        };
+        let mut outer_velocities = velocities(outer);
+        if self.gravity_axis().is_some() {
+            outer_velocities.reverse();
+        }
+        let inner: Vec<(usize, Vec<i64>)> = (0..3).filter(|&axis| axis != outer).map(|axis| (axis, velocities(axis))).collect();
+
+        let group = move |o: i64| {
+            let mut candidates = vec![];
+            for &a in &inner[0].1 {
+                for &b in &inner[1].1 {
+                    let mut velocity = Pos::new_3d(0, 0, 0);
+                    *velocity.axis_mut(outer) = o;
+                    *velocity.axis_mut(inner[0].0) = a;
+                    *velocity.axis_mut(inner[1].0) = b;
+                    candidates.push(velocity);
+                }
+            }
+            candidates
+        };
+        (outer_velocities, group)
+    }
 
-            position += velocity;
+    fn hits(&self, initial_position: Pos, velocity: Pos) -> bool {
+        self.simulate(initial_position, velocity).hit.is_some()
+    }
 
-            velocity -= 1;
+    pub fn max_height(&self, initial_position: Pos) -> Option<i64> {
+        self.max_height_with(initial_position, Strategy::ClosedForm)
+    }
 
-            if position < std::cmp::min(self.y_end, self.y_begin) {
-                break;
+    // the highest point along the gravity axis of any trajectory that hits
+    pub fn max_height_with(&self, initial_position: Pos, strategy: Strategy) -> Option<i64> {
+        let axis = self.gravity_axis()?;
+        match strategy {
+            Strategy::Simulate => self.optimum_trajectory(initial_position).map(|trajectory| trajectory.apex.axis(axis)),
+            Strategy::ClosedForm => {
+                let start = initial_position.axis(axis);
+                let apex = |velocity: i64| start + if velocity > 0 { velocity * (velocity + 1) / 2 } else { 0 };
+                let windows = self.step_windows(initial_position);
+                let others: Vec<usize> = (0..3).filter(|&other| other != axis).collect();
+                let reachable = |axis_windows: &StepWindows| {
+                    windows[others[0]].iter().any(|(_, a)| {
+                        let steps = intersect_windows(axis_windows, a);
+                        !steps.is_empty() && windows[others[1]].iter().any(|(_, b)| windows_overlap(&steps, b))
+                    })
+                };
+
+                // below the launcher the probe passes its start height again with speed v+1, so the
+                // fastest shot lands exactly on the bottom edge if the other axes can be there in time
+                let (begin, end) = self.range(axis);
+                if end < start {
+                    let velocity = start - begin - 1;
+                    if reachable(&self.step_windows_axis(axis, start, velocity)) {
+                        return Some(apex(velocity));
+                    }
+                }
+
+                windows[axis].iter().rev().find(|(_, w)| reachable(w)).map(|&(velocity, _)| apex(velocity))
             }
         }
-        false
     }
 
-    fn find_possible_velocities_x(&self, initial_position: i64) -> Vec<i64> {
-        let max_velocity = std::cmp::max(self.x_end - initial_position, self.x_begin - initial_position);
-        let min_velocity = 0;
-        let mut possible_velocities = Vec::new();
-        for possible_velocity in std::cmp::min(min_velocity, max_velocity)..=std::cmp::max(min_velocity, max_velocity) {
-            if self.would_hit_x(initial_position, possible_velocity) {
-                possible_velocities.push(possible_velocity);
-            }
-        }
-        possible_velocities
+    pub fn count_initial_velocities(&self, initial_position: Pos) -> usize {
+        self.count_initial_velocities_with(initial_position, Strategy::ClosedForm)
     }
 
-    fn find_possible_velocities_y(&self, initial_position: i64) -> Vec<i64> {
-        let max_velocity = std::cmp::max((self.y_end - initial_position).abs(), (self.y_begin - initial_position).abs());
-        let min_velocity = -std::cmp::max((self.y_end - initial_position).abs(), (self.y_begin - initial_position).abs());
-        let mut possible_velocities = Vec::new();
-        for possible_velocity in std::cmp::min(min_velocity, max_velocity)..=std::cmp::max(min_velocity, max_velocity) {
-            if self.would_hit_y(initial_position, possible_velocity) {
-                possible_velocities.push(possible_velocity);
-            }
+    pub fn count_initial_velocities_with(&self, initial_position: Pos, strategy: Strategy) -> usize {
+        match strategy {
+            Strategy::Simulate => self.initial_velocities(initial_position).len(),
+            Strategy::ClosedForm => self.hitting_windows(initial_position).len(),
         }
-        possible_velocities
     }
 
-    pub fn all_initial_velocities(&self, initial_position: Pos) -> Vec<(i64, i64)> {
-        let x_velocities = self.find_possible_velocities_x(initial_position.x);
-        let y_velocities = self.find_possible_velocities_y(initial_position.y);
-        let mut velocities: Vec<(i64, i64)> = Vec::new();
-        for &y_vel in y_velocities.iter().rev() {
-            for &x_vel in x_velocities.iter() {
-                let trajectory = self.simulate_trajectory(&initial_position, x_vel, y_vel);
-                if trajectory.iter().any(|pos| self.inside_target_area(pos)) {
-                    velocities.push((x_vel, y_vel));
+    // every velocity that hits, with the steps at which the probe is inside the target
+    fn hitting_windows(&self, initial_position: Pos) -> Vec<(Pos, StepWindows)> {
+        let windows = self.step_windows(initial_position);
+        let mut hits = vec![];
+        for (velocity_x, x) in &windows[0] {
+            for (velocity_y, y) in &windows[1] {
+                let xy = intersect_windows(x, y);
+                if xy.is_empty() {
+                    continue;
+                }
+                for (velocity_z, z) in &windows[2] {
+                    let steps = intersect_windows(&xy, z);
+                    if !steps.is_empty() {
+                        hits.push((Pos::new_3d(*velocity_x, *velocity_y, *velocity_z), steps));
+                    }
                 }
             }
         }
-        velocities.into_iter().collect()
+        hits
     }
 
-    pub fn optimum_trajectory(&self, initial_position: Pos) -> Option<Vec<Pos>> {
-        let x_velocities = self.find_possible_velocities_x(initial_position.x);
-        let y_velocities = self.find_possible_velocities_y(initial_position.y);
-        for &y_vel in y_velocities.iter().rev() {
-            for &x_vel in x_velocities.iter() {
-                let trajectory = self.simulate_trajectory(&initial_position, x_vel, y_vel);
-                if trajectory.iter().any(|pos| self.inside_target_area(pos)) {
-                    return Some(trajectory);
-                }
-            }
+    // every velocity that hits, with the step of its first impact
+    pub fn initial_velocities_with_impact(&self, initial_position: Pos) -> Vec<(Pos, usize)> {
+        self.hitting_windows(initial_position)
+            .into_iter()
+            .map(|(velocity, steps)| (velocity, steps.iter().map(|&(begin, _)| begin).min().unwrap()))
+            .collect()
+    }
+
+    pub fn velocities_hitting_at_step(&self, initial_position: Pos, step: usize) -> Vec<Pos> {
+        self.hitting_windows(initial_position)
+            .into_iter()
+            .filter(|(_, steps)| steps.iter().any(|&(begin, end)| begin <= step && step <= end))
+            .map(|(velocity, _)| velocity)
+            .collect()
+    }
+
+    pub fn velocities_hitting_within(&self, initial_position: Pos, max_steps: usize) -> Vec<(Pos, usize)> {
+        self.initial_velocities_with_impact(initial_position).into_iter().filter(|&(_, impact)| impact <= max_steps).collect()
+    }
+
+    pub fn initial_velocities(&self, initial_position: Pos) -> Vec<Pos> {
+        let (outer_velocities, group) = self.candidate_velocities(initial_position);
+        outer_velocities
+            .into_par_iter()
+            .flat_map_iter(|o| group(o).into_iter().filter(|&velocity| self.hits(initial_position, velocity)))
+            .collect()
+    }
+
+    pub fn all_initial_velocities(&self, initial_position: Pos) -> Vec<(i64, i64)> {
+        self.initial_velocities(initial_position).iter().map(|velocity| (velocity.x, velocity.y)).collect()
+    }
+
+    // draws x across and height (the gravity axis) down, like the puzzle text
+    pub fn render(&self, trajectory: &[Pos]) -> String {
+        let height_axis = self.height_axis();
+        let (height_begin, height_end) = self.range(height_axis);
+        let xs = trajectory.iter().map(|p| p.x).chain([self.x_begin, self.x_end]);
+        let heights = trajectory.iter().map(|p| p.axis(height_axis)).chain([height_begin, height_end]);
+        let (min_x, max_x) = (xs.clone().min().unwrap(), xs.max().unwrap());
+        let (min_height, max_height) = (heights.clone().min().unwrap(), heights.max().unwrap());
+
+        let mut rows = vec![];
+        for height in (min_height..=max_height).rev() {
+            let row: String = (min_x..=max_x)
+                .map(|x| {
+                    let at = |p: &Pos| p.x == x && p.axis(height_axis) == height;
+                    if trajectory.first().is_some_and(at) {
+                        'S'
+                    } else if trajectory.iter().any(at) {
+                        '#'
+                    } else if self.inside_axis(0, x) && self.inside_axis(height_axis, height) {
+                        'T'
+                    } else {
+                        '.'
+                    }
+                })
+                .collect();
+            rows.push(row);
         }
-        None
+        rows.join("\n")
+    }
+
+    pub fn optimum_trajectory(&self, initial_position: Pos) -> Option<TrajectoryResult> {
+        let (outer_velocities, group) = self.candidate_velocities(initial_position);
+        outer_velocities.into_par_iter().find_map_first(|o| {
+            group(o)
+                .into_iter()
+                .map(|velocity| self.simulate(initial_position, velocity))
+                .find(|trajectory| trajectory.hit.is_some())
+        })
+    }
+}
+
+const EXAMPLE: &str = "target area: x=20..30, y=-10..-5";
+
+impl puzzle::Puzzle for TargetArea {
+    fn parse(input: &str) -> Result<Self, error::Error> {
+        input.parse()
+    }
+
+    fn part1(&self) -> Result<puzzle::Answer, error::Error> {
+        let max_height = self.max_height(Pos::new(0, 0)).ok_or_else(|| error::Error::General("no trajectory hits the target".to_string()))?;
+        Ok(max_height.into())
+    }
+
+    fn part2(&self) -> Result<puzzle::Answer, error::Error> {
+        Ok(self.count_initial_velocities(Pos::new(0, 0)).into())
+    }
+
+    fn examples() -> Vec<puzzle::Example> {
+        vec![(EXAMPLE, Some("45"), Some("112"))]
     }
 }
 
@@ -191,14 +511,14 @@ fn test_find_possible_velocities() -> Result<(), error::Error> {
     let target_area: TargetArea = "target area: x=-5..-5, y=-5..-5".parse()?;
     assert!(target_area.inside_target_area(&Pos::new(-5, -5)));
 
-    let velocities = target_area.find_possible_velocities_x(-1);
+    let velocities = target_area.possible_velocities(0, -1);
     assert_eq!(velocities, vec![-4]);
 
-    let velocities = target_area.find_possible_velocities_y(-1);
+    let velocities = target_area.possible_velocities(1, -1);
     assert_eq!(velocities, vec![-4, 3]);
 
-    let target_area: TargetArea = "target area: x=20..30, y=-10..-5".parse()?;
-    let velocities = target_area.find_possible_velocities_y(0);
+    let target_area: TargetArea = EXAMPLE.parse()?;
+    let velocities = target_area.possible_velocities(1, 0);
     assert!(velocities.iter().any(|&v| v == 9));
 
     Ok(())
@@ -228,41 +548,265 @@ fn test_target_area() -> Result<(), error::Error> {
     assert!(!target_area.inside_target_area(&Pos::new(-9, 15)));
     assert!(!target_area.inside_target_area(&Pos::new(-20, 16)));
 
-    let target_area: TargetArea = "target area: x=20..30, y=-10..-5".parse()?;
+    let target_area: TargetArea = EXAMPLE.parse()?;
     assert!(target_area.inside_target_area(&Pos::new(28, -7)));
 
     let trajectory = target_area.simulate_trajectory(&Pos::new(0, 0), 7, 2);
-    assert_eq!(target_area.hits_target(&trajectory), Some(Pos::new(28, -7)));
+    assert_eq!(target_area.hits_target(&trajectory.positions), Some(Pos::new(28, -7)));
 
     let trajectory = target_area.simulate_trajectory(&Pos::new(0, 0), 6, 3);
-    assert_eq!(target_area.hits_target(&trajectory), Some(Pos::new(21, -9)));
+    assert_eq!(target_area.hits_target(&trajectory.positions), Some(Pos::new(21, -9)));
 
     let trajectory = target_area.simulate_trajectory(&Pos::new(0, 0), 9, 0);
-    assert_eq!(target_area.hits_target(&trajectory), Some(Pos::new(30, -6)));
+    assert_eq!(target_area.hits_target(&trajectory.positions), Some(Pos::new(30, -6)));
 
     let trajectory = target_area.simulate_trajectory(&Pos::new(0, 0), 17, -4);
-    assert_eq!(target_area.hits_target(&trajectory), None);
+    assert_eq!(target_area.hits_target(&trajectory.positions), None);
 
     Ok(())
 }
 
 #[test]
 fn test_day17() -> Result<(), error::Error> {
-    let target_area: TargetArea = "target area: x=20..30, y=-10..-5".parse()?;
+    let target_area: TargetArea = EXAMPLE.parse()?;
     assert_eq!(target_area.x_begin, 20);
     assert_eq!(target_area.x_end, 30);
     assert_eq!(target_area.y_begin, -10);
     assert_eq!(target_area.y_end, -5);
-    let trajectory: Vec<Pos> = target_area.optimum_trajectory(Pos::new(0, 0)).unwrap();
-    assert_eq!(trajectory.iter().map(|p| p.y).max().unwrap(), 45);
+    let trajectory = target_area.optimum_trajectory(Pos::new(0, 0)).unwrap();
+    assert_eq!(trajectory.apex.y, 45);
     let all_initial_velocities = target_area.all_initial_velocities(Pos::new(0, 0));
     assert_eq!(all_initial_velocities.len(), 112);
 
     let target_area: TargetArea = std::fs::read_to_string("input_day17")?.parse()?;
-    let trajectory: Vec<Pos> = target_area.optimum_trajectory(Pos::new(0, 0)).unwrap();
-    assert_eq!(trajectory.iter().map(|p| p.y).max().unwrap(), 5151);
+    let trajectory = target_area.optimum_trajectory(Pos::new(0, 0)).unwrap();
+    assert_eq!(trajectory.apex.y, 5151);
     let all_initial_velocities = target_area.all_initial_velocities(Pos::new(0, 0));
     assert_eq!(all_initial_velocities.len(), 968);
 
     Ok(())
 }
+
+#[test]
+fn test_day17_closed_form() -> Result<(), error::Error> {
+    let target_area: TargetArea = EXAMPLE.parse()?;
+    assert_eq!(target_area.max_height(Pos::new(0, 0)), Some(45));
+    assert_eq!(target_area.count_initial_velocities(Pos::new(0, 0)), 112);
+
+    let target_area: TargetArea = std::fs::read_to_string("input_day17")?.parse()?;
+    assert_eq!(target_area.max_height(Pos::new(0, 0)), Some(5151));
+    assert_eq!(target_area.count_initial_velocities(Pos::new(0, 0)), 968);
+
+    for area in [
+        "target area: x=20..30, y=-10..-5",
+        "target area: x=20..30, y=5..10",
+        "target area: x=20..30, y=-5..5",
+        "target area: x=5..9, y=-30..-25",
+        "target area: x=100..101, y=-3..-1",
+    ] {
+        let target_area: TargetArea = area.parse()?;
+        for initial_position in [Pos::new(0, 0), Pos::new(3, -2)] {
+            assert_eq!(
+                target_area.max_height_with(initial_position, Strategy::ClosedForm),
+                target_area.max_height_with(initial_position, Strategy::Simulate),
+                "{} from {:?}",
+                area,
+                initial_position
+            );
+            assert_eq!(
+                target_area.count_initial_velocities_with(initial_position, Strategy::ClosedForm),
+                target_area.count_initial_velocities_with(initial_position, Strategy::Simulate),
+                "{} from {:?}",
+                area,
+                initial_position
+            );
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_day17_behind_launcher() -> Result<(), error::Error> {
+    let target_area: TargetArea = "target area: x=-30..-20, y=-10..-5".parse()?;
+    let trajectory = target_area.simulate_trajectory(&Pos::new(0, 0), -7, 2);
+    assert_eq!(target_area.hits_target(&trajectory.positions), Some(Pos::new(-28, -7)));
+    for strategy in [Strategy::Simulate, Strategy::ClosedForm] {
+        assert_eq!(target_area.max_height_with(Pos::new(0, 0), strategy), Some(45));
+        assert_eq!(target_area.count_initial_velocities_with(Pos::new(0, 0), strategy), 112);
+    }
+    assert!(target_area.all_initial_velocities(Pos::new(0, 0)).iter().all(|&(x, _)| x < 0));
+
+    // the probe stops exactly at the edge of the target
+    let target_area: TargetArea = "target area: x=-1..-1, y=-1..-1".parse()?;
+    assert!(!target_area.step_windows_axis(0, 0, -1).is_empty());
+    assert_eq!(target_area.possible_velocities(0, 0), vec![-1]);
+
+    let target_area: TargetArea = "target area: x=-5..5, y=-10..-5".parse()?;
+    let velocities = target_area.all_initial_velocities(Pos::new(0, 0));
+    assert!(velocities.contains(&(-2, 0)) && velocities.contains(&(2, 0)) && velocities.contains(&(0, 0)));
+    assert_eq!(target_area.count_initial_velocities_with(Pos::new(0, 0), Strategy::ClosedForm), velocities.len());
+
+    let input = std::fs::read_to_string("input_day17")?;
+    let target_area: TargetArea = input.parse()?;
+    let mirrored = TargetArea {
+        x_begin: -target_area.x_end,
+        x_end: -target_area.x_begin,
+        ..target_area
+    };
+    assert_eq!(mirrored.max_height(Pos::new(0, 0)), Some(5151));
+    assert_eq!(mirrored.count_initial_velocities_with(Pos::new(0, 0), Strategy::Simulate), 968);
+
+    Ok(())
+}
+
+#[test]
+fn test_day17_3d() -> Result<(), error::Error> {
+    // a 2d target stretched along y behaves like the 2d puzzle with z as height
+    let target_area: TargetArea = "target area: x=20..30, y=-1000..1000, z=-10..-5".parse()?;
+    assert!(target_area.is_3d());
+    let trajectory = target_area.simulate(Pos::new_3d(0, 0, 0), Pos::new_3d(7, 3, 2));
+    assert_eq!(trajectory.positions[1], Pos::new_3d(7, 3, 2));
+    assert_eq!(target_area.hits_target(&trajectory.positions), Some(Pos::new_3d(28, 21, -7)));
+    assert_eq!(target_area.max_height(Pos::new_3d(0, 0, 0)), Some(45));
+
+    let target_area: TargetArea = "target area: x=20..30, y=-1..3, z=-10..-5".parse()?;
+    assert_eq!(target_area.max_height_with(Pos::new_3d(0, 0, 0), Strategy::Simulate), Some(45));
+    let velocities = target_area.initial_velocities(Pos::new_3d(0, 0, 0));
+    assert!(velocities.contains(&Pos::new_3d(7, 0, 2)));
+    for strategy in [Strategy::Simulate, Strategy::ClosedForm] {
+        assert_eq!(target_area.count_initial_velocities_with(Pos::new_3d(0, 0, 0), strategy), velocities.len());
+        assert_eq!(target_area.max_height_with(Pos::new_3d(0, 0, 0), strategy), Some(45));
+    }
+
+    // drag on every axis and gravity pulling along y instead
+    let target_area = TargetArea::new_3d((5, 9), (-30, -25), (-4, -2)).with_forces(Force::Drag, Force::Gravity, Force::Drag);
+    assert_eq!(target_area.max_height(Pos::new_3d(0, 0, 0)), TargetArea::new((5, 9), (-30, -25)).max_height(Pos::new(0, 0)));
+    assert_eq!(
+        target_area.count_initial_velocities_with(Pos::new_3d(0, 0, 0), Strategy::Simulate),
+        target_area.count_initial_velocities_with(Pos::new_3d(0, 0, 0), Strategy::ClosedForm)
+    );
+
+    // no gravity, so a probe stopping inside the span of every axis has to end the run at rest
+    let target_area = TargetArea::new((-1, 1), (-1, 1)).with_forces(Force::Drag, Force::Drag, Force::None);
+    let trajectory = target_area.simulate(Pos::new(0, 0), Pos::new(0, 0));
+    assert_eq!(trajectory.positions, vec![Pos::new(0, 0), Pos::new(0, 0)]);
+    assert_eq!(trajectory.hit, Some((Pos::new(0, 0), 0)));
+    let trajectory = target_area.simulate(Pos::new(0, 0), Pos::new(1, -1));
+    assert_eq!(trajectory.positions.last(), Some(&Pos::new(1, -1)));
+    assert_eq!(target_area.initial_velocities(Pos::new(0, 0)).len(), 9);
+    assert_eq!(target_area.count_initial_velocities_with(Pos::new(0, 0), Strategy::Simulate), 9);
+    assert_eq!(target_area.count_initial_velocities_with(Pos::new(0, 0), Strategy::ClosedForm), 9);
+
+    Ok(())
+}
+
+#[test]
+fn test_day17_render() -> Result<(), error::Error> {
+    let target_area: TargetArea = EXAMPLE.parse()?;
+    let trajectory = target_area.simulate_trajectory(&Pos::new(0, 0), 7, 2);
+    let (_, hit) = trajectory.hit.unwrap();
+    let expected = r#".............#....#............
+.......#..............#........
+...............................
+S........................#.....
+...............................
+...............................
+...........................#...
+...............................
+....................TTTTTTTTTTT
+....................TTTTTTTTTTT
+....................TTTTTTTT#TT
+....................TTTTTTTTTTT
+....................TTTTTTTTTTT
+....................TTTTTTTTTTT"#;
+    assert_eq!(target_area.render(&trajectory.positions[..=hit]), expected);
+
+    let trajectory = target_area.simulate_trajectory(&Pos::new(0, 0), 17, -4);
+    let expected = r#"S.................................
+..................................
+..................................
+..................................
+.................#................
+....................TTTTTTTTTTT...
+....................TTTTTTTTTTT...
+....................TTTTTTTTTTT...
+....................TTTTTTTTTTT...
+....................TTTTTTTTTTT..#
+....................TTTTTTTTTTT..."#;
+    assert_eq!(target_area.render(&trajectory.positions[..3]), expected);
+
+    Ok(())
+}
+
+#[test]
+fn test_day17_steps() -> Result<(), error::Error> {
+    let target_area: TargetArea = EXAMPLE.parse()?;
+    let start = Pos::new(0, 0);
+    let impacts = target_area.initial_velocities_with_impact(start);
+    assert_eq!(impacts.len(), 112);
+    for (velocity, expected) in [(Pos::new(7, 2), 7), (Pos::new(6, 3), 9), (Pos::new(9, 0), 4), (Pos::new(6, 9), 20)] {
+        assert!(impacts.contains(&(velocity, expected)), "{:?}", velocity);
+    }
+    for &(velocity, impact) in &impacts {
+        assert_eq!(target_area.simulate(start, velocity).hit.map(|(_, step)| step), Some(impact));
+    }
+
+    for step in [1, 5, 20] {
+        let mut expected: Vec<Pos> = target_area
+            .initial_velocities(start)
+            .into_iter()
+            .filter(|&velocity| target_area.simulate(start, velocity).positions.get(step).is_some_and(|p| target_area.inside_target_area(p)))
+            .collect();
+        let mut velocities = target_area.velocities_hitting_at_step(start, step);
+        expected.sort_by_key(|v| (v.x, v.y));
+        velocities.sort_by_key(|v| (v.x, v.y));
+        assert_eq!(velocities, expected);
+    }
+    // every velocity straight into the target hits on the first step
+    assert_eq!(target_area.velocities_hitting_at_step(start, 1).len(), 11 * 6);
+
+    let within = target_area.velocities_hitting_within(start, 4);
+    assert!(within.iter().all(|&(_, impact)| impact <= 4));
+    assert_eq!(within.len(), impacts.iter().filter(|&&(_, impact)| impact <= 4).count());
+    assert!(target_area.velocities_hitting_within(start, 0).is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_day17_trajectory_result() -> Result<(), error::Error> {
+    let target_area: TargetArea = EXAMPLE.parse()?;
+    let trajectory = target_area.simulate_trajectory(&Pos::new(0, 0), 6, 3);
+    assert_eq!(trajectory.hit, Some((Pos::new(21, -9), 9)));
+    assert_eq!(trajectory.apex, Pos::new(15, 6));
+    assert_eq!(trajectory.positions[9], Pos::new(21, -9));
+
+    let trajectory = target_area.simulate_trajectory(&Pos::new(0, 0), 17, -4);
+    assert_eq!(trajectory.hit, None);
+    assert_eq!(trajectory.apex, Pos::new(0, 0));
+
+    let trajectory = target_area.optimum_trajectory(Pos::new(0, 0)).unwrap();
+    assert_eq!(trajectory.positions[1], Pos::new(6, 9));
+    assert_eq!(trajectory.apex, Pos::new(21, 45));
+    assert_eq!(trajectory.hit, Some((Pos::new(21, -10), 20)));
+
+    let target_area: TargetArea = "target area: x=20..30, y=-1..3, z=-10..-5".parse()?;
+    let trajectory = target_area.optimum_trajectory(Pos::new_3d(0, 0, 0)).unwrap();
+    assert_eq!(trajectory.apex.z, 45);
+
+    Ok(())
+}
+
+#[test]
+fn test_day17_large_area() -> Result<(), error::Error> {
+    let target_area: TargetArea = "target area: x=1000..1400, y=-800..-500".parse()?;
+    let start = Pos::new(0, 0);
+    let velocities = target_area.initial_velocities(start);
+    assert_eq!(velocities.len(), target_area.count_initial_velocities_with(start, Strategy::ClosedForm));
+    // results keep the sequential order: fastest upwards first
+    assert!(velocities.windows(2).all(|pair| pair[0].y > pair[1].y || (pair[0].y == pair[1].y && pair[0].x < pair[1].x)));
+    assert_eq!(target_area.max_height_with(start, Strategy::Simulate), Some(799 * 800 / 2));
+
+    Ok(())
+}
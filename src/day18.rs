@@ -1,17 +1,29 @@
 use crate::error;
+use crate::puzzle;
 
-use permutator::copy::{Combination, Permutation};
-use std::cell::RefCell;
-use std::ops::{Deref, DerefMut};
-use std::rc::Rc;
+use crate::par::*;
+use alloc::borrow::ToOwned;
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use itertools::Itertools;
 
-#[derive(PartialEq, Debug, Clone)]
+#[derive(PartialEq, Eq, Hash, Debug, Clone)]
 pub enum Element {
-    Pair(Rc<RefCell<Element>>, Rc<RefCell<Element>>),
+    Pair(Box<Element>, Box<Element>),
     Number(i64),
 }
 
-#[derive(PartialEq, Debug)]
+// homework only ever nests four deep, but a number is parsed, printed and reduced recursively, so
+// how deep it may go is capped well short of running out of stack
+pub const MAX_DEPTH: usize = 256;
+
+// homework has single digits and the worked examples two. the explodes and splits it takes to
+// reduce a number grow with the sum of its regular numbers, so larger ones don't parse. explodes
+// only move value around and splits keep it, so no number can grow past that sum either.
+pub const MAX_NUMBER: i64 = 99;
+
+#[derive(Clone, PartialEq, Debug)]
 pub enum Token {
     LeftBracket,
     RightBracket,
@@ -19,12 +31,83 @@ pub enum Token {
     Comma,
 }
 
-impl std::fmt::Display for Element {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+struct Explosion {
+    left: i64,
+    right: i64,
+    carry_left: Option<i64>,
+    carry_right: Option<i64>,
+}
+
+// position is the index of the first regular number involved, counting from the left
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Action {
+    Explode { position: usize, left: i64, right: i64 },
+    Split { position: usize, number: i64 },
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct ReductionStep {
+    pub action: Action,
+    pub result: String,
+}
+
+// formatted like the worked example in the puzzle text
+impl core::fmt::Display for ReductionStep {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self.action {
+            Action::Explode { .. } => write!(f, "after explode:  {}", self.result),
+            Action::Split { .. } => write!(f, "after split:    {}", self.result),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Strategy {
+    // recursive explode/split on the boxed tree
+    Tree,
+    // explode/split by splicing a flat list of bracket and number tokens
+    Flat,
+}
+
+// a snailfish number as its tokens without the commas, e.g. [[1,2],3] is "[ [ 1 2 ] 3 ]"
+#[derive(Clone, PartialEq, Debug)]
+pub struct FlatNumber {
+    tokens: Vec<Token>,
+}
+
+pub struct Reduction {
+    element: Element,
+}
+
+impl Reduction {
+    pub fn element(&self) -> &Element {
+        &self.element
+    }
+
+    pub fn into_element(self) -> Element {
+        self.element
+    }
+}
+
+impl Iterator for Reduction {
+    type Item = ReductionStep;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let action = self.element.explode_step().or_else(|| self.element.split_step())?;
+        Some(ReductionStep {
+            action,
+            result: self.element.to_string(),
+        })
+    }
+}
+
+impl core::fmt::Display for Element {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        if f.alternate() {
+            return self.fmt_tree(f, 0);
+        }
         match self {
             Element::Pair(x, y) => {
-                let x = &x.borrow();
-                let y = &y.borrow();
                 write!(f, "[{},{}]", x, y)
             }
             &Element::Number(number) => {
@@ -34,217 +117,354 @@ impl std::fmt::Display for Element {
     }
 }
 
+impl core::ops::Add for Element {
+    type Output = Element;
+
+    fn add(self, rhs: Element) -> Element {
+        Element::add(self, rhs)
+    }
+}
+
+impl core::ops::AddAssign for Element {
+    fn add_assign(&mut self, rhs: Element) {
+        let lhs = core::mem::replace(self, Element::Number(0));
+        *self = lhs + rhs;
+    }
+}
+
+impl core::str::FromStr for Element {
+    type Err = error::Error;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let tokens = Element::tokenize_with_positions(line)?;
+        let end = line.len();
+        let mut iterator = tokens.iter();
+
+        Element::expect(&mut iterator, end, Token::LeftBracket)?;
+        let pair = Element::parse_pair(&mut iterator, end, 1)?;
+        Element::expect(&mut iterator, end, Token::RightBracket)?;
+
+        if let Some((position, token)) = iterator.next() {
+            return Err(error::Error::Parse(format!(
+                "unbalanced brackets: unexpected {:?} at position {} after the end of the number",
+                token, position
+            )));
+        }
+
+        Ok(pair)
+    }
+}
+
 impl Element {
-    pub fn new(s: &str) -> Result<Rc<RefCell<Element>>, error::Error> {
-        let pairs = s.trim_start().trim_end().lines().map(|line| {
-            let tokens = Element::tokenize(line).unwrap();
-            let mut iterator = tokens.iter();
-
-            let lb = iterator.next().unwrap();
-            if lb != &Token::LeftBracket {
-                panic!("expected left bracket");
-            }
+    // parses one number per line and adds them all up
+    pub fn new(s: &str) -> Result<Element, error::Error> {
+        Element::new_with(s, Strategy::Tree)
+    }
 
-            let pair = Element::parse_pair(&mut iterator).unwrap();
+    pub fn new_with(s: &str, strategy: Strategy) -> Result<Element, error::Error> {
+        let mut numbers = Vec::new();
+        for (index, line) in s.trim_start().trim_end().lines().enumerate() {
+            let pair = line.parse::<Element>().map_err(|e| match e {
+                error::Error::Parse(message) => error::Error::Parse(format!("line {}: {}", index + 1, message)),
+                e => e,
+            })?;
+            numbers.push(pair);
+        }
+
+        if numbers.is_empty() {
+            return Err(error::Error::Parse("no snailfish numbers in input".to_string()));
+        }
 
-            let rb = iterator.next().unwrap();
-            if rb != &Token::RightBracket {
-                panic!("expected right bracket");
+        Ok(match strategy {
+            Strategy::Tree => numbers.into_iter().reduce(|sum, pair| sum + pair).unwrap(),
+            Strategy::Flat => {
+                let sum = numbers.iter().map(FlatNumber::from).reduce(FlatNumber::add).unwrap();
+                Element::from(&sum)
             }
+        })
+    }
 
-            pair
-        });
+    // same as lhs + rhs, spelled out for callers summing numbers one at a time
+    #[allow(clippy::should_implement_trait)]
+    pub fn add(lhs: Element, rhs: Element) -> Element {
+        let mut sum = Element::Pair(Box::new(lhs), Box::new(rhs));
+        sum.reduce();
+        sum
+    }
 
-        let mut sum: Option<Rc<RefCell<Element>>> = None;
-        for pair in pairs {
-            if sum.is_none() {
-                sum = Some(Rc::new(RefCell::new(pair)));
-            } else {
-                let new_element = Element::Pair(sum.unwrap(), Rc::new(RefCell::new(pair)));
-                let new_element_rc = Rc::new(RefCell::new(new_element));
-                loop {
-                    if !Element::explode(new_element_rc.clone()) && !Element::split(new_element_rc.clone()) {
-                        break;
-                    }
-                }
-                sum = Some(new_element_rc);
+    pub fn reduce(&mut self) {
+        loop {
+            if !self.explode() && !self.split() {
+                break;
             }
         }
-
-        Ok(sum.unwrap())
     }
 
-    pub fn traverse<F>(element: Rc<RefCell<Element>>, depth: usize, f: &mut F)
+    pub fn traverse<F>(&self, depth: usize, f: &mut F)
     where
-        F: FnMut(Rc<RefCell<Element>>, usize),
+        F: FnMut(&Element, usize),
     {
-        f(element.clone(), depth + 1);
-        match element.borrow().deref() {
+        f(self, depth + 1);
+        match self {
             Element::Pair(x, y) => {
-                Element::traverse(x.clone(), depth + 1, f);
-                Element::traverse(y.clone(), depth + 1, f);
+                x.traverse(depth + 1, f);
+                y.traverse(depth + 1, f);
             }
             Element::Number(_) => {}
         }
     }
 
-    pub fn split(element: Rc<RefCell<Element>>) -> bool {
-        let mut has_split = false;
-        Element::traverse(element, 0, &mut |element, _| {
-            if !has_split {
-                let mut new_element = None;
-                if let Element::Number(n) = element.borrow().deref() {
-                    if *n >= 10 {
-                        let (left, right) = split_number_into_two(*n);
-                        new_element = Some(Element::Pair(Rc::new(RefCell::new(Element::Number(left))), Rc::new(RefCell::new(Element::Number(right)))));
-                    }
-                }
-                if let Some(new) = new_element {
-                    *element.borrow_mut().deref_mut() = new;
-                    has_split = true;
-                }
-            }
-        });
-
-        has_split
+    pub fn split(&mut self) -> bool {
+        self.split_step().is_some()
     }
 
-    pub fn explode(element: Rc<RefCell<Element>>) -> bool {
-        let mut ref_explode = None;
-        let mut left_number = None;
-        let mut right_number = None;
-        let mut ref_left_element = None;
-        let mut ref_right_element = None;
-        let mut skip_some = 0;
+    fn split_step(&mut self) -> Option<Action> {
+        let mut position = 0;
+        self.split_at(&mut position).map(|number| Action::Split { position, number })
+    }
 
-        Element::traverse(element, 0, &mut |element, depth| {
-            if ref_explode.is_none() {
-                if let Element::Number(_) = element.borrow().deref() {
-                    ref_left_element = Some(element.clone());
+    // splits the leftmost regular number of 10 or more, counting the numbers passed on the way
+    fn split_at(&mut self, position: &mut usize) -> Option<i64> {
+        match self {
+            Element::Pair(x, y) => x.split_at(position).or_else(|| y.split_at(position)),
+            &mut Element::Number(n) => {
+                if n < 10 {
+                    *position += 1;
+                    return None;
                 }
+                let (left, right) = split_number_into_two(n);
+                *self = Element::Pair(Box::new(Element::Number(left)), Box::new(Element::Number(right)));
+                Some(n)
             }
+        }
+    }
 
-            if depth >= 5 && ref_explode.is_none() {
-                if let Element::Pair(x, y) = element.borrow().deref() {
-                    if let Element::Number(n1) = x.borrow().deref() {
-                        left_number = Some(*n1);
-                    }
-                    if let Element::Number(n2) = y.borrow().deref() {
-                        right_number = Some(*n2);
-                    }
+    pub fn explode(&mut self) -> bool {
+        self.explode_step().is_some()
+    }
 
-                    if left_number.is_some() && right_number.is_some() {
-                        ref_explode = Some(element.clone());
-                        skip_some = 3;
-                    }
-                }
-            }
+    fn explode_step(&mut self) -> Option<Action> {
+        let mut position = 0;
+        self.explode_at(0, &mut position).map(|explosion| Action::Explode {
+            position,
+            left: explosion.left,
+            right: explosion.right,
+        })
+    }
 
-            if ref_explode.is_some() && ref_right_element.is_none() && skip_some == 0 {
-                if let Element::Number(_) = element.borrow().deref() {
-                    ref_right_element = Some(element.clone());
-                }
+    // explodes the leftmost pair nested inside four pairs, handing back the numbers
+    // that still have to be added to the nearest regular number on either side
+    fn explode_at(&mut self, depth: usize, position: &mut usize) -> Option<Explosion> {
+        let (x, y) = match self {
+            Element::Pair(x, y) => (x, y),
+            Element::Number(_) => {
+                *position += 1;
+                return None;
             }
+        };
 
-            if skip_some > 0 {
-                skip_some -= 1;
+        if depth >= 4 {
+            if let (&Element::Number(left), &Element::Number(right)) = (x.as_ref(), y.as_ref()) {
+                *self = Element::Number(0);
+                return Some(Explosion {
+                    left,
+                    right,
+                    carry_left: Some(left),
+                    carry_right: Some(right),
+                });
             }
-        });
-
-        if ref_explode.is_none() {
-            return false;
         }
 
-        *ref_explode.as_ref().unwrap().borrow_mut() = Element::Number(0);
-        *ref_explode.as_ref().unwrap().borrow_mut() = Element::Number(0);
-
-        if ref_left_element.is_some() {
-            if let Element::Number(ref mut n) = ref_left_element.as_ref().unwrap().borrow_mut().deref_mut() {
-                *n += left_number.unwrap();
+        if let Some(explosion) = x.explode_at(depth + 1, position) {
+            if let Some(right) = explosion.carry_right {
+                y.add_to_edge(right, true);
             }
+            return Some(Explosion { carry_right: None, ..explosion });
         }
 
-        if ref_right_element.is_some() {
-            if let Element::Number(ref mut n) = ref_right_element.as_ref().unwrap().borrow_mut().deref_mut() {
-                *n += right_number.unwrap();
+        if let Some(explosion) = y.explode_at(depth + 1, position) {
+            if let Some(left) = explosion.carry_left {
+                x.add_to_edge(left, false);
             }
+            return Some(Explosion { carry_left: None, ..explosion });
         }
 
-        true
+        None
+    }
+
+    // yields every explode and split until the number is reduced
+    pub fn reduction(self) -> Reduction {
+        Reduction { element: self }
+    }
+
+    fn add_to_edge(&mut self, value: i64, leftmost: bool) {
+        match self {
+            Element::Pair(x, y) => {
+                if leftmost {
+                    x.add_to_edge(value, leftmost)
+                } else {
+                    y.add_to_edge(value, leftmost)
+                }
+            }
+            Element::Number(n) => *n += value,
+        }
     }
 
     pub fn tokenize(input: &str) -> Result<Vec<Token>, error::Error> {
+        Ok(Element::tokenize_with_positions(input)?.into_iter().map(|(_, token)| token).collect())
+    }
+
+    fn tokenize_with_positions(input: &str) -> Result<Vec<(usize, Token)>, error::Error> {
         let mut tokens = Vec::new();
         let bytes = input.as_bytes();
         let mut index = 0;
-        loop {
+        while index < bytes.len() {
+            let position = index;
             let token = match bytes[index] {
                 b'[' => Token::LeftBracket,
                 b']' => Token::RightBracket,
                 b',' => Token::Comma,
-                _ => {
-                    let from = index;
-                    let mut to = from + 1;
-                    loop {
-                        if !bytes[to].is_ascii_digit() {
-                            break;
-                        }
-                        to += 1;
+                b'0'..=b'9' => {
+                    while index + 1 < bytes.len() && bytes[index + 1].is_ascii_digit() {
+                        index += 1;
                     }
-                    index += (to - from) - 1;
-                    Token::Number(String::from_utf8_lossy(&bytes[from..to]).parse()?)
+                    let number = input[position..=index]
+                        .parse()
+                        .map_err(|e| error::Error::Parse(format!("invalid number at position {}: {}", position, e)))?;
+                    Token::Number(number)
+                }
+                c if c.is_ascii_whitespace() => {
+                    index += 1;
+                    continue;
+                }
+                _ => {
+                    let c = input[position..].chars().next().unwrap();
+                    return Err(error::Error::Parse(format!("unexpected character {:?} at position {}", c, position)));
                 }
             };
 
-            tokens.push(token);
+            tokens.push((position, token));
 
             index += 1;
-
-            if index == bytes.len() {
-                break;
-            }
         }
         Ok(tokens)
     }
 
-    fn parse_element<'a>(tokens: &mut impl Iterator<Item = &'a Token>) -> Result<Element, error::Error> {
-        let token = tokens.next().unwrap();
+    fn next_token<'a>(tokens: &mut impl Iterator<Item = &'a (usize, Token)>, end: usize, expected: &str) -> Result<&'a (usize, Token), error::Error> {
+        tokens.next().ok_or_else(|| error::Error::Parse(format!("truncated input: expected {} at position {}", expected, end)))
+    }
 
-        let element = match token {
-            Token::LeftBracket => {
-                let pair = Element::parse_pair(tokens)?;
+    fn expect<'a>(tokens: &mut impl Iterator<Item = &'a (usize, Token)>, end: usize, expected: Token) -> Result<(), error::Error> {
+        let description = match expected {
+            Token::LeftBracket => "'['",
+            Token::RightBracket => "']'",
+            Token::Comma => "','",
+            Token::Number(_) => "a number",
+        };
+        let (position, token) = Element::next_token(tokens, end, description)?;
+        if *token != expected {
+            return Err(error::Error::Parse(format!("expected {} at position {}, found {:?}", description, position, token)));
+        }
+        Ok(())
+    }
 
-                let rb = tokens.next().unwrap();
-                if rb != &Token::RightBracket {
-                    return Err(error::Error::Parse("expected right bracket".to_string()));
-                }
+    // depth is how many pairs the element is in
+    fn parse_element<'a>(tokens: &mut impl Iterator<Item = &'a (usize, Token)>, end: usize, depth: usize) -> Result<Element, error::Error> {
+        let (position, token) = Element::next_token(tokens, end, "'[' or a number")?;
 
+        let element = match token {
+            Token::LeftBracket if depth >= MAX_DEPTH => {
+                return Err(error::Error::Parse(format!("pair at position {} is nested more than {} deep", position, MAX_DEPTH)));
+            }
+            Token::LeftBracket => {
+                let pair = Element::parse_pair(tokens, end, depth + 1)?;
+                Element::expect(tokens, end, Token::RightBracket)?;
                 pair
             }
+            &Token::Number(n) if n > MAX_NUMBER => {
+                return Err(error::Error::Parse(format!("number at position {} is more than {}", position, MAX_NUMBER)));
+            }
             Token::Number(n) => Element::Number(n.to_owned()),
-            _ => return Err(error::Error::Parse(format!("invalid token for x: {:?}", token))),
+            _ => return Err(error::Error::Parse(format!("expected '[' or a number at position {}, found {:?}", position, token))),
         };
 
         Ok(element)
     }
 
-    fn parse_pair<'a>(tokens: &mut impl Iterator<Item = &'a Token>) -> Result<Element, error::Error> {
-        let x = Element::parse_element(tokens)?;
+    fn parse_pair<'a>(tokens: &mut impl Iterator<Item = &'a (usize, Token)>, end: usize, depth: usize) -> Result<Element, error::Error> {
+        let x = Element::parse_element(tokens, end, depth)?;
+        Element::expect(tokens, end, Token::Comma)?;
+        let y = Element::parse_element(tokens, end, depth)?;
+
+        Ok(Element::Pair(Box::new(x), Box::new(y)))
+    }
+
+    // one line per node, indented by how many pairs enclose it
+    fn fmt_tree(&self, f: &mut core::fmt::Formatter<'_>, depth: usize) -> core::fmt::Result {
+        let indent = "  ".repeat(depth);
+        match self {
+            Element::Pair(x, y) => {
+                let explodes = depth >= 4 && matches!((x.as_ref(), y.as_ref()), (Element::Number(_), Element::Number(_)));
+                writeln!(f, "{}pair (depth {}){}", indent, depth, if explodes { " <- explodes" } else { "" })?;
+                x.fmt_tree(f, depth + 1)?;
+                y.fmt_tree(f, depth + 1)
+            }
+            &Element::Number(n) => writeln!(f, "{}{}{}", indent, n, if n >= 10 { " <- splits" } else { "" }),
+        }
+    }
+
+    pub fn render_tree(&self) -> String {
+        format!("{:#}", self)
+    }
 
-        let token = tokens.next().unwrap();
+    // how many pairs deep the number is nested, a regular number being 0
+    pub fn depth(&self) -> usize {
+        match self {
+            Element::Pair(x, y) => 1 + core::cmp::max(x.depth(), y.depth()),
+            Element::Number(_) => 0,
+        }
+    }
 
-        if token != &Token::Comma {
-            return Err(error::Error::Parse("expected comma".to_string()));
+    pub fn leaf_count(&self) -> usize {
+        match self {
+            Element::Pair(x, y) => x.leaf_count() + y.leaf_count(),
+            Element::Number(_) => 1,
         }
+    }
 
-        let y = Element::parse_element(tokens)?;
+    // checks that the number is fully reduced: nothing to explode and nothing to split
+    pub fn validate(&self) -> Result<(), error::Error> {
+        if self.depth() > 4 {
+            return Err(error::Error::General(format!("{} has a pair nested inside {} pairs", self, self.depth() - 1)));
+        }
+        let mut result = Ok(());
+        self.traverse(0, &mut |element, _| {
+            if let &Element::Number(n) = element {
+                if n >= 10 && result.is_ok() {
+                    result = Err(error::Error::General(format!("{} contains the regular number {}", self, n)));
+                }
+            }
+        });
+        result
+    }
 
-        Ok(Element::Pair(Rc::new(RefCell::new(x)), Rc::new(RefCell::new(y))))
+    fn push_tokens(&self, tokens: &mut Vec<Token>) {
+        match self {
+            Element::Pair(x, y) => {
+                tokens.push(Token::LeftBracket);
+                x.push_tokens(tokens);
+                y.push_tokens(tokens);
+                tokens.push(Token::RightBracket);
+            }
+            &Element::Number(n) => tokens.push(Token::Number(n)),
+        }
     }
 
+    // a reduced number is at most four pairs deep, but an unreduced one can be deep enough for
+    // the magnitude not to fit, and then it stops at i64::MAX
     pub fn magnitude_recursive(element: &Element) -> i64 {
         match element {
-            Element::Pair(x, y) => 3 * Element::magnitude(&x.borrow()) + 2 * Element::magnitude(&y.borrow()),
+            Element::Pair(x, y) => x.magnitude().saturating_mul(3).saturating_add(y.magnitude().saturating_mul(2)),
             Element::Number(n) => *n,
         }
     }
@@ -254,9 +474,42 @@ impl Element {
     }
 }
 
-impl std::iter::Sum for Element {
+// a number known to be fully reduced; plain owned data, so it can be hashed and sent between threads
+#[derive(PartialEq, Eq, Hash, Debug, Clone)]
+pub struct ReducedElement(Element);
+
+impl TryFrom<Element> for ReducedElement {
+    type Error = error::Error;
+
+    fn try_from(element: Element) -> Result<Self, Self::Error> {
+        element.validate()?;
+        Ok(ReducedElement(element))
+    }
+}
+
+impl core::fmt::Display for ReducedElement {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl ReducedElement {
+    pub fn element(&self) -> &Element {
+        &self.0
+    }
+
+    pub fn into_element(self) -> Element {
+        self.0
+    }
+
+    pub fn magnitude(&self) -> i64 {
+        self.0.magnitude()
+    }
+}
+
+impl core::iter::Sum for Element {
     fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
-        iter.reduce(|acc, elem| Element::Pair(Rc::new(RefCell::new(acc)), Rc::new(RefCell::new(elem)))).unwrap()
+        iter.reduce(Element::add).expect("cannot sum an empty list of snailfish numbers")
     }
 }
 
@@ -266,7 +519,174 @@ fn split_number_into_two(number: i64) -> (i64, i64) {
     (left, right)
 }
 
+impl core::fmt::Display for FlatNumber {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let mut previous: Option<&Token> = None;
+        for token in &self.tokens {
+            let starts_value = matches!(token, Token::LeftBracket | Token::Number(_));
+            if starts_value && matches!(previous, Some(Token::RightBracket | Token::Number(_))) {
+                write!(f, ",")?;
+            }
+            match token {
+                Token::LeftBracket => write!(f, "[")?,
+                Token::RightBracket => write!(f, "]")?,
+                Token::Number(n) => write!(f, "{}", n)?,
+                Token::Comma => {}
+            }
+            previous = Some(token);
+        }
+        Ok(())
+    }
+}
+
+impl core::str::FromStr for FlatNumber {
+    type Err = error::Error;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        Ok(FlatNumber::from(&line.parse::<Element>()?))
+    }
+}
+
+impl From<&Element> for FlatNumber {
+    fn from(element: &Element) -> Self {
+        let mut tokens = Vec::new();
+        element.push_tokens(&mut tokens);
+        FlatNumber { tokens }
+    }
+}
+
+impl From<&FlatNumber> for Element {
+    fn from(number: &FlatNumber) -> Self {
+        let mut stack = Vec::new();
+        for token in &number.tokens {
+            match *token {
+                Token::Number(n) => stack.push(Element::Number(n)),
+                Token::RightBracket => {
+                    let y = stack.pop().unwrap();
+                    let x = stack.pop().unwrap();
+                    stack.push(Element::Pair(Box::new(x), Box::new(y)));
+                }
+                _ => {}
+            }
+        }
+        stack.pop().unwrap()
+    }
+}
+
+impl core::ops::Add for FlatNumber {
+    type Output = FlatNumber;
+
+    fn add(self, rhs: FlatNumber) -> FlatNumber {
+        FlatNumber::add(self, rhs)
+    }
+}
+
+impl FlatNumber {
+    #[allow(clippy::should_implement_trait)]
+    pub fn add(lhs: FlatNumber, rhs: FlatNumber) -> FlatNumber {
+        let mut tokens = Vec::with_capacity(lhs.tokens.len() + rhs.tokens.len() + 2);
+        tokens.push(Token::LeftBracket);
+        tokens.extend(lhs.tokens);
+        tokens.extend(rhs.tokens);
+        tokens.push(Token::RightBracket);
+        let mut sum = FlatNumber { tokens };
+        sum.reduce();
+        sum
+    }
+
+    pub fn reduce(&mut self) {
+        loop {
+            if !self.explode() && !self.split() {
+                break;
+            }
+        }
+    }
+
+    // same rules as Element::explode, but the neighbours are simply the closest numbers in the list
+    pub fn explode(&mut self) -> bool {
+        let mut depth = 0;
+        let mut index = 0;
+        while index < self.tokens.len() {
+            match self.tokens[index] {
+                Token::LeftBracket => depth += 1,
+                Token::RightBracket => depth -= 1,
+                _ => {}
+            }
+            if let (Token::LeftBracket, Some(&[Token::Number(left), Token::Number(right), Token::RightBracket])) = (&self.tokens[index], self.tokens.get(index + 1..index + 4)) {
+                if depth > 4 {
+                    if let Some(Token::Number(n)) = self.tokens[..index].iter_mut().rev().find(|token| matches!(token, Token::Number(_))) {
+                        *n += left;
+                    }
+                    if let Some(Token::Number(n)) = self.tokens[index + 4..].iter_mut().find(|token| matches!(token, Token::Number(_))) {
+                        *n += right;
+                    }
+                    self.tokens.splice(index..index + 4, [Token::Number(0)]);
+                    return true;
+                }
+            }
+            index += 1;
+        }
+        false
+    }
+
+    pub fn split(&mut self) -> bool {
+        let found = self.tokens.iter().position(|token| matches!(token, &Token::Number(n) if n >= 10));
+        match found {
+            Some(index) => {
+                if let Token::Number(n) = self.tokens[index] {
+                    let (left, right) = split_number_into_two(n);
+                    self.tokens.splice(index..=index, [Token::LeftBracket, Token::Number(left), Token::Number(right), Token::RightBracket]);
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn magnitude(&self) -> i64 {
+        let mut stack = Vec::new();
+        for token in &self.tokens {
+            match *token {
+                Token::Number(n) => stack.push(n),
+                Token::RightBracket => {
+                    let y = stack.pop().unwrap();
+                    let x = stack.pop().unwrap();
+                    // saturating like Element::magnitude
+                    stack.push(x.saturating_mul(3).saturating_add(y.saturating_mul(2)));
+                }
+                _ => {}
+            }
+        }
+        stack.pop().unwrap()
+    }
+}
+
+// the largest magnitude of any sum of two different numbers, trying every ordered pair
 pub fn find_max_magnitude(input: &str) -> Result<i64, error::Error> {
+    find_max_magnitude_with(input, Strategy::Tree)
+}
+
+pub fn find_max_magnitude_with(input: &str, strategy: Strategy) -> Result<i64, error::Error> {
+    let numbers = input.trim_start().trim_end().lines().map(|line| line.parse()).collect::<Result<Vec<Element>, _>>()?;
+    let flat_numbers: Vec<FlatNumber> = match strategy {
+        Strategy::Tree => Vec::new(),
+        Strategy::Flat => numbers.iter().map(FlatNumber::from).collect(),
+    };
+
+    let max_magnitude = (0..numbers.len())
+        .into_par_iter()
+        .flat_map_iter(|i| (0..numbers.len()).filter(move |&j| j != i).map(move |j| (i, j)))
+        .map(|(i, j)| match strategy {
+            Strategy::Tree => Element::add(numbers[i].clone(), numbers[j].clone()).magnitude(),
+            Strategy::Flat => FlatNumber::add(flat_numbers[i].clone(), flat_numbers[j].clone()).magnitude(),
+        })
+        .max();
+
+    max_magnitude.ok_or_else(|| error::Error::General("need at least two numbers".to_string()))
+}
+
+// only tries pairs among the 22 most deeply nested lines; fast, but not guaranteed to find the maximum
+pub fn find_max_magnitude_approx(input: &str) -> Result<i64, error::Error> {
     let mut lines: Vec<&str> = input.trim_start().trim_end().lines().collect();
 
     lines.sort_by(|&a, &b| {
@@ -287,20 +707,53 @@ pub fn find_max_magnitude(input: &str) -> Result<i64, error::Error> {
 
     let mut max_magnitude = -1i64;
 
-    for mut combination in lines.combination(2) {
-        for permutation in combination.permutation() {
-            let input: String = permutation.join("\n");
-            let element = Element::new(&input)?;
-            let magnitude = element.borrow().magnitude();
-            if max_magnitude < magnitude {
-                max_magnitude = magnitude;
-            }
+    let numbers = lines.iter().map(|line| line.parse()).collect::<Result<Vec<Element>, _>>()?;
+
+    for permutation in (0..numbers.len()).permutations(2) {
+        let element = Element::add(numbers[permutation[0]].clone(), numbers[permutation[1]].clone());
+        let magnitude = element.magnitude();
+        if max_magnitude < magnitude {
+            max_magnitude = magnitude;
         }
     }
 
     Ok(max_magnitude)
 }
 
+// the snailfish numbers, one per line
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Homework(pub String);
+
+const EXAMPLE: &str = r#"
+[[[0,[5,8]],[[1,7],[9,6]]],[[4,[1,2]],[[1,4],2]]]
+[[[5,[2,8]],4],[5,[[9,9],0]]]
+[6,[[[6,2],[5,6]],[[7,6],[4,7]]]]
+[[[6,[0,7]],[0,9]],[4,[9,[9,0]]]]
+[[[7,[6,4]],[3,[1,3]]],[[[5,5],1],9]]
+[[6,[[7,3],[3,2]]],[[[3,8],[5,7]],4]]
+[[[[5,4],[7,7]],8],[[8,3],8]]
+[[9,3],[[9,9],[6,[4,9]]]]
+[[2,[[7,7],7]],[[5,8],[[9,3],[0,2]]]]
+[[[[5,2],5],[8,[3,7]]],[[5,[7,5]],[4,4]]]"#;
+
+impl puzzle::Puzzle for Homework {
+    fn parse(input: &str) -> Result<Self, error::Error> {
+        Ok(Homework(input.into()))
+    }
+
+    fn part1(&self) -> Result<puzzle::Answer, error::Error> {
+        Ok(Element::new(&self.0)?.magnitude().into())
+    }
+
+    fn part2(&self) -> Result<puzzle::Answer, error::Error> {
+        Ok(find_max_magnitude(&self.0)?.into())
+    }
+
+    fn examples() -> Vec<puzzle::Example> {
+        vec![(EXAMPLE, Some("4140"), Some("3993"))]
+    }
+}
+
 #[test]
 fn test_split_number_into_two() {
     assert_eq!(split_number_into_two(9), (4, 5));
@@ -336,44 +789,49 @@ fn test_pair_tokenizer() -> Result<(), error::Error> {
 #[test]
 fn test_magnitude() -> Result<(), error::Error> {
     let pair = Element::new("[9,1]")?;
-    assert_eq!(pair.borrow().magnitude(), 29);
+    assert_eq!(pair.magnitude(), 29);
     let pair = Element::new("[1,9]")?;
-    assert_eq!(pair.borrow().magnitude(), 21);
+    assert_eq!(pair.magnitude(), 21);
     let pair = Element::new("[[9,1],[1,9]]")?;
-    assert_eq!(pair.borrow().magnitude(), 129);
+    assert_eq!(pair.magnitude(), 129);
     let pair = Element::new("[[1,2],[[3,4],5]]")?;
-    assert_eq!(pair.borrow().magnitude(), 143);
+    assert_eq!(pair.magnitude(), 143);
     let pair = Element::new("[[[[0,7],4],[[7,8],[6,0]]],[8,1]]")?;
-    assert_eq!(pair.borrow().magnitude(), 1384);
+    assert_eq!(pair.magnitude(), 1384);
     let pair = Element::new("[[[[1,1],[2,2]],[3,3]],[4,4]]")?;
-    assert_eq!(pair.borrow().magnitude(), 445);
+    assert_eq!(pair.magnitude(), 445);
     let pair = Element::new("[[[[3,0],[5,3]],[4,4]],[5,5]]")?;
-    assert_eq!(pair.borrow().magnitude(), 791);
+    assert_eq!(pair.magnitude(), 791);
     let pair = Element::new("[[[[5,0],[7,4]],[5,5]],[6,6]]")?;
-    assert_eq!(pair.borrow().magnitude(), 1137);
+    assert_eq!(pair.magnitude(), 1137);
     let pair = Element::new("[[[[8,7],[7,7]],[[8,6],[7,7]]],[[[0,7],[6,6]],[8,7]]]")?;
-    assert_eq!(pair.borrow().magnitude(), 3488);
+    assert_eq!(pair.magnitude(), 3488);
+
+    // too deep for the magnitude to fit
+    let deep = "[".repeat(60) + "9,9]" + &",9]".repeat(59);
+    assert_eq!(Element::new(&deep)?.magnitude(), i64::MAX);
+    assert_eq!(deep.parse::<FlatNumber>()?.magnitude(), i64::MAX);
     Ok(())
 }
 
 #[test]
 fn test_display() -> Result<(), error::Error> {
     let pair = Element::new("[[[[8,7],[7,7]],[[8,6],[7,7]]],[[[0,7],[6,6]],[8,7]]]")?;
-    assert_eq!(format!("{}", pair.borrow()), "[[[[8,7],[7,7]],[[8,6],[7,7]]],[[[0,7],[6,6]],[8,7]]]");
+    assert_eq!(format!("{}", pair), "[[[[8,7],[7,7]],[[8,6],[7,7]]],[[[0,7],[6,6]],[8,7]]]");
     Ok(())
 }
 
 #[test]
 fn test_explode() -> Result<(), error::Error> {
-    let pair = Element::new(r#"[[[[[9,8],1],2],3],4]"#)?;
-    assert!(Element::explode(pair.clone()));
-    assert!(!Element::explode(pair.clone()));
-    assert_eq!(pair.borrow().to_string(), "[[[[0,9],2],3],4]");
+    let mut pair = Element::new(r#"[[[[[9,8],1],2],3],4]"#)?;
+    assert!(pair.explode());
+    assert!(!pair.explode());
+    assert_eq!(pair.to_string(), "[[[[0,9],2],3],4]");
 
-    let pair = Element::new(r#"[7,[6,[5,[4,[3,2]]]]]"#)?;
-    assert!(Element::explode(pair.clone()));
-    assert!(!Element::explode(pair.clone()));
-    assert_eq!(pair.borrow().to_string(), "[7,[6,[5,[7,0]]]]");
+    let mut pair = Element::new(r#"[7,[6,[5,[4,[3,2]]]]]"#)?;
+    assert!(pair.explode());
+    assert!(!pair.explode());
+    assert_eq!(pair.to_string(), "[7,[6,[5,[7,0]]]]");
 
     Ok(())
 }
@@ -387,7 +845,7 @@ fn test_day18() -> Result<(), error::Error> {
 [3,3]
 [4,4]"#,
     )?;
-    assert_eq!(pair.borrow().to_string(), "[[[[1,1],[2,2]],[3,3]],[4,4]]");
+    assert_eq!(pair.to_string(), "[[[[1,1],[2,2]],[3,3]],[4,4]]");
 
     let pair = Element::new(
         r#"
@@ -397,7 +855,7 @@ fn test_day18() -> Result<(), error::Error> {
 [4,4]
 [5,5]"#,
     )?;
-    assert_eq!(pair.borrow().to_string(), "[[[[3,0],[5,3]],[4,4]],[5,5]]");
+    assert_eq!(pair.to_string(), "[[[[3,0],[5,3]],[4,4]],[5,5]]");
 
     let pair = Element::new(
         r#"
@@ -408,7 +866,7 @@ fn test_day18() -> Result<(), error::Error> {
 [5,5]
 [6,6]"#,
     )?;
-    assert_eq!(pair.borrow().to_string(), "[[[[5,0],[7,4]],[5,5]],[6,6]]");
+    assert_eq!(pair.to_string(), "[[[[5,0],[7,4]],[5,5]],[6,6]]");
 
     let pair = Element::new(
         r#"
@@ -423,48 +881,276 @@ fn test_day18() -> Result<(), error::Error> {
 [[[5,[7,4]],7],1]
 [[[[4,2],2],6],[8,7]]"#,
     )?;
-    assert_eq!(pair.borrow().to_string(), "[[[[8,7],[7,7]],[[8,6],[7,7]]],[[[0,7],[6,6]],[8,7]]]");
+    assert_eq!(pair.to_string(), "[[[[8,7],[7,7]],[[8,6],[7,7]]],[[[0,7],[6,6]],[8,7]]]");
 
-    let pair = Element::new(
-        r#"
-[[[0,[5,8]],[[1,7],[9,6]]],[[4,[1,2]],[[1,4],2]]]
-[[[5,[2,8]],4],[5,[[9,9],0]]]
-[6,[[[6,2],[5,6]],[[7,6],[4,7]]]]
-[[[6,[0,7]],[0,9]],[4,[9,[9,0]]]]
-[[[7,[6,4]],[3,[1,3]]],[[[5,5],1],9]]
-[[6,[[7,3],[3,2]]],[[[3,8],[5,7]],4]]
-[[[[5,4],[7,7]],8],[[8,3],8]]
-[[9,3],[[9,9],[6,[4,9]]]]
-[[2,[[7,7],7]],[[5,8],[[9,3],[0,2]]]]
-[[[[5,2],5],[8,[3,7]]],[[5,[7,5]],[4,4]]]"#,
-    )?;
-    assert_eq!(pair.borrow().to_string(), "[[[[6,6],[7,6]],[[7,7],[7,0]]],[[[7,7],[7,7]],[[7,8],[9,9]]]]");
+    let pair = Element::new(EXAMPLE)?;
+    assert_eq!(pair.to_string(), "[[[[6,6],[7,6]],[[7,7],[7,0]]],[[[7,7],[7,7]],[[7,8],[9,9]]]]");
 
     let pair = Element::new(&std::fs::read_to_string("input_day18")?)?;
-    assert_eq!(pair.borrow().magnitude(), 3806);
+    assert_eq!(pair.magnitude(), 3806);
 
     Ok(())
 }
 
 #[test]
 fn test_day18_part2() -> Result<(), error::Error> {
-    let s = r#"
-[[[0,[5,8]],[[1,7],[9,6]]],[[4,[1,2]],[[1,4],2]]]
-[[[5,[2,8]],4],[5,[[9,9],0]]]
-[6,[[[6,2],[5,6]],[[7,6],[4,7]]]]
-[[[6,[0,7]],[0,9]],[4,[9,[9,0]]]]
-[[[7,[6,4]],[3,[1,3]]],[[[5,5],1],9]]
-[[6,[[7,3],[3,2]]],[[[3,8],[5,7]],4]]
-[[[[5,4],[7,7]],8],[[8,3],8]]
-[[9,3],[[9,9],[6,[4,9]]]]
-[[2,[[7,7],7]],[[5,8],[[9,3],[0,2]]]]
-[[[[5,2],5],[8,[3,7]]],[[5,[7,5]],[4,4]]]"#;
+    let s = EXAMPLE;
 
     assert_eq!(find_max_magnitude(s)?, 3993);
+    assert_eq!(find_max_magnitude_approx(s)?, 3993);
 
     let s = std::fs::read_to_string("input_day18")?;
 
     assert_eq!(find_max_magnitude(&s)?, 4727);
+    assert_eq!(find_max_magnitude_approx(&s)?, 4727);
+
+    // the best pair is not among the most nested lines, so only the exhaustive search finds it
+    let s = [vec!["[[[[1,1],[1,1]],[[1,1],[1,1]]],[[[1,1],[1,1]],[[1,1],[1,1]]]]"; 22], vec!["[9,9]", "[[9,9],[9,9]]"]]
+        .concat()
+        .join("\n");
+    let nines = Element::add("[[9,9],[9,9]]".parse()?, "[9,9]".parse()?).magnitude();
+    assert!(find_max_magnitude(&s)? >= nines);
+    assert!(find_max_magnitude_approx(&s)? < find_max_magnitude(&s)?);
+    assert!(find_max_magnitude("[1,1]").is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_day18_add() -> Result<(), error::Error> {
+    let a = Element::new("[[[[4,3],4],4],[7,[[8,4],9]]]")?;
+    let b = Element::new("[1,1]")?;
+    assert_eq!((a.clone() + b.clone()).to_string(), "[[[[0,7],4],[[7,8],[6,0]]],[8,1]]");
+
+    let mut sum = Element::new("[[[0,[4,5]],[0,0]],[[[4,5],[2,6]],[9,5]]]")?;
+    sum += Element::new("[7,[[[3,7],[4,3]],[[6,3],[8,8]]]]")?;
+    assert_eq!(sum.to_string(), "[[[[4,0],[5,4]],[[7,7],[6,0]]],[[8,[7,7]],[[7,9],[5,0]]]]");
+
+    let mut pair = Element::new("[[[[[4,3],4],4],[7,[[8,4],9]]],[1,1]]")?;
+    assert!(pair.explode());
+    assert_eq!(pair.to_string(), "[[[[0,7],4],[7,[[8,4],9]]],[1,1]]");
+    assert!(pair.explode());
+    assert_eq!(pair.to_string(), "[[[[0,7],4],[15,[0,13]]],[1,1]]");
+    assert!(!pair.explode());
+    assert!(pair.split());
+    assert_eq!(pair.to_string(), "[[[[0,7],4],[[7,8],[0,13]]],[1,1]]");
+
+    Ok(())
+}
+
+#[test]
+fn test_day18_add_and_reduce() -> Result<(), error::Error> {
+    let input = std::fs::read_to_string("input_day18")?;
+    let mut sum: Option<Element> = None;
+    for line in input.lines().filter(|line| !line.trim().is_empty()) {
+        let number: Element = line.parse()?;
+        sum = Some(match sum {
+            None => number,
+            Some(sum) => Element::add(sum, number),
+        });
+    }
+    assert_eq!(sum.unwrap().magnitude(), 3806);
+
+    // an unreduced pair built by hand
+    let mut number = Element::Pair(Box::new("[[[[4,3],4],4],[7,[[8,4],9]]]".parse()?), Box::new("[1,1]".parse()?));
+    assert_eq!(number.to_string(), "[[[[[4,3],4],4],[7,[[8,4],9]]],[1,1]]");
+    number.reduce();
+    assert_eq!(number.to_string(), "[[[[0,7],4],[[7,8],[6,0]]],[8,1]]");
+    number.reduce();
+    assert_eq!(number.to_string(), "[[[[0,7],4],[[7,8],[6,0]]],[8,1]]");
+
+    Ok(())
+}
+
+#[test]
+fn test_day18_parse_errors() -> Result<(), error::Error> {
+    let parse = |s: &str| match s.parse::<Element>() {
+        Err(error::Error::Parse(message)) => message,
+        other => panic!("expected a parse error for {:?}, got {:?}", s, other),
+    };
+    assert_eq!(parse("[1,2]]"), "unbalanced brackets: unexpected RightBracket at position 5 after the end of the number");
+    assert_eq!(parse("[[1,2]"), "truncated input: expected ',' at position 6");
+    assert_eq!(parse("[1,2"), "truncated input: expected ']' at position 4");
+    assert_eq!(parse("[1,x]"), "unexpected character 'x' at position 3");
+    assert_eq!(parse("[1;2]"), "unexpected character ';' at position 2");
+    assert_eq!(parse("[1,]"), "expected '[' or a number at position 3, found RightBracket");
+    assert_eq!(parse("[1 2]"), "expected ',' at position 3, found Number(2)");
+    assert_eq!(parse("1"), "expected '[' at position 0, found Number(1)");
+    assert_eq!(parse(""), "truncated input: expected '[' at position 0");
+    assert_eq!(parse("[1,é]"), "unexpected character 'é' at position 3");
+    assert!(parse("[1,99999999999999999999]").starts_with("invalid number at position 3"));
+    assert_eq!(parse("[1,100]"), "number at position 3 is more than 99");
+    assert_eq!(format!("[1,{}]", MAX_NUMBER).parse::<Element>()?.magnitude(), 3 + 2 * MAX_NUMBER);
+    let nested = |depth: usize| "[".repeat(depth) + "1,2]" + &",3]".repeat(depth - 1);
+    assert_eq!(nested(MAX_DEPTH).parse::<Element>()?.depth(), MAX_DEPTH);
+    assert_eq!(parse(&nested(MAX_DEPTH + 1)), format!("pair at position {} is nested more than {} deep", MAX_DEPTH, MAX_DEPTH));
+
+    assert_eq!(" [ 1 , 2 ] ".parse::<Element>()?.to_string(), "[1,2]");
+    assert_eq!(Element::new("[1,1]\n[2,2"), Err(error::Error::Parse("line 2: truncated input: expected ']' at position 4".to_string())));
+    assert!(Element::new("\n").is_err());
+    assert!(Element::tokenize("[1,2]x").is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_day18_reduction() -> Result<(), error::Error> {
+    let sum = Element::Pair(Box::new("[[[[4,3],4],4],[7,[[8,4],9]]]".parse()?), Box::new("[1,1]".parse()?));
+    let mut reduction = sum.reduction();
+    let steps: Vec<ReductionStep> = reduction.by_ref().collect();
+    assert_eq!(
+        steps.iter().map(|step| step.to_string()).collect::<Vec<_>>().join("\n"),
+        r#"after explode:  [[[[0,7],4],[7,[[8,4],9]]],[1,1]]
+after explode:  [[[[0,7],4],[15,[0,13]]],[1,1]]
+after split:    [[[[0,7],4],[[7,8],[0,13]]],[1,1]]
+after split:    [[[[0,7],4],[[7,8],[0,[6,7]]]],[1,1]]
+after explode:  [[[[0,7],4],[[7,8],[6,0]]],[8,1]]"#
+    );
+    assert_eq!(
+        steps.iter().map(|step| step.action).collect::<Vec<_>>(),
+        vec![
+            Action::Explode { position: 0, left: 4, right: 3 },
+            Action::Explode { position: 4, left: 8, right: 4 },
+            Action::Split { position: 3, number: 15 },
+            Action::Split { position: 6, number: 13 },
+            Action::Explode { position: 6, left: 6, right: 7 },
+        ]
+    );
+    assert_eq!(reduction.next(), None);
+    assert_eq!(reduction.into_element(), Element::new("[[[[4,3],4],4],[7,[[8,4],9]]]\n[1,1]")?);
+
+    assert_eq!(Element::new("[1,2]")?.reduction().count(), 0);
+
+    Ok(())
+}
+
+#[test]
+fn test_day18_structure() -> Result<(), error::Error> {
+    let number: Element = "[[[[[4,3],4],4],[7,[[8,4],9]]],[1,1]]".parse()?;
+    assert_eq!(number.depth(), 5);
+    assert_eq!(number.leaf_count(), 10);
+    assert!(number.validate().is_err());
+    assert_eq!(Element::Number(3).depth(), 0);
+    assert_eq!(Element::Number(3).leaf_count(), 1);
+
+    let number: Element = "[[[[0,7],4],[15,[0,13]]],[1,1]]".parse()?;
+    assert_eq!(number.depth(), 4);
+    assert_eq!(
+        number.validate(),
+        Err(error::Error::General("[[[[0,7],4],[15,[0,13]]],[1,1]] contains the regular number 15".to_string()))
+    );
+
+    let sum = Element::new(&std::fs::read_to_string("input_day18")?)?;
+    sum.validate()?;
+    assert!(sum.depth() <= 4);
+
+    Ok(())
+}
+
+#[test]
+fn test_day18_render_tree() -> Result<(), error::Error> {
+    let number: Element = "[[1,2],3]".parse()?;
+    assert_eq!(
+        number.render_tree(),
+        r#"pair (depth 0)
+  pair (depth 1)
+    1
+    2
+  3
+"#
+    );
+    assert_eq!(format!("{}", number), "[[1,2],3]");
+
+    let number: Element = "[[[[[9,8],1],2],3],12]".parse()?;
+    assert_eq!(
+        format!("{:#}", number),
+        r#"pair (depth 0)
+  pair (depth 1)
+    pair (depth 2)
+      pair (depth 3)
+        pair (depth 4) <- explodes
+          9
+          8
+        1
+      2
+    3
+  12 <- splits
+"#
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_day18_flat() -> Result<(), error::Error> {
+    let mut number: FlatNumber = "[[[[[4,3],4],4],[7,[[8,4],9]]],[1,1]]".parse()?;
+    assert_eq!(number.to_string(), "[[[[[4,3],4],4],[7,[[8,4],9]]],[1,1]]");
+    assert!(number.explode());
+    assert_eq!(number.to_string(), "[[[[0,7],4],[7,[[8,4],9]]],[1,1]]");
+    assert!(number.explode());
+    assert_eq!(number.to_string(), "[[[[0,7],4],[15,[0,13]]],[1,1]]");
+    assert!(!number.explode());
+    assert!(number.split());
+    assert_eq!(number.to_string(), "[[[[0,7],4],[[7,8],[0,13]]],[1,1]]");
+    number.reduce();
+    assert_eq!(number.to_string(), "[[[[0,7],4],[[7,8],[6,0]]],[8,1]]");
+    assert_eq!(number.magnitude(), Element::from(&number).magnitude());
+
+    let mut number: FlatNumber = "[7,[6,[5,[4,[3,2]]]]]".parse()?;
+    assert!(number.explode());
+    assert_eq!(number.to_string(), "[7,[6,[5,[7,0]]]]");
+
+    // the largest numbers that parse still reduce, and the same way both ways
+    let big = format!("[{0},[{0},{0}]]", MAX_NUMBER);
+    let tree = Element::add(big.parse()?, big.parse()?);
+    assert_eq!(Element::from(&FlatNumber::add(big.parse()?, big.parse()?)), tree);
+    assert!(tree.depth() <= 4);
+
+    let input = std::fs::read_to_string("input_day18")?;
+    let tree = Element::new_with(&input, Strategy::Tree)?;
+    let flat = Element::new_with(&input, Strategy::Flat)?;
+    assert_eq!(tree, flat);
+    assert_eq!(flat.magnitude(), 3806);
+    assert!(Element::new_with("", Strategy::Flat).is_err());
+
+    for strategy in [Strategy::Tree, Strategy::Flat] {
+        assert_eq!(find_max_magnitude_with(&input, strategy)?, 4727);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_day18_sum() -> Result<(), error::Error> {
+    let input = std::fs::read_to_string("input_day18")?;
+    let numbers = input.lines().filter(|line| !line.trim().is_empty()).map(|line| line.parse()).collect::<Result<Vec<Element>, _>>()?;
+    let sum: Element = numbers.into_iter().sum();
+    assert_eq!(sum, Element::new(&input)?);
+    assert_eq!(sum.magnitude(), 3806);
+
+    let sum: Element = ["[[[[4,3],4],4],[7,[[8,4],9]]]".parse::<Element>()?, "[1,1]".parse()?].into_iter().sum();
+    assert_eq!(sum.to_string(), "[[[[0,7],4],[[7,8],[6,0]]],[8,1]]");
+
+    Ok(())
+}
+
+#[test]
+fn test_day18_reduced_element() -> Result<(), error::Error> {
+    let sum = ReducedElement::try_from(Element::new(&std::fs::read_to_string("input_day18")?)?)?;
+    let shared = alloc::sync::Arc::new(sum.clone());
+    let magnitude = {
+        let shared = shared.clone();
+        std::thread::spawn(move || shared.magnitude()).join().unwrap()
+    };
+    assert_eq!(magnitude, 3806);
+
+    let mut seen = crate::collections::HashSet::new();
+    assert!(seen.insert(sum.clone()));
+    assert!(!seen.insert(ReducedElement::try_from(sum.element().clone())?));
+    assert_eq!(sum.to_string(), sum.element().to_string());
+
+    assert!(ReducedElement::try_from("[[[[[4,3],4],4],[7,[[8,4],9]]],[1,1]]".parse::<Element>()?).is_err());
+    assert!(ReducedElement::try_from("[10,1]".parse::<Element>()?).is_err());
 
     Ok(())
 }
@@ -1,15 +1,47 @@
 use crate::error;
-
+use crate::parse;
+use crate::puzzle;
+
+use crate::collections::{HashMap, HashSet};
+use crate::par::*;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt::Formatter;
 use itertools::Itertools;
-use std::collections::{HashMap, HashSet};
-use std::fmt::Formatter;
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Game {
     scanners: Vec<Vec<Vec3D>>,
+    sensor: Sensor,
+}
+
+// how many probes two scanners must share to be lined up, and how far a scanner can see along each axis
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Sensor {
+    pub min_overlap: usize,
+    pub range: i64,
+}
+
+// far beyond any scanner's range, and small enough that the squared distances between probes can't overflow
+pub const MAX_COORDINATE: i64 = 1 << 20;
+
+impl Default for Sensor {
+    fn default() -> Self {
+        Sensor { min_overlap: 12, range: 1000 }
+    }
+}
+
+impl Sensor {
+    // every two shared probes share the distance between them
+    fn min_shared_distances(&self) -> usize {
+        self.min_overlap * self.min_overlap.saturating_sub(1) / 2
+    }
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Ord, PartialOrd, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Vec3D {
     pub x: i64,
     pub y: i64,
@@ -22,14 +54,14 @@ struct DistanceAndPos {
     pos: Vec3D,
 }
 
-impl std::fmt::Display for Vec3D {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+impl core::fmt::Display for Vec3D {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         write!(f, "[{},{},{}]", self.x, self.y, self.z)
     }
 }
 
-impl std::fmt::Display for DistanceAndPos {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+impl core::fmt::Display for DistanceAndPos {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         write!(f, "{}=>{}", self.distance, self.pos)
     }
 }
@@ -39,8 +71,19 @@ impl Vec3D {
         let a = self.x.pow(2);
         let b = self.y.pow(2);
         let c = self.z.pow(2);
-        let distance = ((a + b + c) as f64).sqrt();
-        distance.round() as i64
+        // the square root rounded to the nearest whole number. f64::sqrt needs std, and the root
+        // is past halfway to the next number once the square is more than root * (root + 1)
+        let squared = a + b + c;
+        let root = squared.isqrt();
+        if squared > root * (root + 1) {
+            root + 1
+        } else {
+            root
+        }
+    }
+
+    pub fn squared_distance(&self) -> i64 {
+        self.x.pow(2) + self.y.pow(2) + self.z.pow(2)
     }
 
     pub fn subtract(&self, rhs: &Vec3D) -> Vec3D {
@@ -62,91 +105,166 @@ impl Vec3D {
         }
     }
 
-    pub fn transform_and_flip(&self, transformation: &ScannerTransformation) -> Vec3D {
-        let mut new_pos = *self;
+    pub fn rotate(&self, rotation: &Rotation) -> Vec3D {
+        rotation.apply(self)
+    }
+}
 
-        new_pos = match transformation.rotation {
-            ScannerRotation::XYZ => Vec3D {
-                x: new_pos.x,
-                y: new_pos.y,
-                z: new_pos.z,
-            },
-            ScannerRotation::XZY => Vec3D {
-                x: new_pos.x,
-                y: new_pos.z,
-                z: new_pos.y,
-            },
-            ScannerRotation::YXZ => Vec3D {
-                x: new_pos.y,
-                y: new_pos.x,
-                z: new_pos.z,
-            },
-            ScannerRotation::YZX => Vec3D {
-                x: new_pos.y,
-                y: new_pos.z,
-                z: new_pos.x,
-            },
-            ScannerRotation::ZXY => Vec3D {
-                x: new_pos.z,
-                y: new_pos.x,
-                z: new_pos.y,
-            },
-            ScannerRotation::ZYX => Vec3D {
-                x: new_pos.z,
-                y: new_pos.y,
-                z: new_pos.x,
-            },
-        };
+// one of the 24 ways a scanner can be turned: a 3x3 matrix with a single 1 or -1 in every row and column
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rotation {
+    rows: [[i64; 3]; 3],
+}
+
+impl core::fmt::Display for Rotation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        let rows: Vec<String> = self.rows.iter().map(|row| format!("[{},{},{}]", row[0], row[1], row[2])).collect();
+        write!(f, "[{}]", rows.join(","))
+    }
+}
 
-        if transformation.flip_x {
-            new_pos.x = -new_pos.x;
+impl Rotation {
+    pub fn identity() -> Rotation {
+        Rotation {
+            rows: [[1, 0, 0], [0, 1, 0], [0, 0, 1]],
         }
+    }
+
+    pub fn from_rows(rows: [[i64; 3]; 3]) -> Rotation {
+        Rotation { rows }
+    }
 
-        if transformation.flip_y {
-            new_pos.y = -new_pos.y;
+    // every axis permutation with every choice of signs, keeping the ones that don't mirror
+    pub fn all() -> Vec<Rotation> {
+        let mut rotations = Vec::with_capacity(24);
+        for axes in (0..3).permutations(3) {
+            for signs in 0..8 {
+                let mut rows = [[0; 3]; 3];
+                for (row, &axis) in axes.iter().enumerate() {
+                    rows[row][axis] = if signs & (1 << row) != 0 { -1 } else { 1 };
+                }
+                let rotation = Rotation { rows };
+                if rotation.determinant() == 1 {
+                    rotations.push(rotation);
+                }
+            }
         }
+        rotations
+    }
 
-        if transformation.flip_z {
-            new_pos.z = -new_pos.z;
+    pub fn determinant(&self) -> i64 {
+        let m = &self.rows;
+        m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1]) - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0]) + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+    }
+
+    pub fn apply(&self, pos: &Vec3D) -> Vec3D {
+        let row = |r: &[i64; 3]| r[0] * pos.x + r[1] * pos.y + r[2] * pos.z;
+        Vec3D {
+            x: row(&self.rows[0]),
+            y: row(&self.rows[1]),
+            z: row(&self.rows[2]),
         }
+    }
 
-        new_pos
+    // self first, then next
+    pub fn then(&self, next: &Rotation) -> Rotation {
+        let mut rows = [[0; 3]; 3];
+        for (i, row) in rows.iter_mut().enumerate() {
+            for (j, cell) in row.iter_mut().enumerate() {
+                *cell = (0..3).map(|k| next.rows[i][k] * self.rows[k][j]).sum();
+            }
+        }
+        Rotation { rows }
+    }
+
+    // rotations are orthogonal, so the transpose undoes them
+    pub fn inverse(&self) -> Rotation {
+        let mut rows = [[0; 3]; 3];
+        for (i, row) in rows.iter_mut().enumerate() {
+            for (j, cell) in row.iter_mut().enumerate() {
+                *cell = self.rows[j][i];
+            }
+        }
+        Rotation { rows }
     }
 }
 
-#[derive(Debug, Clone, Copy)]
-pub enum ScannerRotation {
-    XYZ,
-    XZY,
-    YXZ,
-    YZX,
-    ZXY,
-    ZYX,
+// where a scanner sits relative to scanner 0, and how its readings are turned to line up with it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScannerPlacement {
+    pub index: usize,
+    pub position: Vec3D,
+    pub rotation: Rotation,
 }
 
-#[derive(Debug)]
-pub struct ScannerTransformation {
-    rotation: ScannerRotation,
-    flip_x: bool,
-    flip_y: bool,
-    flip_z: bool,
+#[derive(Debug, PartialEq)]
+pub struct ScanResult {
+    // every probe, in the coordinates of scanner 0, without duplicates
+    pub probes: Vec<Vec3D>,
+    // one per scanner, in input order
+    pub scanners: Vec<ScannerPlacement>,
 }
 
-impl std::fmt::Display for ScannerTransformation {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "[{:?}|{}|{}|{}]", self.rotation, self.flip_x, self.flip_y, self.flip_z)
+impl ScanResult {
+    pub fn scanner_positions(&self) -> Vec<Vec3D> {
+        self.scanners.iter().map(|scanner| scanner.position).collect()
     }
 }
 
-impl std::str::FromStr for Game {
+impl Game {
+    pub fn with_min_overlap(self, min_overlap: usize) -> Game {
+        Game {
+            sensor: Sensor { min_overlap, ..self.sensor },
+            ..self
+        }
+    }
+
+    pub fn with_range(self, range: i64) -> Game {
+        Game {
+            sensor: Sensor { range, ..self.sensor },
+            ..self
+        }
+    }
+
+    pub fn find_probes_and_scanners(&self) -> Result<ScanResult, error::Error> {
+        find_probes_and_scanners_with(&self.scanners, &self.sensor)
+    }
+
+    // lines up one more scanner report against the probes found so far and adds it to both,
+    // leaving them untouched if it doesn't overlap
+    pub fn register(&mut self, result: &mut ScanResult, probes: Vec<Vec3D>) -> Result<ScannerPlacement, error::Error> {
+        let name = format!("scanner {} and the probes found so far", self.scanners.len());
+        let alignment = align(&probes, &result.probes, &self.sensor, &name)?
+            .ok_or_else(|| error::Error::General(format!("scanner {} doesn't share {} probes with the ones found so far", self.scanners.len(), self.sensor.min_overlap)))?;
+        let (position, rotation) = (alignment.scanner_position, alignment.rotation);
+
+        let placement = ScannerPlacement {
+            index: self.scanners.len(),
+            position,
+            rotation,
+        };
+        result.probes.extend(probes.iter().map(|p| p.rotate(&rotation).move_to_scanner(&position)));
+        result.probes.sort();
+        result.probes.dedup();
+        result.scanners.push(placement);
+        self.scanners.push(probes);
+
+        Ok(placement)
+    }
+}
+
+impl core::str::FromStr for Game {
     type Err = error::Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut game = Game { scanners: Vec::new() };
+        let mut game = Game {
+            scanners: Vec::new(),
+            sensor: Sensor::default(),
+        };
 
         let mut probes = Vec::new();
 
-        for line in s.trim_start().trim_end().lines().filter(|l| !l.is_empty()) {
+        for (number, line) in parse::lines(s) {
             if line.starts_with("---") {
                 if !probes.is_empty() {
                     game.scanners.push(probes);
@@ -154,7 +272,10 @@ impl std::str::FromStr for Game {
                 probes = Vec::new();
                 continue;
             }
-            let (x, y, z) = scan_fmt::scan_fmt!(line, "{d},{d},{d}", i64, i64, i64)?;
+            let [x, y, z] = parse::on_line(number, parse::scan(line, "{},{},{}"))?;
+            if [x, y, z].iter().any(|c| !(-MAX_COORDINATE..=MAX_COORDINATE).contains(c)) {
+                return Err(error::Error::Parse(format!("line {}, probe {:?} is more than {} away from its scanner", number, line, MAX_COORDINATE)));
+            }
             probes.push(Vec3D { x, y, z })
         }
 
@@ -179,6 +300,10 @@ fn get_probe_distances_for_probe_index(probes: &Vec<Vec3D>, index: usize) -> Vec
 }
 
 pub fn find_probe_indexes_with_enough_overlapping_probes(lhs: &Vec<Vec3D>, rhs: &Vec<Vec3D>) -> Option<(usize, usize)> {
+    find_anchor_probes(lhs, rhs, &Sensor::default())
+}
+
+fn find_anchor_probes(lhs: &Vec<Vec3D>, rhs: &Vec<Vec3D>, sensor: &Sensor) -> Option<(usize, usize)> {
     let mut counts = Vec::new();
     let range = 0..usize::max(lhs.len(), rhs.len());
     let mut done: HashSet<(usize, usize)> = HashSet::new();
@@ -193,10 +318,10 @@ pub fn find_probe_indexes_with_enough_overlapping_probes(lhs: &Vec<Vec3D>, rhs:
         }
         let lhs_distances = get_probe_distances_for_probe_index(lhs, lhs_index);
         let rhs_distances = get_probe_distances_for_probe_index(rhs, rhs_index);
-        let lhs_set: HashSet<i64> = HashSet::from_iter(lhs_distances.iter().map(|x| x.distance.distance()));
+        let lhs_set: HashSet<i64> = HashSet::from_iter(lhs_distances.iter().map(|x| x.distance.squared_distance()));
         let mut count = 0;
         for distance in rhs_distances {
-            if lhs_set.contains(&distance.distance.distance()) {
+            if lhs_set.contains(&distance.distance.squared_distance()) {
                 count += 1;
             }
         }
@@ -211,7 +336,7 @@ pub fn find_probe_indexes_with_enough_overlapping_probes(lhs: &Vec<Vec3D>, rhs:
         None
     } else {
         let (count, lhs_index, rhs_index) = counts[0];
-        if count >= 12 {
+        if count >= sensor.min_overlap {
             Some((lhs_index, rhs_index))
         } else {
             None
@@ -220,115 +345,147 @@ pub fn find_probe_indexes_with_enough_overlapping_probes(lhs: &Vec<Vec3D>, rhs:
 }
 
 pub struct ConvertResult {
-    probes: Vec<Vec3D>,
-    scanner_position: Vec3D,
-    scanner_transformation: ScannerTransformation,
+    pub probes: Vec<Vec3D>,
+    pub scanner_position: Vec3D,
+    pub scanner_rotation: Rotation,
 }
 
-pub fn convert_probes(scanner_from: &Vec<Vec3D>, scanner_to: &Vec<Vec3D>) -> Option<ConvertResult> {
-    if let Some((probe_index_from, probe_index_to)) = find_probe_indexes_with_enough_overlapping_probes(scanner_from, scanner_to) {
-        let distances_from = get_probe_distances_for_probe_index(scanner_from, probe_index_from);
-        let distances_to = get_probe_distances_for_probe_index(scanner_to, probe_index_to);
+// None if the scanners don't overlap, an error if they do but no rotation lines them up
+pub fn convert_probes(scanner_from: &Vec<Vec3D>, scanner_to: &Vec<Vec3D>) -> Result<Option<ConvertResult>, error::Error> {
+    let alignment = align(scanner_from, scanner_to, &Sensor::default(), "the scanners")?;
+    Ok(alignment.map(|alignment| ConvertResult {
+        probes: scanner_from.iter().map(|p| alignment.apply(p)).collect(),
+        scanner_position: alignment.scanner_position,
+        scanner_rotation: alignment.rotation,
+    }))
+}
+
+// lines up scanner_from with scanner_to; None if they don't share enough probes, an error if they seem to
+// but there isn't exactly one rotation and position that puts enough of them right on top of each other
+fn align(scanner_from: &Vec<Vec3D>, scanner_to: &Vec<Vec3D>, sensor: &Sensor, name: &str) -> Result<Option<Alignment>, error::Error> {
+    if find_anchor_probes(scanner_from, scanner_to, sensor).is_none() {
+        return Ok(None);
+    }
 
-        let map: HashMap<i64, Vec3D> = HashMap::from_iter(distances_to.iter().map(|x| (x.distance.distance(), x.pos)));
-        let mut same_probes = Vec::new();
-        for dist_and_pos in distances_from.iter() {
-            if map.contains_key(&dist_and_pos.distance.distance()) {
-                let to = map[&dist_and_pos.distance.distance()];
-                let from = dist_and_pos.pos;
-                same_probes.push(VecPair { to, from });
+    // every probe pair votes for the position it implies; a position only counts if enough probes agree exactly
+    let mut verified = Vec::new();
+    for rotation in Rotation::all() {
+        let mut votes: HashMap<Vec3D, usize> = HashMap::new();
+        for from in scanner_from.iter() {
+            let turned = from.rotate(&rotation);
+            for to in scanner_to.iter() {
+                *votes.entry(to.subtract(&turned)).or_insert(0) += 1;
             }
         }
+        verified.extend(
+            votes
+                .into_iter()
+                .filter(|&(_, count)| count >= sensor.min_overlap)
+                .map(|(scanner_position, _)| Alignment { rotation, scanner_position }),
+        );
+    }
 
-        if let Some((scanner_position, scanner_transformation)) = find_rhs_scanner_position_and_transformation(&same_probes) {
-            let probes = scanner_from.iter().map(|&p| p.transform_and_flip(&scanner_transformation).move_to_scanner(&scanner_position)).collect();
-
-            Some(ConvertResult {
-                probes,
-                scanner_position,
-                scanner_transformation,
-            })
-        } else {
-            panic!("failed to find scanner position and transformation for\nfrom: {:?}\nto: {:?}", &scanner_from, &scanner_to,);
-        }
-    } else {
-        None
+    match verified.len() {
+        0 => Err(error::Error::General(format!("{} share enough probes, but no rotation lines them up", name))),
+        1 => Ok(Some(verified[0])),
+        n => Err(error::Error::General(format!("{} line up in {} different ways", name, n))),
     }
 }
 
-struct VecPair {
-    from: Vec3D,
-    to: Vec3D,
+// how probes of one scanner end up in the coordinates of another: turned first, then moved
+#[derive(Debug, Clone, Copy)]
+struct Alignment {
+    rotation: Rotation,
+    scanner_position: Vec3D,
 }
 
-fn find_rhs_scanner_position_and_transformation(positions: &Vec<VecPair>) -> Option<(Vec3D, ScannerTransformation)> {
-    let rotations = vec![
-        ScannerRotation::XYZ,
-        ScannerRotation::XZY,
-        ScannerRotation::YXZ,
-        ScannerRotation::YZX,
-        ScannerRotation::ZXY,
-        ScannerRotation::ZYX,
-    ];
+impl Alignment {
+    fn identity() -> Alignment {
+        Alignment {
+            rotation: Rotation::identity(),
+            scanner_position: Vec3D { x: 0, y: 0, z: 0 },
+        }
+    }
 
-    let flips = vec![
-        (false, false, false),
-        (false, false, true),
-        (false, true, false),
-        (false, true, true),
-        (true, false, false),
-        (true, false, true),
-        (true, true, false),
-        (true, true, true),
-    ];
+    fn apply(&self, pos: &Vec3D) -> Vec3D {
+        pos.rotate(&self.rotation).move_to_scanner(&self.scanner_position)
+    }
 
-    for rotation in rotations.iter() {
-        for &flip in flips.iter() {
-            let transformation = ScannerTransformation {
-                rotation: *rotation,
-                flip_x: flip.0,
-                flip_y: flip.1,
-                flip_z: flip.2,
-            };
-            let mut sample = None;
-            let num_matching = positions
-                .windows(2)
-                .filter(|window| {
-                    let from_1 = window[0].from.transform_and_flip(&transformation);
-                    let from_2 = window[1].from.transform_and_flip(&transformation);
-                    let to_1 = window[0].to;
-                    let to_2 = window[1].to;
-                    let from_diff = from_1.subtract(&from_2);
-                    let to_diff = to_1.subtract(&to_2);
-                    if from_diff == to_diff && sample.is_none() {
-                        sample = Some(VecPair { from: from_1, to: to_1 });
-                    }
-                    from_diff == to_diff
-                })
-                .count();
-            if num_matching >= 7 {
-                let sample = sample.unwrap();
-                let scanner_position = sample.to.subtract(&sample.from);
-                return Some((scanner_position, transformation));
-            }
+    // self first, then next
+    fn then(&self, next: &Alignment) -> Alignment {
+        Alignment {
+            rotation: self.rotation.then(&next.rotation),
+            scanner_position: next.apply(&self.scanner_position),
         }
     }
 
-    None
+    fn inverse(&self) -> Alignment {
+        let rotation = self.rotation.inverse();
+        let moved = self.scanner_position.rotate(&rotation);
+        Alignment {
+            rotation,
+            scanner_position: Vec3D {
+                x: -moved.x,
+                y: -moved.y,
+                z: -moved.z,
+            },
+        }
+    }
 }
 
-fn build_graph(scanners: &Vec<Vec<Vec3D>>) -> petgraph::graph::UnGraph<u32, ()> {
-    let mut edges = Vec::new();
-    for indices in (0..scanners.len()).combinations(2) {
-        let index_lhs = indices[0];
-        let index_rhs = indices[1];
-        let scanner_lhs = &scanners[index_lhs];
-        let scanner_rhs = &scanners[index_rhs];
-        if let Some((_, _)) = find_probe_indexes_with_enough_overlapping_probes(scanner_lhs, scanner_rhs) {
-            edges.push((index_lhs as u32, index_rhs as u32));
+// squared distances between every two probes of a scanner, sorted; they don't depend on orientation
+fn fingerprint(probes: &[Vec3D]) -> Vec<i64> {
+    let mut distances: Vec<i64> = probes.iter().tuple_combinations().map(|(a, b)| a.subtract(b).squared_distance()).collect();
+    distances.sort_unstable();
+    distances
+}
+
+// size of the multiset intersection of two sorted fingerprints
+fn count_shared_distances(lhs: &[i64], rhs: &[i64]) -> usize {
+    let (mut i, mut j, mut count) = (0, 0, 0);
+    while i < lhs.len() && j < rhs.len() {
+        match lhs[i].cmp(&rhs[j]) {
+            core::cmp::Ordering::Less => i += 1,
+            core::cmp::Ordering::Greater => j += 1,
+            core::cmp::Ordering::Equal => {
+                count += 1;
+                i += 1;
+                j += 1;
+            }
         }
     }
-    petgraph::graph::UnGraph::<u32, ()>::from_edges(edges)
+    count
+}
+
+fn build_graph(scanners: &Vec<Vec<Vec3D>>, sensor: &Sensor) -> Result<petgraph::graph::UnGraph<u32, Alignment>, error::Error> {
+    let fingerprints: Vec<Vec<i64>> = scanners.iter().map(|probes| fingerprint(probes)).collect();
+    let candidates: Vec<(usize, usize)> = (0..scanners.len())
+        .tuple_combinations()
+        .filter(|&(index_lhs, index_rhs)| count_shared_distances(&fingerprints[index_lhs], &fingerprints[index_rhs]) >= sensor.min_shared_distances())
+        .collect();
+    let edges = candidates
+        .into_par_iter()
+        .filter_map(|(index_lhs, index_rhs)| {
+            let name = format!("scanners {} and {}", index_lhs, index_rhs);
+            let alignment = match align(&scanners[index_lhs], &scanners[index_rhs], sensor, &name) {
+                Ok(alignment) => alignment?,
+                Err(e) => return Some(Err(e)),
+            };
+            // two scanners more than twice the range apart can't have seen the same probes
+            if alignment.scanner_position.any_above(2 * sensor.range) {
+                return None;
+            }
+            Some(Ok((index_lhs as u32, index_rhs as u32, alignment)))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // from_edges only creates the nodes it sees, so make sure every scanner has one
+    let mut graph = petgraph::graph::UnGraph::<u32, Alignment>::with_capacity(scanners.len(), edges.len());
+    for index in 0..scanners.len() {
+        graph.add_node(index as u32);
+    }
+    graph.extend_with_edges(edges);
+    Ok(graph)
 }
 
 pub fn count_same_probes(lhs: &Vec<Vec3D>, rhs: &Vec<Vec3D>) -> usize {
@@ -342,62 +499,76 @@ pub fn count_same_probes(lhs: &Vec<Vec3D>, rhs: &Vec<Vec3D>) -> usize {
     count
 }
 
-pub fn find_probes_and_scanners(scanners: &Vec<Vec<Vec3D>>) -> (Vec<Vec3D>, Vec<Vec3D>) {
-    let graph = build_graph(scanners);
+pub fn find_probes_and_scanners(scanners: &Vec<Vec<Vec3D>>) -> Result<ScanResult, error::Error> {
+    find_probes_and_scanners_with(scanners, &Sensor::default())
+}
+
+pub fn find_probes_and_scanners_with(scanners: &Vec<Vec<Vec3D>>, sensor: &Sensor) -> Result<ScanResult, error::Error> {
+    if scanners.is_empty() {
+        return Err(error::Error::General("no scanners".to_string()));
+    }
+
+    let graph = build_graph(scanners, sensor)?;
 
     let mut all_probes = Vec::new();
-    let mut all_scanners = Vec::new();
 
     all_probes.append(&mut scanners[0].clone());
 
-    for index in 1..scanners.len() {
-        if let Some((_cost, path)) = petgraph::algo::astar(
-            &graph,
-            petgraph::visit::NodeIndexable::from_index(&graph, index),
-            |finish| finish == petgraph::visit::NodeIndexable::from_index(&graph, 0),
-            |_| 1,
-            |_| 0,
-        ) {
-            let mut work_probes = None;
-            let mut scanner = None;
-            for index in path.windows(2) {
-                let from = index[0].index() as usize;
-                let to = index[1].index() as usize;
-                let mut new_probes = scanners[from].clone();
-                if work_probes.is_none() {
-                    work_probes = Some(new_probes);
-                } else {
-                    let mut existing = work_probes.unwrap();
-                    let count = count_same_probes(&existing, &new_probes);
-                    if count != 12 {
-                        panic!("count != 12");
-                    }
-                    existing.append(&mut new_probes);
-                    work_probes = Some(existing);
-                }
-                if let Some(result) = convert_probes(work_probes.as_ref().unwrap(), &scanners[to]) {
-                    work_probes = Some(result.probes);
-                    if scanner.is_none() {
-                        scanner = Some(result.scanner_position);
-                    } else {
-                        let old_scanner = scanner.unwrap();
-                        scanner = Some(old_scanner.transform_and_flip(&result.scanner_transformation).move_to_scanner(&result.scanner_position));
-                    }
-                } else {
-                    panic!("failed to convert probes");
+    // every scanner is aligned along its own path to scanner 0 by chaining the alignments found for each edge
+    let aligned: Vec<Option<(Vec<Vec3D>, ScannerPlacement)>> = (1..scanners.len())
+        .into_par_iter()
+        .map(|index| {
+            if let Some((_cost, path)) = petgraph::algo::astar(
+                &graph,
+                petgraph::visit::NodeIndexable::from_index(&graph, index),
+                |finish| finish == petgraph::visit::NodeIndexable::from_index(&graph, 0),
+                |_| 1,
+                |_| 0,
+            ) {
+                let mut alignment = Alignment::identity();
+                for nodes in path.windows(2) {
+                    let edge = graph.find_edge(nodes[0], nodes[1]).expect("path follows the edges of the graph");
+                    // edges are stored from the lower scanner index to the higher one
+                    let step = if nodes[0] < nodes[1] { graph[edge] } else { graph[edge].inverse() };
+                    alignment = alignment.then(&step);
                 }
+                let probes = scanners[index].iter().map(|p| alignment.apply(p)).collect();
+                Some((
+                    probes,
+                    ScannerPlacement {
+                        index,
+                        position: alignment.scanner_position,
+                        rotation: alignment.rotation,
+                    },
+                ))
+            } else {
+                None
             }
-            all_probes.append(&mut work_probes.unwrap());
-            all_scanners.push(scanner.unwrap());
-        } else {
-            panic!("can't reach scanner {} from scanner {}", 0, index);
-        }
+        })
+        .collect();
+
+    let unreachable: Vec<usize> = aligned.iter().zip(1..).filter(|(result, _)| result.is_none()).map(|(_, index)| index).collect();
+    if !unreachable.is_empty() {
+        return Err(error::Error::General(format!("scanners {:?} don't overlap with anything connected to scanner 0", unreachable)));
+    }
+
+    let mut all_scanners = vec![ScannerPlacement {
+        index: 0,
+        position: Vec3D { x: 0, y: 0, z: 0 },
+        rotation: Rotation::identity(),
+    }];
+    for (mut probes, scanner) in aligned.into_iter().flatten() {
+        all_probes.append(&mut probes);
+        all_scanners.push(scanner);
     }
 
     all_probes.sort();
     all_probes.dedup();
 
-    (all_probes, all_scanners)
+    Ok(ScanResult {
+        probes: all_probes,
+        scanners: all_scanners,
+    })
 }
 
 fn manhattan_distance(lhs: &Vec3D, rhs: &Vec3D) -> i64 {
@@ -420,44 +591,90 @@ pub fn max_manhattan_distance(points: &Vec<Vec3D>) -> i64 {
     max
 }
 
+impl puzzle::Puzzle for Game {
+    fn parse(input: &str) -> Result<Self, error::Error> {
+        input.parse()
+    }
+
+    fn part1(&self) -> Result<puzzle::Answer, error::Error> {
+        Ok(self.find_probes_and_scanners()?.probes.len().into())
+    }
+
+    fn part2(&self) -> Result<puzzle::Answer, error::Error> {
+        let result = self.find_probes_and_scanners()?;
+        Ok(max_manhattan_distance(&result.scanner_positions()).into())
+    }
+
+    fn examples() -> Vec<puzzle::Example> {
+        vec![(EXAMPLE, Some("79"), Some("3621"))]
+    }
+}
+
 #[test]
-fn test_scan_fmt() -> Result<(), error::Error> {
-    let (x, y, z) = scan_fmt::scan_fmt!("404,-588,-901", "{d},{d},{d}", i64, i64, i64)?;
-    assert_eq!(x, 404);
-    assert_eq!(y, -588);
-    assert_eq!(z, -901);
+fn test_parse_probes() -> Result<(), error::Error> {
+    let game: Game = "--- scanner 0 ---\n404,-588,-901\n528,-643,409".parse()?;
+    assert_eq!(game.scanners, vec![vec![Vec3D { x: 404, y: -588, z: -901 }, Vec3D { x: 528, y: -643, z: 409 }]]);
+    assert!(matches!(
+        "--- scanner 0 ---\n404,-588,-901\n528,-643".parse::<Game>(),
+        Err(error::Error::Parse(message)) if message.starts_with("line 3, column 9:")
+    ));
     Ok(())
 }
 
 #[test]
 fn test_pos_transform() -> Result<(), error::Error> {
+    // x and z swapped, then every axis flipped
+    let rotation = Rotation::from_rows([[0, 0, -1], [0, -1, 0], [-1, 0, 0]]);
+    assert_eq!(Vec3D { x: 5, y: 6, z: 2 }.rotate(&rotation), Vec3D { x: -2, y: -6, z: -5 });
+    // a mirror image, which no scanner can be turned into, but still just a matrix
     assert_eq!(
-        Vec3D { x: 5, y: 6, z: 2 }.transform_and_flip(&ScannerTransformation {
-            rotation: ScannerRotation::ZYX,
-            flip_x: true,
-            flip_y: true,
-            flip_z: true
-        }),
-        Vec3D { x: -2, y: -6, z: -5 }
-    );
-    assert_eq!(
-        Vec3D { x: 1, y: 2, z: 3 }.transform_and_flip(&ScannerTransformation {
-            rotation: ScannerRotation::XZY,
-            flip_x: true,
-            flip_y: false,
-            flip_z: true
-        }),
+        Vec3D { x: 1, y: 2, z: 3 }.rotate(&Rotation::from_rows([[-1, 0, 0], [0, 0, 1], [0, -1, 0]])),
         Vec3D { x: -1, y: 3, z: -2 }
     );
     Ok(())
 }
 
+#[test]
+fn test_rotation() -> Result<(), error::Error> {
+    let rotations = Rotation::all();
+    assert_eq!(rotations.len(), 24);
+    assert_eq!(rotations.iter().collect::<HashSet<_>>().len(), 24);
+    assert!(rotations.contains(&Rotation::identity()));
+
+    let pos = Vec3D { x: 1, y: 2, z: 3 };
+    for a in rotations.iter() {
+        assert_eq!(a.then(&a.inverse()), Rotation::identity());
+        assert_eq!(a.inverse().apply(&a.apply(&pos)), pos);
+        for b in rotations.iter() {
+            assert_eq!(a.then(b).apply(&pos), b.apply(&a.apply(&pos)));
+            assert!(rotations.contains(&a.then(b)));
+        }
+    }
+
+    let alignment = Alignment {
+        rotation: rotations[5],
+        scanner_position: Vec3D { x: 10, y: -20, z: 30 },
+    };
+    let next = Alignment {
+        rotation: rotations[17],
+        scanner_position: Vec3D { x: -7, y: 0, z: 4 },
+    };
+    assert_eq!(alignment.inverse().apply(&alignment.apply(&pos)), pos);
+    assert_eq!(alignment.then(&next).apply(&pos), next.apply(&alignment.apply(&pos)));
+
+    Ok(())
+}
+
 #[test]
 fn test_pos_distance_to() -> Result<(), error::Error> {
     let pos1 = Vec3D { x: 5, y: 6, z: 2 };
     let pos2 = Vec3D { x: -7, y: 11, z: -13 };
 
     assert_eq!(pos1.subtract(&pos2).distance(), 20);
+    // rounded to the nearest, sqrt(6) is 2.45 and sqrt(8) is 2.83
+    assert_eq!(Vec3D { x: 2, y: 1, z: 1 }.distance(), 2);
+    assert_eq!(Vec3D { x: 2, y: 2, z: 0 }.distance(), 3);
+    assert_eq!(Vec3D { x: 0, y: 0, z: 0 }.distance(), 0);
 
     Ok(())
 }
@@ -482,9 +699,7 @@ fn test_manhattan_distance() -> Result<(), error::Error> {
     Ok(())
 }
 
-#[test]
-fn test_day19() -> Result<(), error::Error> {
-    let input = r#"
+const EXAMPLE: &str = r#"
 --- scanner 0 ---
 404,-588,-901
 528,-643,409
@@ -622,9 +837,11 @@ fn test_day19() -> Result<(), error::Error> {
 -652,-548,-490
 30,-46,-14"#;
 
-    let game: Game = input.parse()?;
+#[test]
+fn test_day19() -> Result<(), error::Error> {
+    let game: Game = EXAMPLE.parse()?;
 
-    let result = convert_probes(&game.scanners[1], &game.scanners[0]).unwrap();
+    let result = convert_probes(&game.scanners[1], &game.scanners[0])?.unwrap();
 
     let count = count_same_probes(&result.probes, &game.scanners[0]);
     assert_eq!(count, 12);
@@ -632,19 +849,164 @@ fn test_day19() -> Result<(), error::Error> {
     assert_eq!(game.scanners.len(), 5);
     assert_eq!(game.scanners.iter().map(|s| s.len()).sum::<usize>(), 127);
 
-    let (probes, scanners) = find_probes_and_scanners(&game.scanners);
+    let result = find_probes_and_scanners(&game.scanners)?;
 
-    assert_eq!(probes.len(), 79);
-    assert_eq!(max_manhattan_distance(&scanners), 3621);
+    assert_eq!(result.probes.len(), 79);
+    assert_eq!(max_manhattan_distance(&result.scanner_positions()), 3621);
 
     let game: Game = std::fs::read_to_string("input_day19")?.parse()?;
 
     assert_eq!(game.scanners.len(), 31);
     assert_eq!(game.scanners[30].len(), 26);
 
-    let (probes, scanners) = find_probes_and_scanners(&game.scanners);
-    assert_eq!(probes.len(), 376);
-    assert_eq!(max_manhattan_distance(&scanners), 10772);
+    let result = find_probes_and_scanners(&game.scanners)?;
+    assert_eq!(result.probes.len(), 376);
+    assert_eq!(result.scanners.len(), 31);
+    assert_eq!(max_manhattan_distance(&result.scanner_positions()), 10772);
+
+    Ok(())
+}
+
+#[test]
+fn test_day19_fingerprint() -> Result<(), error::Error> {
+    let game: Game = EXAMPLE.parse()?;
+    let fingerprints: Vec<Vec<i64>> = game.scanners.iter().map(|probes| fingerprint(probes)).collect();
+    assert_eq!(fingerprints[0].len(), 25 * 24 / 2);
+
+    // the example overlaps are 0-1, 1-3, 1-4 and 2-4
+    for indices in (0..game.scanners.len()).combinations(2) {
+        let shared = count_shared_distances(&fingerprints[indices[0]], &fingerprints[indices[1]]);
+        let overlapping = matches!((indices[0], indices[1]), (0, 1) | (1, 3) | (1, 4) | (2, 4));
+        assert_eq!(shared >= Sensor::default().min_shared_distances(), overlapping, "scanners {:?} share {} distances", indices, shared);
+    }
+
+    assert_eq!(count_shared_distances(&[1, 1, 2, 5], &[1, 2, 2, 5, 7]), 3);
+
+    Ok(())
+}
+
+#[test]
+fn test_day19_errors() -> Result<(), error::Error> {
+    // scanner 2 only overlaps with scanner 4
+    let game: Game = EXAMPLE[..EXAMPLE.find("--- scanner 4").unwrap()].parse()?;
+    assert_eq!(
+        find_probes_and_scanners(&game.scanners),
+        Err(error::Error::General("scanners [2] don't overlap with anything connected to scanner 0".to_string()))
+    );
+
+    let game: Game = EXAMPLE.parse()?;
+    assert!(convert_probes(&game.scanners[0], &game.scanners[2])?.is_none());
+    assert!(find_probes_and_scanners(&Vec::new()).is_err());
+    assert_eq!(
+        "--- scanner 0 ---\n1,2,3\n9223372036854775807,0,0".parse::<Game>().err(),
+        Some(error::Error::Parse("line 3, probe \"9223372036854775807,0,0\" is more than 1048576 away from its scanner".to_string()))
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_day19_placements() -> Result<(), error::Error> {
+    let game: Game = EXAMPLE.parse()?;
+    let result = find_probes_and_scanners(&game.scanners)?;
+
+    assert_eq!(result.scanners.iter().map(|scanner| scanner.index).collect::<Vec<_>>(), vec![0, 1, 2, 3, 4]);
+    assert_eq!(
+        result.scanner_positions(),
+        vec![
+            Vec3D { x: 0, y: 0, z: 0 },
+            Vec3D { x: 68, y: -1246, z: -43 },
+            Vec3D { x: 1105, y: -1205, z: 1229 },
+            Vec3D { x: -92, y: -2380, z: -20 },
+            Vec3D { x: -20, y: -1133, z: 1061 },
+        ]
+    );
+    assert_eq!(result.scanners[0].rotation, Rotation::identity());
+
+    // turning and moving each scanner's own readings puts them among the merged probes
+    for scanner in result.scanners.iter() {
+        for probe in game.scanners[scanner.index].iter() {
+            let probe = probe.rotate(&scanner.rotation).move_to_scanner(&scanner.position);
+            assert!(result.probes.binary_search(&probe).is_ok());
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_day19_sensor() -> Result<(), error::Error> {
+    // scanners 0 and 1 of the example, with four of the twelve shared probes dropped from scanner 1
+    let shared = ["686,422,578", "605,423,415", "515,917,-361", "-336,658,858"];
+    let end = EXAMPLE.find("--- scanner 2").unwrap();
+    let input: String = EXAMPLE[..end].lines().filter(|line| !shared.contains(line)).map(|line| format!("{}\n", line)).collect();
+
+    let game: Game = input.parse()?;
+    assert!(game.find_probes_and_scanners().is_err());
+
+    let game = game.with_min_overlap(8);
+    let result = game.find_probes_and_scanners()?;
+    assert_eq!(result.scanner_positions(), vec![Vec3D { x: 0, y: 0, z: 0 }, Vec3D { x: 68, y: -1246, z: -43 }]);
+    assert_eq!(result.probes.len(), 25 + 21 - 8);
+
+    // scanner 1 is 1246 away along y, too far for sensors that only see 500 units
+    let game = game.with_range(500);
+    assert!(game.find_probes_and_scanners().is_err());
+
+    let game: Game = EXAMPLE.parse()?;
+    assert_eq!(game.find_probes_and_scanners()?, find_probes_and_scanners(&game.scanners)?);
+
+    Ok(())
+}
+
+#[test]
+fn test_day19_register() -> Result<(), error::Error> {
+    let full: Game = EXAMPLE.parse()?;
+    let expected = full.find_probes_and_scanners()?;
+
+    // scanner 2 only overlaps with scanner 4, so it has to come last
+    let begin = EXAMPLE.find("--- scanner 2").unwrap();
+    let end = EXAMPLE.find("--- scanner 3").unwrap();
+    let mut game: Game = format!("{}{}", &EXAMPLE[..begin], &EXAMPLE[end..]).parse()?;
+    let mut result = game.find_probes_and_scanners()?;
+    assert_eq!(result.scanners.len(), 4);
+
+    let placement = game.register(&mut result, full.scanners[2].clone())?;
+    assert_eq!(placement.index, 4);
+    assert_eq!(placement.position, Vec3D { x: 1105, y: -1205, z: 1229 });
+    assert_eq!(result.probes, expected.probes);
+    assert_eq!(result.scanners.len(), 5);
+
+    let mut game: Game = EXAMPLE[..EXAMPLE.find("--- scanner 2").unwrap()].parse()?;
+    let mut result = game.find_probes_and_scanners()?;
+    let before = result.probes.clone();
+    assert!(game.register(&mut result, full.scanners[2].clone()).is_err());
+    assert_eq!(result.probes, before);
+    assert_eq!(game.register(&mut result, full.scanners[4].clone())?.position, Vec3D { x: -20, y: -1133, z: 1061 });
+    assert_eq!(game.register(&mut result, full.scanners[2].clone())?.position, Vec3D { x: 1105, y: -1205, z: 1229 });
+
+    Ok(())
+}
+
+#[test]
+fn test_day19_verification() -> Result<(), error::Error> {
+    // 10 and sqrt(101) round to the same distance
+    assert_eq!(Vec3D { x: 0, y: 0, z: 10 }.distance(), Vec3D { x: 6, y: 8, z: 1 }.distance());
+    assert_ne!(Vec3D { x: 0, y: 0, z: 10 }.squared_distance(), Vec3D { x: 6, y: 8, z: 1 }.squared_distance());
+
+    // half a turn around z maps this cloud onto itself, so two rotations fit equally well
+    let half: Vec<Vec3D> = vec![Vec3D { x: 1, y: 2, z: 3 }, Vec3D { x: 4, y: 7, z: -5 }, Vec3D { x: 10, y: -3, z: 8 }, Vec3D { x: -6, y: 11, z: 2 }];
+    let cloud: Vec<Vec3D> = half.iter().flat_map(|p| [*p, Vec3D { x: -p.x, y: -p.y, z: p.z }]).collect();
+    let sensor = Sensor { min_overlap: 8, range: 1000 };
+    assert_eq!(
+        align(&cloud, &cloud, &sensor, "the clouds").map(|alignment| alignment.is_some()),
+        Err(error::Error::General("the clouds line up in 2 different ways".to_string()))
+    );
+
+    // the example lines up in exactly one way
+    let game: Game = EXAMPLE.parse()?;
+    let alignment = align(&game.scanners[1], &game.scanners[0], &Sensor::default(), "scanners 1 and 0")?.unwrap();
+    assert_eq!(alignment.scanner_position, Vec3D { x: 68, y: -1246, z: -43 });
 
     Ok(())
 }
@@ -1,4 +1,8 @@
 use crate::error;
+use crate::puzzle;
+
+use alloc::string::ToString;
+use alloc::vec::Vec;
 
 pub struct NavigationResult {
     horizontal_position: u64,
@@ -7,19 +11,33 @@ pub struct NavigationResult {
 }
 
 impl NavigationResult {
-    pub fn sum(&self) -> u64 {
-        self.horizontal_position * self.depth
+    pub fn sum(&self) -> Result<u64, error::Error> {
+        self.horizontal_position.checked_mul(self.depth).ok_or_else(too_far)
     }
 }
 
+fn too_far() -> error::Error {
+    error::Error::General("the course goes further than 64 bits can count".to_string())
+}
+
+// depth and aim can't go below the surface, and the course has to stay inside 64 bits
+fn sub(value: u64, v: u64) -> Result<u64, error::Error> {
+    value.checked_sub(v).ok_or_else(|| error::Error::General("the submarine can't rise above the surface".to_string()))
+}
+
+fn add(value: u64, v: u64) -> Result<u64, error::Error> {
+    value.checked_add(v).ok_or_else(too_far)
+}
+
 #[derive(PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Command {
     Forward(u64),
     Up(u64),
     Down(u64),
 }
 
-impl std::str::FromStr for Command {
+impl core::str::FromStr for Command {
     type Err = error::Error;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let tokens: Vec<&str> = s.split(' ').collect();
@@ -49,7 +67,7 @@ pub fn parse_commands(s: &str) -> Result<Vec<Command>, error::Error> {
     Ok(commands)
 }
 
-pub fn navigate(commands: &Vec<Command>) -> NavigationResult {
+pub fn navigate(commands: &Vec<Command>) -> Result<NavigationResult, error::Error> {
     let mut res = NavigationResult {
         horizontal_position: 0,
         depth: 0,
@@ -58,16 +76,16 @@ pub fn navigate(commands: &Vec<Command>) -> NavigationResult {
 
     for command in commands {
         match command {
-            Command::Forward(v) => res.horizontal_position += v,
-            Command::Up(v) => res.depth -= v,
-            Command::Down(v) => res.depth += v,
+            Command::Forward(v) => res.horizontal_position = add(res.horizontal_position, *v)?,
+            Command::Up(v) => res.depth = sub(res.depth, *v)?,
+            Command::Down(v) => res.depth = add(res.depth, *v)?,
         }
     }
 
-    res
+    Ok(res)
 }
 
-pub fn navigate_aim(commands: &Vec<Command>) -> NavigationResult {
+pub fn navigate_aim(commands: &Vec<Command>) -> Result<NavigationResult, error::Error> {
     let mut res = NavigationResult {
         horizontal_position: 0,
         depth: 0,
@@ -77,15 +95,45 @@ pub fn navigate_aim(commands: &Vec<Command>) -> NavigationResult {
     for command in commands {
         match command {
             Command::Forward(v) => {
-                res.horizontal_position += v;
-                res.depth += res.aim * v
+                res.horizontal_position = add(res.horizontal_position, *v)?;
+                res.depth = add(res.depth, res.aim.checked_mul(*v).ok_or_else(too_far)?)?;
             }
-            Command::Up(v) => res.aim -= v,
-            Command::Down(v) => res.aim += v,
+            Command::Up(v) => res.aim = sub(res.aim, *v)?,
+            Command::Down(v) => res.aim = add(res.aim, *v)?,
         }
     }
 
-    res
+    Ok(res)
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Course(pub Vec<Command>);
+
+const EXAMPLE: &str = r#"
+forward 5
+down 5
+forward 8
+up 3
+down 8
+forward 2
+"#;
+
+impl puzzle::Puzzle for Course {
+    fn parse(input: &str) -> Result<Self, error::Error> {
+        Ok(Course(parse_commands(input)?))
+    }
+
+    fn part1(&self) -> Result<puzzle::Answer, error::Error> {
+        Ok(navigate(&self.0)?.sum()?.into())
+    }
+
+    fn part2(&self) -> Result<puzzle::Answer, error::Error> {
+        Ok(navigate_aim(&self.0)?.sum()?.into())
+    }
+
+    fn examples() -> Vec<puzzle::Example> {
+        vec![(EXAMPLE, Some("150"), Some("900"))]
+    }
 }
 
 #[test]
@@ -106,28 +154,35 @@ up 31
     Ok(())
 }
 
+#[test]
+fn test_navigate_errors() -> Result<(), error::Error> {
+    let above_surface = Err(error::Error::General("the submarine can't rise above the surface".to_string()));
+    let commands = parse_commands("forward 1\nup 5")?;
+    assert_eq!(navigate(&commands).map(|res| res.depth), above_surface);
+    assert_eq!(navigate_aim(&commands).map(|res| res.depth), above_surface);
+
+    let too_far = Err(error::Error::General("the course goes further than 64 bits can count".to_string()));
+    let commands = parse_commands(&format!("down {}\nforward 2", u64::MAX))?;
+    assert_eq!(navigate(&commands)?.sum(), too_far);
+    assert_eq!(navigate_aim(&commands).map(|res| res.depth), too_far);
+    Ok(())
+}
+
 #[test]
 fn test_navigate() -> Result<(), error::Error> {
-    let input = r#"
-forward 5
-down 5
-forward 8
-up 3
-down 8
-forward 2
-    "#;
+    let input = EXAMPLE;
 
     let commands: Vec<Command> = parse_commands(input)?;
 
-    let navres = navigate(&commands);
+    let navres = navigate(&commands)?;
     assert_eq!(navres.horizontal_position, 15);
     assert_eq!(navres.depth, 10);
-    assert_eq!(navres.sum(), 150);
+    assert_eq!(navres.sum()?, 150);
 
-    let navres = navigate_aim(&commands);
+    let navres = navigate_aim(&commands)?;
     assert_eq!(navres.horizontal_position, 15);
     assert_eq!(navres.depth, 60);
-    assert_eq!(navres.sum(), 900);
+    assert_eq!(navres.sum()?, 900);
 
     Ok(())
 }
@@ -137,14 +192,14 @@ fn test_navigate_input() -> Result<(), error::Error> {
     let input = std::fs::read_to_string("input_day2")?;
     let commands: Vec<Command> = parse_commands(&input)?;
 
-    let navres = navigate(&commands);
+    let navres = navigate(&commands)?;
     assert_eq!(navres.horizontal_position, 1967);
     assert_eq!(navres.depth, 1031);
-    assert_eq!(navres.sum(), 2027977);
+    assert_eq!(navres.sum()?, 2027977);
 
-    let navres = navigate_aim(&commands);
+    let navres = navigate_aim(&commands)?;
     assert_eq!(navres.horizontal_position, 1967);
     assert_eq!(navres.depth, 967791);
-    assert_eq!(navres.sum(), 1903644897);
+    assert_eq!(navres.sum()?, 1903644897);
     Ok(())
 }
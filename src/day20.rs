@@ -1,153 +1,559 @@
+use crate::collections::HashMap;
 use crate::error;
-use std::collections::HashMap;
+use crate::grid;
+use crate::puzzle;
+use alloc::string::{String, ToString};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Image {
-    pub enhancement: String,
-    pub pixels: HashMap<i64, Vec<i64>>,
-    pub oob_index: usize,
+    // shared between an image and everything enhanced from it; 2^(k*k) entries for a k x k kernel
+    pub enhancement: Arc<str>,
+    pixels: Pixels,
+    pub background: Background,
 }
 
-#[derive(Debug)]
-enum PixelEnhancementResult {
+// every pixel outside the part of the image that has been worked out looks the same
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Background {
     Dark,
-    Light,
+    Lit,
+}
+
+impl Background {
+    // a pixel surrounded by background only sees background, so it's either the first or the last entry
+    fn enhance(self, enhancement: &str) -> Background {
+        let index = match self {
+            Background::Dark => 0,
+            Background::Lit => enhancement.len() - 1,
+        };
+        if enhancement.as_bytes()[index] == b'#' {
+            Background::Lit
+        } else {
+            Background::Dark
+        }
+    }
+}
+
+// inclusive on every side
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Rect {
+    pub min_x: i64,
+    pub min_y: i64,
+    pub max_x: i64,
+    pub max_y: i64,
+}
+
+impl Rect {
+    pub fn width(&self) -> usize {
+        (self.max_x - self.min_x + 1).max(0) as usize
+    }
+
+    pub fn height(&self) -> usize {
+        (self.max_y - self.min_y + 1).max(0) as usize
+    }
+
+    pub fn area(&self) -> usize {
+        self.width() * self.height()
+    }
+
+    fn intersect(&self, other: &Rect) -> Rect {
+        Rect {
+            min_x: self.min_x.max(other.min_x),
+            min_y: self.min_y.max(other.min_y),
+            max_x: self.max_x.min(other.max_x),
+            max_y: self.max_y.min(other.max_y),
+        }
+    }
+
+    fn pixels(&self) -> impl Iterator<Item = (i64, i64)> {
+        let (min_x, max_x) = (self.min_x, self.max_x);
+        (self.min_y..=self.max_y).flat_map(move |y| (min_x..=max_x).map(move |x| (x, y)))
+    }
+}
+
+// how to get from one image to another, row by row
+#[derive(PartialEq, Eq, Debug)]
+pub struct ImageDiff {
+    pub added: Vec<(i64, i64)>,
+    pub removed: Vec<(i64, i64)>,
+    // if the backgrounds differ, so does every pixel outside both images
+    pub background_changed: bool,
+}
+
+impl ImageDiff {
+    pub fn num_added(&self) -> usize {
+        self.added.len()
+    }
+
+    pub fn num_removed(&self) -> usize {
+        self.removed.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && !self.background_changed
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Backend {
+    // lit x coordinates per row, and the rectangle that has been worked out, which grows the same way
+    Sparse,
+    // every pixel of a rectangle that grows by the kernel radius in each direction per enhancement
+    Dense,
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+enum Pixels {
+    Sparse {
+        #[cfg_attr(feature = "serde", serde(serialize_with = "crate::snapshot::sorted_map"))]
+        rows: HashMap<i64, Vec<i64>>,
+        bounds: Rect,
+    },
+    Dense(Canvas),
+}
+
+// a grid whose top left pixel is at (min_x, min_y)
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct Canvas {
+    min_x: i64,
+    min_y: i64,
+    lit: grid::Grid<bool>,
+}
+
+impl Canvas {
+    fn new(min_x: i64, min_y: i64, width: usize, height: usize) -> Canvas {
+        Canvas {
+            min_x,
+            min_y,
+            lit: grid::Grid::new(width, height, false),
+        }
+    }
+
+    // moves the canvas and makes every pixel dark, keeping the allocation
+    fn reset(&mut self, min_x: i64, min_y: i64, width: usize, height: usize) {
+        self.min_x = min_x;
+        self.min_y = min_y;
+        self.lit.reset(width, height, false);
+    }
+
+    fn max_x(&self) -> i64 {
+        self.min_x + self.lit.width() as i64 - 1
+    }
+
+    fn max_y(&self) -> i64 {
+        self.min_y + self.lit.height() as i64 - 1
+    }
+
+    fn get(&self, x: i64, y: i64) -> Option<bool> {
+        self.lit.get(x - self.min_x, y - self.min_y).copied()
+    }
+
+    fn set(&mut self, x: i64, y: i64) {
+        self.lit[(x - self.min_x, y - self.min_y)] = true;
+    }
+}
+
+// the odd kernel size k that an enhancement with 2^(k*k) entries is for
+fn kernel_size(enhancement_len: usize) -> Option<usize> {
+    [1, 3, 5, 7].into_iter().find(|&k| k * k < usize::BITS as usize && 1 << (k * k) == enhancement_len)
+}
+
+// only '#' and '.', and as many of them as a kernel has neighbourhoods
+fn check_enhancement(enhancement: &str) -> Result<(), error::Error> {
+    if let Some((index, char)) = enhancement.chars().enumerate().find(|&(_, c)| c != '#' && c != '.') {
+        return Err(error::Error::Parse(format!("invalid character {:?} in enhancement at position {}", char, index)));
+    }
+    if kernel_size(enhancement.len()).is_none() {
+        return Err(error::Error::Parse(format!(
+            "enhancement must be 512 characters, or 2^(k*k) for an odd kernel size k, got {}",
+            enhancement.len()
+        )));
+    }
+    Ok(())
 }
 
 impl Image {
+    pub fn kernel_size(&self) -> usize {
+        kernel_size(self.enhancement.len()).expect("enhancement checked when parsing")
+    }
+
+    fn kernel_radius(&self) -> i64 {
+        self.kernel_size() as i64 / 2
+    }
+
     pub fn num_lit_pixels(&self) -> usize {
-        self.pixels.iter().map(|(_, v)| v.len()).sum()
+        match &self.pixels {
+            Pixels::Sparse { rows, .. } => rows.values().map(Vec::len).sum(),
+            Pixels::Dense(grid) => grid.lit.cells().iter().filter(|&&lit| lit).count(),
+        }
+    }
+
+    pub fn backend(&self) -> Backend {
+        match self.pixels {
+            Pixels::Sparse { .. } => Backend::Sparse,
+            Pixels::Dense(_) => Backend::Dense,
+        }
+    }
+
+    // the same lit pixels over the same worked out part, stored the other way
+    pub fn with_backend(self, backend: Backend) -> Image {
+        if self.backend() == backend {
+            return self;
+        }
+        let bounds = self.bounds();
+        let lit = self.lit_pixels();
+        let pixels = match backend {
+            Backend::Sparse => Pixels::Sparse { rows: HashMap::new(), bounds },
+            Backend::Dense => Pixels::Dense(Canvas::new(bounds.min_x, bounds.min_y, bounds.width(), bounds.height())),
+        };
+        let mut image = Image {
+            enhancement: self.enhancement,
+            pixels,
+            background: self.background,
+        };
+        for (x, y) in lit {
+            image.add_pixel(x, y);
+        }
+        image
+    }
+
+    fn lit_pixels(&self) -> Vec<(i64, i64)> {
+        match &self.pixels {
+            Pixels::Sparse { rows, .. } => rows.iter().flat_map(|(&y, v)| v.iter().map(move |&x| (x, y))).collect(),
+            Pixels::Dense(grid) => (grid.min_y..=grid.max_y())
+                .flat_map(|y| (grid.min_x..=grid.max_x()).map(move |x| (x, y)))
+                .filter(|&(x, y)| grid.get(x, y) == Some(true))
+                .collect(),
+        }
     }
 
     fn minmax_x(&self) -> (i64, i64) {
-        let mut min = None;
-        let mut max = None;
-        for (_, v) in self.pixels.iter() {
-            if let Some(&first) = v.first() {
-                if min.is_none() || min.unwrap() > first {
-                    min = Some(first);
-                }
-            }
-            if let Some(&last) = v.last() {
-                if max.is_none() || max.unwrap() < last {
-                    max = Some(last);
+        let bounds = self.bounds();
+        (bounds.min_x, bounds.max_x)
+    }
+
+    fn minmax_y(&self) -> (i64, i64) {
+        let bounds = self.bounds();
+        (bounds.min_y, bounds.max_y)
+    }
+
+    pub fn is_lit(&self, x: i64, y: i64) -> bool {
+        match &self.pixels {
+            Pixels::Sparse { rows, .. } => {
+                if let Some(vec) = rows.get(&y) {
+                    vec.contains(&x)
+                } else {
+                    false
                 }
             }
+            Pixels::Dense(grid) => grid.get(x, y) == Some(true),
         }
-        (min.expect("no pixels"), max.expect("no pixels"))
     }
 
-    fn minmax_y(&self) -> (i64, i64) {
-        let mut min = None;
-        let mut max = None;
-        for (&k, _) in self.pixels.iter() {
-            if min.is_none() || min.unwrap() > k {
-                min = Some(k);
-            }
-            if max.is_none() || max.unwrap() < k {
-                max = Some(k);
-            }
+    // the part of the image that has been worked out; everything outside it is background
+    pub fn bounds(&self) -> Rect {
+        match &self.pixels {
+            Pixels::Sparse { bounds, .. } => *bounds,
+            Pixels::Dense(grid) => Rect {
+                min_x: grid.min_x,
+                min_y: grid.min_y,
+                max_x: grid.max_x(),
+                max_y: grid.max_y(),
+            },
         }
-        (min.expect("no pixels"), max.expect("no pixels"))
     }
 
-    pub fn is_lit(&self, x: i64, y: i64) -> bool {
-        if let Some(vec) = self.pixels.get(&y) {
-            vec.contains(&x)
+    // unlike is_lit, pixels outside the bounds take the colour of the background
+    fn pixel(&self, bounds: &Rect, x: i64, y: i64) -> bool {
+        if x < bounds.min_x || x > bounds.max_x || y < bounds.min_y || y > bounds.max_y {
+            self.background == Background::Lit
         } else {
-            false
+            self.is_lit(x, y)
         }
     }
 
-    pub fn draw(&self) {
+    // finite even when the background is lit, as long as rect is
+    pub fn count_lit_in(&self, rect: Rect) -> usize {
+        let inside = rect.intersect(&self.bounds());
+        let lit_inside = inside.pixels().filter(|&(x, y)| self.is_lit(x, y)).count();
+        match self.background {
+            Background::Dark => lit_inside,
+            Background::Lit => rect.area() - inside.area() + lit_inside,
+        }
+    }
+
+    // a dense image of exactly rect, background included; outside of it is background again
+    pub fn crop(&self, rect: Rect) -> Image {
+        let mut grid = Canvas::new(rect.min_x, rect.min_y, rect.width(), rect.height());
+        let bounds = self.bounds();
+        for (x, y) in rect.pixels().filter(|&(x, y)| self.pixel(&bounds, x, y)) {
+            grid.set(x, y);
+        }
+        Image {
+            enhancement: self.enhancement.clone(),
+            pixels: Pixels::Dense(grid),
+            background: self.background,
+        }
+    }
+
+    // pixels lit in other but not in self are added, the other way round removed;
+    // compares everything either image has worked out, backgrounds included
+    pub fn diff(&self, other: &Image) -> ImageDiff {
+        let (a, b) = (self.bounds(), other.bounds());
+        let union = Rect {
+            min_x: a.min_x.min(b.min_x),
+            min_y: a.min_y.min(b.min_y),
+            max_x: a.max_x.max(b.max_x),
+            max_y: a.max_y.max(b.max_y),
+        };
+        let mut diff = ImageDiff {
+            added: vec![],
+            removed: vec![],
+            background_changed: self.background != other.background,
+        };
+        for (x, y) in union.pixels() {
+            match (self.pixel(&a, x, y), other.pixel(&b, x, y)) {
+                (false, true) => diff.added.push((x, y)),
+                (true, false) => diff.removed.push((x, y)),
+                _ => {}
+            }
+        }
+        diff
+    }
+
+    // the part of the image that has been worked out, one line per row
+    pub fn render(&self) -> String {
         let (x_start, x_end) = self.minmax_x();
         let (y_start, y_end) = self.minmax_y();
+        let mut s = String::new();
         for y in y_start..=y_end {
             for x in x_start..=x_end {
-                print!("{}", if self.is_lit(x, y) { "#" } else { "." });
+                s.push(if self.is_lit(x, y) { '#' } else { '.' });
             }
-            println!();
+            s.push('\n');
         }
-        println!();
+        s
     }
 
     fn add_pixel(&mut self, x: i64, y: i64) {
-        let vec = self.pixels.entry(y).or_default();
-        vec.push(x);
-        vec.sort_unstable();
-    }
-
-    fn next_oob_index(enhancement: &str, cur_index: usize) -> usize {
-        if enhancement.as_bytes()[0] == b'#' {
-            if cur_index == 0 {
-                511
-            } else {
-                0
+        match &mut self.pixels {
+            Pixels::Sparse { rows, .. } => {
+                let vec = rows.entry(y).or_default();
+                vec.push(x);
+                vec.sort_unstable();
             }
-        } else {
-            0
+            Pixels::Dense(grid) => grid.set(x, y),
         }
     }
 
     pub fn enhance(&self) -> Self {
         let (min_x, max_x) = self.minmax_x();
         let (min_y, max_y) = self.minmax_y();
-
+        let radius = self.kernel_radius();
+
+        let pixels = match self.pixels {
+            Pixels::Sparse { .. } => Pixels::Sparse {
+                rows: HashMap::new(),
+                bounds: Rect {
+                    min_x: min_x - radius,
+                    min_y: min_y - radius,
+                    max_x: max_x + radius,
+                    max_y: max_y + radius,
+                },
+            },
+            Pixels::Dense(_) => Pixels::Dense(Canvas::new(0, 0, 0, 0)),
+        };
         let mut image = Image {
             enhancement: self.enhancement.clone(),
-            pixels: HashMap::new(),
-            oob_index: Image::next_oob_index(&self.enhancement, self.oob_index),
+            pixels,
+            background: self.background.enhance(&self.enhancement),
         };
 
-        for y in (min_y - 1)..=(max_y + 1) {
-            for x in (min_x - 1)..=(max_x + 1) {
-                match self.enhance_pixel(x, y, min_x, max_x, min_y, max_y) {
-                    PixelEnhancementResult::Dark => {}
-                    PixelEnhancementResult::Light => {
-                        image.add_pixel(x, y);
-                    }
-                }
-            }
+        if let Pixels::Dense(grid) = &mut image.pixels {
+            self.enhance_into(grid);
+            return image;
+        }
+
+        for y in (min_y - radius)..=(max_y + radius) {
+            self.enhance_row(y, (min_x, max_x), (min_y, max_y), |x| image.add_pixel(x, y));
         }
 
         image
     }
 
-    fn enhance_pixel(&self, x: i64, y: i64, min_x: i64, max_x: i64, min_y: i64, max_y: i64) -> PixelEnhancementResult {
-        let mut index_string = String::with_capacity(9);
-        for y in (y - 1)..=(y + 1) {
-            for x in (x - 1)..=(x + 1) {
-                if x < min_x || x > max_x || y < min_y || y > max_y {
-                    index_string += match self.enhancement.as_bytes()[self.oob_index] {
-                        b'.' => "0",
-                        b'#' => "1",
-                        _ => panic!("invalid something"),
-                    };
-                } else {
-                    index_string += if self.is_lit(x, y) { "1" } else { "0" };
+    // enhances n times, reusing the same two grids for a dense image
+    pub fn enhance_n(self, n: usize) -> Image {
+        let mut image = self;
+        let grow = 2 * n * image.kernel_radius() as usize;
+        let mut spare = match &image.pixels {
+            // big enough for the last step, so resetting it never reallocates
+            Pixels::Dense(grid) => Canvas::new(0, 0, grid.lit.width() + grow, grid.lit.height() + grow),
+            Pixels::Sparse { .. } => {
+                for _ in 0..n {
+                    image = image.enhance();
                 }
+                return image;
             }
+        };
+
+        for _ in 0..n {
+            image.enhance_into(&mut spare);
+            if let Pixels::Dense(grid) = &mut image.pixels {
+                core::mem::swap(grid, &mut spare);
+            }
+            image.background = image.background.enhance(&image.enhancement);
         }
 
-        let index = usize::from_str_radix(&index_string, 2).unwrap();
+        image
+    }
+
+    // writes the next enhancement step into grid, which grows by the kernel radius on every side
+    fn enhance_into(&self, grid: &mut Canvas) {
+        let (min_x, max_x) = self.minmax_x();
+        let (min_y, max_y) = self.minmax_y();
+        let radius = self.kernel_radius();
+        grid.reset(min_x - radius, min_y - radius, (max_x - min_x + 1 + 2 * radius) as usize, (max_y - min_y + 1 + 2 * radius) as usize);
+
+        for y in (min_y - radius)..=(max_y + radius) {
+            self.enhance_row(y, (min_x, max_x), (min_y, max_y), |x| grid.set(x, y));
+        }
+    }
 
-        let result = match self.enhancement.as_bytes()[index] {
-            b'#' => PixelEnhancementResult::Light,
-            b'.' => PixelEnhancementResult::Dark,
-            _ => panic!("invalid enhancement"),
+    // calls light with every x from min_x - radius to max_x + radius that is lit in row y after enhancing.
+    // the index is built up one column of k bits at a time, top row first: each step shifts the
+    // window left and masks out the column that falls out of it
+    fn enhance_row(&self, y: i64, (min_x, max_x): (i64, i64), (min_y, max_y): (i64, i64), mut light: impl FnMut(i64)) {
+        let size = self.kernel_size();
+        let radius = self.kernel_radius();
+        let pixel = |x: i64, y: i64| -> usize {
+            if x < min_x || x > max_x || y < min_y || y > max_y {
+                usize::from(self.background == Background::Lit)
+            } else {
+                usize::from(self.is_lit(x, y))
+            }
         };
+        let column = |x: i64| (0..size).fold(0, |column, row| (column << size) | pixel(x, y - radius + row as i64));
+        // every bit except the rightmost column, which is where the next one goes
+        let mask = (0..size).fold((1 << (size * size)) - 1, |mask, row| mask & !(1 << (row * size)));
+
+        // only '#' and '.' make it past parsing
+        let enhancement = self.enhancement.as_bytes();
+        let mut index = 0;
+        // the first few steps only fill the window
+        for x in (min_x - 3 * radius)..=(max_x + radius) {
+            index = ((index << 1) & mask) | column(x + radius);
+            if x >= min_x - radius && enhancement[index] == b'#' {
+                light(x);
+            }
+        }
+    }
+}
+
+#[cfg(feature = "image")]
+impl Image {
+    // grayscale, lit pixels black, covering width x height pixels from (min_x, min_y)
+    fn plot(&self, (min_x, min_y): (i64, i64), (width, height): (usize, usize), scale: usize) -> Vec<u8> {
+        let background = if self.background == Background::Lit { 0 } else { 255 };
+        let (image_min_x, image_max_x) = self.minmax_x();
+        let (image_min_y, image_max_y) = self.minmax_y();
+        let mut data = Vec::with_capacity(width * height * scale * scale);
+        for y in min_y..min_y + height as i64 {
+            let mut row = Vec::with_capacity(width * scale);
+            for x in min_x..min_x + width as i64 {
+                let inside = (image_min_x..=image_max_x).contains(&x) && (image_min_y..=image_max_y).contains(&y);
+                let value = match (inside, self.is_lit(x, y)) {
+                    (false, _) => background,
+                    (true, true) => 0,
+                    (true, false) => 255,
+                };
+                row.resize(row.len() + scale, value);
+            }
+            for _ in 0..scale {
+                data.extend_from_slice(&row);
+            }
+        }
+        data
+    }
+
+    fn size(&self) -> (usize, usize) {
+        let (min_x, max_x) = self.minmax_x();
+        let (min_y, max_y) = self.minmax_y();
+        ((max_x - min_x + 1) as usize, (max_y - min_y + 1) as usize)
+    }
 
-        result
+    pub fn write_pgm<W: std::io::Write>(&self, mut writer: W, scale: usize) -> Result<(), error::Error> {
+        let (width, height) = self.size();
+        let data = self.plot((self.minmax_x().0, self.minmax_y().0), (width, height), scale);
+        write!(writer, "P5\n{} {}\n255\n", width * scale, height * scale)?;
+        writer.write_all(&data)?;
+        Ok(())
+    }
+
+    pub fn write_png<W: std::io::Write>(&self, writer: W, scale: usize) -> Result<(), error::Error> {
+        let (width, height) = self.size();
+        let data = self.plot((self.minmax_x().0, self.minmax_y().0), (width, height), scale);
+        let mut encoder = png::Encoder::new(writer, (width * scale) as u32, (height * scale) as u32);
+        encoder.set_color(png::ColorType::Grayscale);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.write_header()?.write_image_data(&data)?;
+        Ok(())
+    }
+
+    // one frame for the image as it is and one per enhancement step, all the size of the last one
+    pub fn write_gif<W: std::io::Write>(self, writer: W, steps: usize, scale: usize) -> Result<Image, error::Error> {
+        let (width, height) = self.size();
+        let grow = steps as i64 * self.kernel_radius();
+        let origin = (self.minmax_x().0 - grow, self.minmax_y().0 - grow);
+        let size = (width + 2 * grow as usize, height + 2 * grow as usize);
+        let too_large = || error::Error::General("image too large for a gif".to_string());
+        let frame_width = u16::try_from(size.0 * scale).map_err(|_| too_large())?;
+        let frame_height = u16::try_from(size.1 * scale).map_err(|_| too_large())?;
+
+        let mut encoder = gif::Encoder::new(writer, frame_width, frame_height, &[0, 0, 0, 255, 255, 255])?;
+        encoder.set_repeat(gif::Repeat::Infinite)?;
+
+        let mut image = self;
+        for step in 0..=steps {
+            if step > 0 {
+                image = image.enhance();
+            }
+            // palette index 0 is black, 1 is white
+            let buffer: Vec<u8> = image.plot(origin, size, scale).into_iter().map(|value| value / 255).collect();
+            let frame = gif::Frame {
+                width: frame_width,
+                height: frame_height,
+                delay: 20,
+                buffer: alloc::borrow::Cow::Owned(buffer),
+                ..gif::Frame::default()
+            };
+            encoder.write_frame(&frame)?;
+        }
+
+        Ok(image)
     }
 }
 
-impl std::str::FromStr for Image {
+impl core::str::FromStr for Image {
     type Err = error::Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut enhancement = String::new();
         let mut image = Image {
-            enhancement: String::new(),
-            pixels: HashMap::new(),
-            oob_index: 0,
+            enhancement: Arc::from(""),
+            pixels: Pixels::Sparse {
+                rows: HashMap::new(),
+                bounds: Rect {
+                    min_x: 0,
+                    min_y: 0,
+                    max_x: -1,
+                    max_y: -1,
+                },
+            },
+            background: Background::Dark,
         };
 
         enum State {
@@ -158,38 +564,71 @@ impl std::str::FromStr for Image {
         let mut state = State::Enhancement;
 
         let mut line_index = 0;
+        let mut width = 0;
 
         for line in s.lines().map(|l| l.trim_start().trim_end()) {
-            if line.is_empty() && !image.enhancement.is_empty() {
+            if line.is_empty() && !enhancement.is_empty() {
                 state = State::Image;
                 continue;
             }
             match state {
                 State::Enhancement => {
-                    image.enhancement.push_str(line);
+                    enhancement.push_str(line);
                 }
                 State::Image => {
                     for (index, char) in line.chars().enumerate() {
                         match char {
                             '#' => image.add_pixel(index as i64, line_index),
                             '.' => {}
-                            _ => panic!("invalid input"),
+                            _ => return Err(error::Error::Parse(format!("invalid character {:?} in image at line {}, column {}", char, line_index + 1, index + 1))),
                         }
                     }
                     line_index += 1;
+                    width = width.max(line.len());
                 }
             }
         }
 
-        image.oob_index = Image::next_oob_index(&image.enhancement, image.oob_index);
+        check_enhancement(&enhancement)?;
+        if line_index == 0 {
+            return Err(error::Error::Parse("no image after the enhancement".to_string()));
+        }
+        image.enhancement = Arc::from(enhancement);
+
+        // the dense grid starts out covering the whole input, dark edges included
+        let lit = image.lit_pixels();
+        image.pixels = Pixels::Dense(Canvas::new(0, 0, width, line_index as usize));
+        for (x, y) in lit {
+            image.add_pixel(x, y);
+        }
 
         Ok(image)
     }
 }
 
-#[test]
-fn test_day19() -> Result<(), error::Error> {
-    let input = r#"
+// checks the enhancement the way parsing does, so a snapshot with a table of the wrong size is an
+// error and not an image that panics when enhanced
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Image {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(serde::Deserialize)]
+        struct Raw {
+            enhancement: Arc<str>,
+            pixels: Pixels,
+            background: Background,
+        }
+
+        let raw = Raw::deserialize(deserializer)?;
+        check_enhancement(&raw.enhancement).map_err(serde::de::Error::custom)?;
+        Ok(Image {
+            enhancement: raw.enhancement,
+            pixels: raw.pixels,
+            background: raw.background,
+        })
+    }
+}
+
+const EXAMPLE: &str = r#"
 ..#.#..#####.#.#.#.###.##.....###.##.#..###.####..#####..#....#..#..##..##
 #..######.###...####..#..#####..##..#.#####...##.#.#..#.##..#.#......#.###
 .######.###.####...#.##.##..#..#..#####.....#.#....###..#.##......#.....#.
@@ -204,11 +643,33 @@ fn test_day19() -> Result<(), error::Error> {
 ..#..
 ..###"#;
 
+impl puzzle::Puzzle for Image {
+    fn parse(input: &str) -> Result<Self, error::Error> {
+        input.parse()
+    }
+
+    fn part1(&self) -> Result<puzzle::Answer, error::Error> {
+        Ok(self.enhance().enhance().num_lit_pixels().into())
+    }
+
+    fn part2(&self) -> Result<puzzle::Answer, error::Error> {
+        Ok(self.enhance().enhance_n(49).num_lit_pixels().into())
+    }
+
+    fn examples() -> Vec<puzzle::Example> {
+        vec![(EXAMPLE, Some("35"), Some("3351"))]
+    }
+}
+
+#[test]
+fn test_day19() -> Result<(), error::Error> {
+    let input = EXAMPLE;
+
     let image: Image = input.parse()?;
 
     assert_eq!(image.enhancement.len(), 512);
-    assert_eq!(image.pixels.len(), 5);
-    assert_eq!(image.pixels.iter().map(|(_, v)| v.len()).sum::<usize>(), 10);
+    assert_eq!(image.backend(), Backend::Dense);
+    assert_eq!(image.lit_pixels().len(), 10);
     assert_eq!(image.minmax_x(), (0, 4));
     assert_eq!(image.minmax_y(), (0, 4));
     assert_eq!(image.num_lit_pixels(), 10);
@@ -217,6 +678,10 @@ fn test_day19() -> Result<(), error::Error> {
     let image = image.enhance();
     assert_eq!(image.num_lit_pixels(), 35);
 
+    let image = image.with_backend(Backend::Sparse);
+    assert_eq!(image.backend(), Backend::Sparse);
+    assert_eq!(image.num_lit_pixels(), 35);
+
     let mut image: Image = std::fs::read_to_string("input_day20")?.parse()?;
     assert_eq!(image.enhancement.len(), 512);
     assert_eq!(image.minmax_x(), (0, 99));
@@ -226,11 +691,272 @@ fn test_day19() -> Result<(), error::Error> {
     image = image.enhance();
     assert_eq!(image.num_lit_pixels(), 5486);
 
-    for _ in 0..48 {
-        image = image.enhance();
-    }
+    image = image.enhance_n(48);
 
     assert_eq!(image.num_lit_pixels(), 20210);
 
     Ok(())
 }
+
+#[test]
+fn test_day20_backends() -> Result<(), error::Error> {
+    let input = std::fs::read_to_string("input_day20")?;
+    let mut dense: Image = input.parse()?;
+    let mut sparse: Image = input.parse::<Image>()?.with_backend(Backend::Sparse);
+    assert_eq!(sparse.num_lit_pixels(), dense.num_lit_pixels());
+
+    for _ in 0..2 {
+        dense = dense.enhance();
+        sparse = sparse.enhance();
+        assert_eq!(sparse.num_lit_pixels(), dense.num_lit_pixels());
+        // including after the first step, which lights up the background
+        assert_eq!(sparse.background, dense.background);
+        assert_eq!(sparse.render(), dense.render());
+    }
+    assert_eq!(dense.num_lit_pixels(), 5486);
+
+    let mut lit = dense.lit_pixels();
+    let mut sparse_lit = sparse.lit_pixels();
+    lit.sort_unstable();
+    sparse_lit.sort_unstable();
+    assert_eq!(lit, sparse_lit);
+
+    // converting back and forth keeps the pixels
+    let round_trip = sparse.with_backend(Backend::Dense);
+    let mut round_trip_lit = round_trip.lit_pixels();
+    round_trip_lit.sort_unstable();
+    assert_eq!(round_trip_lit, lit);
+
+    Ok(())
+}
+
+#[test]
+fn test_day20_empty() -> Result<(), error::Error> {
+    let input = std::fs::read_to_string("input_day20")?;
+    let input = format!("{}\n\n...\n...\n", input.lines().next().unwrap());
+    // both backends cover the whole input, lit or not
+    for backend in [Backend::Sparse, Backend::Dense] {
+        let image = input.parse::<Image>()?.with_backend(Backend::Sparse).with_backend(backend);
+        assert_eq!(image.num_lit_pixels(), 0);
+        assert_eq!(image.bounds().area(), 6);
+        assert_eq!(image.render(), "...\n...\n");
+
+        // the dark background lights up, and then goes dark again along with everything around it
+        let enhanced = image.enhance();
+        assert_eq!(enhanced.background, Background::Lit);
+        let around = Rect {
+            min_x: -5,
+            min_y: -5,
+            max_x: 5,
+            max_y: 5,
+        };
+        assert_eq!(enhanced.count_lit_in(around), 121);
+        let enhanced = image.enhance_n(2);
+        assert_eq!((enhanced.num_lit_pixels(), enhanced.background), (0, Background::Dark));
+    }
+    Ok(())
+}
+
+#[test]
+fn test_day20_enhance_n() -> Result<(), error::Error> {
+    let input = std::fs::read_to_string("input_day20")?;
+    let image: Image = input.parse()?;
+    let stepped = image.enhance().enhance().enhance();
+    let image: Image = input.parse()?;
+    let enhanced = image.enhance_n(3);
+    assert_eq!(enhanced.num_lit_pixels(), stepped.num_lit_pixels());
+    assert_eq!(enhanced.background, stepped.background);
+    assert_eq!(enhanced.minmax_x(), (-3, 102));
+
+    let sparse = input.parse::<Image>()?.with_backend(Backend::Sparse).enhance_n(2);
+    assert_eq!(sparse.num_lit_pixels(), 5486);
+    assert!(Arc::ptr_eq(&sparse.enhancement, &sparse.enhance().enhancement));
+
+    let image: Image = input.parse()?;
+    assert_eq!(image.enhance_n(0).num_lit_pixels(), 5023);
+
+    Ok(())
+}
+
+#[test]
+fn test_day20_render() -> Result<(), error::Error> {
+    let input = EXAMPLE;
+
+    let image: Image = input.parse()?;
+    assert_eq!(image.render(), "#..#.\n#....\n##..#\n..#..\n..###\n");
+    assert_eq!(image.enhance().render(), ".##.##.\n#..#.#.\n##.#..#\n####..#\n.#..##.\n..##..#\n...#.#.\n");
+
+    Ok(())
+}
+
+#[cfg(feature = "image")]
+#[test]
+fn test_day20_export() -> Result<(), error::Error> {
+    let image: Image = std::fs::read_to_string("input_day20")?.parse()?;
+
+    let mut pgm = vec![];
+    image.write_pgm(&mut pgm, 2)?;
+    assert!(pgm.starts_with(b"P5\n200 200\n255\n"));
+    assert_eq!(pgm.len(), "P5\n200 200\n255\n".len() + 200 * 200);
+    assert_eq!(pgm.iter().skip("P5\n200 200\n255\n".len()).filter(|&&value| value == 0).count(), 4 * 5023);
+
+    let mut png = vec![];
+    image.write_png(&mut png, 1)?;
+    assert_eq!(&png[1..4], b"PNG");
+
+    let mut gif = vec![];
+    let image = image.write_gif(&mut gif, 2, 1)?;
+    assert_eq!(&gif[..6], b"GIF89a");
+    assert_eq!(image.num_lit_pixels(), 5486);
+
+    Ok(())
+}
+
+#[test]
+fn test_day20_background() -> Result<(), error::Error> {
+    // the real input lights up the whole background on odd steps
+    let image: Image = std::fs::read_to_string("input_day20")?.parse()?;
+    assert_eq!(image.background, Background::Dark);
+    let image = image.enhance();
+    assert_eq!(image.background, Background::Lit);
+    let image = image.enhance();
+    assert_eq!(image.background, Background::Dark);
+
+    let dark = ".".repeat(512);
+    let image: Image = format!("{}\n\n#.\n.#", dark).parse()?;
+    assert_eq!(image.enhance().background, Background::Dark);
+    assert_eq!(image.enhance().num_lit_pixels(), 0);
+
+    // once lit, a background that maps nine lit pixels to lit stays that way
+    let image: Image = format!("#{}#\n\n#", ".".repeat(510)).parse()?;
+    assert_eq!(image.enhance_n(3).background, Background::Lit);
+
+    let parse = |s: String| match s.parse::<Image>() {
+        Err(error::Error::Parse(message)) => message,
+        _ => panic!("expected a parse error"),
+    };
+    assert_eq!(
+        parse(format!("{}\n\n#.", ".".repeat(511))),
+        "enhancement must be 512 characters, or 2^(k*k) for an odd kernel size k, got 511"
+    );
+    assert_eq!(parse(format!("{}x\n\n#.", ".".repeat(511))), "invalid character 'x' in enhancement at position 511");
+    assert_eq!(parse(format!("{}\n\n#.\n.o", dark)), "invalid character 'o' in image at line 2, column 2");
+    assert_eq!(parse(dark), "no image after the enhancement");
+
+    Ok(())
+}
+
+#[test]
+fn test_day20_region() -> Result<(), error::Error> {
+    let image: Image = std::fs::read_to_string("input_day20")?.parse()?;
+    let bounds = image.bounds();
+    assert_eq!(
+        bounds,
+        Rect {
+            min_x: 0,
+            min_y: 0,
+            max_x: 99,
+            max_y: 99
+        }
+    );
+    assert_eq!(image.count_lit_in(bounds), 5023);
+    let corner = Rect {
+        min_x: -10,
+        min_y: -10,
+        max_x: 9,
+        max_y: 9,
+    };
+    assert_eq!(image.count_lit_in(corner), image.crop(Rect { min_x: 0, min_y: 0, ..corner }).num_lit_pixels());
+
+    // with a lit background, everything around the worked out part counts too
+    let image = image.enhance();
+    assert_eq!(image.background, Background::Lit);
+    let bounds = image.bounds();
+    let around = Rect {
+        min_x: bounds.min_x - 2,
+        min_y: bounds.min_y - 2,
+        max_x: bounds.max_x + 2,
+        max_y: bounds.max_y + 2,
+    };
+    assert_eq!(image.count_lit_in(around), image.count_lit_in(bounds) + around.area() - bounds.area());
+    assert_eq!(image.count_lit_in(bounds), image.num_lit_pixels());
+
+    let cropped = image.crop(around);
+    assert_eq!(cropped.bounds(), around);
+    assert_eq!(cropped.num_lit_pixels(), image.count_lit_in(around));
+    assert_eq!(cropped.count_lit_in(corner), image.count_lit_in(corner));
+
+    let example: Image = format!("{}\n\n#..#.\n#....\n##..#\n..#..\n..###", ".".repeat(512)).parse()?;
+    let window = Rect {
+        min_x: 1,
+        min_y: 2,
+        max_x: 3,
+        max_y: 4,
+    };
+    assert_eq!(example.crop(window).render(), "#..\n.#.\n.##\n");
+    assert_eq!(example.count_lit_in(Rect { max_x: 0, ..window }), 0);
+
+    Ok(())
+}
+
+#[test]
+fn test_day20_kernel_size() -> Result<(), error::Error> {
+    let image: Image = std::fs::read_to_string("input_day20")?.parse()?;
+    assert_eq!(image.kernel_size(), 3);
+
+    // lights up everything within two pixels of a lit one
+    let grow: String = (0..1 << 25).map(|index| if index == 0 { '.' } else { '#' }).collect();
+    let image: Image = format!("{}\n\n...\n.#.\n...", grow).parse()?;
+    assert_eq!(image.kernel_size(), 5);
+    let enhanced = image.enhance();
+    assert_eq!(enhanced.background, Background::Dark);
+    assert_eq!(enhanced.minmax_x(), (-2, 4));
+    assert_eq!(enhanced.num_lit_pixels(), 25);
+    assert_eq!(
+        enhanced.count_lit_in(Rect {
+            min_x: -1,
+            min_y: -1,
+            max_x: 3,
+            max_y: 3
+        }),
+        25
+    );
+
+    assert_eq!(enhanced.enhance().num_lit_pixels(), 81);
+    let sparse = image.with_backend(Backend::Sparse).enhance_n(2);
+    assert_eq!(sparse.num_lit_pixels(), 81);
+
+    // the middle pixel of a 1 x 1 kernel is all there is to see
+    let image: Image = "#.\n\n#.\n.#".parse()?;
+    assert_eq!(image.kernel_size(), 1);
+    assert_eq!(image.enhance().render(), ".#\n#.\n");
+
+    Ok(())
+}
+
+#[test]
+fn test_day20_diff() -> Result<(), error::Error> {
+    let input = std::fs::read_to_string("input_day20")?;
+    let image: Image = input.parse()?;
+    let sparse = input.parse::<Image>()?.with_backend(Backend::Sparse);
+    assert!(image.diff(&sparse).is_empty());
+
+    let enhanced = image.enhance().enhance();
+    let diff = image.diff(&enhanced);
+    assert!(!diff.background_changed);
+    assert_eq!(diff.num_added() as i64 - diff.num_removed() as i64, 5486 - 5023);
+    assert_eq!(enhanced.diff(&image).added, diff.removed);
+
+    // one step lights up the background, which the pixels around the input have to show
+    let diff = image.diff(&image.enhance());
+    assert!(diff.background_changed);
+    assert!(diff.added.contains(&(-1, -1)));
+
+    let a: Image = format!("{}\n\n#..\n.#.", ".".repeat(512)).parse()?;
+    let b: Image = format!("{}\n\n...\n.##", ".".repeat(512)).parse()?;
+    let diff = a.diff(&b);
+    assert_eq!(diff.added, vec![(2, 1)]);
+    assert_eq!(diff.removed, vec![(0, 0)]);
+
+    Ok(())
+}
@@ -1,16 +1,93 @@
 use crate::error;
+use crate::parse;
+use crate::puzzle;
 
-use std::collections::HashMap;
+use crate::collections::BTreeMap;
+use crate::collections::HashMap;
+use crate::par::*;
+use alloc::string::ToString;
+use alloc::vec::Vec;
 
 #[derive(Debug, PartialEq)]
 pub struct DiceOutcome {
-    value: usize,
-    weight: usize,
+    pub value: usize,
+    // universes, counted in 64 bits like the wins
+    pub weight: u64,
 }
 
-pub trait Die {
-    fn roll_three(&mut self) -> Vec<DiceOutcome>;
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rules {
+    pub die_sides: usize,
+    pub rolls_per_turn: usize,
+    pub track_length: usize,
+    pub winning_score: usize,
+}
+
+impl Rules {
+    pub fn practice() -> Self {
+        Self {
+            die_sides: 100,
+            rolls_per_turn: 3,
+            track_length: 10,
+            winning_score: 1000,
+        }
+    }
+
+    pub fn dirac() -> Self {
+        Self {
+            die_sides: 3,
+            rolls_per_turn: 3,
+            track_length: 10,
+            winning_score: 21,
+        }
+    }
+
+    pub fn with_die_sides(mut self, die_sides: usize) -> Self {
+        self.die_sides = die_sides;
+        self
+    }
+
+    pub fn with_rolls_per_turn(mut self, rolls_per_turn: usize) -> Self {
+        self.rolls_per_turn = rolls_per_turn;
+        self
+    }
+
+    pub fn with_track_length(mut self, track_length: usize) -> Self {
+        self.track_length = track_length;
+        self
+    }
+
+    pub fn with_winning_score(mut self, winning_score: usize) -> Self {
+        self.winning_score = winning_score;
+        self
+    }
+
+    // a die without sides has nothing to roll, and a track without spaces has nowhere to move to
+    fn check(&self) -> Result<(), error::Error> {
+        if self.die_sides == 0 {
+            return Err(error::Error::General("a die needs at least one side".to_string()));
+        }
+        if self.track_length == 0 {
+            return Err(error::Error::General("a track needs at least one space".to_string()));
+        }
+        Ok(())
+    }
+}
+
+// rolls the same numbers every time it's used from scratch, like the practice die
+pub trait DeterministicDie {
+    fn roll(&mut self, sides: usize) -> usize;
     fn num_rolls(&self) -> usize;
+
+    fn roll_turn(&mut self, rules: &Rules) -> usize {
+        (0..rules.rolls_per_turn).map(|_| self.roll(rules.die_sides)).sum()
+    }
+}
+
+// splits the universe on every roll, so a turn has all its outcomes at once and nothing to count.
+// enough rolls per turn split it into more universes than there are outcomes to count them in
+pub trait QuantumDie {
+    fn outcomes(&self, rules: &Rules) -> Result<Vec<DiceOutcome>, error::Error>;
 }
 
 #[derive(Default)]
@@ -19,23 +96,57 @@ pub struct PracticeDie {
 }
 
 #[derive(Default)]
-pub struct DiracDie {
+pub struct DiracDie;
+
+// rolls the given numbers over and over, whatever the rules say the die looks like
+pub struct FixedDie {
+    rolls: Vec<usize>,
     num_rolls: usize,
 }
 
-impl PracticeDie {
-    fn roll(&mut self) -> usize {
-        self.num_rolls += 1;
-        ((self.num_rolls() - 1) % 100) + 1
+// a quantum die where each face comes up in as many universes as its weight
+pub struct LoadedDie {
+    faces: Vec<DiceOutcome>,
+}
+
+impl FixedDie {
+    pub fn new(rolls: Vec<usize>) -> Result<Self, error::Error> {
+        if rolls.is_empty() {
+            return Err(error::Error::General("a fixed die needs at least one roll".to_string()));
+        }
+        Ok(Self { rolls, num_rolls: 0 })
+    }
+}
+
+impl LoadedDie {
+    pub fn new(faces: Vec<DiceOutcome>) -> Self {
+        Self { faces }
     }
 }
 
-impl Die for PracticeDie {
-    fn roll_three(&mut self) -> Vec<DiceOutcome> {
-        vec![DiceOutcome {
-            value: self.roll() + self.roll() + self.roll(),
-            weight: 1,
-        }]
+// every sum the rolls of a turn can add up to, and in how many universes
+fn turn_outcomes(faces: &[DiceOutcome], rolls_per_turn: usize) -> Result<Vec<DiceOutcome>, error::Error> {
+    let mut sums: BTreeMap<usize, u64> = BTreeMap::from([(0, 1)]);
+    for _ in 0..rolls_per_turn {
+        let mut new_sums: BTreeMap<usize, u64> = BTreeMap::new();
+        for (sum, weight) in sums {
+            for face in faces {
+                let value = sum.checked_add(face.value).ok_or_else(|| error::Error::General("the rolls of a turn add up to too much".to_string()))?;
+                let universes = new_sums.entry(value).or_default();
+                *universes = weight.checked_mul(face.weight).and_then(|weight| universes.checked_add(weight)).ok_or_else(too_many_universes)?;
+            }
+        }
+        // a turn has to fit in as many universes as the wins are counted in
+        new_sums.values().try_fold(0u64, |total, &universes| total.checked_add(universes)).ok_or_else(too_many_universes)?;
+        sums = new_sums;
+    }
+    Ok(sums.into_iter().map(|(value, weight)| DiceOutcome { value, weight }).collect())
+}
+
+impl DeterministicDie for PracticeDie {
+    fn roll(&mut self, sides: usize) -> usize {
+        self.num_rolls += 1;
+        ((self.num_rolls() - 1) % sides) + 1
     }
 
     fn num_rolls(&self) -> usize {
@@ -43,18 +154,10 @@ impl Die for PracticeDie {
     }
 }
 
-impl Die for DiracDie {
-    fn roll_three(&mut self) -> Vec<DiceOutcome> {
-        self.num_rolls += 3;
-        vec![
-            DiceOutcome { value: 3, weight: 1 },
-            DiceOutcome { value: 4, weight: 3 },
-            DiceOutcome { value: 5, weight: 6 },
-            DiceOutcome { value: 6, weight: 7 },
-            DiceOutcome { value: 7, weight: 6 },
-            DiceOutcome { value: 8, weight: 3 },
-            DiceOutcome { value: 9, weight: 1 },
-        ]
+impl DeterministicDie for FixedDie {
+    fn roll(&mut self, _sides: usize) -> usize {
+        self.num_rolls += 1;
+        self.rolls[(self.num_rolls - 1) % self.rolls.len()]
     }
 
     fn num_rolls(&self) -> usize {
@@ -62,12 +165,35 @@ impl Die for DiracDie {
     }
 }
 
+impl QuantumDie for DiracDie {
+    fn outcomes(&self, rules: &Rules) -> Result<Vec<DiceOutcome>, error::Error> {
+        let faces: Vec<DiceOutcome> = (1..=rules.die_sides).map(|value| DiceOutcome { value, weight: 1 }).collect();
+        turn_outcomes(&faces, rules.rolls_per_turn)
+    }
+}
+
+impl QuantumDie for LoadedDie {
+    fn outcomes(&self, rules: &Rules) -> Result<Vec<DiceOutcome>, error::Error> {
+        turn_outcomes(&self.faces, rules.rolls_per_turn)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Strategy {
+    // depth first from the starting state, remembering every state on the way
+    Memoized,
+    // every state that isn't over, one combined score at a time from the highest down, each in parallel
+    Parallel,
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Game {
     player1_starting_position: usize,
     player2_starting_position: usize,
 }
 
-#[derive(Eq, PartialEq, Hash, Copy, Clone)]
+#[derive(Eq, PartialEq, Hash, Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GameState {
     p1_pos: usize,
     p2_pos: usize,
@@ -91,16 +217,16 @@ impl GameState {
         self.p1_score >= winning_score || self.p2_score >= winning_score
     }
 
-    fn play(&self, value: usize) -> Self {
+    fn play(&self, value: usize, track_length: usize) -> Self {
         let mut new_state = *self;
         match new_state.next_player {
             1 => {
-                new_state.move_p1(value);
+                new_state.move_p1(value, track_length);
                 new_state.p1_score += new_state.p1_pos;
                 new_state.next_player = 2;
             }
             2 => {
-                new_state.move_p2(value);
+                new_state.move_p2(value, track_length);
                 new_state.p2_score += new_state.p2_pos;
                 new_state.next_player = 1;
             }
@@ -109,104 +235,305 @@ impl GameState {
         new_state
     }
 
-    fn move_player(from: usize, steps: usize) -> usize {
-        let new_pos = from + steps;
-        ((new_pos - 1) % 10) + 1
+    fn move_player(from: usize, steps: usize, track_length: usize) -> usize {
+        let new_pos = from + steps + track_length;
+        ((new_pos - 1) % track_length) + 1
     }
 
-    fn move_p1(&mut self, steps: usize) {
-        self.p1_pos = GameState::move_player(self.p1_pos, steps);
+    fn move_p1(&mut self, steps: usize, track_length: usize) {
+        self.p1_pos = GameState::move_player(self.p1_pos, steps, track_length);
     }
 
-    fn move_p2(&mut self, steps: usize) {
-        self.p2_pos = GameState::move_player(self.p2_pos, steps);
+    fn move_p2(&mut self, steps: usize, track_length: usize) {
+        self.p2_pos = GameState::move_player(self.p2_pos, steps, track_length);
     }
 }
 
-pub struct GameResult {
-    pub states: HashMap<GameState, usize>,
+pub struct DeterministicResult {
+    pub winner: usize,
+    pub end_state: GameState,
     pub num_die_rolls: usize,
 }
 
-impl GameResult {
+impl DeterministicResult {
     pub fn calc_part1(&self) -> usize {
-        if self.states.len() != 1 {
-            panic!("invalid game state for part1 (1)");
-        }
-        let state = self.states.iter().next().unwrap();
-        if state.1 != &1 {
-            panic!("invalid game state for part1 (2)");
+        usize::min(self.end_state.p1_score, self.end_state.p2_score) * self.num_die_rolls
+    }
+}
+
+// universes won, counted in 64 bits whatever the platform's usize
+pub struct GameResult {
+    pub p1_wins: u64,
+    pub p2_wins: u64,
+}
+
+impl GameResult {
+    pub fn calc_part2(&self) -> u64 {
+        self.wins_per_player().into_iter().max().unwrap()
+    }
+
+    // universes won, player 1 first
+    pub fn wins_per_player(&self) -> Vec<u64> {
+        vec![self.p1_wins, self.p2_wins]
+    }
+
+    // the share of universes each player wins, or nothing at all if there were no universes
+    pub fn win_probabilities(&self) -> Vec<f64> {
+        if self.p1_wins == 0 && self.p2_wins == 0 {
+            return vec![0.0; 2];
         }
-        let state = state.0;
-        usize::min(state.p1_score, state.p2_score) * self.num_die_rolls
+        let total = self.p1_wins as f64 + self.p2_wins as f64;
+        self.wins_per_player().into_iter().map(|wins| wins as f64 / total).collect()
     }
+}
 
-    pub fn calc_part2(&self) -> usize {
-        let mut p1_wins = 0;
-        let mut p2_wins = 0;
-        for (state, num) in self.states.iter() {
-            if state.p1_score > state.p2_score {
-                p1_wins += num;
-            } else {
-                p2_wins += num;
-            }
+#[cfg(feature = "bignum")]
+impl GameResult {
+    pub fn exact_win_probabilities(&self) -> Vec<num_rational::BigRational> {
+        if self.p1_wins == 0 && self.p2_wins == 0 {
+            return vec![num_rational::BigRational::from_integer(0.into()); 2];
         }
-        usize::max(p1_wins, p2_wins)
+        let total = num_bigint::BigInt::from(self.p1_wins) + self.p2_wins;
+        self.wins_per_player().into_iter().map(|wins| num_rational::BigRational::new(wins.into(), total.clone())).collect()
     }
 }
 
-impl Game {
-    pub fn play(&self, die: &mut impl Die, winning_score: usize) -> GameResult {
-        let initial_state = GameState::new(self.player1_starting_position, self.player2_starting_position);
+// the wins so far plus the wins after an outcome, in as many universes as the outcome comes up in, or
+// nothing if a long race has split into more universes than 64 bits can count
+fn add_wins(wins: (u64, u64), (p1_wins, p2_wins): (u64, u64), weight: u64) -> Option<(u64, u64)> {
+    let add = |total: u64, wins: u64| wins.checked_mul(weight).and_then(|wins| total.checked_add(wins));
+    Some((add(wins.0, p1_wins)?, add(wins.1, p2_wins)?))
+}
 
-        let mut states: HashMap<GameState, usize> = HashMap::new();
-        let mut end_states: HashMap<GameState, usize> = HashMap::new();
+fn too_many_universes() -> error::Error {
+    error::Error::General("more universes than 64 bits can count".to_string())
+}
 
-        *states.entry(initial_state).or_default() += 1;
+// wins per player from every state worked out so far
+struct Universes<'a> {
+    outcomes: Vec<DiceOutcome>,
+    rules: &'a Rules,
+    wins: HashMap<GameState, (u64, u64)>,
+}
 
-        loop {
-            let mut new_states: HashMap<GameState, usize> = HashMap::new();
+impl Universes<'_> {
+    // depth first, but on a stack of its own rather than the call stack, which a long race would overflow
+    fn count_wins(&mut self, initial_state: GameState) -> Result<(u64, u64), error::Error> {
+        let mut stack = vec![initial_state];
+        while let Some(&state) = stack.last() {
+            if self.wins.contains_key(&state) {
+                stack.pop();
+                continue;
+            }
+
+            // whatever this state leads to has to be worked out first
+            let unknown: Vec<GameState> = self
+                .outcomes
+                .iter()
+                .map(|outcome| state.play(outcome.value, self.rules.track_length))
+                .filter(|new_state| !new_state.is_end_state(self.rules.winning_score) && !self.wins.contains_key(new_state))
+                .collect();
+            if !unknown.is_empty() {
+                stack.extend(unknown);
+                continue;
+            }
 
-            for (state, &amount) in states.iter() {
-                let dice_outcomes = die.roll_three();
-                for outcome in dice_outcomes.iter() {
-                    let new_state = state.play(outcome.value);
-                    if new_state.is_end_state(winning_score) {
-                        *end_states.entry(new_state).or_default() += amount * outcome.weight;
+            let mut wins = (0, 0);
+            for DiceOutcome { value, weight } in &self.outcomes {
+                let new_state = state.play(*value, self.rules.track_length);
+                let new_wins = if new_state.is_end_state(self.rules.winning_score) {
+                    if new_state.p1_score > new_state.p2_score {
+                        (1, 0)
                     } else {
-                        *new_states.entry(new_state).or_default() += amount * outcome.weight;
+                        (0, 1)
                     }
-                }
+                } else {
+                    self.wins[&new_state]
+                };
+                wins = add_wins(wins, new_wins, *weight).ok_or_else(too_many_universes)?;
             }
+            self.wins.insert(state, wins);
+            stack.pop();
+        }
+        Ok(self.wins[&initial_state])
+    }
+}
 
-            if new_states.is_empty() {
-                break;
-            }
+#[derive(Debug, PartialEq)]
+pub struct Turn {
+    pub player: usize,
+    pub rolls: Vec<usize>,
+    pub position: usize,
+    pub score: usize,
+}
 
-            states = new_states;
-        }
+// a deterministic game, one turn at a time, ending with the turn that wins it
+pub struct Turns<'a, D: DeterministicDie> {
+    die: &'a mut D,
+    rules: Rules,
+    state: GameState,
+}
+
+impl<D: DeterministicDie> Turns<'_, D> {
+    pub fn num_die_rolls(&self) -> usize {
+        self.die.num_rolls()
+    }
+}
+
+impl<D: DeterministicDie> Iterator for Turns<'_, D> {
+    type Item = Turn;
 
-        GameResult {
-            states: end_states,
-            num_die_rolls: die.num_rolls(),
+    fn next(&mut self) -> Option<Turn> {
+        if self.state.is_end_state(self.rules.winning_score) {
+            return None;
         }
+        let player = self.state.next_player;
+        let rolls: Vec<usize> = (0..self.rules.rolls_per_turn).map(|_| self.die.roll(self.rules.die_sides)).collect();
+        self.state = self.state.play(rolls.iter().sum(), self.rules.track_length);
+        let (position, score) = match player {
+            1 => (self.state.p1_pos, self.state.p1_score),
+            _ => (self.state.p2_pos, self.state.p2_score),
+        };
+        Some(Turn { player, rolls, position, score })
     }
 }
 
-impl std::str::FromStr for Game {
+// wins per player from every state that isn't over. a move always adds to the mover's score, so the
+// states with a given combined score only need the ones above them and can be worked out side by side.
+// states that can't be reached from the start can still have too many universes to count, so that's
+// only an error for the ones the starting state needs
+fn count_wins_in_layers(outcomes: &[DiceOutcome], rules: &Rules, initial_state: GameState) -> Result<(u64, u64), error::Error> {
+    let wins_from = |state: GameState, wins: &HashMap<GameState, Option<(u64, u64)>>| {
+        outcomes.iter().try_fold((0, 0), |total, outcome| {
+            let new_state = state.play(outcome.value, rules.track_length);
+            let new_wins = if new_state.is_end_state(rules.winning_score) {
+                if new_state.p1_score > new_state.p2_score {
+                    (1, 0)
+                } else {
+                    (0, 1)
+                }
+            } else {
+                wins[&new_state]?
+            };
+            add_wins(total, new_wins, outcome.weight)
+        })
+    };
+
+    let mut wins = HashMap::new();
+    for total in (0..=2 * rules.winning_score.saturating_sub(1)).rev() {
+        let layer: Vec<GameState> = (0..rules.winning_score)
+            .filter(|&p1_score| p1_score <= total && total - p1_score < rules.winning_score)
+            .flat_map(|p1_score| {
+                (1..=rules.track_length).flat_map(move |p1_pos| {
+                    (1..=rules.track_length).flat_map(move |p2_pos| {
+                        [1, 2].map(|next_player| GameState {
+                            p1_pos,
+                            p2_pos,
+                            p1_score,
+                            p2_score: total - p1_score,
+                            next_player,
+                        })
+                    })
+                })
+            })
+            .collect();
+        let layer_wins: Vec<(GameState, Option<(u64, u64)>)> = layer.into_par_iter().map(|state| (state, wins_from(state, &wins))).collect();
+        wins.extend(layer_wins);
+    }
+
+    // the starting positions don't have to be on the track yet
+    wins_from(initial_state, &wins).ok_or_else(too_many_universes)
+}
+
+impl Game {
+    fn initial_state(&self) -> GameState {
+        GameState::new(self.player1_starting_position, self.player2_starting_position)
+    }
+
+    pub fn play_deterministic(&self, die: &mut impl DeterministicDie, rules: &Rules) -> Result<DeterministicResult, error::Error> {
+        let mut turns = self.turns(die, rules)?;
+        let winner = turns
+            .by_ref()
+            .last()
+            .ok_or_else(|| error::Error::General("nobody can win with a winning score of 0".to_string()))?
+            .player;
+        Ok(DeterministicResult {
+            winner,
+            end_state: turns.state,
+            num_die_rolls: turns.num_die_rolls(),
+        })
+    }
+
+    pub fn play_quantum(&self, die: &impl QuantumDie, rules: &Rules) -> Result<GameResult, error::Error> {
+        self.play_quantum_with(die, rules, Strategy::Memoized)
+    }
+
+    pub fn play_quantum_with(&self, die: &impl QuantumDie, rules: &Rules, strategy: Strategy) -> Result<GameResult, error::Error> {
+        rules.check()?;
+        let (p1_wins, p2_wins) = match strategy {
+            Strategy::Memoized => {
+                let mut universes = Universes {
+                    outcomes: die.outcomes(rules)?,
+                    rules,
+                    wins: HashMap::new(),
+                };
+                universes.count_wins(self.initial_state())?
+            }
+            Strategy::Parallel => count_wins_in_layers(&die.outcomes(rules)?, rules, self.initial_state())?,
+        };
+        Ok(GameResult { p1_wins, p2_wins })
+    }
+
+    pub fn turns<'a, D: DeterministicDie>(&self, die: &'a mut D, rules: &Rules) -> Result<Turns<'a, D>, error::Error> {
+        rules.check()?;
+        Ok(Turns {
+            die,
+            rules: *rules,
+            state: self.initial_state(),
+        })
+    }
+}
+
+impl core::str::FromStr for Game {
     type Err = error::Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut lines = s.lines().map(|l| l.trim_start().trim_end()).filter(|l| !l.is_empty());
-        let p1_start = lines.next().unwrap().split(':').nth(1).unwrap().trim_start().trim_end();
-        let p2_start = lines.next().unwrap().split(':').nth(1).unwrap().trim_start().trim_end();
+        let mut starting_positions = parse::lines(s).map(|(number, line)| parse::on_line(number, parse::labeled(line, "starting position:")));
+        let mut next = || {
+            starting_positions
+                .next()
+                .unwrap_or_else(|| Err(error::Error::Parse("expected a starting position for both players".to_string())))
+        };
         Ok(Self {
-            player1_starting_position: p1_start.parse()?,
-            player2_starting_position: p2_start.parse()?,
+            player1_starting_position: next()?,
+            player2_starting_position: next()?,
         })
     }
 }
 
+const EXAMPLE: &str = r#"
+Player 1 starting position: 4
+Player 2 starting position: 8
+"#;
+
+impl puzzle::Puzzle for Game {
+    fn parse(input: &str) -> Result<Self, error::Error> {
+        input.parse()
+    }
+
+    fn part1(&self) -> Result<puzzle::Answer, error::Error> {
+        Ok(self.play_deterministic(&mut PracticeDie::default(), &Rules::practice())?.calc_part1().into())
+    }
+
+    fn part2(&self) -> Result<puzzle::Answer, error::Error> {
+        Ok(self.play_quantum(&DiracDie, &Rules::dirac())?.calc_part2().into())
+    }
+
+    fn examples() -> Vec<puzzle::Example> {
+        vec![(EXAMPLE, Some("739785"), Some("444356092776315"))]
+    }
+}
+
 #[test]
 fn test_i64() {
     assert!(444356092776315 < i64::MAX);
@@ -216,19 +543,37 @@ fn test_i64() {
 #[test]
 fn test_die() -> Result<(), error::Error> {
     let mut die = PracticeDie::default();
+    let rules = Rules::practice();
 
-    assert_eq!(die.roll_three(), vec![DiceOutcome { value: 1 + 2 + 3, weight: 1 }]);
-    assert_eq!(die.roll_three(), vec![DiceOutcome { value: 4 + 5 + 6, weight: 1 }]);
-    assert_eq!(die.roll_three(), vec![DiceOutcome { value: 7 + 8 + 9, weight: 1 }]);
-    assert_eq!(die.roll_three(), vec![DiceOutcome { value: 10 + 11 + 12, weight: 1 }]);
+    assert_eq!(die.roll_turn(&rules), 1 + 2 + 3);
+    assert_eq!(die.roll_turn(&rules), 4 + 5 + 6);
+    assert_eq!(die.roll_turn(&rules), 7 + 8 + 9);
+    assert_eq!(die.roll_turn(&rules), 10 + 11 + 12);
     for _ in 0..26 {
-        die.roll_three();
+        die.roll_turn(&rules);
     }
-    assert_eq!(die.roll_three(), vec![DiceOutcome { value: 91 + 92 + 93, weight: 1 }]);
+    assert_eq!(die.roll_turn(&rules), 91 + 92 + 93);
+
+    assert_eq!(die.num_rolls(), 93);
 
-    let mut die = DiracDie::default();
-    assert_eq!(die.roll_three().iter().map(|o| o.weight).sum::<usize>(), 27);
-    assert_eq!(die.roll_three().iter().map(|o| o.value).sum::<usize>(), 42);
+    let die = DiracDie;
+    let rules = Rules::dirac();
+    assert_eq!(die.outcomes(&rules)?.iter().map(|o| o.weight).sum::<u64>(), 27);
+    assert_eq!(die.outcomes(&rules)?.iter().map(|o| o.value).sum::<usize>(), 42);
+    assert_eq!(die.outcomes(&rules)?[3], DiceOutcome { value: 6, weight: 7 });
+
+    let rules = Rules::dirac().with_die_sides(4).with_rolls_per_turn(2);
+    assert_eq!(
+        die.outcomes(&rules)?.iter().map(|o| (o.value, o.weight)).collect::<Vec<_>>(),
+        vec![(2, 1), (3, 2), (4, 3), (5, 4), (6, 3), (7, 2), (8, 1)]
+    );
+
+    // 3^40 universes a turn still fit in 64 bits, 3^41 don't
+    let rules = Rules::dirac().with_rolls_per_turn(40);
+    assert_eq!(die.outcomes(&rules)?.iter().map(|o| o.weight).sum::<u64>(), 3u64.pow(40));
+    let rules = Rules::dirac().with_rolls_per_turn(41);
+    assert_eq!(die.outcomes(&rules).err(), Some(too_many_universes()));
+    assert!(LoadedDie::new(vec![DiceOutcome { value: usize::MAX, weight: 1 }]).outcomes(&Rules::dirac()).is_err());
 
     Ok(())
 }
@@ -236,45 +581,208 @@ fn test_die() -> Result<(), error::Error> {
 #[test]
 fn test_board() -> Result<(), error::Error> {
     let mut state = GameState::new(4, 8);
-    state = state.play(1 + 2 + 3);
-    state = state.play(4 + 5 + 6);
-    state = state.play(7 + 8 + 9);
-    state = state.play(10 + 11 + 12);
+    state = state.play(1 + 2 + 3, 10);
+    state = state.play(4 + 5 + 6, 10);
+    state = state.play(7 + 8 + 9, 10);
+    state = state.play(10 + 11 + 12, 10);
     assert_eq!(state.p1_score, 14);
     assert_eq!(state.p2_score, 9);
+
+    let state = GameState::new(18, 1).play(1 + 2 + 3, 20);
+    assert_eq!(state.p1_pos, 4);
+    assert_eq!(state.p1_score, 4);
     Ok(())
 }
 
 #[test]
 fn test_day21() -> Result<(), error::Error> {
-    let input = r#"
-Player 1 starting position: 4
-Player 2 starting position: 8
-"#;
+    let input = EXAMPLE;
     let game: Game = input.parse()?;
     assert_eq!(game.player1_starting_position, 4);
     assert_eq!(game.player2_starting_position, 8);
+    assert!("Player 1 starting position: 4".parse::<Game>().is_err());
+    assert!(matches!(
+        "Player 1 starting position: 4\nPlayer 2 starting position: x".parse::<Game>(),
+        Err(error::Error::Parse(message)) if message.starts_with("line 2, column 29:")
+    ));
 
     let mut die = PracticeDie::default();
-    let result = game.play(&mut die, 1000);
+    let result = game.play_deterministic(&mut die, &Rules::practice())?;
     //assert_eq!(result.num_die_rolls, 993);
     assert_eq!(result.calc_part1(), 739785);
 
-    let mut die = DiracDie::default();
-    let result = game.play(&mut die, 21);
+    let result = game.play_quantum(&DiracDie, &Rules::dirac())?;
     assert_eq!(result.calc_part2(), 444356092776315);
+    assert_eq!(result.wins_per_player(), vec![444356092776315, 341960390180808]);
 
     let game: Game = std::fs::read_to_string("input_day21")?.parse()?;
     assert_eq!(game.player1_starting_position, 4);
     assert_eq!(game.player2_starting_position, 10);
 
     let mut die = PracticeDie::default();
-    let result = game.play(&mut die, 1000);
+    let result = game.play_deterministic(&mut die, &Rules::practice())?;
     assert_eq!(result.calc_part1(), 855624);
 
-    let mut die = DiracDie::default();
-    let result = game.play(&mut die, 21);
+    let result = game.play_quantum(&DiracDie, &Rules::dirac())?;
     assert_eq!(result.calc_part2(), 187451244607486);
 
+    // a shorter race still reaches every end state
+    let result = game.play_quantum(&DiracDie, &Rules::dirac().with_winning_score(1))?;
+    assert_eq!(result.p1_wins + result.p2_wins, 27);
+    assert_eq!(result.p2_wins, 0);
+
+    let mut die = PracticeDie::default();
+    let result = game.play_deterministic(&mut die, &Rules::practice().with_track_length(20).with_die_sides(6))?;
+    assert!(result.end_state.is_end_state(1000));
+    assert_eq!(result.num_die_rolls % 3, 0);
+
+    let error = |message: &str| Some(error::Error::General(message.to_string()));
+    let rules = Rules::dirac().with_die_sides(0);
+    assert_eq!(game.play_quantum(&DiracDie, &rules).err(), error("a die needs at least one side"));
+    let rules = Rules::practice().with_track_length(0);
+    assert_eq!(game.play_deterministic(&mut PracticeDie::default(), &rules).err(), error("a track needs at least one space"));
+    assert!(game.turns(&mut PracticeDie::default(), &rules).is_err());
+    let rules = Rules::practice().with_winning_score(0);
+    assert_eq!(game.play_deterministic(&mut PracticeDie::default(), &rules).err(), error("nobody can win with a winning score of 0"));
+
+    Ok(())
+}
+
+#[test]
+fn test_day21_turns() -> Result<(), error::Error> {
+    let game: Game = "Player 1 starting position: 4\nPlayer 2 starting position: 8".parse()?;
+    let mut die = PracticeDie::default();
+    let mut turns = game.turns(&mut die, &Rules::practice())?;
+    assert_eq!(
+        turns.next(),
+        Some(Turn {
+            player: 1,
+            rolls: vec![1, 2, 3],
+            position: 10,
+            score: 10
+        })
+    );
+    assert_eq!(
+        turns.next(),
+        Some(Turn {
+            player: 2,
+            rolls: vec![4, 5, 6],
+            position: 3,
+            score: 3
+        })
+    );
+
+    let last = turns.by_ref().last().unwrap();
+    assert_eq!(last.player, 1);
+    assert_eq!(last.score, 1000);
+    assert_eq!(turns.num_die_rolls(), 993);
+    assert_eq!(turns.next(), None);
+
+    // the turns add up to what play works out
+    let mut die = PracticeDie::default();
+    let result = game.play_deterministic(&mut die, &Rules::practice())?;
+    assert_eq!(result.winner, 1);
+    assert_eq!(game.turns(&mut PracticeDie::default(), &Rules::practice())?.count() * 3, result.num_die_rolls);
+
+    Ok(())
+}
+
+#[test]
+fn test_day21_custom_dice() -> Result<(), error::Error> {
+    let game: Game = std::fs::read_to_string("input_day21")?.parse()?;
+
+    // a loaded die with the faces of the dirac die is the dirac die
+    let fair = LoadedDie::new((1..=3).map(|value| DiceOutcome { value, weight: 1 }).collect());
+    assert_eq!(game.play_quantum(&fair, &Rules::dirac())?.calc_part2(), 187451244607486);
+
+    let loaded = LoadedDie::new(vec![DiceOutcome { value: 1, weight: 1 }, DiceOutcome { value: 3, weight: 2 }]);
+    assert_eq!(
+        loaded.outcomes(&Rules::dirac().with_rolls_per_turn(2))?,
+        vec![DiceOutcome { value: 2, weight: 1 }, DiceOutcome { value: 4, weight: 4 }, DiceOutcome { value: 6, weight: 4 }]
+    );
+    let result = game.play_quantum(&loaded, &Rules::dirac())?;
+    assert_ne!(result.wins_per_player(), game.play_quantum(&DiracDie, &Rules::dirac())?.wins_per_player());
+
+    // a die that never splits the universe scores 1 a turn on a track of one space, in a race far
+    // longer than the call stack is deep
+    let single = LoadedDie::new(vec![DiceOutcome { value: 1, weight: 1 }]);
+    let rules = Rules::dirac().with_rolls_per_turn(1).with_track_length(1).with_winning_score(200_000);
+    assert_eq!(game.play_quantum(&single, &rules)?.wins_per_player(), vec![1, 0]);
+
+    // the practice die as a sequence
+    let mut die = FixedDie::new((1..=100).collect())?;
+    assert_eq!(game.play_deterministic(&mut die, &Rules::practice())?.calc_part1(), 855624);
+
+    // player 1 moves 3 from 4 every turn, player 2 stays on 10
+    let mut die = FixedDie::new(vec![1, 1, 1, 10, 10, 10])?;
+    let result = game.play_deterministic(&mut die, &Rules::practice().with_winning_score(30))?;
+    assert_eq!(result.winner, 2);
+    assert_eq!(result.end_state.p2_score, 30);
+    assert_eq!(result.end_state.p1_score, 7 + 10 + 3);
+
+    assert!(FixedDie::new(vec![]).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_day21_win_probabilities() -> Result<(), error::Error> {
+    let game: Game = "Player 1 starting position: 4\nPlayer 2 starting position: 8".parse()?;
+    let result = game.play_quantum(&DiracDie, &Rules::dirac())?;
+    let probabilities = result.win_probabilities();
+    assert!((probabilities[0] - 444356092776315.0 / 786316482957123.0).abs() < 1e-12);
+    assert!((probabilities.iter().sum::<f64>() - 1.0).abs() < 1e-12);
+
+    // the first player always wins a race to 1
+    let result = game.play_quantum(&DiracDie, &Rules::dirac().with_winning_score(1))?;
+    assert_eq!(result.win_probabilities(), vec![1.0, 0.0]);
+
+    let result = game.play_quantum(&LoadedDie::new(vec![]), &Rules::dirac())?;
+    assert_eq!(result.win_probabilities(), vec![0.0, 0.0]);
+
+    Ok(())
+}
+
+#[cfg(feature = "bignum")]
+#[test]
+fn test_day21_exact_win_probabilities() -> Result<(), error::Error> {
+    let game: Game = "Player 1 starting position: 4\nPlayer 2 starting position: 8".parse()?;
+    let result = game.play_quantum(&DiracDie, &Rules::dirac())?;
+    let probabilities = result.exact_win_probabilities();
+    assert_eq!(probabilities[0], num_rational::BigRational::new(444356092776315u64.into(), 786316482957123u64.into()));
+    assert_eq!(&probabilities[0] + &probabilities[1], num_rational::BigRational::from_integer(1.into()));
+
+    Ok(())
+}
+
+#[test]
+fn test_day21_strategies() -> Result<(), error::Error> {
+    let game: Game = std::fs::read_to_string("input_day21")?.parse()?;
+    let result = game.play_quantum_with(&DiracDie, &Rules::dirac(), Strategy::Parallel)?;
+    assert_eq!(result.calc_part2(), 187451244607486);
+
+    for rules in [
+        Rules::dirac().with_winning_score(28),
+        Rules::dirac().with_die_sides(4).with_track_length(20),
+        Rules::dirac().with_winning_score(1),
+    ] {
+        let memoized = game.play_quantum_with(&DiracDie, &rules, Strategy::Memoized)?;
+        let parallel = game.play_quantum_with(&DiracDie, &rules, Strategy::Parallel)?;
+        assert_eq!(parallel.wins_per_player(), memoized.wins_per_player());
+    }
+
+    // starting off the track
+    let game: Game = "Player 1 starting position: 14\nPlayer 2 starting position: 8".parse()?;
+    let parallel = game.play_quantum_with(&DiracDie, &Rules::dirac(), Strategy::Parallel)?;
+    assert_eq!(parallel.wins_per_player(), game.play_quantum(&DiracDie, &Rules::dirac())?.wins_per_player());
+
+    // a race to 29 is too long to count in 64 bits
+    let too_many = Some(error::Error::General("more universes than 64 bits can count".to_string()));
+    for strategy in [Strategy::Memoized, Strategy::Parallel] {
+        assert_eq!(game.play_quantum_with(&DiracDie, &Rules::dirac().with_winning_score(29), strategy).err(), too_many);
+    }
+    assert_eq!(game.play_quantum(&DiracDie, &Rules::dirac().with_winning_score(60)).err(), too_many);
+    assert_eq!(game.play_quantum(&DiracDie, &Rules::dirac().with_rolls_per_turn(41)).err(), too_many);
+
     Ok(())
 }
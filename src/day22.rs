@@ -0,0 +1,800 @@
+use crate::error;
+use crate::parse;
+use crate::puzzle;
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+// a cuboid of cubes, inclusive on every side, indexed x, y, z
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Range3D {
+    pub begin: [i64; 3],
+    pub end: [i64; 3],
+}
+
+// plenty for any reboot, and small enough that the faces one past the end of a cuboid can't overflow.
+// a cuboid, or any number of them that don't overlap, holds fewer than 2^64 cubes, so volumes are
+// counted in 64 bits whatever the platform's usize, which is only 32 on wasm32
+pub const MAX_COORDINATE: i64 = 1 << 20;
+
+// the cubes part 1 is about
+pub const INITIALIZATION_REGION: Range3D = Range3D {
+    begin: [-50, -50, -50],
+    end: [50, 50, 50],
+};
+
+impl Range3D {
+    pub fn new(begin: [i64; 3], end: [i64; 3]) -> Self {
+        Self { begin, end }
+    }
+
+    pub fn volume(&self) -> u64 {
+        (0..3).map(|axis| (self.end[axis] - self.begin[axis] + 1) as u64).product()
+    }
+
+    pub fn is_superset_of(&self, other: &Range3D) -> bool {
+        (0..3).all(|axis| self.begin[axis] <= other.begin[axis] && other.end[axis] <= self.end[axis])
+    }
+
+    pub fn overlaps(&self, other: &Range3D) -> bool {
+        (0..3).all(|axis| self.begin[axis] <= other.end[axis] && other.begin[axis] <= self.end[axis])
+    }
+
+    pub fn intersect(&self, other: &Range3D) -> Option<Range3D> {
+        if !self.overlaps(other) {
+            return None;
+        }
+        Some(Range3D {
+            begin: [0, 1, 2].map(|axis| self.begin[axis].max(other.begin[axis])),
+            end: [0, 1, 2].map(|axis| self.end[axis].min(other.end[axis])),
+        })
+    }
+
+    // what's left of self without other, as at most six cuboids that don't overlap: a slab on either
+    // side of the overlap along x, then along y within what's left, then along z
+    pub fn subtract(&self, other: &Range3D) -> Vec<Range3D> {
+        let overlap = match self.intersect(other) {
+            Some(overlap) => overlap,
+            None => return vec![*self],
+        };
+        let mut pieces = vec![];
+        let mut rest = *self;
+        for axis in 0..3 {
+            if rest.begin[axis] < overlap.begin[axis] {
+                let mut slab = rest;
+                slab.end[axis] = overlap.begin[axis] - 1;
+                pieces.push(slab);
+                rest.begin[axis] = overlap.begin[axis];
+            }
+            if overlap.end[axis] < rest.end[axis] {
+                let mut slab = rest;
+                slab.begin[axis] = overlap.end[axis] + 1;
+                pieces.push(slab);
+                rest.end[axis] = overlap.end[axis];
+            }
+        }
+        pieces
+    }
+
+    // the number of cubes in at least one of ranges
+    pub fn union_volume(ranges: &[Range3D]) -> u64 {
+        let mut disjoint: Vec<Range3D> = vec![];
+        for range in ranges {
+            let mut pieces = vec![*range];
+            for seen in disjoint.iter() {
+                pieces = pieces.iter().flat_map(|piece| piece.subtract(seen)).collect();
+            }
+            disjoint.extend(pieces);
+        }
+        disjoint.iter().map(|range| range.volume()).sum()
+    }
+
+    // splits self along every face of others and yields the pieces inside at least one of them, one at a time.
+    // each piece is either inside or outside each of others, and together with the ones left out they make up self
+    pub fn cut<'a>(&self, others: &'a [Range3D]) -> impl Iterator<Item = Range3D> + 'a {
+        let bounds = [0, 1, 2].map(|axis| {
+            let mut bounds: Vec<i64> = others
+                .iter()
+                .flat_map(|other| [other.begin[axis], other.end[axis] + 1])
+                .filter(|&plane| plane > self.begin[axis] && plane <= self.end[axis])
+                .chain([self.begin[axis], self.end[axis] + 1])
+                .collect();
+            bounds.sort_unstable();
+            bounds.dedup();
+            bounds
+        });
+        let [x, y, z] = bounds;
+        let windows = |bounds: &Vec<i64>| bounds.windows(2).map(|bound| (bound[0], bound[1] - 1)).collect::<Vec<_>>();
+        let (x, y, z) = (windows(&x), windows(&y), windows(&z));
+        itertools::iproduct!(x, y, z)
+            .map(|((x_begin, x_end), (y_begin, y_end), (z_begin, z_end))| Range3D::new([x_begin, y_begin, z_begin], [x_end, y_end, z_end]))
+            .filter(move |piece| others.iter().any(|other| other.overlaps(piece)))
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Axis {
+    X,
+    Y,
+    Z,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum State {
+    On,
+    Off,
+}
+
+// one line of the reboot steps, e.g. "on x=10..12,y=10..12,z=10..12"
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RebootStep {
+    pub state: State,
+    pub range: Range3D,
+}
+
+impl core::str::FromStr for RebootStep {
+    type Err = error::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (name, _) = s.split_once(' ').ok_or_else(|| error::Error::Parse(format!("expected a state and a cuboid: {:?}", s)))?;
+        let state = match name {
+            "on" => State::On,
+            "off" => State::Off,
+            _ => return Err(error::Error::Parse(format!("unknown state {:?}, expected on or off", name))),
+        };
+        let values = parse::scan(s, &format!("{} x={{}}..{{}},y={{}}..{{}},z={{}}..{{}}", name))?;
+        if let Some(value) = values.into_iter().find(|value| !(-MAX_COORDINATE..=MAX_COORDINATE).contains(value)) {
+            return Err(error::Error::Parse(format!("{} is outside -{}..{} in {:?}", value, MAX_COORDINATE, MAX_COORDINATE, s)));
+        }
+        let [x_begin, x_end, y_begin, y_end, z_begin, z_end] = values;
+        // an inverted range would have a negative size, which the volumes can't hold
+        for (axis, begin, end) in [("x", x_begin, x_end), ("y", y_begin, y_end), ("z", z_begin, z_end)] {
+            if begin > end {
+                return Err(error::Error::Parse(format!("{}={}..{} ends before it begins in {:?}", axis, begin, end, s)));
+            }
+        }
+        Ok(RebootStep {
+            state,
+            range: Range3D::new([x_begin, y_begin, z_begin], [x_end, y_end, z_end]),
+        })
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Strategy {
+    // every lit cuboid in one list, checked against every step
+    Flat,
+    // lit cuboids in a loose octree, so a step only looks at the ones near it
+    Octree,
+    // just the steps, worked out on a grid of cells split at every face of every step when asked
+    Compressed,
+}
+
+impl Strategy {
+    pub fn all() -> [Strategy; 3] {
+        [Strategy::Flat, Strategy::Octree, Strategy::Compressed]
+    }
+}
+
+// every step splits space at its faces, so each cell between the splits is either all inside or all outside
+// of a step. a cell is whatever the last step to cover it says, so each x slab of cells takes the steps last
+// to first and every row of it keeps the z runs settled so far, which the earlier steps skip over
+fn for_each_lit_cell(steps: &[RebootStep], region: Option<&Range3D>, mut visit: impl FnMut(Range3D)) {
+    let steps: Vec<RebootStep> = steps
+        .iter()
+        .filter_map(|step| match region {
+            Some(region) => step.range.intersect(region).map(|range| RebootStep { state: step.state, range }),
+            None => Some(*step),
+        })
+        .collect();
+    let bounds = [0, 1, 2].map(|axis| {
+        let mut bounds: Vec<i64> = steps.iter().flat_map(|step| [step.range.begin[axis], step.range.end[axis] + 1]).collect();
+        bounds.sort_unstable();
+        bounds.dedup();
+        bounds
+    });
+    let index = |axis: usize, value: i64| bounds[axis].binary_search(&value).expect("every face is a bound");
+
+    let mut settled: Vec<Vec<(usize, usize)>> = vec![vec![]; bounds[1].len().saturating_sub(1)];
+    for x in 0..bounds[0].len().saturating_sub(1) {
+        settled.iter_mut().for_each(Vec::clear);
+        for step in steps.iter().rev().filter(|step| step.range.begin[0] <= bounds[0][x] && bounds[0][x] <= step.range.end[0]) {
+            let (begin, end) = (index(2, step.range.begin[2]), index(2, step.range.end[2] + 1));
+            for y in index(1, step.range.begin[1])..index(1, step.range.end[1] + 1) {
+                settle(&mut settled[y], begin, end, |z_begin, z_end| {
+                    if step.state == State::On {
+                        visit(Range3D::new(
+                            [bounds[0][x], bounds[1][y], bounds[2][z_begin]],
+                            [bounds[0][x + 1] - 1, bounds[1][y + 1] - 1, bounds[2][z_end] - 1],
+                        ));
+                    }
+                });
+            }
+        }
+    }
+}
+
+// adds begin..end to the sorted runs of a row, merging the runs it meets, and passes on the parts of it that
+// no run had settled yet
+fn settle(runs: &mut Vec<(usize, usize)>, begin: usize, end: usize, mut unsettled: impl FnMut(usize, usize)) {
+    let first = runs.partition_point(|&(_, run_end)| run_end < begin);
+    let last = runs.partition_point(|&(run_begin, _)| run_begin <= end);
+    let mut at = begin;
+    for &(run_begin, run_end) in &runs[first..last] {
+        if at < run_begin {
+            unsettled(at, run_begin);
+        }
+        at = at.max(run_end);
+    }
+    if at < end {
+        unsettled(at, end);
+    }
+    let merged = match &runs[first..last] {
+        [] => (begin, end),
+        met => (met[0].0.min(begin), met[met.len() - 1].1.max(end)),
+    };
+    runs.splice(first..last, [merged]);
+}
+
+// the number of lit cubes, worked out with every strategy, or an error if they don't agree
+pub fn cross_check(s: &str) -> Result<u64, error::Error> {
+    let counts = Strategy::all().map(|strategy| Grid::new_with(s, strategy).map(|grid| grid.num_lit()));
+    let [flat, octree, compressed] = counts;
+    let (flat, octree, compressed) = (flat?, octree?, compressed?);
+    if flat != octree || flat != compressed {
+        return Err(error::Error::General(format!("strategies disagree: flat {}, octree {}, compressed {}", flat, octree, compressed)));
+    }
+    Ok(flat)
+}
+
+// cells stop splitting at this size; the root cell covers -2^32..2^32 on every axis
+const OCTREE_MIN_SIZE: i64 = 16;
+const OCTREE_ROOT_SIZE: i64 = 1 << 33;
+
+// a cell of begin..begin + size on every axis, holding the cuboids that fit inside its loose bounds (the
+// cell grown by half its size on every side) but not inside the loose bounds of the child they belong to
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct Octree {
+    begin: [i64; 3],
+    size: i64,
+    ranges: Vec<Range3D>,
+    children: Vec<Octree>,
+}
+
+impl Octree {
+    fn new(begin: [i64; 3], size: i64) -> Octree {
+        Octree {
+            begin,
+            size,
+            ranges: vec![],
+            children: vec![],
+        }
+    }
+
+    fn root() -> Octree {
+        Octree::new([-OCTREE_ROOT_SIZE / 2; 3], OCTREE_ROOT_SIZE)
+    }
+
+    fn loose_bounds(&self) -> Range3D {
+        Range3D {
+            begin: self.begin.map(|begin| begin - self.size / 2),
+            end: self.begin.map(|begin| begin + self.size + self.size / 2 - 1),
+        }
+    }
+
+    fn insert(&mut self, range: Range3D) {
+        if self.size > OCTREE_MIN_SIZE {
+            let half = self.size / 2;
+            if self.children.is_empty() {
+                self.children = (0..8).map(|index| Octree::new([0, 1, 2].map(|axis| self.begin[axis] + half * ((index >> axis) & 1)), half)).collect();
+            }
+            // the child the middle of range is in
+            let index = (0..3)
+                .filter(|&axis| (range.begin[axis] + range.end[axis]).div_euclid(2) >= self.begin[axis] + half)
+                .map(|axis| 1 << axis)
+                .sum::<usize>();
+            if self.children[index].loose_bounds().is_superset_of(&range) {
+                self.children[index].insert(range);
+                return;
+            }
+        }
+        self.ranges.push(range);
+    }
+
+    fn remove(&mut self, range: &Range3D) {
+        if self.ranges.iter().any(|lit| lit.overlaps(range)) {
+            self.ranges = self.ranges.iter().flat_map(|lit| lit.subtract(range)).collect();
+        }
+        for child in self.children.iter_mut().filter(|child| child.loose_bounds().overlaps(range)) {
+            child.remove(range);
+        }
+    }
+
+    fn overlapping(&self, range: &Range3D, found: &mut Vec<Range3D>) {
+        found.extend(self.ranges.iter().filter(|lit| lit.overlaps(range)));
+        for child in self.children.iter().filter(|child| child.loose_bounds().overlaps(range)) {
+            child.overlapping(range, found);
+        }
+    }
+
+    fn num_lit(&self) -> u64 {
+        self.ranges.iter().map(|range| range.volume()).sum::<u64>() + self.children.iter().map(|child| child.num_lit()).sum::<u64>()
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+enum Cuboids {
+    Flat(Vec<Range3D>),
+    Octree(Octree),
+    Compressed(Vec<RebootStep>),
+}
+
+// the lit cubes as cuboids that don't overlap
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Grid {
+    cuboids: Cuboids,
+}
+
+impl Default for Grid {
+    fn default() -> Self {
+        Grid::empty(Strategy::Flat)
+    }
+}
+
+impl Grid {
+    pub fn empty(strategy: Strategy) -> Grid {
+        let cuboids = match strategy {
+            Strategy::Flat => Cuboids::Flat(vec![]),
+            Strategy::Octree => Cuboids::Octree(Octree::root()),
+            Strategy::Compressed => Cuboids::Compressed(vec![]),
+        };
+        Grid { cuboids }
+    }
+
+    // one step per line; empty lines and lines starting with # are skipped, so steps can be commented out
+    pub fn new_with(s: &str, strategy: Strategy) -> Result<Grid, error::Error> {
+        let mut grid = Grid::empty(strategy);
+        for (number, line) in parse::lines(s) {
+            if line.starts_with('#') {
+                continue;
+            }
+            grid.apply(&parse::on_line(number, line.parse())?);
+        }
+        Ok(grid)
+    }
+
+    pub fn apply(&mut self, step: &RebootStep) {
+        tracing::debug!(state = ?step.state, range = ?step.range, "applying");
+        match step.state {
+            State::On => self.add_range(step.range),
+            State::Off => self.remove_range(&step.range),
+        }
+    }
+
+    pub fn strategy(&self) -> Strategy {
+        match self.cuboids {
+            Cuboids::Flat(_) => Strategy::Flat,
+            Cuboids::Octree(_) => Strategy::Octree,
+            Cuboids::Compressed(_) => Strategy::Compressed,
+        }
+    }
+
+    pub fn add_range(&mut self, range: Range3D) {
+        if let Cuboids::Compressed(steps) = &mut self.cuboids {
+            steps.push(RebootStep { state: State::On, range });
+            return;
+        }
+        // whatever was lit inside range already would otherwise be counted twice
+        self.remove_range(&range);
+        match &mut self.cuboids {
+            Cuboids::Flat(ranges) => ranges.push(range),
+            Cuboids::Octree(octree) => octree.insert(range),
+            Cuboids::Compressed(_) => unreachable!(),
+        }
+    }
+
+    pub fn remove_range(&mut self, range: &Range3D) {
+        match &mut self.cuboids {
+            Cuboids::Flat(ranges) => *ranges = ranges.iter().flat_map(|lit| lit.subtract(range)).collect(),
+            Cuboids::Octree(octree) => octree.remove(range),
+            Cuboids::Compressed(steps) => steps.push(RebootStep { state: State::Off, range: *range }),
+        }
+    }
+
+    pub fn num_lit(&self) -> u64 {
+        match &self.cuboids {
+            Cuboids::Flat(ranges) => ranges.iter().map(|range| range.volume()).sum(),
+            Cuboids::Octree(octree) => octree.num_lit(),
+            Cuboids::Compressed(steps) => {
+                let mut num_lit = 0;
+                for_each_lit_cell(steps, None, |cell| num_lit += cell.volume());
+                num_lit
+            }
+        }
+    }
+
+    // the lit cuboids that share at least one cube with range
+    fn overlapping(&self, range: &Range3D) -> Vec<Range3D> {
+        match &self.cuboids {
+            Cuboids::Flat(ranges) => ranges.iter().filter(|lit| lit.overlaps(range)).copied().collect(),
+            Cuboids::Octree(octree) => {
+                let mut found = vec![];
+                octree.overlapping(range, &mut found);
+                found
+            }
+            Cuboids::Compressed(steps) => {
+                let mut found = vec![];
+                for_each_lit_cell(steps, Some(range), |cell| found.push(cell));
+                found
+            }
+        }
+    }
+
+    pub fn is_lit(&self, x: i64, y: i64, z: i64) -> bool {
+        !self.overlapping(&Range3D::new([x, y, z], [x, y, z])).is_empty()
+    }
+
+    pub fn count_lit_in(&self, range: &Range3D) -> u64 {
+        if let Cuboids::Compressed(steps) = &self.cuboids {
+            let mut num_lit = 0;
+            for_each_lit_cell(steps, Some(range), |cell| num_lit += cell.volume());
+            return num_lit;
+        }
+        self.overlapping(range).iter().filter_map(|lit| lit.intersect(range)).map(|piece| piece.volume()).sum()
+    }
+
+    // the plane at `at` along axis, within the other two axes of area, one line per row. the rows go along
+    // the later of the other two axes and the columns along the earlier, so a z slice has x to the right
+    pub fn render_slice(&self, axis: Axis, at: i64, area: &Range3D) -> String {
+        let axis = axis as usize;
+        let (column_axis, row_axis) = match axis {
+            0 => (1, 2),
+            1 => (0, 2),
+            _ => (0, 1),
+        };
+        let mut slice = *area;
+        slice.begin[axis] = at;
+        slice.end[axis] = at;
+        let width = (slice.end[column_axis] - slice.begin[column_axis] + 1).max(0) as usize;
+        let height = (slice.end[row_axis] - slice.begin[row_axis] + 1).max(0) as usize;
+
+        let mut lit = vec![false; width * height];
+        for piece in self.overlapping(&slice).iter().filter_map(|lit| lit.intersect(&slice)) {
+            for row in piece.begin[row_axis]..=piece.end[row_axis] {
+                for column in piece.begin[column_axis]..=piece.end[column_axis] {
+                    lit[(row - slice.begin[row_axis]) as usize * width + (column - slice.begin[column_axis]) as usize] = true;
+                }
+            }
+        }
+
+        let mut s = String::with_capacity((width + 1) * height);
+        for row in lit.chunks(width.max(1)).take(height) {
+            s.extend(row.iter().map(|&lit| if lit { '#' } else { '.' }));
+            s.push('\n');
+        }
+        s
+    }
+
+    // the part 1 answer, whatever the steps did outside the region
+    pub fn num_lit_in_initialization_region(&self) -> u64 {
+        self.count_lit_in(&INITIALIZATION_REGION)
+    }
+}
+
+impl core::str::FromStr for Grid {
+    type Err = error::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Grid::new_with(s, Strategy::Flat)
+    }
+}
+
+const EXAMPLE_SMALL: &str = r#"
+on x=10..12,y=10..12,z=10..12
+on x=11..13,y=11..13,z=11..13
+off x=9..11,y=9..11,z=9..11
+on x=10..10,y=10..10,z=10..10
+"#;
+
+const EXAMPLE_LARGE: &str = r#"
+on x=-20..26,y=-36..17,z=-47..7
+on x=-20..33,y=-21..23,z=-26..28
+on x=-22..28,y=-29..23,z=-38..16
+on x=-46..7,y=-6..46,z=-50..-1
+on x=-49..1,y=-3..46,z=-24..28
+on x=2..47,y=-22..22,z=-23..27
+on x=-27..23,y=-28..26,z=-21..29
+on x=-39..5,y=-6..47,z=-3..44
+on x=-30..21,y=-8..43,z=-13..34
+on x=-22..26,y=-27..20,z=-29..19
+off x=-48..-32,y=26..41,z=-47..-37
+on x=-12..35,y=6..50,z=-50..-2
+off x=-48..-32,y=-32..-16,z=-15..-5
+on x=-18..26,y=-33..15,z=-7..46
+off x=-40..-22,y=-38..-28,z=23..41
+on x=-16..35,y=-41..10,z=-47..6
+off x=-32..-23,y=11..30,z=-14..3
+on x=-49..-5,y=-3..45,z=-29..18
+off x=18..30,y=-20..-8,z=-3..13
+on x=-41..9,y=-7..43,z=-33..15
+on x=-54112..-39298,y=-85059..-49293,z=-27449..7877
+on x=967..23432,y=45373..81175,z=27513..53682
+"#;
+
+// the example for the whole reactor, with a different answer for the initialization region
+const EXAMPLE_PART2: &str = r#"
+on x=-5..47,y=-31..22,z=-19..33
+on x=-44..5,y=-27..21,z=-14..35
+on x=-49..-1,y=-11..42,z=-10..38
+on x=-20..34,y=-40..6,z=-44..1
+off x=26..39,y=40..50,z=-2..11
+on x=-41..5,y=-41..6,z=-36..8
+off x=-43..-33,y=-45..-28,z=7..25
+on x=-33..15,y=-32..19,z=-34..11
+off x=35..47,y=-46..-34,z=-11..5
+on x=-14..36,y=-6..44,z=-16..29
+on x=-57795..-6158,y=29564..72030,z=20435..90618
+on x=36731..105352,y=-21140..28532,z=16094..90401
+on x=30999..107136,y=-53464..15513,z=8553..71215
+on x=13528..83982,y=-99403..-27377,z=-24141..23996
+on x=-72682..-12347,y=18159..111354,z=7391..80950
+on x=-1060..80757,y=-65301..-20884,z=-103788..-16709
+on x=-83015..-9461,y=-72160..-8347,z=-81239..-26856
+on x=-52752..22273,y=-49450..9096,z=54442..119054
+on x=-29982..40483,y=-108474..-28371,z=-24328..38471
+on x=-4958..62750,y=40422..118853,z=-7672..65583
+on x=55694..108686,y=-43367..46958,z=-26781..48729
+on x=-98497..-18186,y=-63569..3412,z=1232..88485
+on x=-726..56291,y=-62629..13224,z=18033..85226
+on x=-110886..-34664,y=-81338..-8658,z=8914..63723
+on x=-55829..24974,y=-16897..54165,z=-121762..-28058
+on x=-65152..-11147,y=22489..91432,z=-58782..1780
+on x=-120100..-32970,y=-46592..27473,z=-11695..61039
+on x=-18631..37533,y=-124565..-50804,z=-35667..28308
+on x=-57817..18248,y=49321..117703,z=5745..55881
+on x=14781..98692,y=-1341..70827,z=15753..70151
+on x=-34419..55919,y=-19626..40991,z=39015..114138
+on x=-60785..11593,y=-56135..2999,z=-95368..-26915
+on x=-32178..58085,y=17647..101866,z=-91405..-8878
+on x=-53655..12091,y=50097..105568,z=-75335..-4862
+on x=-111166..-40997,y=-71714..2688,z=5609..50954
+on x=-16602..70118,y=-98693..-44401,z=5197..76897
+on x=16383..101554,y=4615..83635,z=-44907..18747
+off x=-95822..-15171,y=-19987..48940,z=10804..104439
+on x=-89813..-14614,y=16069..88491,z=-3297..45228
+on x=41075..99376,y=-20427..49978,z=-52012..13762
+on x=-21330..50085,y=-17944..62733,z=-112280..-30197
+on x=-16478..35915,y=36008..118594,z=-7885..47086
+off x=-98156..-27851,y=-49952..43171,z=-99005..-8456
+off x=2032..69770,y=-71013..4824,z=7471..94418
+on x=43670..120875,y=-42068..12382,z=-24787..38892
+off x=37514..111226,y=-45862..25743,z=-16714..54663
+off x=25699..97951,y=-30668..59918,z=-15349..69697
+off x=-44271..17935,y=-9516..60759,z=49131..112598
+on x=-61695..-5813,y=40978..94975,z=8655..80240
+off x=-101086..-9439,y=-7088..67543,z=33935..83858
+off x=18020..114017,y=-48931..32606,z=21474..89843
+off x=-77139..10506,y=-89994..-18797,z=-80..59318
+off x=8476..79288,y=-75520..11602,z=-96624..-24783
+on x=-47488..-1262,y=24338..100707,z=16292..72967
+off x=-84341..13987,y=2429..92914,z=-90671..-1318
+off x=-37810..49457,y=-71013..-7894,z=-105357..-13188
+off x=-27365..46395,y=31009..98017,z=15428..76570
+off x=-70369..-16548,y=22648..78696,z=-1892..86821
+on x=-53470..21291,y=-120233..-33476,z=-44150..38147
+off x=-93533..-4276,y=-16170..68771,z=-104985..-24507
+"#;
+
+impl puzzle::Puzzle for Grid {
+    fn parse(input: &str) -> Result<Self, error::Error> {
+        Grid::new_with(input, Strategy::Octree)
+    }
+
+    fn part1(&self) -> Result<puzzle::Answer, error::Error> {
+        Ok(self.num_lit_in_initialization_region().into())
+    }
+
+    fn part2(&self) -> Result<puzzle::Answer, error::Error> {
+        Ok(self.num_lit().into())
+    }
+
+    fn examples() -> Vec<puzzle::Example> {
+        // the larger example doesn't give an answer for the whole reactor
+        vec![
+            (EXAMPLE_SMALL, Some("39"), Some("39")),
+            (EXAMPLE_LARGE, Some("590784"), None),
+            (EXAMPLE_PART2, Some("474140"), Some("2758514936282235")),
+        ]
+    }
+}
+
+#[test]
+fn test_cut() {
+    let range = Range3D::new([0, 0, 0], [9, 9, 9]);
+    let corner = Range3D::new([5, 5, 5], [20, 20, 20]);
+    assert_eq!(range.cut(&[corner]).collect::<Vec<_>>(), vec![Range3D::new([5, 5, 5], [9, 9, 9])]);
+    assert_eq!(range.cut(&[Range3D::new([2, 2, 2], [3, 3, 3])]).count(), 1);
+    assert_eq!(range.cut(&[Range3D::new([20, 20, 20], [30, 30, 30])]).count(), 0);
+    assert_eq!(range.cut(&[range]).collect::<Vec<_>>(), vec![range]);
+
+    // two overlapping cubes cut range into 3 x 3 x 3 pieces, of which the cubes cover 8 + 8 - 1
+    let others = [Range3D::new([2, 2, 2], [5, 5, 5]), Range3D::new([4, 4, 4], [7, 7, 7])];
+    let pieces: Vec<Range3D> = range.cut(&others).collect();
+    assert_eq!(pieces.len(), 15);
+    assert_eq!(pieces.iter().map(|piece| piece.volume()).sum::<u64>(), Range3D::union_volume(&others));
+    assert!(pieces.iter().all(|piece| others.iter().all(|other| other.is_superset_of(piece) || !other.overlaps(piece))));
+}
+
+#[test]
+fn test_range_algebra() {
+    let range = Range3D::new([0, 0, 0], [9, 9, 9]);
+    let corner = Range3D::new([5, 5, 5], [20, 20, 20]);
+    assert!(range.overlaps(&corner));
+    assert_eq!(range.intersect(&corner), Some(Range3D::new([5, 5, 5], [9, 9, 9])));
+    assert_eq!(range.intersect(&Range3D::new([10, 0, 0], [10, 9, 9])), None);
+    assert!(!range.overlaps(&Range3D::new([0, 0, 10], [9, 9, 10])));
+
+    let rest = range.subtract(&corner);
+    assert_eq!(rest.len(), 3);
+    assert_eq!(rest.iter().map(|piece| piece.volume()).sum::<u64>(), 1000 - 125);
+    let hole = range.subtract(&Range3D::new([2, 2, 2], [3, 3, 3]));
+    assert_eq!(hole.len(), 6);
+    assert_eq!(hole.iter().map(|piece| piece.volume()).sum::<u64>(), 1000 - 8);
+    assert!(hole.iter().all(|piece| hole.iter().filter(|other| other.overlaps(piece)).count() == 1));
+    assert_eq!(range.subtract(&range), vec![]);
+    assert_eq!(range.subtract(&Range3D::new([20, 20, 20], [30, 30, 30])), vec![range]);
+
+    assert_eq!(Range3D::union_volume(&[range, corner]), 1000 + 16 * 16 * 16 - 125);
+    assert_eq!(Range3D::union_volume(&[range, range, Range3D::new([2, 2, 2], [3, 3, 3])]), 1000);
+    assert_eq!(Range3D::union_volume(&[]), 0);
+}
+
+#[test]
+fn test_reboot_step() -> Result<(), error::Error> {
+    let step: RebootStep = "off x=-48..-32,y=26..41,z=-47..-37".parse()?;
+    assert_eq!(step.state, State::Off);
+    assert_eq!(step.range, Range3D::new([-48, 26, -47], [-32, 41, -37]));
+
+    let parse = |s: &str| match s.parse::<RebootStep>() {
+        Err(error::Error::Parse(message)) => message,
+        _ => panic!("expected a parse error"),
+    };
+    assert_eq!(parse("toggle x=1..2,y=1..2,z=1..2"), "unknown state \"toggle\", expected on or off");
+    assert_eq!(parse("on"), "expected a state and a cuboid: \"on\"");
+    assert_eq!(parse("on x=1..2,y=1..2"), "column 17: expected \",z=\" in \"on x=1..2,y=1..2\"");
+    assert_eq!(
+        parse("on x=0..9223372036854775807,y=1..2,z=1..2"),
+        "9223372036854775807 is outside -1048576..1048576 in \"on x=0..9223372036854775807,y=1..2,z=1..2\""
+    );
+    assert_eq!(parse("on x=10..5,y=0..0,z=0..0"), "x=10..5 ends before it begins in \"on x=10..5,y=0..0,z=0..0\"");
+    assert_eq!(parse("on x=1..1,y=0..0,z=3..-3"), "z=3..-3 ends before it begins in \"on x=1..1,y=0..0,z=3..-3\"");
+    assert!("on x=10..5,y=10..5,z=0..0".parse::<Grid>().is_err());
+    assert!(matches!(
+        "on x=1..2,y=1..2,z=1..2\non x=1..2,y=1..2".parse::<Grid>(),
+        Err(error::Error::Parse(message)) if message.starts_with("line 2, column 17:")
+    ));
+
+    // commented out steps are skipped
+    let grid: Grid = "on x=1..2,y=1..2,z=1..2\n# off x=1..2,y=1..2,z=1..2\n".parse()?;
+    assert_eq!(grid.num_lit(), 8);
+
+    Ok(())
+}
+
+#[test]
+fn test_day22() -> Result<(), error::Error> {
+    let input = EXAMPLE_SMALL;
+    let grid: Grid = input.parse()?;
+    assert_eq!(grid.num_lit(), 39);
+
+    let input = EXAMPLE_LARGE;
+    let grid: Grid = input.parse()?;
+    assert_eq!(grid.num_lit_in_initialization_region(), 590784);
+    assert_eq!(Grid::new_with(input, Strategy::Compressed)?.num_lit_in_initialization_region(), 590784);
+    assert_eq!(cross_check(input)?, grid.num_lit());
+    // the last two steps are outside the region
+    assert_eq!(grid.num_lit(), 590784 + 14815 * 35767 * 35327 + 22466 * 35803 * 26170);
+
+    for strategy in Strategy::all() {
+        let grid = Grid::new_with(EXAMPLE_PART2, strategy)?;
+        assert_eq!(grid.num_lit(), 2758514936282235);
+        assert_eq!(grid.num_lit_in_initialization_region(), 474140);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_day22_initialization_region() -> Result<(), error::Error> {
+    let grid: Grid = "on x=-60..-40,y=0..0,z=0..0\non x=40..60,y=0..9,z=50..51".parse()?;
+    assert_eq!(grid.num_lit(), 21 + 21 * 10 * 2);
+    assert_eq!(grid.num_lit_in_initialization_region(), 11 + 11 * 10);
+
+    Ok(())
+}
+
+#[test]
+fn test_day22_strategies() -> Result<(), error::Error> {
+    // lots of small overlapping steps, the kind the octree is for
+    let mut seed: u64 = 22;
+    let mut random = |below: i64| {
+        seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        ((seed >> 33) % below as u64) as i64
+    };
+    let mut input = String::new();
+    for _ in 0..500 {
+        let state = if random(3) == 0 { "off" } else { "on" };
+        let begin: Vec<i64> = (0..3).map(|_| random(2000) - 1000).collect();
+        let end: Vec<i64> = begin.iter().map(|begin| begin + random(400)).collect();
+        input.push_str(&format!("{} x={}..{},y={}..{},z={}..{}\n", state, begin[0], end[0], begin[1], end[1], begin[2], end[2]));
+    }
+
+    let flat = Grid::new_with(&input, Strategy::Flat)?;
+    let octree = Grid::new_with(&input, Strategy::Octree)?;
+    assert_eq!(octree.strategy(), Strategy::Octree);
+    assert_eq!(octree.num_lit(), flat.num_lit());
+    assert_eq!(octree.num_lit_in_initialization_region(), flat.num_lit_in_initialization_region());
+    assert_eq!(cross_check(&input)?, flat.num_lit());
+
+    let input = "on x=-20..26,y=-36..17,z=-47..7\non x=-54112..-39298,y=-85059..-49293,z=-27449..7877\noff x=-50000..0,y=-80000..10,z=-20..0";
+    assert_eq!(Grid::new_with(input, Strategy::Octree)?.num_lit(), Grid::new_with(input, Strategy::Flat)?.num_lit());
+    assert_eq!(cross_check(input)?, Grid::new_with(input, Strategy::Flat)?.num_lit());
+
+    Ok(())
+}
+
+#[test]
+fn test_settle() {
+    let mut runs = vec![];
+    let settle_into = |runs: &mut Vec<(usize, usize)>, begin: usize, end: usize| {
+        let mut unsettled = vec![];
+        settle(runs, begin, end, |begin, end| unsettled.push((begin, end)));
+        unsettled
+    };
+    assert_eq!(settle_into(&mut runs, 4, 6), vec![(4, 6)]);
+    assert_eq!(settle_into(&mut runs, 10, 12), vec![(10, 12)]);
+    assert_eq!(settle_into(&mut runs, 0, 2), vec![(0, 2)]);
+    assert_eq!(runs, vec![(0, 2), (4, 6), (10, 12)]);
+    // a run that only meets another one is merged with it
+    assert_eq!(settle_into(&mut runs, 2, 3), vec![(2, 3)]);
+    assert_eq!(runs, vec![(0, 3), (4, 6), (10, 12)]);
+    assert_eq!(settle_into(&mut runs, 1, 11), vec![(3, 4), (6, 10)]);
+    assert_eq!(runs, vec![(0, 12)]);
+    assert_eq!(settle_into(&mut runs, 5, 7), vec![]);
+}
+
+#[test]
+fn test_day22_queries() -> Result<(), error::Error> {
+    let input = EXAMPLE_SMALL;
+    for strategy in Strategy::all() {
+        let grid = Grid::new_with(input, strategy)?;
+        assert!(grid.is_lit(10, 10, 10));
+        assert!(!grid.is_lit(11, 11, 11));
+        assert!(grid.is_lit(13, 13, 13));
+        assert!(!grid.is_lit(14, 13, 13));
+        assert_eq!(grid.count_lit_in(&Range3D::new([0, 0, 0], [20, 20, 20])), 39);
+        assert_eq!(grid.count_lit_in(&Range3D::new([12, 12, 12], [13, 13, 13])), 8);
+        assert_eq!(grid.count_lit_in(&Range3D::new([9, 9, 9], [11, 11, 11])), 1);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_day22_render_slice() -> Result<(), error::Error> {
+    let input = EXAMPLE_SMALL;
+    let area = Range3D::new([9, 9, 9], [14, 14, 14]);
+    for strategy in Strategy::all() {
+        let grid = Grid::new_with(input, strategy)?;
+        assert_eq!(grid.render_slice(Axis::Z, 10, &area), "......\n.#.#..\n...#..\n.###..\n......\n......\n");
+        assert_eq!(grid.render_slice(Axis::Z, 12, &area), "......\n.###..\n.####.\n.####.\n..###.\n......\n");
+        assert_eq!(grid.render_slice(Axis::X, 13, &area), "......\n......\n..###.\n..###.\n..###.\n......\n");
+        assert_eq!(grid.render_slice(Axis::Y, 20, &area), "......\n".repeat(6));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,336 @@
+use crate::error;
+use crate::puzzle;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Amphipod {
+    Amber,
+    Bronze,
+    Copper,
+    Desert,
+}
+
+impl Amphipod {
+    fn from_char(c: char) -> Option<Self> {
+        match c {
+            'A' => Some(Amphipod::Amber),
+            'B' => Some(Amphipod::Bronze),
+            'C' => Some(Amphipod::Copper),
+            'D' => Some(Amphipod::Desert),
+            _ => None,
+        }
+    }
+
+    fn to_char(self) -> char {
+        match self {
+            Amphipod::Amber => 'A',
+            Amphipod::Bronze => 'B',
+            Amphipod::Copper => 'C',
+            Amphipod::Desert => 'D',
+        }
+    }
+
+    pub fn energy(self) -> usize {
+        match self {
+            Amphipod::Amber => 1,
+            Amphipod::Bronze => 10,
+            Amphipod::Copper => 100,
+            Amphipod::Desert => 1000,
+        }
+    }
+
+    // the room it wants to end up in, counted from the left
+    pub fn room(self) -> usize {
+        self as usize
+    }
+}
+
+const HALLWAY_LEN: usize = 11;
+
+// the hallway spots right outside each room, nobody may stop there
+const DOORS: [usize; 4] = [2, 4, 6, 8];
+
+// the rows folded out of the diagram for part 2, top to bottom
+const UNFOLDED: [[Amphipod; 4]; 2] = [
+    [Amphipod::Desert, Amphipod::Copper, Amphipod::Bronze, Amphipod::Amber],
+    [Amphipod::Desert, Amphipod::Bronze, Amphipod::Amber, Amphipod::Copper],
+];
+
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Burrow {
+    pub hallway: [Option<Amphipod>; HALLWAY_LEN],
+    // each room from the hallway down
+    pub rooms: [Vec<Option<Amphipod>>; 4],
+}
+
+impl Burrow {
+    pub fn depth(&self) -> usize {
+        self.rooms[0].len()
+    }
+
+    // inserts the two rows that were folded away between the first and second row of every room
+    pub fn unfold(&self) -> Burrow {
+        let mut burrow = self.clone();
+        for (room, amphipods) in burrow.rooms.iter_mut().enumerate() {
+            for (row, unfolded) in UNFOLDED.iter().enumerate() {
+                amphipods.insert(1 + row, Some(unfolded[room]));
+            }
+        }
+        burrow
+    }
+
+    pub fn is_organized(&self) -> bool {
+        self.rooms.iter().enumerate().all(|(room, amphipods)| amphipods.iter().all(|a| a.map(|a| a.room()) == Some(room)))
+    }
+
+    // a room that is empty or only holds amphipods that belong there, so nobody has to leave it
+    fn is_settled(&self, room: usize) -> bool {
+        self.rooms[room].iter().flatten().all(|a| a.room() == room)
+    }
+
+    // the hallway between from and to, to included, has nobody in it
+    fn is_clear(&self, from: usize, to: usize) -> bool {
+        let range = if from < to { from + 1..=to } else { to..=from - 1 };
+        self.hallway[range].iter().all(|spot| spot.is_none())
+    }
+
+    // every burrow one move away together with the energy that move takes
+    pub fn moves(&self) -> Vec<(Burrow, usize)> {
+        // moving into a room is never a mistake, so when one is possible there is no need to look further
+        for (from, amphipod) in self.hallway.iter().enumerate() {
+            if let Some(amphipod) = amphipod {
+                if let Some(next) = self.move_into_room(from, *amphipod) {
+                    return vec![next];
+                }
+            }
+        }
+
+        let mut moves = vec![];
+        for (room, &door) in DOORS.iter().enumerate() {
+            if self.is_settled(room) {
+                continue;
+            }
+            let depth = self.rooms[room].iter().position(|a| a.is_some()).expect("unsettled room has an amphipod");
+            let amphipod = self.rooms[room][depth].expect("amphipod");
+            for to in 0..HALLWAY_LEN {
+                if DOORS.contains(&to) || !self.is_clear(door, to) {
+                    continue;
+                }
+                let mut next = self.clone();
+                next.rooms[room][depth] = None;
+                next.hallway[to] = Some(amphipod);
+                let steps = depth + 1 + door.abs_diff(to);
+                moves.push((next, steps * amphipod.energy()));
+            }
+        }
+        moves
+    }
+
+    fn move_into_room(&self, from: usize, amphipod: Amphipod) -> Option<(Burrow, usize)> {
+        let room = amphipod.room();
+        if !self.is_settled(room) || !self.is_clear(from, DOORS[room]) {
+            return None;
+        }
+        let depth = self.rooms[room].iter().rposition(|a| a.is_none())?;
+        let mut next = self.clone();
+        next.hallway[from] = None;
+        next.rooms[room][depth] = Some(amphipod);
+        let steps = from.abs_diff(DOORS[room]) + depth + 1;
+        Some((next, steps * amphipod.energy()))
+    }
+
+    // the energy it would take if nobody were ever in the way, which never overestimates
+    pub fn min_energy_left(&self) -> usize {
+        let mut energy = 0;
+        for (from, amphipod) in self.hallway.iter().enumerate() {
+            if let Some(amphipod) = amphipod {
+                energy += (from.abs_diff(DOORS[amphipod.room()]) + 1) * amphipod.energy();
+            }
+        }
+        for (room, amphipods) in self.rooms.iter().enumerate() {
+            // the ones below everybody who has to leave can stay
+            let keep = amphipods.iter().rev().take_while(|a| a.map(|a| a.room()) == Some(room)).count();
+            for (depth, amphipod) in amphipods.iter().enumerate().take(amphipods.len() - keep) {
+                if let Some(amphipod) = amphipod {
+                    // out of the way and back in when it is already home
+                    let across = DOORS[room].abs_diff(DOORS[amphipod.room()]).max(2);
+                    energy += (depth + 1 + across + 1) * amphipod.energy();
+                }
+            }
+        }
+        energy
+    }
+
+    // the cheapest way to get everybody home, every burrow along the way and the total energy
+    pub fn organize(&self) -> Option<(Vec<Burrow>, usize)> {
+        pathfinding::directed::astar::astar(self, |burrow| burrow.moves(), |burrow| burrow.min_energy_left(), |burrow| burrow.is_organized())
+    }
+
+    pub fn least_energy(&self) -> Result<usize, error::Error> {
+        self.organize()
+            .map(|(_, energy)| energy)
+            .ok_or_else(|| error::Error::General("the amphipods can't be organized".to_string()))
+    }
+}
+
+impl std::str::FromStr for Burrow {
+    type Err = error::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lines: Vec<&str> = s.lines().filter(|l| !l.trim().is_empty()).collect();
+        if lines.len() < 4 {
+            return Err(error::Error::Parse("expected a burrow diagram".to_string()));
+        }
+
+        let hallway_line: Vec<char> = lines[1].trim().chars().collect();
+        if hallway_line.len() != HALLWAY_LEN + 2 {
+            return Err(error::Error::Parse(format!("invalid hallway: {:?}", lines[1])));
+        }
+        let mut hallway = [None; HALLWAY_LEN];
+        for (spot, c) in hallway.iter_mut().zip(&hallway_line[1..=HALLWAY_LEN]) {
+            *spot = match c {
+                '.' => None,
+                c => Some(Amphipod::from_char(*c).ok_or_else(|| error::Error::Parse(format!("invalid amphipod: {:?}", c)))?),
+            };
+        }
+
+        // every line between the hallway and the bottom wall is one row of the rooms, the rooms
+        // being the columns right below the doors
+        let mut rooms: [Vec<Option<Amphipod>>; 4] = Default::default();
+        for line in &lines[2..lines.len() - 1] {
+            let line: Vec<char> = line.chars().collect();
+            for (room, door) in DOORS.iter().enumerate() {
+                let amphipod = match line.get(door + 1) {
+                    Some('.') => None,
+                    Some(c) => Some(Amphipod::from_char(*c).ok_or_else(|| error::Error::Parse(format!("invalid amphipod: {:?}", c)))?),
+                    None => return Err(error::Error::Parse(format!("room {} is missing from {:?}", room, line.iter().collect::<String>()))),
+                };
+                rooms[room].push(amphipod);
+            }
+        }
+
+        Ok(Burrow { hallway, rooms })
+    }
+}
+
+impl std::fmt::Display for Burrow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let spot = |a: &Option<Amphipod>| a.map_or('.', |a| a.to_char());
+        writeln!(f, "{}", "#".repeat(HALLWAY_LEN + 2))?;
+        writeln!(f, "#{}#", self.hallway.iter().map(spot).collect::<String>())?;
+        for depth in 0..self.depth() {
+            let (edge, wall) = if depth == 0 { ("###", "###") } else { ("  #", "#") };
+            let row: Vec<String> = self.rooms.iter().map(|room| spot(&room[depth]).to_string()).collect();
+            writeln!(f, "{}{}{}", edge, row.join("#"), wall)?;
+        }
+        writeln!(f, "  {}", "#".repeat(HALLWAY_LEN - 2))
+    }
+}
+
+const EXAMPLE: &str = r#"
+#############
+#...........#
+###B#C#B#D###
+  #A#D#C#A#
+  #########
+"#;
+
+impl puzzle::Puzzle for Burrow {
+    fn parse(input: &str) -> Result<Self, error::Error> {
+        input.parse()
+    }
+
+    fn part1(&self) -> Result<puzzle::Answer, error::Error> {
+        Ok(self.least_energy()?.into())
+    }
+
+    fn part2(&self) -> Result<puzzle::Answer, error::Error> {
+        Ok(self.unfold().least_energy()?.into())
+    }
+
+    fn examples() -> Vec<puzzle::Example> {
+        vec![(EXAMPLE, Some("12521"), Some("44169"))]
+    }
+}
+
+#[test]
+fn test_parse() -> Result<(), error::Error> {
+    let burrow: Burrow = EXAMPLE.parse()?;
+    assert_eq!(burrow.depth(), 2);
+    assert_eq!(burrow.hallway, [None; HALLWAY_LEN]);
+    assert_eq!(burrow.rooms[0], vec![Some(Amphipod::Bronze), Some(Amphipod::Amber)]);
+    assert_eq!(burrow.rooms[3], vec![Some(Amphipod::Desert), Some(Amphipod::Amber)]);
+    assert_eq!(burrow.to_string(), EXAMPLE.trim_start());
+    assert!(!burrow.is_organized());
+
+    let unfolded = burrow.unfold();
+    assert_eq!(
+        unfolded.to_string(),
+        r#"#############
+#...........#
+###B#C#B#D###
+  #D#C#B#A#
+  #D#B#A#C#
+  #A#D#C#A#
+  #########
+"#
+    );
+    assert_eq!(unfolded.to_string().parse::<Burrow>()?, unfolded);
+
+    assert!("".parse::<Burrow>().is_err());
+    assert!(EXAMPLE.replace('D', "E").parse::<Burrow>().is_err());
+    Ok(())
+}
+
+#[test]
+fn test_moves() -> Result<(), error::Error> {
+    let burrow: Burrow = EXAMPLE.parse()?;
+    // the top amphipod of each room to any of the 7 spots in the hallway
+    assert_eq!(burrow.moves().len(), 28);
+    assert!(burrow.moves().iter().all(|(next, _)| next.hallway.iter().flatten().count() == 1));
+
+    // the bronze from the third room two steps to the left of its door
+    let (next, energy) = burrow.moves().into_iter().find(|(next, _)| next.hallway[3].is_some() && next.rooms[2][0].is_none()).unwrap();
+    assert_eq!(energy, 40);
+
+    // the copper from the second room steps out to the right of its door, the bronze is in the way on the left
+    let moves = next.moves();
+    assert!(moves.iter().all(|(after, _)| after.rooms[1][0].is_some() || after.hallway[..3].iter().all(|spot| spot.is_none())));
+    let (next, energy) = moves.into_iter().find(|(next, _)| next.hallway[5] == Some(Amphipod::Copper)).unwrap();
+    assert_eq!(energy, 200);
+
+    // and from there home is the only move worth making
+    let moves = next.moves();
+    assert_eq!(moves.len(), 1);
+    assert_eq!(moves[0].1, 200);
+    assert_eq!(moves[0].0.rooms[2], vec![Some(Amphipod::Copper), Some(Amphipod::Copper)]);
+    Ok(())
+}
+
+#[test]
+fn test_min_energy_left() -> Result<(), error::Error> {
+    let burrow: Burrow = EXAMPLE.parse()?;
+    let organized: Burrow = "#############\n#...........#\n###A#B#C#D###\n  #A#B#C#D#\n  #########".parse()?;
+    assert!(organized.is_organized());
+    assert_eq!(organized.min_energy_left(), 0);
+    assert!(burrow.min_energy_left() <= 12521);
+
+    // a single amber one step away from home
+    let almost: Burrow = "#############\n#.A.........#\n###.#B#C#D###\n  #A#B#C#D#\n  #########".parse()?;
+    assert_eq!(almost.min_energy_left(), 2);
+    assert_eq!(almost.moves(), vec![(organized, 2)]);
+    Ok(())
+}
+
+#[test]
+fn test_day23() -> Result<(), error::Error> {
+    let burrow: Burrow = EXAMPLE.parse()?;
+    let (path, energy) = burrow.organize().unwrap();
+    assert_eq!(energy, 12521);
+    assert_eq!(path.first(), Some(&burrow));
+    assert!(path.last().unwrap().is_organized());
+
+    assert_eq!(burrow.unfold().least_energy()?, 44169);
+    Ok(())
+}
@@ -0,0 +1,460 @@
+use crate::error;
+use crate::puzzle;
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Register {
+    W,
+    X,
+    Y,
+    Z,
+}
+
+impl core::str::FromStr for Register {
+    type Err = error::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "w" => Ok(Register::W),
+            "x" => Ok(Register::X),
+            "y" => Ok(Register::Y),
+            "z" => Ok(Register::Z),
+            _ => Err(error::Error::Parse(format!("invalid register: {:?}", s))),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Operand {
+    Register(Register),
+    Value(i64),
+}
+
+impl core::str::FromStr for Operand {
+    type Err = error::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.parse() {
+            Ok(register) => Ok(Operand::Register(register)),
+            Err(_) => Ok(Operand::Value(s.parse()?)),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Instruction {
+    Inp(Register),
+    Add(Register, Operand),
+    Mul(Register, Operand),
+    Div(Register, Operand),
+    Mod(Register, Operand),
+    Eql(Register, Operand),
+}
+
+impl core::str::FromStr for Instruction {
+    type Err = error::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = s.split_whitespace().collect();
+        match parts[..] {
+            ["inp", a] => Ok(Instruction::Inp(a.parse()?)),
+            ["add", a, b] => Ok(Instruction::Add(a.parse()?, b.parse()?)),
+            ["mul", a, b] => Ok(Instruction::Mul(a.parse()?, b.parse()?)),
+            ["div", a, b] => Ok(Instruction::Div(a.parse()?, b.parse()?)),
+            ["mod", a, b] => Ok(Instruction::Mod(a.parse()?, b.parse()?)),
+            ["eql", a, b] => Ok(Instruction::Eql(a.parse()?, b.parse()?)),
+            _ => Err(error::Error::Parse(format!("invalid instruction: {:?}", s))),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Program {
+    pub instructions: Vec<Instruction>,
+}
+
+impl core::str::FromStr for Program {
+    type Err = error::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let instructions = s.lines().map(|l| l.trim()).filter(|l| !l.is_empty()).map(|l| l.parse()).collect::<Result<_, _>>()?;
+        Ok(Program { instructions })
+    }
+}
+
+#[derive(Clone, Default, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Alu {
+    pub registers: [i64; 4],
+}
+
+impl Alu {
+    pub fn get(&self, register: Register) -> i64 {
+        self.registers[register as usize]
+    }
+
+    fn value(&self, operand: Operand) -> i64 {
+        match operand {
+            Operand::Register(register) => self.get(register),
+            Operand::Value(value) => value,
+        }
+    }
+
+    // runs the program on top of whatever is in the registers already, reading inputs as it goes
+    pub fn run<I: IntoIterator<Item = i64>>(&mut self, program: &Program, inputs: I) -> Result<(), error::Error> {
+        let mut inputs = inputs.into_iter();
+        for instruction in &program.instructions {
+            // the registers are 64 bits and nothing says what happens past that, so it's an error
+            let overflow = || error::Error::General(format!("overflow: {:?}", instruction));
+            let (register, result) = match *instruction {
+                Instruction::Inp(a) => (a, inputs.next().ok_or_else(|| error::Error::General("out of input".to_string()))?),
+                Instruction::Add(a, b) => (a, self.get(a).checked_add(self.value(b)).ok_or_else(overflow)?),
+                Instruction::Mul(a, b) => (a, self.get(a).checked_mul(self.value(b)).ok_or_else(overflow)?),
+                Instruction::Div(a, b) => match self.value(b) {
+                    0 => return Err(error::Error::General(format!("division by zero: {:?}", instruction))),
+                    b => (a, self.get(a).checked_div(b).ok_or_else(overflow)?),
+                },
+                Instruction::Mod(a, b) => match (self.get(a), self.value(b)) {
+                    (a_value, b) if a_value < 0 || b <= 0 => return Err(error::Error::General(format!("mod of {} by {}: {:?}", a_value, b, instruction))),
+                    (a_value, b) => (a, a_value % b),
+                },
+                Instruction::Eql(a, b) => (a, (self.get(a) == self.value(b)) as i64),
+            };
+            self.registers[register as usize] = result;
+        }
+        Ok(())
+    }
+}
+
+// every digit of the model number is checked by the same block of instructions, only these three
+// numbers differ between them
+const BLOCK: &str = r#"
+inp w
+mul x 0
+add x z
+mod x 26
+div z {div}
+add x {check}
+eql x w
+eql x 0
+mul y 0
+add y 25
+mul y x
+add y 1
+mul z y
+mul y 0
+add y w
+add y {offset}
+mul y x
+add z y
+"#;
+
+pub(crate) fn block(div: i64, check: i64, offset: i64) -> String {
+    BLOCK.replace("{div}", &div.to_string()).replace("{check}", &check.to_string()).replace("{offset}", &offset.to_string())
+}
+
+// z is a stack of base 26 digits: a block that divides by 1 pushes its digit plus offset, and a
+// block that divides by 26 pops one and only keeps z from growing when its own digit is the popped
+// one plus check. the model number is valid when z is empty at the end, so every pop pairs up two
+// digits with a fixed difference between them.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Constraint {
+    pub first: usize,
+    pub second: usize,
+    // the second digit minus the first
+    pub difference: i64,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Monad {
+    pub program: Program,
+    pub num_digits: usize,
+    pub constraints: Vec<Constraint>,
+}
+
+impl Monad {
+    pub fn from_program(program: Program) -> Result<Self, error::Error> {
+        let mut constraints = vec![];
+        let mut stack = vec![];
+        let blocks: Vec<&[Instruction]> = program.instructions.chunks(18).collect();
+        if blocks.is_empty() {
+            return Err(error::Error::Parse("no inp blocks for the digits of a model number".to_string()));
+        }
+        // the model number is one decimal digit per block, and 20 of them don't fit in a u64
+        if blocks.len() > 19 {
+            return Err(error::Error::Parse(format!("{} digits is too long for a model number", blocks.len())));
+        }
+        for (digit, instructions) in blocks.iter().enumerate() {
+            let (div, check, offset) = match (instructions.get(4), instructions.get(5), instructions.get(15)) {
+                (Some(Instruction::Div(Register::Z, Operand::Value(div))), Some(Instruction::Add(Register::X, Operand::Value(check))), Some(Instruction::Add(Register::Y, Operand::Value(offset)))) => {
+                    (*div, *check, *offset)
+                }
+                _ => return Err(error::Error::Parse(format!("block {} doesn't look like the MONAD", digit))),
+            };
+            let expected: Program = block(div, check, offset).parse()?;
+            if expected.instructions[..] != instructions[..] {
+                return Err(error::Error::Parse(format!("block {} doesn't look like the MONAD", digit)));
+            }
+
+            match div {
+                // no digit can match a check above 9, so these always push
+                1 if check > 9 => stack.push((digit, offset)),
+                26 => {
+                    let (first, offset) = stack.pop().ok_or_else(|| error::Error::Parse(format!("block {} has nothing to pop", digit)))?;
+                    constraints.push(Constraint {
+                        first,
+                        second: digit,
+                        difference: offset + check,
+                    });
+                }
+                1 => return Err(error::Error::Parse(format!("block {} only pushes for some digits", digit))),
+                _ => return Err(error::Error::Parse(format!("block {} divides z by {}", digit, div))),
+            }
+        }
+        if !stack.is_empty() {
+            return Err(error::Error::Parse("z can never be empty at the end".to_string()));
+        }
+
+        let num_digits = blocks.len();
+        Ok(Monad { program, num_digits, constraints })
+    }
+
+    // every pair of digits as high or as low as the difference between them allows
+    fn model_number(&self, largest: bool) -> Result<u64, error::Error> {
+        let mut digits = vec![0; self.num_digits];
+        for constraint in &self.constraints {
+            let difference = constraint.difference;
+            if difference.abs() > 8 {
+                return Err(error::Error::General(format!("no digits are {} apart", difference)));
+            }
+            let first = if largest { 9 - difference.max(0) } else { 1 - difference.min(0) };
+            digits[constraint.first] = first;
+            digits[constraint.second] = first + difference;
+        }
+
+        let mut alu = Alu::default();
+        alu.run(&self.program, digits.iter().copied())?;
+        if alu.get(Register::Z) != 0 {
+            return Err(error::Error::General(format!("MONAD rejects {:?}", digits)));
+        }
+        Ok(digits.iter().fold(0, |number, digit| number * 10 + *digit as u64))
+    }
+
+    pub fn largest_model_number(&self) -> Result<u64, error::Error> {
+        self.model_number(true)
+    }
+
+    pub fn smallest_model_number(&self) -> Result<u64, error::Error> {
+        self.model_number(false)
+    }
+}
+
+impl core::str::FromStr for Monad {
+    type Err = error::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Monad::from_program(s.parse()?)
+    }
+}
+
+// the puzzle text doesn't come with a MONAD, so this is a made up one of the same shape that only
+// checks four digits: the third has to be one less than the second and the fourth four less than the first
+const EXAMPLE: &str = r#"
+inp w
+mul x 0
+add x z
+mod x 26
+div z 1
+add x 12
+eql x w
+eql x 0
+mul y 0
+add y 25
+mul y x
+add y 1
+mul z y
+mul y 0
+add y w
+add y 4
+mul y x
+add z y
+inp w
+mul x 0
+add x z
+mod x 26
+div z 1
+add x 11
+eql x w
+eql x 0
+mul y 0
+add y 25
+mul y x
+add y 1
+mul z y
+mul y 0
+add y w
+add y 2
+mul y x
+add z y
+inp w
+mul x 0
+add x z
+mod x 26
+div z 26
+add x -3
+eql x w
+eql x 0
+mul y 0
+add y 25
+mul y x
+add y 1
+mul z y
+mul y 0
+add y w
+add y 7
+mul y x
+add z y
+inp w
+mul x 0
+add x z
+mod x 26
+div z 26
+add x -8
+eql x w
+eql x 0
+mul y 0
+add y 25
+mul y x
+add y 1
+mul z y
+mul y 0
+add y w
+add y 5
+mul y x
+add z y
+"#;
+
+impl puzzle::Puzzle for Monad {
+    fn parse(input: &str) -> Result<Self, error::Error> {
+        input.parse()
+    }
+
+    fn part1(&self) -> Result<puzzle::Answer, error::Error> {
+        Ok(self.largest_model_number()?.into())
+    }
+
+    fn part2(&self) -> Result<puzzle::Answer, error::Error> {
+        Ok(self.smallest_model_number()?.into())
+    }
+
+    fn examples() -> Vec<puzzle::Example> {
+        vec![(EXAMPLE, Some("9985"), Some("5211"))]
+    }
+}
+
+#[test]
+fn test_alu() -> Result<(), error::Error> {
+    let negate: Program = "inp x\nmul x -1".parse()?;
+    let mut alu = Alu::default();
+    alu.run(&negate, [7])?;
+    assert_eq!(alu.get(Register::X), -7);
+
+    let three_times: Program = "inp z\ninp x\nmul z 3\neql z x".parse()?;
+    let mut alu = Alu::default();
+    alu.run(&three_times, [2, 6])?;
+    assert_eq!(alu.get(Register::Z), 1);
+    let mut alu = Alu::default();
+    alu.run(&three_times, [2, 7])?;
+    assert_eq!(alu.get(Register::Z), 0);
+
+    let binary: Program = r#"
+inp w
+add z w
+mod z 2
+div w 2
+add y w
+mod y 2
+div w 2
+add x w
+mod x 2
+div w 2
+mod w 2
+"#
+    .parse()?;
+    let mut alu = Alu::default();
+    alu.run(&binary, [11])?;
+    assert_eq!(alu.registers, [1, 0, 1, 1]);
+
+    assert!(Alu::default().run(&negate, []).is_err());
+    assert!(Alu::default().run(&"div x 0".parse()?, []).is_err());
+    assert!(Alu::default().run(&"add x -1\nmod x 2".parse()?, []).is_err());
+
+    let overflow = |program: &str| Alu::default().run(&program.parse()?, [2]);
+    assert!(matches!(overflow("inp x\nmul x 9223372036854775807"), Err(error::Error::General(message)) if message.starts_with("overflow: Mul")));
+    assert!(overflow("add x 9223372036854775807\nadd x 1").is_err());
+    assert!(overflow("add x -9223372036854775807\nadd x -1\ndiv x -1").is_err());
+    assert_eq!(overflow("add x -9223372036854775807\nadd x -1\ndiv x 1"), Ok(()));
+    assert!("jmp x 1".parse::<Program>().is_err());
+    assert!("add q 1".parse::<Program>().is_err());
+    Ok(())
+}
+
+#[test]
+fn test_monad() -> Result<(), error::Error> {
+    let monad: Monad = EXAMPLE.parse()?;
+    assert_eq!(monad.num_digits, 4);
+    assert_eq!(
+        monad.constraints,
+        vec![Constraint { first: 1, second: 2, difference: -1 }, Constraint { first: 0, second: 3, difference: -4 }]
+    );
+    assert_eq!(monad.largest_model_number()?, 9985);
+    assert_eq!(monad.smallest_model_number()?, 5211);
+
+    // the full fourteen digits, seven pairs
+    let blocks = [
+        (1, 11, 5),
+        (1, 12, 7),
+        (1, 14, 1),
+        (26, -1, 9),
+        (1, 13, 10),
+        (26, -2, 3),
+        (1, 10, 4),
+        (1, 15, 12),
+        (26, -11, 6),
+        (1, 11, 3),
+        (26, -10, 2),
+        (26, -6, 8),
+        (26, -12, 1),
+        (26, -2, 11),
+    ];
+    let source: String = blocks.iter().map(|&(div, check, offset)| block(div, check, offset)).collect();
+    let monad: Monad = source.parse()?;
+    assert_eq!(monad.constraints.len(), 7);
+    assert_eq!(monad.largest_model_number()?, 69991998992749);
+    assert_eq!(monad.smallest_model_number()?, 16111931281114);
+
+    // one more than the largest breaks the last pair
+    let mut alu = Alu::default();
+    alu.run(&monad.program, "69991998992750".chars().map(|c| c.to_digit(10).unwrap() as i64))?;
+    assert_ne!(alu.get(Register::Z), 0);
+
+    let unbalanced: String = blocks[..13].iter().map(|&(div, check, offset)| block(div, check, offset)).collect();
+    assert!(unbalanced.parse::<Monad>().is_err());
+    assert!(block(1, 5, 3).parse::<Monad>().is_err());
+    assert!(block(26, 20, 3).parse::<Monad>().is_err());
+    assert!("inp w\nadd z w".parse::<Monad>().is_err());
+
+    let pair = block(1, 11, 5) + &block(26, -1, 9);
+    assert_eq!(pair.repeat(9).parse::<Monad>()?.largest_model_number()?, 595_959_595_959_595_959);
+    assert_eq!(pair.repeat(10).parse::<Monad>().err(), Some(error::Error::Parse("20 digits is too long for a model number".to_string())));
+    assert_eq!("".parse::<Monad>().err(), Some(error::Error::Parse("no inp blocks for the digits of a model number".to_string())));
+    Ok(())
+}
@@ -0,0 +1,180 @@
+use crate::error;
+use crate::grid;
+use crate::puzzle;
+
+use alloc::vec::Vec;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Cell {
+    Empty,
+    East,
+    South,
+}
+
+impl core::fmt::Display for Cell {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let c = match self {
+            Cell::Empty => '.',
+            Cell::East => '>',
+            Cell::South => 'v',
+        };
+        write!(f, "{}", c)
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SeaFloor {
+    pub grid: grid::Grid<Cell>,
+}
+
+impl SeaFloor {
+    // moves the whole herd one spot ahead where that spot was free before anybody in it moved,
+    // wrapping around the edges. returns how many moved.
+    fn move_herd(&mut self, herd: Cell, (dx, dy): (i64, i64)) -> usize {
+        let (width, height) = (self.grid.width() as i64, self.grid.height() as i64);
+        let moves: Vec<((i64, i64), (i64, i64))> = self
+            .grid
+            .positions()
+            .filter(|&pos| self.grid[pos] == herd)
+            .map(|(x, y)| ((x, y), ((x + dx) % width, (y + dy) % height)))
+            .filter(|&(_, to)| self.grid[to] == Cell::Empty)
+            .collect();
+        for &(from, to) in &moves {
+            self.grid[from] = Cell::Empty;
+            self.grid[to] = herd;
+        }
+        moves.len()
+    }
+
+    // the east facing herd moves first, then the south facing one
+    pub fn step(&self) -> (SeaFloor, usize) {
+        let mut next = self.clone();
+        let moved = next.move_herd(Cell::East, (1, 0)) + next.move_herd(Cell::South, (0, 1));
+        (next, moved)
+    }
+
+    pub fn steps(&self) -> Steps {
+        Steps { floor: Some(self.clone()) }
+    }
+
+    pub fn first_step_without_moves(&self) -> usize {
+        self.steps().count()
+    }
+}
+
+// the sea floor after every step and how many moved in it, up to and including the first step
+// where nobody could
+pub struct Steps {
+    floor: Option<SeaFloor>,
+}
+
+impl Iterator for Steps {
+    type Item = (SeaFloor, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (next, moved) = self.floor.take()?.step();
+        if moved > 0 {
+            self.floor = Some(next.clone());
+        }
+        Some((next, moved))
+    }
+}
+
+impl core::str::FromStr for SeaFloor {
+    type Err = error::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let grid = grid::Grid::parse_with(s, |c| match c {
+            '.' => Some(Cell::Empty),
+            '>' => Some(Cell::East),
+            'v' => Some(Cell::South),
+            _ => None,
+        })?;
+        Ok(SeaFloor { grid })
+    }
+}
+
+impl core::fmt::Display for SeaFloor {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}", self.grid)
+    }
+}
+
+const EXAMPLE: &str = r#"
+v...>>.vv>
+.vv>>.vv..
+>>.>v>...v
+>>v>>.>.v.
+v>v.vv.v..
+>.>>..v...
+.vv..>.>v.
+v.v..>>v.v
+....v..v.>
+"#;
+
+impl puzzle::Puzzle for SeaFloor {
+    fn parse(input: &str) -> Result<Self, error::Error> {
+        input.parse()
+    }
+
+    fn part1(&self) -> Result<puzzle::Answer, error::Error> {
+        Ok(self.first_step_without_moves().into())
+    }
+
+    // the last star comes for free with the other 49
+    fn part2(&self) -> Result<puzzle::Answer, error::Error> {
+        Ok("Merry Christmas!".into())
+    }
+
+    fn examples() -> Vec<puzzle::Example> {
+        vec![(EXAMPLE, Some("58"), None)]
+    }
+}
+
+#[test]
+fn test_step() -> Result<(), error::Error> {
+    let floor: SeaFloor = "...>>>>>...".parse()?;
+    let (floor, moved) = floor.step();
+    assert_eq!((floor.to_string().as_str(), moved), ("...>>>>.>..\n", 1));
+    let (floor, moved) = floor.step();
+    assert_eq!((floor.to_string().as_str(), moved), ("...>>>.>.>.\n", 2));
+
+    // east first, so the south facing one moves into the spot just left
+    let floor: SeaFloor = "..........\n.>v....v..\n.......>..\n..........".parse()?;
+    let (floor, _) = floor.step();
+    assert_eq!(floor.to_string(), "..........\n.>........\n..v....v>.\n..........\n");
+
+    // off the right and bottom edges and back in on the other side
+    let floor: SeaFloor = "...>\n....\n..v.".parse()?;
+    assert_eq!(floor.step().0.to_string(), ">.v.\n....\n....\n");
+
+    assert!("..x.".parse::<SeaFloor>().is_err());
+    Ok(())
+}
+
+#[test]
+fn test_day25() -> Result<(), error::Error> {
+    let floor: SeaFloor = EXAMPLE.parse()?;
+    let steps: Vec<(SeaFloor, usize)> = floor.steps().collect();
+    assert_eq!(steps.len(), 58);
+    assert!(steps[..57].iter().all(|(_, moved)| *moved > 0));
+    assert_eq!(steps[57].1, 0);
+    assert_eq!(steps[56].0, steps[57].0);
+    assert_eq!(
+        steps[57].0.to_string(),
+        r#"..>>v>vv..
+..v.>>vv..
+..>>v>>vv.
+..>>>>>vv.
+v......>vv
+v>v....>>v
+vvv.....>>
+>vv......>
+.>v.vv.v..
+"#
+    );
+    assert_eq!(floor.first_step_without_moves(), 58);
+    Ok(())
+}
@@ -1,3 +1,10 @@
+use crate::error;
+use crate::puzzle;
+
+use alloc::borrow::ToOwned;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
 pub fn count_01(nums: &Vec<String>, index: usize) -> (u64, u64) {
     let mut count_0s: u64 = 0;
     let mut count_1s: u64 = 0;
@@ -37,9 +44,9 @@ impl LifeSupport {
     }
 }
 
-pub fn calculate_power_consumption(numbers: &Vec<String>) -> PowerConsumption {
+pub fn calculate_power_consumption(numbers: &Vec<String>) -> Result<PowerConsumption, error::Error> {
     if numbers.is_empty() {
-        panic!("no numbers");
+        return Err(error::Error::General("no numbers".to_string()));
     }
 
     let mut pc = PowerConsumption { gamma_rate: 0, epsilon_rate: 0 };
@@ -52,7 +59,7 @@ pub fn calculate_power_consumption(numbers: &Vec<String>) -> PowerConsumption {
         let (count_0s, count_1s) = count_01(numbers, index);
 
         if count_0s == count_1s {
-            panic!("bad algo");
+            return Err(error::Error::General(format!("as many 0s as 1s at bit {}", index)));
         }
 
         if count_1s > count_0s {
@@ -66,15 +73,15 @@ pub fn calculate_power_consumption(numbers: &Vec<String>) -> PowerConsumption {
         index += 1;
     }
 
-    pc.gamma_rate = u64::from_str_radix(&gamma, 2).unwrap();
-    pc.epsilon_rate = u64::from_str_radix(&epsilon, 2).unwrap();
+    pc.gamma_rate = u64::from_str_radix(&gamma, 2)?;
+    pc.epsilon_rate = u64::from_str_radix(&epsilon, 2)?;
 
-    pc
+    Ok(pc)
 }
 
-pub fn calculate_life_support(numbers: &Vec<String>) -> LifeSupport {
+pub fn calculate_life_support(numbers: &Vec<String>) -> Result<LifeSupport, error::Error> {
     if numbers.is_empty() {
-        panic!("no numbers");
+        return Err(error::Error::General("no numbers".to_string()));
     }
 
     let mut ls = LifeSupport { oxygen: 0, co2: 0 };
@@ -107,15 +114,16 @@ pub fn calculate_life_support(numbers: &Vec<String>) -> LifeSupport {
         index += 1;
     }
 
-    ls.oxygen = u64::from_str_radix(&oxygen_nums[0], 2).unwrap();
-    ls.co2 = u64::from_str_radix(&co2_nums[0], 2).unwrap();
+    ls.oxygen = u64::from_str_radix(&oxygen_nums[0], 2)?;
+    ls.co2 = u64::from_str_radix(&co2_nums[0], 2)?;
 
-    ls
+    Ok(ls)
 }
 
-#[test]
-fn test_power_consumption() {
-    let input = r#"00100
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Report(pub Vec<String>);
+
+const EXAMPLE: &str = r#"00100
 11110
 10110
 10111
@@ -128,8 +136,51 @@ fn test_power_consumption() {
 00010
 01010"#;
 
+impl puzzle::Puzzle for Report {
+    fn parse(input: &str) -> Result<Self, error::Error> {
+        let numbers: Vec<String> = input.lines().map(|l| l.trim()).filter(|l| !l.is_empty()).map(|l| l.into()).collect();
+        let width = numbers.first().ok_or_else(|| error::Error::Parse("no numbers".to_string()))?.len();
+        // every bit is counted across all the numbers, and the rates have to fit a u64
+        for number in &numbers {
+            if number.len() != width || width > 64 || !number.chars().all(|c| c == '0' || c == '1') {
+                return Err(error::Error::Parse(format!("invalid binary number: {}", number)));
+            }
+        }
+        Ok(Report(numbers))
+    }
+
+    fn part1(&self) -> Result<puzzle::Answer, error::Error> {
+        Ok(calculate_power_consumption(&self.0)?.sum().into())
+    }
+
+    fn part2(&self) -> Result<puzzle::Answer, error::Error> {
+        Ok(calculate_life_support(&self.0)?.sum().into())
+    }
+
+    fn examples() -> Vec<puzzle::Example> {
+        vec![(EXAMPLE, Some("198"), Some("230"))]
+    }
+}
+
+#[test]
+fn test_parse_errors() {
+    use puzzle::Puzzle;
+
+    assert_eq!(Report::parse("").err(), Some(error::Error::Parse("no numbers".to_string())));
+    assert_eq!(Report::parse("x").err(), Some(error::Error::Parse("invalid binary number: x".to_string())));
+    assert_eq!(Report::parse("01\n1").err(), Some(error::Error::Parse("invalid binary number: 1".to_string())));
+    assert_eq!(
+        Report::parse("01\n10").and_then(|report| report.part1()),
+        Err(error::Error::General("as many 0s as 1s at bit 0".to_string()))
+    );
+}
+
+#[test]
+fn test_power_consumption() -> Result<(), error::Error> {
+    let input = EXAMPLE;
+
     let nums: Vec<String> = input.lines().map(|l| l.to_string()).collect();
-    let res = calculate_power_consumption(&nums);
+    let res = calculate_power_consumption(&nums)?;
 
     assert_eq!(res.gamma_rate, 22);
     assert_eq!(res.epsilon_rate, 9);
@@ -137,30 +188,21 @@ fn test_power_consumption() {
 
     let input = std::fs::read_to_string("input_day3").unwrap();
     let nums: Vec<String> = input.lines().map(|l| l.to_string()).collect();
-    let res = calculate_power_consumption(&nums);
+    let res = calculate_power_consumption(&nums)?;
 
     assert_eq!(res.gamma_rate, 2601);
     assert_eq!(res.epsilon_rate, 1494);
     assert_eq!(res.sum(), 3885894);
+
+    Ok(())
 }
 
 #[test]
-fn test_life_support() {
-    let input = r#"00100
-11110
-10110
-10111
-10101
-01111
-00111
-11100
-10000
-11001
-00010
-01010"#;
+fn test_life_support() -> Result<(), error::Error> {
+    let input = EXAMPLE;
 
     let nums: Vec<String> = input.lines().map(|l| l.to_string()).collect();
-    let res = calculate_life_support(&nums);
+    let res = calculate_life_support(&nums)?;
 
     assert_eq!(res.oxygen, 23);
     assert_eq!(res.co2, 10);
@@ -168,9 +210,11 @@ fn test_life_support() {
 
     let input = std::fs::read_to_string("input_day3").unwrap();
     let nums: Vec<String> = input.lines().map(|l| l.to_string()).collect();
-    let res = calculate_life_support(&nums);
+    let res = calculate_life_support(&nums)?;
 
     assert_eq!(res.oxygen, 3775);
     assert_eq!(res.co2, 1159);
     assert_eq!(res.sum(), 4375225);
+
+    Ok(())
 }
@@ -1,12 +1,18 @@
 use crate::error;
+use crate::puzzle;
+
+use alloc::string::ToString;
+use alloc::vec::Vec;
 
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Number {
     pub number: u64,
     pub selected: bool,
 }
 
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Board {
     pub matrix: Vec<Number>,
 }
@@ -72,19 +78,10 @@ impl Board {
     pub fn sum_unmarked(&self) -> u64 {
         self.matrix.iter().filter(|n| !n.selected).map(|n| n.number).sum()
     }
-
-    fn _dump(&self) {
-        for y in 0..=4u64 {
-            for x in 0..=4u64 {
-                let n = self.at(x, y);
-                print!("{:4 }{}", n.number, if n.selected { "X" } else { "-" });
-            }
-            println!();
-        }
-        println!();
-    }
 }
 
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Bingo {
     pub drawn_numbers: Vec<u64>,
     pub boards: Vec<Board>,
@@ -113,6 +110,7 @@ pub fn play_bingo(mut bingo: Bingo) -> BingoResult {
                 board.mark(drawn_number);
 
                 if board.is_bingo() {
+                    tracing::debug!(number = drawn_number, winner = winners.len() + 1, "bingo");
                     winners.push(Winner {
                         board: board.clone(),
                         winning_number: drawn_number,
@@ -132,7 +130,7 @@ fn parse_drawn_numbers(line: &str) -> Result<Vec<u64>, error::Error> {
 pub fn parse_bingo(input: &str) -> Result<Bingo, error::Error> {
     let mut line_iterator = input.lines().filter(|l| !l.trim_start().trim_end().is_empty());
     let mut bingo = Bingo {
-        drawn_numbers: parse_drawn_numbers(line_iterator.next().unwrap())?,
+        drawn_numbers: parse_drawn_numbers(line_iterator.next().ok_or_else(|| error::Error::Parse("missing drawn numbers".to_string()))?)?,
         boards: vec![],
     };
     for board_lines in line_iterator.collect::<Vec<&str>>().chunks(5) {
@@ -141,15 +139,16 @@ pub fn parse_bingo(input: &str) -> Result<Bingo, error::Error> {
             let numbers: Result<Vec<u64>, _> = board_line.split(' ').filter(|token| !token.trim_start().trim_end().is_empty()).map(|token| token.parse()).collect();
             matrix.append(&mut numbers?);
         }
+        if matrix.len() != 5 * 5 {
+            return Err(error::Error::Parse(format!("board {} is not 5x5", bingo.boards.len() + 1)));
+        }
         let board = Board::from_numbers(matrix);
         bingo.boards.push(board);
     }
     Ok(bingo)
 }
 
-#[test]
-fn test_bingo() -> Result<(), error::Error> {
-    let input = r#"
+const EXAMPLE: &str = r#"
 7,4,9,5,11,17,23,2,0,14,21,24,10,16,13,6,15,25,12,22,18,20,8,19,3,26,1
 
 22 13 17 11  0
@@ -169,7 +168,40 @@ fn test_bingo() -> Result<(), error::Error> {
 18  8 23 26 20
 22 11 13  6  5
  2  0 12  3  7
-    "#;
+"#;
+
+impl puzzle::Puzzle for Bingo {
+    fn parse(input: &str) -> Result<Self, error::Error> {
+        parse_bingo(input)
+    }
+
+    fn part1(&self) -> Result<puzzle::Answer, error::Error> {
+        let result = play_bingo(self.clone());
+        let winner = result.winners.first().ok_or_else(|| error::Error::General("no board wins".to_string()))?;
+        Ok(winner.score().into())
+    }
+
+    fn part2(&self) -> Result<puzzle::Answer, error::Error> {
+        let result = play_bingo(self.clone());
+        let winner = result.winners.last().ok_or_else(|| error::Error::General("no board wins".to_string()))?;
+        Ok(winner.score().into())
+    }
+
+    fn examples() -> Vec<puzzle::Example> {
+        vec![(EXAMPLE, Some("4512"), Some("1924"))]
+    }
+}
+
+#[test]
+fn test_parse_errors() {
+    assert_eq!(parse_bingo("").err(), Some(error::Error::Parse("missing drawn numbers".to_string())));
+    assert_eq!(parse_bingo("x").err(), Some(error::Error::Parse("invalid digit found in string".to_string())));
+    assert_eq!(parse_bingo("1,2\n\n1 2\n3 4").err(), Some(error::Error::Parse("board 1 is not 5x5".to_string())));
+}
+
+#[test]
+fn test_bingo() -> Result<(), error::Error> {
+    let input = EXAMPLE;
 
     let bingo = parse_bingo(input)?;
     assert_eq!(bingo.drawn_numbers.len(), 27);
@@ -1,5 +1,9 @@
 use crate::error;
-use std::cmp::Ordering;
+use crate::grid;
+use crate::puzzle;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
 
 #[derive(PartialEq, Debug)]
 pub struct Point {
@@ -7,7 +11,8 @@ pub struct Point {
     pub y: u64,
 }
 
-#[derive(PartialEq, Debug)]
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Line {
     pub x1: u64,
     pub y1: u64,
@@ -57,7 +62,7 @@ impl Line {
     }
 }
 
-impl std::str::FromStr for Line {
+impl core::str::FromStr for Line {
     type Err = error::Error;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let tokens: Vec<&str> = s.split(&[',', ' ', '-', '>'][..]).filter(|line| !line.trim_start().trim_end().is_empty()).collect();
@@ -85,37 +90,32 @@ pub fn load_lines_from_str(input: &str) -> Result<Vec<Line>, error::Error> {
     Ok(lines)
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LineMap {
-    pub width: u64,
-    pub height: u64,
-    pub points: Vec<u64>,
+    pub points: grid::Grid<u64>,
 }
 
 impl LineMap {
     pub fn from_lines(lines: Vec<Line>) -> Self {
-        let width = std::cmp::max(
+        let width = core::cmp::max(
             lines.iter().map(|line| line.x1).max_by(|lhs, rhs| lhs.cmp(rhs)),
             lines.iter().map(|line| line.x2).max_by(|lhs, rhs| lhs.cmp(rhs)),
         )
-        .unwrap()
+        .unwrap_or(0)
             + 1;
-        let height = std::cmp::max(
+        let height = core::cmp::max(
             lines.iter().map(|line| line.y1).max_by(|lhs, rhs| lhs.cmp(rhs)),
             lines.iter().map(|line| line.y2).max_by(|lhs, rhs| lhs.cmp(rhs)),
         )
-        .unwrap()
+        .unwrap_or(0)
             + 1;
 
         if width == 0 || height == 0 {
             panic!("invalid size for LineMap");
         }
 
-        let map_size = (width * height) as usize;
-
         let mut map = LineMap {
-            width,
-            height,
-            points: vec![0; map_size],
+            points: grid::Grid::new(width as usize, height as usize, 0),
         };
 
         for line in &lines {
@@ -125,14 +125,20 @@ impl LineMap {
         map
     }
 
+    pub fn width(&self) -> usize {
+        self.points.width()
+    }
+
+    pub fn height(&self) -> usize {
+        self.points.height()
+    }
+
     pub fn at(&self, x: u64, y: u64) -> u64 {
-        let pos = (y * self.width + x) as usize;
-        self.points[pos]
+        self.points[(x as i64, y as i64)]
     }
 
     fn mark_point(&mut self, x: u64, y: u64) {
-        let pos = (y * self.width + x) as usize;
-        self.points[pos] += 1;
+        self.points[(x as i64, y as i64)] += 1;
     }
 
     fn mark_line(&mut self, line: &Line) {
@@ -143,13 +149,14 @@ impl LineMap {
     }
 
     pub fn num_points_overlap(&self) -> u64 {
-        self.points.iter().filter(|&p| p > &1u64).count() as u64
+        self.points.cells().iter().filter(|&p| p > &1u64).count() as u64
     }
 }
 
-#[test]
-fn test_load_lines() -> Result<(), error::Error> {
-    let input = r#"
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Vents(pub Vec<Line>);
+
+const EXAMPLE: &str = r#"
 0,9 -> 5,9
 8,0 -> 0,8
 9,4 -> 3,4
@@ -160,6 +167,44 @@ fn test_load_lines() -> Result<(), error::Error> {
 3,4 -> 1,4
 0,0 -> 8,8
 5,5 -> 8,2"#;
+
+impl puzzle::Puzzle for Vents {
+    fn parse(input: &str) -> Result<Self, error::Error> {
+        let lines = load_lines_from_str(input)?;
+        if lines.is_empty() {
+            return Err(error::Error::Parse("no vent lines".to_string()));
+        }
+        Ok(Vents(lines))
+    }
+
+    fn part1(&self) -> Result<puzzle::Answer, error::Error> {
+        let lines = self.0.iter().filter(|l| l.is_horizontal_or_vertical()).cloned().collect();
+        Ok(LineMap::from_lines(lines).num_points_overlap().into())
+    }
+
+    fn part2(&self) -> Result<puzzle::Answer, error::Error> {
+        Ok(LineMap::from_lines(self.0.clone()).num_points_overlap().into())
+    }
+
+    fn examples() -> Vec<puzzle::Example> {
+        vec![(EXAMPLE, Some("5"), Some("12"))]
+    }
+}
+
+#[test]
+fn test_parse_errors() -> Result<(), error::Error> {
+    use puzzle::Puzzle;
+
+    assert_eq!(Vents::parse("").err(), Some(error::Error::Parse("no vent lines".to_string())));
+    assert_eq!(Vents::parse("x").err(), Some(error::Error::Parse("invalid line: x tokens: [\"x\"]".to_string())));
+    // only diagonals leave part 1 nothing to mark
+    assert_eq!(Vents::parse("0,0 -> 1,1")?.part1()?, 0u64.into());
+    Ok(())
+}
+
+#[test]
+fn test_load_lines() -> Result<(), error::Error> {
+    let input = EXAMPLE;
     let lines = load_lines_from_str(input)?;
     assert_eq!(lines.len(), 10);
     assert_eq!(lines.first().unwrap().x1, 0);
@@ -191,30 +236,20 @@ fn test_line() -> Result<(), error::Error> {
 
 #[test]
 fn test_complete() -> Result<(), error::Error> {
-    let input = r#"
-0,9 -> 5,9
-8,0 -> 0,8
-9,4 -> 3,4
-2,2 -> 2,1
-7,0 -> 7,4
-6,4 -> 2,0
-0,9 -> 2,9
-3,4 -> 1,4
-0,0 -> 8,8
-5,5 -> 8,2"#;
+    let input = EXAMPLE;
     let lines = load_lines_from_str(input)?;
     let lines = lines.into_iter().filter(|line| line.is_horizontal_or_vertical()).collect();
     let map = LineMap::from_lines(lines);
 
-    assert_eq!(map.width, 10);
-    assert_eq!(map.height, 10);
+    assert_eq!(map.width(), 10);
+    assert_eq!(map.height(), 10);
 
     assert_eq!(map.at(7, 0), 1);
     assert_eq!(map.at(0, 9), 2);
 
     #[rustfmt::skip]
     assert_eq!(
-        map.points,
+        map.points.cells(),
         vec![
             0, 0, 0, 0, 0, 0, 0, 1, 0, 0,
             0, 0, 1, 0, 0, 0, 0, 1, 0, 0,
@@ -236,7 +271,7 @@ fn test_complete() -> Result<(), error::Error> {
 
     #[rustfmt::skip]
     assert_eq!(
-        map.points,
+        map.points.cells(),
         vec![
             1, 0, 1, 0, 0, 0, 0, 1, 1, 0,
             0, 1, 1, 1, 0, 0, 0, 2, 0, 0,
@@ -263,8 +298,8 @@ fn test_day5() -> Result<(), error::Error> {
     let lines = lines.into_iter().filter(|line| line.is_horizontal_or_vertical()).collect();
     let map = LineMap::from_lines(lines);
 
-    assert_eq!(map.width, 988);
-    assert_eq!(map.height, 990);
+    assert_eq!(map.width(), 988);
+    assert_eq!(map.height(), 990);
 
     assert_eq!(map.num_points_overlap(), 5306);
 
@@ -1,6 +1,11 @@
 use crate::error;
+use crate::puzzle;
+
+use alloc::string::ToString;
+use alloc::vec::Vec;
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Fish {
     age: u64,
 }
@@ -12,20 +17,27 @@ impl Fish {
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FishGame {
     pub fish: Vec<Fish>,
 }
 
-impl std::str::FromStr for FishGame {
+impl core::str::FromStr for FishGame {
     type Err = error::Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let fish: Vec<Fish> = s
-            .split(&[',', '\n'][..])
-            .filter(|token| !token.trim_start().trim_end().is_empty())
-            .map(|value_str| value_str.parse().unwrap())
-            .map(Fish::from_age)
-            .collect();
+        let mut fish: Vec<Fish> = vec![];
+        for token in s.split(&[',', '\n'][..]).filter(|token| !token.trim_start().trim_end().is_empty()) {
+            let age: u64 = token.trim().parse()?;
+            // a new fish starts at 8, so there is no bucket for anything older
+            if age > 8 {
+                return Err(error::Error::Parse(format!("invalid fish timer: {}", age)));
+            }
+            fish.push(Fish::from_age(age));
+        }
+        if fish.is_empty() {
+            return Err(error::Error::Parse("no fish".to_string()));
+        }
         Ok(FishGame { fish })
     }
 }
@@ -35,7 +47,7 @@ impl FishGame {
         let mut fish = self.fish.clone();
         for _day in 0..days {
             let mut new_fish: Vec<Fish> = Vec::new();
-            for mut fish in fish.iter_mut() {
+            for fish in fish.iter_mut() {
                 if fish.age == 0 {
                     fish.age = 7;
                     new_fish.push(Fish::from_age(8));
@@ -48,7 +60,7 @@ impl FishGame {
     }
 
     pub fn simulate_days2(&self, days: u64) -> u64 {
-        let mut buckets = vec![0u64; 9];
+        let mut buckets = [0u64; 9];
         for f in &self.fish {
             buckets[f.age as usize] += 1;
         }
@@ -75,10 +87,37 @@ impl FishGame {
     }
 }
 
+const EXAMPLE: &str = r#"
+3,4,3,1,2"#;
+
+impl puzzle::Puzzle for FishGame {
+    fn parse(input: &str) -> Result<Self, error::Error> {
+        input.parse()
+    }
+
+    fn part1(&self) -> Result<puzzle::Answer, error::Error> {
+        Ok(self.simulate_days2(80).into())
+    }
+
+    fn part2(&self) -> Result<puzzle::Answer, error::Error> {
+        Ok(self.simulate_days2(256).into())
+    }
+
+    fn examples() -> Vec<puzzle::Example> {
+        vec![(EXAMPLE, Some("5934"), Some("26984457539"))]
+    }
+}
+
+#[test]
+fn test_parse_errors() {
+    assert_eq!("".parse::<FishGame>().err(), Some(error::Error::Parse("no fish".to_string())));
+    assert_eq!("x".parse::<FishGame>().err(), Some(error::Error::Parse("invalid digit found in string".to_string())));
+    assert_eq!("3,9".parse::<FishGame>().err(), Some(error::Error::Parse("invalid fish timer: 9".to_string())));
+}
+
 #[test]
 fn test_fish_game() -> Result<(), error::Error> {
-    let input = r#"
-3,4,3,1,2"#;
+    let input = EXAMPLE;
     let game: FishGame = input.parse()?;
     assert_eq!(game.simulate_days(18), 26);
     assert_eq!(game.simulate_days2(18), 26);
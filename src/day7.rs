@@ -1,12 +1,17 @@
 use crate::error;
+use crate::puzzle;
+
+use alloc::borrow::ToOwned;
+use alloc::string::ToString;
+use alloc::vec::Vec;
 
 fn cost_distance_constant(v1: u64, v2: u64) -> u64 {
-    (v1 as i32 - v2 as i32).abs() as u64
+    (v1 as i32 - v2 as i32).unsigned_abs() as u64
 }
 
 fn cost_distance_increasing(v1: u64, v2: u64) -> u64 {
     // https://en.wikipedia.org/wiki/Triangular_number
-    let distance = (v1 as i32 - v2 as i32).abs() as u64;
+    let distance = (v1 as i32 - v2 as i32).unsigned_abs() as u64;
     (distance * (distance + 1)) / 2
 }
 
@@ -24,6 +29,7 @@ impl CrabGameMode {
     }
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CrabGame {
     pub positions: Vec<u64>,
 }
@@ -37,7 +43,7 @@ pub struct CrabGameResult {
 impl CrabGame {
     pub fn cheapest(&self, mode: CrabGameMode) -> CrabGameResult {
         let cheapest = (0..=self.positions.iter().max().unwrap().to_owned())
-            .map(|destination_position| self.positions.iter().map(|&p| mode.distance_cost(p, destination_position as u64)).sum())
+            .map(|destination_position| self.positions.iter().map(|&p| mode.distance_cost(p, destination_position)).sum())
             .enumerate()
             .min_by(|lhs: &(usize, u64), rhs: &(usize, u64)| lhs.1.cmp(&rhs.1))
             .unwrap();
@@ -49,19 +55,43 @@ impl CrabGame {
     }
 }
 
-impl std::str::FromStr for CrabGame {
+impl core::str::FromStr for CrabGame {
     type Err = error::Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let values: Vec<u64> = s
+        let values: Result<Vec<u64>, _> = s
             .split(&[',', '\n', ' '][..])
             .filter(|token| !token.trim_start().trim_end().is_empty())
-            .map(|token| token.parse().unwrap())
+            .map(|token| token.trim().parse())
             .collect();
+        let values = values?;
+        if values.is_empty() {
+            return Err(error::Error::Parse("no crab positions".to_string()));
+        }
         Ok(CrabGame { positions: values })
     }
 }
 
+const EXAMPLE: &str = "16,1,2,0,4,2,7,1,2,14";
+
+impl puzzle::Puzzle for CrabGame {
+    fn parse(input: &str) -> Result<Self, error::Error> {
+        input.parse()
+    }
+
+    fn part1(&self) -> Result<puzzle::Answer, error::Error> {
+        Ok(self.cheapest(CrabGameMode::ConstantCost).cost.into())
+    }
+
+    fn part2(&self) -> Result<puzzle::Answer, error::Error> {
+        Ok(self.cheapest(CrabGameMode::IncreasingCost).cost.into())
+    }
+
+    fn examples() -> Vec<puzzle::Example> {
+        vec![(EXAMPLE, Some("37"), Some("168"))]
+    }
+}
+
 #[test]
 fn test_distance_cost() {
     assert_eq!(cost_distance_constant(0, 0), 0);
@@ -77,9 +107,15 @@ fn test_distance_cost() {
     assert_eq!(cost_distance_increasing(16, 5), 66);
 }
 
+#[test]
+fn test_parse_errors() {
+    assert_eq!("".parse::<CrabGame>().err(), Some(error::Error::Parse("no crab positions".to_string())));
+    assert_eq!("x".parse::<CrabGame>().err(), Some(error::Error::Parse("invalid digit found in string".to_string())));
+}
+
 #[test]
 fn test_crab_game() -> Result<(), error::Error> {
-    let input = "16,1,2,0,4,2,7,1,2,14";
+    let input = EXAMPLE;
     let game: CrabGame = input.parse()?;
 
     assert_eq!(game.positions.len(), 10);
@@ -1,7 +1,9 @@
 use crate::error;
+use crate::puzzle;
 
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
 use itertools::Itertools;
-use permutator::copy::Permutation;
 
 #[derive(Debug)]
 pub struct Mapper {
@@ -10,7 +12,7 @@ pub struct Mapper {
 
 impl Mapper {
     fn from_patterns(patterns: &Vec<String>) -> Result<Self, error::Error> {
-        for permutation in vec!['a', 'b', 'c', 'd', 'e', 'f', 'g'].permutation() {
+        for permutation in ['a', 'b', 'c', 'd', 'e', 'f', 'g'].into_iter().permutations(7) {
             let permutation_string: String = permutation.iter().collect();
             let mapper = Mapper { mapping: permutation_string };
             if patterns.iter().all(|pattern| mapper.to_digit(pattern).is_some()) {
@@ -20,22 +22,23 @@ impl Mapper {
         Err(error::Error::General(format!("failed to find mapping from patterns: {:?}", patterns)))
     }
 
-    fn map_char(&self, c: char) -> char {
-        let pos = self.mapping.find(c).expect("could not map char");
-        match pos {
-            0 => 'a',
-            1 => 'b',
-            2 => 'c',
-            3 => 'd',
-            4 => 'e',
-            5 => 'f',
-            6 => 'g',
-            _ => panic!("invalid char"),
+    // None for anything that isn't one of the seven wires
+    fn map_char(&self, c: char) -> Option<char> {
+        match self.mapping.find(c)? {
+            0 => Some('a'),
+            1 => Some('b'),
+            2 => Some('c'),
+            3 => Some('d'),
+            4 => Some('e'),
+            5 => Some('f'),
+            6 => Some('g'),
+            _ => None,
         }
     }
 
     pub fn to_digit(&self, input: &str) -> Option<char> {
-        let new: String = input.chars().map(|c| self.map_char(c)).sorted().collect();
+        let mapped: Option<Vec<char>> = input.chars().map(|c| self.map_char(c)).collect();
+        let new: String = mapped?.into_iter().sorted().collect();
 
         match new.as_ref() {
             "abcefg" => Some('0'),
@@ -54,6 +57,7 @@ impl Mapper {
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Entry {
     pub patterns: Vec<String>,
     pub output: Vec<String>,
@@ -62,12 +66,16 @@ pub struct Entry {
 impl Entry {
     pub fn output(&self) -> Result<u64, error::Error> {
         let mapper = Mapper::from_patterns(&self.patterns)?;
-        let output_string: String = self.output.iter().map(|o| mapper.to_digit(o).unwrap()).collect();
-        Ok(output_string.parse()?)
+        let output_string: Result<String, error::Error> = self
+            .output
+            .iter()
+            .map(|o| mapper.to_digit(o).ok_or_else(|| error::Error::General(format!("output {} is no digit", o))))
+            .collect();
+        Ok(output_string?.parse()?)
     }
 }
 
-impl std::str::FromStr for Entry {
+impl core::str::FromStr for Entry {
     type Err = error::Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
@@ -76,31 +84,40 @@ impl std::str::FromStr for Entry {
         if tokens.len() != 2 {
             return Err(error::Error::Parse(format!("invalid Entry: {}", s)));
         }
-        Ok(Entry {
-            patterns: tokens[0].trim_start().trim_end().split(' ').map(str::to_string).collect(),
-            output: tokens[1].trim_start().trim_end().split(' ').map(str::to_string).collect(),
-        })
+        let patterns: Vec<String> = tokens[0].split_whitespace().map(str::to_string).collect();
+        let output: Vec<String> = tokens[1].split_whitespace().map(str::to_string).collect();
+        // ten patterns, one for every digit, then four digits of output, all lit on wires a to g
+        let is_wires = |pattern: &String| (1..=7).contains(&pattern.len()) && pattern.chars().all(|c| ('a'..='g').contains(&c));
+        if patterns.len() != 10 || output.len() != 4 || !patterns.iter().chain(output.iter()).all(is_wires) {
+            return Err(error::Error::Parse(format!("invalid Entry: {}", s)));
+        }
+        Ok(Entry { patterns, output })
     }
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Game {
     entries: Vec<Entry>,
 }
 
 impl Game {
-    pub fn sum(&self) -> u64 {
-        self.entries.iter().map(|e| e.output().unwrap()).sum()
+    pub fn sum(&self) -> Result<u64, error::Error> {
+        self.entries.iter().map(|e| e.output()).sum()
     }
 }
 
-impl std::str::FromStr for Game {
+impl core::str::FromStr for Game {
     type Err = error::Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let entries: Result<Vec<Entry>, _> = s.lines().map(|line| line.trim_start().trim_end()).filter(|line| !line.is_empty()).map(|line| line.parse()).collect();
 
-        Ok(Game { entries: entries? })
+        let entries = entries?;
+        if entries.is_empty() {
+            return Err(error::Error::Parse("no entries".to_string()));
+        }
+        Ok(Game { entries })
     }
 }
 
@@ -109,13 +126,55 @@ impl Game {
         let mut count = 0u64;
         for entry in self.entries.iter() {
             let c = entry.output.iter().filter(|p| p.len() == 4 || p.len() == 2 || p.len() == 3 || p.len() == 7).count();
-            //println!("entry: {:?} count: {}", entry, c);
             count += c as u64;
         }
         count
     }
 }
 
+const EXAMPLE: &str = r#"
+be cfbegad cbdgef fgaecd cgeb fdcge agebfd fecdb fabcd edb | fdgacbe cefdb cefbgd gcbe
+edbfga begcd cbg gc gcadebf fbgde acbgfd abcde gfcbed gfec | fcgedb cgb dgebacf gc
+fgaebd cg bdaec gdafb agbcfd gdcbef bgcad gfac gcb cdgabef | cg cg fdcagb cbg
+fbegcd cbd adcefb dageb afcb bc aefdc ecdab fgdeca fcdbega | efabcd cedba gadfec cb
+aecbfdg fbg gf bafeg dbefa fcge gcbea fcaegb dgceab fcbdga | gecf egdcabf bgf bfgea
+fgeab ca afcebg bdacfeg cfaedg gcfdb baec bfadeg bafgc acf | gebdcfa ecba ca fadegcb
+dbcfg fgd bdegcaf fgec aegbdf ecdfab fbedc dacgb gdcebf gf | cefg dcbef fcge gbcadfe
+bdfegc cbegaf gecbf dfcage bdacg ed bedf ced adcbefg gebcd | ed bcgafe cdgba cbgef
+egadfb cdbfeg cegd fecab cgb gbdefca cg fgcdab egfdb bfceg | gbdfcae bgc cg cgb
+gcafb gcf dcaebfg ecagb gf abcdeg gaef cafbge fdbac fegbdc | fgae cfgab fg bagce
+"#;
+
+impl puzzle::Puzzle for Game {
+    fn parse(input: &str) -> Result<Self, error::Error> {
+        input.parse()
+    }
+
+    fn part1(&self) -> Result<puzzle::Answer, error::Error> {
+        Ok(self.count_unique_output_values().into())
+    }
+
+    fn part2(&self) -> Result<puzzle::Answer, error::Error> {
+        Ok(self.sum()?.into())
+    }
+
+    fn examples() -> Vec<puzzle::Example> {
+        vec![(EXAMPLE, Some("26"), Some("61229"))]
+    }
+}
+
+#[test]
+fn test_parse_errors() -> Result<(), error::Error> {
+    assert_eq!("".parse::<Game>().err(), Some(error::Error::Parse("no entries".to_string())));
+    assert_eq!("xyz | abc".parse::<Game>().err(), Some(error::Error::Parse("invalid Entry: xyz | abc".to_string())));
+    let wrong_wire = "acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb ab | cdfeb fcadb cdfeb cdbax";
+    assert_eq!(wrong_wire.parse::<Game>().err(), Some(error::Error::Parse(format!("invalid Entry: {}", wrong_wire))));
+    // wires that only light up as no digit at all
+    let game: Game = "acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb ab | cdfeb fcadb cdfeb cd".parse()?;
+    assert_eq!(game.sum(), Err(error::Error::General("output cd is no digit".to_string())));
+    Ok(())
+}
+
 #[test]
 fn test_mapper() -> Result<(), error::Error> {
     let game: Game = "acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb ab | cdfeb fcadb cdfeb cdbaf".parse()?;
@@ -141,32 +200,21 @@ fn test_day8() -> Result<(), error::Error> {
     assert_eq!(game.entries[0].patterns.len(), 10);
     assert_eq!(game.entries[0].output.len(), 4);
 
-    let input = r#"
-be cfbegad cbdgef fgaecd cgeb fdcge agebfd fecdb fabcd edb | fdgacbe cefdb cefbgd gcbe
-edbfga begcd cbg gc gcadebf fbgde acbgfd abcde gfcbed gfec | fcgedb cgb dgebacf gc
-fgaebd cg bdaec gdafb agbcfd gdcbef bgcad gfac gcb cdgabef | cg cg fdcagb cbg
-fbegcd cbd adcefb dageb afcb bc aefdc ecdab fgdeca fcdbega | efabcd cedba gadfec cb
-aecbfdg fbg gf bafeg dbefa fcge gcbea fcaegb dgceab fcbdga | gecf egdcabf bgf bfgea
-fgeab ca afcebg bdacfeg cfaedg gcfdb baec bfadeg bafgc acf | gebdcfa ecba ca fadegcb
-dbcfg fgd bdegcaf fgec aegbdf ecdfab fbedc dacgb gdcebf gf | cefg dcbef fcge gbcadfe
-bdfegc cbegaf gecbf dfcage bdacg ed bedf ced adcbefg gebcd | ed bcgafe cdgba cbgef
-egadfb cdbfeg cegd fecab cgb gbdefca cg fgcdab egfdb bfceg | gbdfcae bgc cg cgb
-gcafb gcf dcaebfg ecagb gf abcdeg gaef cafbge fdbac fegbdc | fgae cfgab fg bagce
-"#;
+    let input = EXAMPLE;
 
     let game: Game = input.parse()?;
     assert_eq!(game.entries.len(), 10);
     assert_eq!(game.count_unique_output_values(), 26);
     assert_eq!(game.entries[0].output()?, 8394);
     assert_eq!(game.entries[9].output()?, 4315);
-    assert_eq!(game.sum(), 61229);
+    assert_eq!(game.sum()?, 61229);
 
     let input = std::fs::read_to_string("input_day8")?;
     let game: Game = input.parse()?;
 
     assert_eq!(game.entries.len(), 200);
     assert_eq!(game.count_unique_output_values(), 381);
-    assert_eq!(game.sum(), 1023686);
+    assert_eq!(game.sum()?, 1023686);
 
     Ok(())
 }
@@ -1,49 +1,36 @@
+use crate::collections::HashSet;
 use crate::error;
+use crate::grid;
+use crate::puzzle;
+use alloc::vec::Vec;
 use itertools::Itertools;
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct HeightMap {
-    heightmap: Vec<Vec<i8>>,
+    heightmap: grid::Grid<u8>,
 }
 
-impl std::str::FromStr for HeightMap {
+impl core::str::FromStr for HeightMap {
     type Err = error::Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let heightmap: Vec<Vec<i8>> = s
-            .lines()
-            .filter(|line| !line.trim_start().trim_end().is_empty())
-            .map(|line| line.chars().map(|c| c.to_digit(10).unwrap() as i8).collect())
-            .collect();
-
-        if heightmap.is_empty() || !heightmap.iter().all(|row| row.len() == heightmap[0].len()) {
-            return Err(error::Error::Parse("invalid heightmap".to_string()));
-        }
-
-        Ok(HeightMap { heightmap })
+        Ok(HeightMap {
+            heightmap: grid::Grid::parse_digits(s)?,
+        })
     }
 }
 
 impl HeightMap {
-    pub fn width(&self) -> i8 {
-        self.heightmap[0].len() as i8
+    pub fn width(&self) -> usize {
+        self.heightmap.width()
     }
 
-    pub fn height(&self) -> i8 {
-        self.heightmap.len() as i8
+    pub fn height(&self) -> usize {
+        self.heightmap.height()
     }
 
-    pub fn low_points(&self) -> Vec<(i8, i8)> {
-        let mut low_points: Vec<(i8, i8)> = Vec::new();
-
-        for y in 0..self.height() {
-            for x in 0..self.width() {
-                if self.is_low_point(x, y) {
-                    low_points.push((x, y));
-                }
-            }
-        }
-
-        low_points
+    pub fn low_points(&self) -> Vec<(i64, i64)> {
+        self.heightmap.positions().filter(|&(x, y)| self.is_low_point(x, y)).collect()
     }
 
     pub fn sum_risk_levels(&self) -> u64 {
@@ -58,61 +45,64 @@ impl HeightMap {
         self.basins().into_iter().sorted_by(|a, b| b.cmp(a)).take(3).collect()
     }
 
-    fn flow(&self, x: i8, y: i8, last_height: i8) -> Vec<(i8, i8)> {
-        if self.is_oob(x, y) {
-            return vec![];
-        }
-
-        let cur = self.at(x, y);
+    fn flow(&self, x: i64, y: i64, last_height: i8) -> Vec<(i64, i64)> {
+        let cur = self.at(x, y) as i8;
 
         if cur >= 9 || cur <= last_height {
             return vec![];
         }
 
         let mut points = vec![(x, y)];
-        points.append(&mut self.flow(x, y - 1, cur));
-        points.append(&mut self.flow(x + 1, y, cur));
-        points.append(&mut self.flow(x, y + 1, cur));
-        points.append(&mut self.flow(x - 1, y, cur));
+        for (x, y) in self.heightmap.neighbors4(x, y) {
+            points.append(&mut self.flow(x, y, cur));
+        }
 
         points
     }
 
-    fn basin_from_point(&self, x: i8, y: i8) -> i64 {
-        self.flow(x, y, -1).into_iter().unique().count() as i64
+    fn basin_from_point(&self, x: i64, y: i64) -> i64 {
+        self.flow(x, y, -1).into_iter().collect::<HashSet<_>>().len() as i64
     }
 
-    fn is_low_point(&self, x: i8, y: i8) -> bool {
+    fn is_low_point(&self, x: i64, y: i64) -> bool {
         let current = self.at(x, y);
+        self.heightmap.neighbors4(x, y).all(|(x, y)| self.at(x, y) > current)
+    }
+
+    pub fn at(&self, x: i64, y: i64) -> u8 {
+        self.heightmap[(x, y)]
+    }
+}
 
-        self.is_point_higher_than_or_oob(x, y - 1, current)
-            && self.is_point_higher_than_or_oob(x + 1, y, current)
-            && self.is_point_higher_than_or_oob(x, y + 1, current)
-            && self.is_point_higher_than_or_oob(x - 1, y, current)
+const EXAMPLE: &str = r#"
+2199943210
+3987894921
+9856789892
+8767896789
+9899965678
+"#;
+
+impl puzzle::Puzzle for HeightMap {
+    fn parse(input: &str) -> Result<Self, error::Error> {
+        input.parse()
     }
 
-    fn is_oob(&self, x: i8, y: i8) -> bool {
-        x < 0 || x >= self.width() || y < 0 || y >= self.height()
+    fn part1(&self) -> Result<puzzle::Answer, error::Error> {
+        Ok(self.sum_risk_levels().into())
     }
 
-    fn is_point_higher_than_or_oob(&self, x: i8, y: i8, value: i8) -> bool {
-        self.is_oob(x, y) || self.at(x, y) > value
+    fn part2(&self) -> Result<puzzle::Answer, error::Error> {
+        Ok(self.largest_basins().iter().product::<i64>().into())
     }
 
-    pub fn at(&self, x: i8, y: i8) -> i8 {
-        self.heightmap[y as usize][x as usize]
+    fn examples() -> Vec<puzzle::Example> {
+        vec![(EXAMPLE, Some("15"), Some("1134"))]
     }
 }
 
 #[test]
 fn test_day9() -> Result<(), error::Error> {
-    let input = r#"
-2199943210
-3987894921
-9856789892
-8767896789
-9899965678
-"#;
+    let input = EXAMPLE;
 
     let heightmap: HeightMap = input.parse()?;
     assert_eq!(heightmap.width(), 10);
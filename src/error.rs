@@ -1,36 +1,79 @@
-#[derive(Debug, PartialEq)]
+use alloc::string::{String, ToString};
+
+#[derive(Debug, PartialEq, Clone)]
 pub enum Error {
     General(String),
     Parse(String),
     Io(String),
 }
 
-impl From<std::string::FromUtf8Error> for Error {
-    fn from(e: std::string::FromUtf8Error) -> Self {
+// just the message, the kind is for matching on
+impl core::fmt::Display for Error {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Error::General(message) | Error::Parse(message) | Error::Io(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl core::error::Error for Error {}
+
+impl From<alloc::string::FromUtf8Error> for Error {
+    fn from(e: alloc::string::FromUtf8Error) -> Self {
         Error::Parse(e.to_string())
     }
 }
 
-impl From<std::num::ParseIntError> for Error {
-    fn from(e: std::num::ParseIntError) -> Self {
+impl From<core::num::ParseIntError> for Error {
+    fn from(e: core::num::ParseIntError) -> Self {
         Error::Parse(e.to_string())
     }
 }
 
-impl From<std::num::ParseFloatError> for Error {
-    fn from(e: std::num::ParseFloatError) -> Self {
+impl From<core::num::ParseFloatError> for Error {
+    fn from(e: core::num::ParseFloatError) -> Self {
         Error::Parse(e.to_string())
     }
 }
 
+#[cfg(feature = "std")]
 impl From<std::io::Error> for Error {
     fn from(e: std::io::Error) -> Self {
         Error::Io(e.to_string())
     }
 }
 
-impl From<scan_fmt::parse::ScanError> for Error {
-    fn from(e: scan_fmt::parse::ScanError) -> Self {
+#[cfg(feature = "std")]
+impl From<toml::de::Error> for Error {
+    fn from(e: toml::de::Error) -> Self {
         Error::Parse(e.to_string())
     }
 }
+
+#[cfg(feature = "image")]
+impl From<png::EncodingError> for Error {
+    fn from(e: png::EncodingError) -> Self {
+        Error::Io(e.to_string())
+    }
+}
+
+#[cfg(feature = "image")]
+impl From<gif::EncodingError> for Error {
+    fn from(e: gif::EncodingError) -> Self {
+        Error::Io(e.to_string())
+    }
+}
+
+#[cfg(feature = "json")]
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Error::General(e.to_string())
+    }
+}
+
+#[cfg(feature = "download")]
+impl From<ureq::Error> for Error {
+    fn from(e: ureq::Error) -> Self {
+        Error::Io(e.to_string())
+    }
+}
@@ -0,0 +1,501 @@
+use crate::day16;
+use crate::day19;
+use crate::day24;
+
+use proptest::collection::vec;
+use proptest::prelude::*;
+use std::ops::{Range, RangeInclusive};
+
+// random but valid puzzle inputs as proptest strategies, so parsers and solvers can be run on far
+// more inputs than the examples and the one real input. the sizes are parameters, because some
+// solutions are quick on any size and others are only quick on small ones.
+
+fn lines(lines: Vec<String>) -> String {
+    lines.join("\n") + "\n"
+}
+
+// day 1: sonar sweep depths
+pub fn depths(count: Range<usize>) -> impl Strategy<Value = String> {
+    vec(0..10000u64, count).prop_map(|depths| lines(depths.iter().map(|depth| depth.to_string()).collect()))
+}
+
+// day 2: submarine commands
+pub fn course(count: Range<usize>) -> impl Strategy<Value = String> {
+    let command = (prop_oneof![Just("forward"), Just("down"), Just("up")], 1..10u64).prop_map(|(direction, units)| format!("{} {}", direction, units));
+    vec(command, count).prop_map(lines)
+}
+
+// day 3: a diagnostic report of binary numbers that are all the same width
+pub fn diagnostic(width: usize, count: Range<usize>) -> impl Strategy<Value = String> {
+    let number = vec(prop_oneof![Just('0'), Just('1')], width).prop_map(|bits| bits.into_iter().collect::<String>());
+    vec(number, count).prop_map(lines)
+}
+
+// day 4: drawn numbers and 5x5 boards out of 0..100, no number twice on a board or in the draw
+pub fn bingo(boards: Range<usize>, drawn: RangeInclusive<usize>) -> impl Strategy<Value = String> {
+    let numbers = || Just((0..100u64).collect::<Vec<u64>>()).prop_shuffle();
+    let board = numbers().prop_map(|numbers| {
+        let rows: Vec<String> = numbers[..25].chunks(5).map(|row| row.iter().map(|n| format!("{:2}", n)).collect::<Vec<_>>().join(" ")).collect();
+        rows.join("\n")
+    });
+    (numbers(), drawn, vec(board, boards)).prop_map(|(numbers, drawn, boards)| {
+        let drawn: Vec<String> = numbers[..drawn.min(100)].iter().map(|n| n.to_string()).collect();
+        format!("{}\n\n{}\n", drawn.join(","), boards.join("\n\n"))
+    })
+}
+
+// day 5: horizontal, vertical and 45 degree lines of vents with both ends in 0..=max
+pub fn vents(count: Range<usize>, max: i64) -> impl Strategy<Value = String> {
+    let line = (0..=max, 0..=max, -1..=1i64, -1..=1i64, 0..=max).prop_map(move |(x, y, dx, dy, length)| {
+        // as long as it can be in that direction without leaving the area
+        let fits = |start: i64, d: i64| match d {
+            -1 => start,
+            1 => max - start,
+            _ => max,
+        };
+        let length = length.min(fits(x, dx)).min(fits(y, dy));
+        format!("{},{} -> {},{}", x, y, x + dx * length, y + dy * length)
+    });
+    vec(line, count).prop_map(lines)
+}
+
+// day 6: lanternfish timers
+pub fn lanternfish(count: Range<usize>) -> impl Strategy<Value = String> {
+    vec(0..=6u64, count).prop_map(|timers| timers.iter().map(|t| t.to_string()).collect::<Vec<_>>().join(",") + "\n")
+}
+
+// day 7: crab positions
+pub fn crabs(count: Range<usize>, max: u64) -> impl Strategy<Value = String> {
+    vec(0..=max, count).prop_map(|positions| positions.iter().map(|p| p.to_string()).collect::<Vec<_>>().join(",") + "\n")
+}
+
+const DIGIT_SEGMENTS: [&str; 10] = ["abcefg", "cf", "acdeg", "acdfg", "bcdf", "abdfg", "abdefg", "acf", "abcdefg", "abcdfg"];
+
+// day 8: every entry has its own mix-up of the wires and shows all ten digits in a random order
+pub fn seven_segment(count: Range<usize>) -> impl Strategy<Value = String> {
+    let entry = (
+        Just("abcdefg".chars().collect::<Vec<char>>()).prop_shuffle(),
+        Just((0..10).collect::<Vec<usize>>()).prop_shuffle(),
+        vec(0..10usize, 4),
+    )
+        .prop_map(|(wires, order, output)| {
+            let show = |digit: usize| DIGIT_SEGMENTS[digit].chars().map(|segment| wires[(segment as u8 - b'a') as usize]).collect::<String>();
+            let patterns: Vec<String> = order.into_iter().map(show).collect();
+            let output: Vec<String> = output.into_iter().map(show).collect();
+            format!("{} | {}", patterns.join(" "), output.join(" "))
+        });
+    vec(entry, count).prop_map(lines)
+}
+
+// days 9, 11 and 15: a rectangle of single digits
+pub fn digit_grid(width: Range<usize>, height: Range<usize>, digits: RangeInclusive<u8>) -> impl Strategy<Value = String> {
+    (width, height).prop_flat_map(move |(width, height)| {
+        let row = vec(digits.clone(), width).prop_map(|row| row.iter().map(|d| d.to_string()).collect::<String>());
+        vec(row, height).prop_map(lines)
+    })
+}
+
+// one complete chunk, like [<>({}){}[([])<>]]
+fn chunk(depth: u32) -> impl Strategy<Value = String> {
+    let brackets = || prop_oneof![Just(('(', ')')), Just(('[', ']')), Just(('{', '}')), Just(('<', '>'))];
+    brackets().prop_map(|(open, close)| format!("{}{}", open, close)).prop_recursive(depth, 64, 3, move |inner| {
+        (brackets(), vec(inner, 1..4)).prop_map(|((open, close), inside)| format!("{}{}{}", open, inside.concat(), close))
+    })
+}
+
+// day 10: lines of chunks that are cut off somewhere, but never corrupted
+pub fn navigation(count: Range<usize>, depth: u32) -> impl Strategy<Value = String> {
+    let line = (vec(chunk(depth), 1..4), any::<prop::sample::Index>()).prop_map(|(chunks, cut)| {
+        let line = chunks.concat();
+        line[..cut.index(line.len()) + 1].to_string()
+    });
+    vec(line, count).prop_map(lines)
+}
+
+// day 12: tunnels between start, end and a few small and big caves, never two big caves next
+// to each other, which would make the number of paths endless, and always one out of start
+pub fn caves(small: usize, big: usize, tunnels: Range<usize>) -> impl Strategy<Value = String> {
+    let mut caves = vec!["start".to_string(), "end".to_string()];
+    caves.extend((0..small.min(26)).map(|i| ((b'a' + i as u8) as char).to_string().repeat(2)));
+    caves.extend((0..big.min(26)).map(|i| ((b'A' + i as u8) as char).to_string().repeat(2)));
+    let is_big = |cave: &str| cave.chars().all(|c| c.is_ascii_uppercase());
+    let mut pairs = vec![];
+    for (index, from) in caves.iter().enumerate() {
+        for to in &caves[index + 1..] {
+            if !(is_big(from) && is_big(to)) {
+                pairs.push(format!("{}-{}", from, to));
+            }
+        }
+    }
+    let tunnels = tunnels.start.min(pairs.len())..tunnels.end.min(pairs.len() + 1);
+    let from_start: Vec<String> = pairs.iter().filter(|pair| pair.starts_with("start-")).cloned().collect();
+    (prop::sample::select(from_start), prop::sample::subsequence(pairs, tunnels)).prop_map(|(from_start, mut tunnels)| {
+        if !tunnels.iter().any(|tunnel| tunnel.starts_with("start-")) {
+            tunnels.insert(0, from_start);
+        }
+        lines(tunnels)
+    })
+}
+
+// day 13: dots on a (2 * half + 1) square folded in half along x and then along y, with no dot
+// on either fold
+pub fn paper(count: Range<usize>, half: usize) -> impl Strategy<Value = String> {
+    let coordinate = || (0..half * 2).prop_map(move |c| if c >= half { c + 1 } else { c });
+    vec((coordinate(), coordinate()), count).prop_map(move |dots| {
+        let dots: Vec<String> = dots.iter().map(|(x, y)| format!("{},{}", x, y)).collect();
+        format!("{}\n\nfold along x={}\nfold along y={}\n", dots.join("\n"), half, half)
+    })
+}
+
+// day 14: a template out of the first few letters and an insertion rule for every pair of them
+pub fn polymer(elements: usize, template: Range<usize>) -> impl Strategy<Value = String> {
+    let letters: Vec<char> = ('A'..='Z').take(elements.clamp(1, 26)).collect();
+    let pairs: Vec<String> = letters.iter().flat_map(|&a| letters.iter().map(move |&b| format!("{}{}", a, b))).collect();
+    let letter = prop::sample::select(letters);
+    (vec(letter.clone(), template), vec(letter, pairs.len())).prop_map(move |(template, inserts)| {
+        let rules: Vec<String> = pairs.iter().zip(inserts).map(|(pair, insert)| format!("{} -> {}", pair, insert)).collect();
+        format!("{}\n\n{}\n", template.into_iter().collect::<String>(), rules.join("\n"))
+    })
+}
+
+// day 16: a packet up to depth levels deep, comparisons always with the two sub-packets they need
+pub fn packet(depth: u32) -> impl Strategy<Value = day16::Packet> {
+    let literal = (0..8u8, any::<u32>()).prop_map(|(version, value)| day16::Packet {
+        version,
+        payload: day16::Payload::Literal(value as u64),
+    });
+    literal.prop_recursive(depth, 64, 4, |inner| {
+        let operator = |version: u8, op: day16::Operator, children: Vec<day16::Packet>| day16::Packet {
+            version,
+            payload: day16::Payload::Operator { op, children },
+        };
+        let arithmetic = prop_oneof![
+            Just(day16::Operator::Sum),
+            Just(day16::Operator::Product),
+            Just(day16::Operator::Minimum),
+            Just(day16::Operator::Maximum)
+        ];
+        let comparison = prop_oneof![Just(day16::Operator::GreaterThan), Just(day16::Operator::LessThan), Just(day16::Operator::EqualTo)];
+        prop_oneof![
+            (0..8u8, arithmetic, vec(inner.clone(), 1..4)).prop_map(move |(version, op, children)| operator(version, op, children)),
+            (0..8u8, comparison, inner.clone(), inner).prop_map(move |(version, op, lhs, rhs)| operator(version, op, vec![lhs, rhs])),
+        ]
+    })
+}
+
+pub fn transmission(depth: u32) -> impl Strategy<Value = String> {
+    packet(depth).prop_filter_map("packet doesn't encode", |packet| packet.to_hex().ok())
+}
+
+// day 17: a target area to the right of and below the launcher, within max of it
+pub fn target_area(max: i64) -> impl Strategy<Value = String> {
+    let max = max.max(2);
+    (1..max, 1..max)
+        .prop_flat_map(move |(x_begin, y_end)| (Just(x_begin), x_begin..=max, -max..=-y_end, Just(-y_end)))
+        .prop_map(|(x_begin, x_end, y_begin, y_end)| format!("target area: x={}..{}, y={}..{}", x_begin, x_end, y_begin, y_end))
+}
+
+// day 18: a snailfish number with pairs nested at most depth deep and regular numbers 0 to 9
+pub fn snailfish_number(depth: u32) -> impl Strategy<Value = String> {
+    let element = (0..10u8)
+        .prop_map(|n| n.to_string())
+        .prop_recursive(depth.saturating_sub(1), 64, 2, |inner| (inner.clone(), inner).prop_map(|(lhs, rhs)| format!("[{},{}]", lhs, rhs)));
+    (element.clone(), element).prop_map(|(lhs, rhs)| format!("[{},{}]", lhs, rhs))
+}
+
+// the homework is a list of numbers; the puzzle's never nest deeper than four
+pub fn snailfish_homework(count: Range<usize>) -> impl Strategy<Value = String> {
+    vec(snailfish_number(4), count).prop_map(lines)
+}
+
+// day 19: reports of probes within range of their scanner; random reports hardly ever overlap,
+// see overlapping_scanners for ones that do
+pub fn scanner_report(scanners: Range<usize>, probes: Range<usize>, range: i64) -> impl Strategy<Value = String> {
+    let probe = (-range..=range, -range..=range, -range..=range);
+    let scanner = vec(probe, probes);
+    vec(scanner, scanners).prop_map(|scanners| {
+        let reports: Vec<String> = scanners
+            .iter()
+            .enumerate()
+            .map(|(index, probes)| {
+                let probes: Vec<String> = probes.iter().map(|(x, y, z)| format!("{},{},{}", x, y, z)).collect();
+                format!("--- scanner {} ---\n{}", index, probes.join("\n"))
+            })
+            .collect();
+        reports.join("\n\n") + "\n"
+    })
+}
+
+// two reports of the same probes, the second from a scanner at some position within range / 2 of
+// the first, turned some way and listing them in another order. returns the input and where the
+// second scanner is.
+pub fn overlapping_scanners(probes: Range<usize>, range: i64) -> impl Strategy<Value = (String, day19::Vec3D)> {
+    let coordinates = |max: i64| (-max..=max, -max..=max, -max..=max).prop_map(|(x, y, z)| day19::Vec3D { x, y, z });
+    let probes = proptest::collection::hash_set(coordinates(range), probes.start.max(12)..probes.end.max(13));
+    (probes, coordinates(range / 2), prop::sample::select(day19::Rotation::all()))
+        .prop_flat_map(|(probes, position, rotation)| {
+            let seen: Vec<day19::Vec3D> = probes.iter().map(|p| rotation.apply(&p.subtract(&position))).collect();
+            (Just(probes.into_iter().collect::<Vec<_>>()), Just(seen).prop_shuffle(), Just(position))
+        })
+        .prop_map(|(probes, seen, position)| {
+            let report = |probes: &[day19::Vec3D]| probes.iter().map(|p| format!("{},{},{}", p.x, p.y, p.z)).collect::<Vec<_>>().join("\n");
+            (format!("--- scanner 0 ---\n{}\n\n--- scanner 1 ---\n{}\n", report(&probes), report(&seen)), position)
+        })
+}
+
+// day 20: an enhancement algorithm and an image of lit and dark pixels
+pub fn image(width: Range<usize>, height: Range<usize>) -> impl Strategy<Value = String> {
+    let pixel = || prop_oneof![Just('#'), Just('.')];
+    let enhancement = vec(pixel(), 512).prop_map(|pixels| pixels.into_iter().collect::<String>());
+    let image = (width, height).prop_flat_map(move |(width, height)| vec(vec(pixel(), width).prop_map(|row| row.into_iter().collect::<String>()), height));
+    (enhancement, image).prop_map(|(enhancement, rows)| format!("{}\n\n{}", enhancement, lines(rows)))
+}
+
+// day 21: where the two players start
+pub fn dirac_dice() -> impl Strategy<Value = String> {
+    (1..=10usize, 1..=10usize).prop_map(|(p1, p2)| format!("Player 1 starting position: {}\nPlayer 2 starting position: {}\n", p1, p2))
+}
+
+// day 22: reboot steps with cuboids of up to size cubes a side, inside -extent..=extent
+pub fn reboot_steps(count: Range<usize>, extent: i64, size: i64) -> impl Strategy<Value = String> {
+    let range = move || (-extent..=extent, 0..size.max(1)).prop_map(move |(begin, length)| (begin, (begin + length).min(extent)));
+    let step = (prop_oneof![Just("on"), Just("off")], range(), range(), range()).prop_map(|(state, x, y, z)| format!("{} x={}..{},y={}..{},z={}..{}", state, x.0, x.1, y.0, y.1, z.0, z.1));
+    vec(step, count).prop_map(lines)
+}
+
+// day 23: two of each amphipod somewhere in the rooms
+pub fn burrow() -> impl Strategy<Value = String> {
+    Just("AABBCCDD".chars().collect::<Vec<char>>()).prop_shuffle().prop_map(|a| {
+        format!(
+            "#############\n#...........#\n###{}#{}#{}#{}###\n  #{}#{}#{}#{}#\n  #########\n",
+            a[0], a[1], a[2], a[3], a[4], a[5], a[6], a[7]
+        )
+    })
+}
+
+// day 24: a MONAD checking pairs of digits. pushing blocks and the popping blocks that pair with
+// them nest like brackets; every pair is up to 8 apart, so a valid model number always exists.
+pub fn monad(pairs: RangeInclusive<usize>) -> impl Strategy<Value = String> {
+    // one bracket per block: true pushes and false pops
+    let nesting = pairs.prop_flat_map(|pairs| {
+        Just(vec![true; pairs]).prop_perturb(|opens, mut rng| {
+            let mut brackets = vec![];
+            let (mut open, mut unclosed) = (opens.len(), 0);
+            while open + unclosed > 0 {
+                let push = unclosed == 0 || (open > 0 && rng.next_u32() % 2 == 0);
+                brackets.push(push);
+                if push {
+                    open -= 1;
+                    unclosed += 1;
+                } else {
+                    unclosed -= 1;
+                }
+            }
+            brackets
+        })
+    });
+    nesting
+        .prop_flat_map(|brackets| {
+            let blocks = brackets.len();
+            (Just(brackets), vec((10..=16i64, 0..=15i64, -8..=8i64), blocks))
+        })
+        .prop_map(|(brackets, blocks)| {
+            let mut pushed = vec![];
+            let mut program = String::new();
+            for (push, (check, offset, difference)) in brackets.into_iter().zip(blocks) {
+                if push {
+                    program += &day24::block(1, check, offset);
+                    pushed.push(offset);
+                } else {
+                    // the popped digit plus its offset plus check has to be this digit
+                    let pushed_offset = pushed.pop().unwrap_or(0);
+                    program += &day24::block(26, difference - pushed_offset, offset);
+                }
+            }
+            program
+        })
+}
+
+// day 25: a sea floor of empty spots and both herds
+pub fn sea_floor(width: Range<usize>, height: Range<usize>) -> impl Strategy<Value = String> {
+    (width, height).prop_flat_map(|(width, height)| {
+        let row = vec(prop_oneof![Just('.'), Just('>'), Just('v')], width).prop_map(|row| row.into_iter().collect::<String>());
+        vec(row, height).prop_map(lines)
+    })
+}
+
+#[cfg(test)]
+use crate::{day10, day13, day15, day17, day18, day20, day21, day22, day4, error, puzzle};
+
+#[cfg(test)]
+fn parses(day: usize, input: &str) -> Result<(), TestCaseError> {
+    puzzle::solver(day, input)
+        .map(|_| ())
+        .map_err(|e| TestCaseError::fail(format!("day {} doesn't parse {:?}: {}", day, input, e)))
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(32))]
+
+    #[test]
+    fn test_gen_parses_days_1_to_9(
+        d1 in depths(1..50),
+        d2 in course(1..50),
+        d3 in diagnostic(5, 1..50),
+        d4 in bingo(1..5, 5..=100),
+        d5 in vents(1..50, 20),
+        d6 in lanternfish(1..50),
+        d7 in crabs(1..50, 100),
+        d8 in seven_segment(1..10),
+        d9 in digit_grid(1..20, 1..20, 0..=9),
+    ) {
+        for (day, input) in [(1, d1), (2, d2), (3, d3), (4, d4), (5, d5), (6, d6), (7, d7), (8, d8), (9, d9)] {
+            parses(day, &input)?;
+        }
+    }
+
+    #[test]
+    fn test_gen_parses_days_10_to_17(
+        d10 in navigation(1..10, 3),
+        d11 in digit_grid(10..11, 10..11, 0..=9),
+        d12 in caves(4, 2, 1..12),
+        d13 in paper(1..50, 7),
+        d14 in polymer(4, 2..10),
+        d15 in digit_grid(1..20, 1..20, 1..=9),
+        d16 in transmission(3),
+        d17 in target_area(50),
+    ) {
+        for (day, input) in [(10, d10), (11, d11), (12, d12), (13, d13), (14, d14), (15, d15), (16, d16), (17, d17)] {
+            parses(day, &input)?;
+        }
+    }
+
+    #[test]
+    fn test_gen_parses_days_18_to_25(
+        d18 in snailfish_homework(1..10),
+        d19 in scanner_report(1..4, 1..20, 1000),
+        d20 in image(1..10, 1..10),
+        d21 in dirac_dice(),
+        d22 in reboot_steps(1..20, 50, 20),
+        d23 in burrow(),
+        d24 in monad(1..=7),
+        d25 in sea_floor(1..10, 1..10),
+    ) {
+        for (day, input) in [(18, d18), (19, d19), (20, d20), (21, d21), (22, d22), (23, d23), (24, d24), (25, d25)] {
+            parses(day, &input)?;
+        }
+    }
+
+    #[test]
+    fn test_gen_bingo_everybody_wins(input in bingo(1..10, 100..=100)) {
+        let bingo = day4::parse_bingo(&input)?;
+        let boards = bingo.boards.len();
+        prop_assert_eq!(day4::play_bingo(bingo).winners.len(), boards);
+    }
+
+    #[test]
+    fn test_gen_navigation_is_never_corrupted(input in navigation(1..10, 4)) {
+        prop_assert_eq!(input.parse::<day10::Lines>()?.total_score_corrupt(), 0);
+    }
+
+    #[test]
+    fn test_gen_folds_commute(input in paper(1..100, 10)) {
+        let paper: day13::Paper = input.parse()?;
+        let along_x = day13::FoldInstruction::new(day13::FoldType::Vertical, 10);
+        let along_y = day13::FoldInstruction::new(day13::FoldType::Horizontal, 10);
+        prop_assert_eq!(paper.apply(&along_x)?.apply(&along_y)?.render(), paper.apply(&along_y)?.apply(&along_x)?.render());
+    }
+
+    #[test]
+    fn test_gen_risk_strategies_agree(input in digit_grid(1..15, 1..15, 1..=9)) {
+        let board: day15::Board = input.parse()?;
+        let (_, risk) = board.lowest_risk_path_with(day15::Strategy::BucketDijkstra)?;
+        for heuristic in [day15::Heuristic::None, day15::Heuristic::GridDistance] {
+            prop_assert_eq!(board.lowest_risk_path_with(day15::Strategy::AStar(heuristic))?.1, risk);
+        }
+    }
+
+    #[test]
+    fn test_gen_packets_round_trip(packet in packet(4)) {
+        let hex = packet.to_hex()?;
+        prop_assert_eq!(hex.parse::<day16::Packet>()?, packet);
+    }
+
+    #[test]
+    fn test_gen_trajectory_strategies_agree(input in target_area(40)) {
+        let target_area: day17::TargetArea = input.parse()?;
+        let start = day17::Pos::new(0, 0);
+        prop_assert_eq!(
+            target_area.max_height_with(start, day17::Strategy::Simulate),
+            target_area.max_height_with(start, day17::Strategy::ClosedForm)
+        );
+        prop_assert_eq!(
+            target_area.count_initial_velocities_with(start, day17::Strategy::Simulate),
+            target_area.count_initial_velocities_with(start, day17::Strategy::ClosedForm)
+        );
+    }
+
+    #[test]
+    fn test_gen_snailfish_round_trips(number in snailfish_number(4), homework in snailfish_homework(1..8)) {
+        prop_assert_eq!(number.parse::<day18::Element>()?.to_string(), number);
+        prop_assert_eq!(
+            day18::Element::new_with(&homework, day18::Strategy::Tree)?,
+            day18::Element::new_with(&homework, day18::Strategy::Flat)?
+        );
+    }
+
+    #[test]
+    fn test_gen_image_backends_agree(input in image(1..12, 1..12)) {
+        let sparse = input.parse::<day20::Image>()?.with_backend(day20::Backend::Sparse).enhance_n(2);
+        let dense = input.parse::<day20::Image>()?.with_backend(day20::Backend::Dense).enhance_n(2);
+        prop_assert_eq!(sparse.num_lit_pixels(), dense.num_lit_pixels());
+        prop_assert_eq!(sparse.render(), dense.render());
+    }
+
+    #[test]
+    fn test_gen_reboot_strategies_agree(input in reboot_steps(1..30, 30, 15)) {
+        let lit: Vec<u64> = day22::Strategy::all().iter().map(|&strategy| day22::Grid::new_with(&input, strategy).map(|grid| grid.num_lit())).collect::<Result<_, _>>()?;
+        prop_assert!(lit.iter().all(|&n| n == lit[0]), "{:?}", lit);
+    }
+
+    #[test]
+    fn test_gen_monad_has_model_numbers(input in monad(1..=7)) {
+        let monad: day24::Monad = input.parse()?;
+        prop_assert!(monad.smallest_model_number()? <= monad.largest_model_number()?);
+    }
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(4))]
+
+    #[test]
+    fn test_gen_quantum_strategies_agree(input in dirac_dice()) {
+        let game: day21::Game = input.parse()?;
+        let memoized = game.play_quantum_with(&day21::DiracDie, &day21::Rules::dirac(), day21::Strategy::Memoized)?;
+        let parallel = game.play_quantum_with(&day21::DiracDie, &day21::Rules::dirac(), day21::Strategy::Parallel)?;
+        prop_assert_eq!((memoized.p1_wins, memoized.p2_wins), (parallel.p1_wins, parallel.p2_wins));
+    }
+
+    #[test]
+    fn test_gen_overlapping_scanners_line_up((input, position) in overlapping_scanners(12..20, 400)) {
+        let game: day19::Game = input.parse()?;
+        let result = game.find_probes_and_scanners()?;
+        prop_assert_eq!(result.probes.len(), input.lines().filter(|line| line.contains(',')).count() / 2);
+        prop_assert_eq!(result.scanner_positions(), vec![day19::Vec3D { x: 0, y: 0, z: 0 }, position]);
+    }
+}
+
+#[test]
+fn test_gen_examples() -> Result<(), error::Error> {
+    // a fixed seed, so this shows what the generators make
+    let mut runner = proptest::test_runner::TestRunner::deterministic();
+    let mut sample = |strategy: BoxedStrategy<String>| strategy.new_tree(&mut runner).map(|tree| tree.current()).map_err(|e| error::Error::General(e.to_string()));
+    let input = sample(bingo(2..3, 10..=10).boxed())?;
+    assert_eq!(input.lines().count(), 1 + 1 + 5 + 1 + 5);
+    let input = sample(reboot_steps(3..4, 10, 5).boxed())?;
+    assert_eq!(input.lines().count(), 3);
+    assert!(input.lines().all(|line| line.starts_with("on x=") || line.starts_with("off x=")));
+    let input = sample(monad(7..=7).boxed())?;
+    assert_eq!(input.lines().filter(|line| line.starts_with("inp")).count(), 14);
+    Ok(())
+}
@@ -0,0 +1,229 @@
+use crate::error;
+
+use alloc::string::ToString;
+use alloc::vec::Vec;
+
+// a rectangle of cells stored row by row, addressed with signed coordinates so that stepping
+// off an edge is just another position that isn't on the grid
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+const ORTHOGONAL: [(i64, i64); 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+const ALL_AROUND: [(i64, i64); 8] = [(0, -1), (1, -1), (1, 0), (1, 1), (0, 1), (-1, 1), (-1, 0), (-1, -1)];
+
+impl<T: Clone> Grid<T> {
+    pub fn new(width: usize, height: usize, value: T) -> Self {
+        Grid {
+            width,
+            height,
+            cells: vec![value; width * height],
+        }
+    }
+
+    // changes the size and sets every cell to value, keeping the allocation
+    pub fn reset(&mut self, width: usize, height: usize, value: T) {
+        self.width = width;
+        self.height = height;
+        self.cells.clear();
+        self.cells.resize(width * height, value);
+    }
+}
+
+impl<T> Grid<T> {
+    pub fn from_vec(width: usize, height: usize, cells: Vec<T>) -> Result<Self, error::Error> {
+        if width.checked_mul(height) != Some(cells.len()) {
+            return Err(error::Error::General(format!("{} cells don't make a {}x{} grid", cells.len(), width, height)));
+        }
+        Ok(Grid { width, height, cells })
+    }
+
+    // one row per non-empty line, leading and trailing whitespace ignored
+    pub fn parse_with<F: Fn(char) -> Option<T>>(s: &str, cell: F) -> Result<Self, error::Error> {
+        let mut width = None;
+        let mut cells = vec![];
+        for line in s.lines().map(|l| l.trim()).filter(|l| !l.is_empty()) {
+            let len_before = cells.len();
+            for c in line.chars() {
+                cells.push(cell(c).ok_or_else(|| error::Error::Parse(format!("invalid cell: {:?}", c)))?);
+            }
+            if *width.get_or_insert(cells.len() - len_before) != cells.len() - len_before {
+                return Err(error::Error::Parse("grid must be a rectangle".to_string()));
+            }
+        }
+        match width {
+            Some(width) => Ok(Grid {
+                width,
+                height: cells.len() / width,
+                cells,
+            }),
+            None => Err(error::Error::Parse("grid must not be empty".to_string())),
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn contains(&self, x: i64, y: i64) -> bool {
+        x >= 0 && y >= 0 && x < self.width as i64 && y < self.height as i64
+    }
+
+    pub fn index(&self, x: i64, y: i64) -> Option<usize> {
+        if self.contains(x, y) {
+            Some(y as usize * self.width + x as usize)
+        } else {
+            None
+        }
+    }
+
+    pub fn position(&self, index: usize) -> (i64, i64) {
+        ((index % self.width) as i64, (index / self.width) as i64)
+    }
+
+    pub fn get(&self, x: i64, y: i64) -> Option<&T> {
+        self.index(x, y).map(|index| &self.cells[index])
+    }
+
+    pub fn get_mut(&mut self, x: i64, y: i64) -> Option<&mut T> {
+        self.index(x, y).map(move |index| &mut self.cells[index])
+    }
+
+    pub fn cells(&self) -> &[T] {
+        &self.cells
+    }
+
+    pub fn cells_mut(&mut self) -> &mut [T] {
+        &mut self.cells
+    }
+
+    // every position, row by row
+    pub fn positions(&self) -> impl Iterator<Item = (i64, i64)> {
+        let (width, height) = (self.width as i64, self.height as i64);
+        (0..height).flat_map(move |y| (0..width).map(move |x| (x, y)))
+    }
+
+    // up, right, down and left of a position, skipping those off the grid
+    pub fn neighbors4(&self, x: i64, y: i64) -> impl Iterator<Item = (i64, i64)> + '_ {
+        self.neighbors(x, y, &ORTHOGONAL)
+    }
+
+    // the eight positions around a position clockwise from up, skipping those off the grid
+    pub fn neighbors8(&self, x: i64, y: i64) -> impl Iterator<Item = (i64, i64)> + '_ {
+        self.neighbors(x, y, &ALL_AROUND)
+    }
+
+    fn neighbors<'a>(&'a self, x: i64, y: i64, offsets: &'static [(i64, i64)]) -> impl Iterator<Item = (i64, i64)> + 'a {
+        offsets.iter().map(move |&(dx, dy)| (x + dx, y + dy)).filter(move |&(x, y)| self.contains(x, y))
+    }
+
+    pub fn map<U, F: FnMut(&T) -> U>(&self, f: F) -> Grid<U> {
+        Grid {
+            width: self.width,
+            height: self.height,
+            cells: self.cells.iter().map(f).collect(),
+        }
+    }
+}
+
+impl Grid<u8> {
+    // a map of single digits, like the height and risk maps
+    pub fn parse_digits(s: &str) -> Result<Self, error::Error> {
+        Grid::parse_with(s, |c| c.to_digit(10).map(|d| d as u8))
+    }
+}
+
+impl<T> core::ops::Index<(i64, i64)> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, (x, y): (i64, i64)) -> &T {
+        match self.get(x, y) {
+            Some(cell) => cell,
+            None => panic!("{:?} is outside the {}x{} grid", (x, y), self.width, self.height),
+        }
+    }
+}
+
+impl<T> core::ops::IndexMut<(i64, i64)> for Grid<T> {
+    fn index_mut(&mut self, (x, y): (i64, i64)) -> &mut T {
+        let (width, height) = (self.width, self.height);
+        match self.get_mut(x, y) {
+            Some(cell) => cell,
+            None => panic!("{:?} is outside the {}x{} grid", (x, y), width, height),
+        }
+    }
+}
+
+// one line per row with the cells back to back, so a digit map prints the way it was parsed
+impl<T: core::fmt::Display> core::fmt::Display for Grid<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        for row in self.cells.chunks(self.width.max(1)) {
+            for cell in row {
+                write!(f, "{}", cell)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+// goes through from_vec so a snapshot with the wrong number of cells is an error and not a
+// grid that panics when indexed
+#[cfg(feature = "serde")]
+impl<'de, T: serde::Deserialize<'de>> serde::Deserialize<'de> for Grid<T> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(serde::Deserialize)]
+        struct Raw<T> {
+            width: usize,
+            height: usize,
+            cells: Vec<T>,
+        }
+
+        let raw = Raw::deserialize(deserializer)?;
+        Grid::from_vec(raw.width, raw.height, raw.cells).map_err(serde::de::Error::custom)
+    }
+}
+
+#[test]
+fn test_grid() -> Result<(), error::Error> {
+    let grid = Grid::parse_digits(
+        r#"
+        123
+        456"#,
+    )?;
+    assert_eq!((grid.width(), grid.height()), (3, 2));
+    assert_eq!(grid[(0, 0)], 1);
+    assert_eq!(grid[(2, 1)], 6);
+    assert_eq!(grid.get(3, 0), None);
+    assert_eq!(grid.get(-1, 0), None);
+    assert_eq!(grid.position(grid.index(1, 1).unwrap()), (1, 1));
+    assert_eq!(grid.positions().count(), 6);
+    assert_eq!(grid.to_string(), "123\n456\n");
+
+    assert_eq!(grid.neighbors4(0, 0).collect::<Vec<_>>(), vec![(1, 0), (0, 1)]);
+    assert_eq!(grid.neighbors4(1, 1).collect::<Vec<_>>(), vec![(1, 0), (2, 1), (0, 1)]);
+    assert_eq!(grid.neighbors8(1, 0).collect::<Vec<_>>(), vec![(2, 0), (2, 1), (1, 1), (0, 1), (0, 0)]);
+
+    let mut doubled = grid.map(|&d| d as u64 * 2);
+    doubled[(1, 0)] += 1;
+    assert_eq!(doubled.cells(), &[2, 5, 6, 8, 10, 12]);
+
+    assert!(Grid::parse_digits("12\n3").is_err());
+    assert!(Grid::parse_digits("1a").is_err());
+    assert!(Grid::parse_digits("\n\n").is_err());
+    assert!(Grid::from_vec(2, 2, vec![0; 3]).is_err());
+    // dimensions whose product doesn't fit a usize are a mismatch like any other
+    assert_eq!(
+        Grid::from_vec(usize::MAX, 2, vec![0; 2]),
+        Err(error::Error::General(format!("2 cells don't make a {}x2 grid", usize::MAX)))
+    );
+    Ok(())
+}
@@ -0,0 +1,118 @@
+use crate::error;
+
+use std::path::PathBuf;
+
+pub const SESSION_VAR: &str = "AOC_SESSION";
+pub const CACHE_DIR_VAR: &str = "AOC_CACHE_DIR";
+
+// puzzle inputs by day, read from <cache_dir>/input_dayN and downloaded there when missing.
+// the default cache dir is the current directory, where the inputs have always lived.
+pub struct Inputs {
+    cache_dir: PathBuf,
+    session: Option<String>,
+}
+
+impl Inputs {
+    pub fn new<P: Into<PathBuf>>(cache_dir: P) -> Self {
+        Inputs {
+            cache_dir: cache_dir.into(),
+            session: None,
+        }
+    }
+
+    pub fn from_env() -> Self {
+        let inputs = Inputs::new(std::env::var(CACHE_DIR_VAR).unwrap_or_else(|_| ".".to_string()));
+        match std::env::var(SESSION_VAR) {
+            Ok(session) => inputs.with_session(&session),
+            Err(_) => inputs,
+        }
+    }
+
+    pub fn with_session(mut self, session: &str) -> Self {
+        let session = session.trim();
+        self.session = if session.is_empty() { None } else { Some(session.to_string()) };
+        self
+    }
+
+    pub fn path(&self, day: usize) -> PathBuf {
+        self.cache_dir.join(format!("input_day{}", day))
+    }
+
+    pub fn load(&self, day: usize) -> Result<String, error::Error> {
+        if !(1..=25).contains(&day) {
+            return Err(error::Error::General(format!("there is no day {}", day)));
+        }
+
+        let path = self.path(day);
+        if path.exists() {
+            return Ok(std::fs::read_to_string(path)?);
+        }
+
+        let session = self
+            .session
+            .as_ref()
+            .ok_or_else(|| error::Error::Io(format!("{} not found and {} is not set", path.display(), SESSION_VAR)))?;
+        let input = download(day, session)?;
+        std::fs::create_dir_all(&self.cache_dir)?;
+        std::fs::write(path, &input)?;
+        Ok(input)
+    }
+}
+
+// everything the reader has, which has to be text
+pub fn read<R: std::io::Read>(mut reader: R) -> Result<String, error::Error> {
+    let mut input = String::new();
+    reader.read_to_string(&mut input)?;
+    Ok(input)
+}
+
+// a file, or stdin for "-"
+pub fn read_path(path: &str) -> Result<String, error::Error> {
+    if path == "-" {
+        read(std::io::stdin().lock())
+    } else {
+        Ok(std::fs::read_to_string(path)?)
+    }
+}
+
+#[cfg(feature = "download")]
+fn download(day: usize, session: &str) -> Result<String, error::Error> {
+    let url = format!("https://adventofcode.com/2021/day/{}/input", day);
+    let response = ureq::get(&url).set("Cookie", &format!("session={}", session)).call()?;
+    Ok(response.into_string()?)
+}
+
+#[cfg(not(feature = "download"))]
+fn download(day: usize, _session: &str) -> Result<String, error::Error> {
+    Err(error::Error::General(format!("cannot fetch the input for day {}, built without the download feature", day)))
+}
+
+#[test]
+fn test_inputs() -> Result<(), error::Error> {
+    let cache_dir = std::env::temp_dir().join(format!("aoc2021-inputs-{}", std::process::id()));
+    std::fs::create_dir_all(&cache_dir)?;
+    std::fs::write(cache_dir.join("input_day3"), "00100\n11110\n")?;
+
+    // cached inputs never need a session
+    let inputs = Inputs::new(&cache_dir);
+    assert_eq!(inputs.load(3)?, "00100\n11110\n");
+    assert!(matches!(inputs.load(4), Err(error::Error::Io(_))));
+    assert!(inputs.load(0).is_err());
+    assert!(inputs.load(26).is_err());
+
+    // the inputs checked in next to the crate work as a cache as well
+    assert_eq!(Inputs::new(".").load(1)?, std::fs::read_to_string("input_day1")?);
+
+    std::fs::remove_dir_all(&cache_dir)?;
+    Ok(())
+}
+
+#[test]
+fn test_read() -> Result<(), error::Error> {
+    assert_eq!(read("1\n2\n".as_bytes())?, "1\n2\n");
+    assert_eq!(read(std::io::empty())?, "");
+    assert!(matches!(read(&[0xc3, 0x28][..]), Err(error::Error::Io(_))));
+    assert_eq!(read_path("input_day1")?, read(std::fs::File::open("input_day1")?)?);
+    assert!(matches!(read_path("no_such_input"), Err(error::Error::Io(_))));
+    Ok(())
+}
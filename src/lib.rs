@@ -1,7 +1,17 @@
 // I don't agree with changing &Vec<String> to &[String], yet.
 #![allow(clippy::ptr_arg)]
+// the std feature brings in files, threads and clocks, the solvers themselves only need alloc
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[macro_use]
+extern crate alloc;
 
 // pub mod so_question;
+#[cfg(feature = "std")]
+pub mod bench;
+#[cfg(feature = "std")]
+pub mod cache;
+mod collections;
 pub mod day1;
 pub mod day10;
 pub mod day11;
@@ -16,6 +26,11 @@ pub mod day19;
 pub mod day2;
 pub mod day20;
 pub mod day21;
+pub mod day22;
+#[cfg(feature = "std")]
+pub mod day23;
+pub mod day24;
+pub mod day25;
 pub mod day3;
 pub mod day4;
 pub mod day5;
@@ -25,3 +40,18 @@ pub mod day8;
 pub mod day9;
 
 pub mod error;
+#[cfg(any(test, feature = "gen"))]
+pub mod gen;
+pub mod grid;
+#[cfg(feature = "std")]
+pub mod input;
+pub mod ocr;
+mod par;
+pub mod parse;
+pub mod puzzle;
+#[cfg(feature = "serde")]
+pub mod snapshot;
+#[cfg(feature = "std")]
+pub mod verify;
+#[cfg(feature = "wasm")]
+pub mod wasm;
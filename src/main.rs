@@ -0,0 +1,333 @@
+use advent_of_code_2021::bench;
+use advent_of_code_2021::cache;
+use advent_of_code_2021::error;
+use advent_of_code_2021::input;
+use advent_of_code_2021::puzzle;
+use advent_of_code_2021::verify;
+
+use std::time::{Duration, Instant};
+
+const USAGE: &str = "usage: aoc2021 run (--all | --day <1-25> [--part <1|2>] [--input <path>]) [--json | --example] [--no-cache]
+       aoc2021 bench (--all | --day <1-25>) [--runs <n>]
+       aoc2021 bench --day 18 --strategies [--runs <n>]
+       aoc2021 verify [--answers <path>] [--no-cache]
+       aoc2021 cache clear [--day <1-25>]
+a path of - reads from stdin";
+
+struct RunArgs {
+    // every day when there is none
+    day: Option<usize>,
+    part: Option<usize>,
+    input: Option<String>,
+    json: bool,
+    // check the answers for the examples in the puzzle text instead
+    example: bool,
+    // solve everything again, without reading or writing the answer cache
+    no_cache: bool,
+}
+
+fn parse_run_args(args: &[String]) -> Result<RunArgs, error::Error> {
+    let mut all = false;
+    let mut day = None;
+    let mut part = None;
+    let mut input = None;
+    let mut json = false;
+    let mut example = false;
+    let mut no_cache = false;
+
+    let mut args = args.iter();
+    while let Some(flag) = args.next() {
+        match flag.as_str() {
+            "--all" => all = true,
+            "--json" => json = true,
+            "--example" => example = true,
+            "--no-cache" => no_cache = true,
+            _ => {
+                let value = args.next().ok_or_else(|| error::Error::Parse(format!("missing value for {}", flag)))?;
+                match flag.as_str() {
+                    "--day" => day = Some(value.parse()?),
+                    "--part" => match value.parse()? {
+                        part_number @ (1 | 2) => part = Some(part_number),
+                        part_number => return Err(error::Error::Parse(format!("no part {}, expected 1 or 2", part_number))),
+                    },
+                    "--input" => input = Some(value.clone()),
+                    _ => return Err(error::Error::Parse(format!("unknown argument {:?}", flag))),
+                }
+            }
+        }
+    }
+
+    if example && (json || input.is_some()) {
+        return Err(error::Error::Parse("--example doesn't go with --json or --input".to_string()));
+    }
+
+    match (all, day, &part, &input) {
+        (true, None, None, None) | (false, Some(_), _, _) => Ok(RunArgs {
+            day,
+            part,
+            input,
+            json,
+            example,
+            no_cache,
+        }),
+        (true, _, _, _) => Err(error::Error::Parse("--all runs both parts of every day with the default inputs".to_string())),
+        (false, None, _, _) => Err(error::Error::Parse("expected either --all or --day".to_string())),
+    }
+}
+
+#[cfg(feature = "json")]
+fn print_json(results: &[puzzle::PartResult]) -> Result<(), error::Error> {
+    println!("{}", puzzle::to_json(results)?);
+    Ok(())
+}
+
+#[cfg(not(feature = "json"))]
+fn print_json(_results: &[puzzle::PartResult]) -> Result<(), error::Error> {
+    Err(error::Error::General("built without the json feature".to_string()))
+}
+
+fn print_result(result: &puzzle::PartResult) {
+    let elapsed = if result.cached {
+        "cached".to_string()
+    } else {
+        format!("{:?}", Duration::from_micros(result.micros as u64))
+    };
+    if result.answer.is_multiline() {
+        println!("day {} part {} ({}):\n{}", result.day, result.part, elapsed, result.answer);
+    } else {
+        println!("day {} part {}: {} ({})", result.day, result.part, result.answer, elapsed);
+    }
+}
+
+fn answer_cache(no_cache: bool) -> Option<cache::AnswerCache> {
+    if no_cache {
+        None
+    } else {
+        Some(cache::AnswerCache::from_env())
+    }
+}
+
+fn run(args: &RunArgs) -> Result<(), error::Error> {
+    if args.example {
+        return run_examples(args.day, args.part);
+    }
+    let cache = answer_cache(args.no_cache);
+    let day = match args.day {
+        Some(day) => day,
+        None => return run_all(args.json, cache.as_ref()),
+    };
+    let input = match &args.input {
+        Some(path) => input::read_path(path)?,
+        None => input::Inputs::from_env().load(day)?,
+    };
+    let parts = match args.part {
+        Some(part) => vec![part],
+        None => vec![1, 2],
+    };
+
+    // nothing to parse when the cache has it all
+    if let Some(results) = cache.as_ref().and_then(|cache| cache.lookup(day, &parts, &input)) {
+        if args.json {
+            return print_json(&results);
+        }
+        results.iter().for_each(print_result);
+        return Ok(());
+    }
+
+    let start = Instant::now();
+    let solver = puzzle::solver(day, &input)?;
+    let parse_elapsed = start.elapsed();
+
+    let results = parts.into_iter().map(|part| puzzle::solve_part(solver.as_ref(), day, part)).collect::<Result<Vec<_>, _>>()?;
+    if let Some(cache) = &cache {
+        cache.store(&input, &results);
+    }
+
+    if args.json {
+        return print_json(&results);
+    }
+    println!("day {} parse ({:?})", day, parse_elapsed);
+    results.iter().for_each(print_result);
+    Ok(())
+}
+
+fn run_all(json: bool, cache: Option<&cache::AnswerCache>) -> Result<(), error::Error> {
+    let run = puzzle::run_all(&input::Inputs::from_env(), cache);
+    let mut results = vec![];
+    for day in &run.days {
+        match &day.parts {
+            Ok(parts) => results.extend(parts.iter().cloned()),
+            Err(e) => eprintln!("day {} failed: {:?}", day.day, e),
+        }
+    }
+
+    if json {
+        return print_json(&results);
+    }
+    results.iter().for_each(print_result);
+    println!("total {:?}, {:?} one day at a time", run.elapsed, run.serial_time());
+    Ok(())
+}
+
+fn run_examples(day: Option<usize>, part: Option<usize>) -> Result<(), error::Error> {
+    let days: Vec<usize> = match day {
+        Some(day) => vec![day],
+        None => puzzle::DAYS.collect(),
+    };
+    let mut checks = vec![];
+    for day in days {
+        let day_checks = verify::verify_examples(day)?;
+        if day_checks.is_empty() {
+            println!("day {} has no examples", day);
+        }
+        checks.extend(day_checks.into_iter().filter(|check| part.is_none() || part == Some(check.expected.part)));
+    }
+    report(&checks, "examples")
+}
+
+struct BenchArgs {
+    day: Option<usize>,
+    runs: usize,
+    // day 18 reduced every way it can be instead of the day's parts
+    strategies: bool,
+}
+
+fn parse_bench_args(args: &[String]) -> Result<BenchArgs, error::Error> {
+    let mut all = false;
+    let mut day = None;
+    let mut runs = 10;
+    let mut strategies = false;
+
+    let mut args = args.iter();
+    while let Some(flag) = args.next() {
+        if flag == "--all" {
+            all = true;
+            continue;
+        }
+        if flag == "--strategies" {
+            strategies = true;
+            continue;
+        }
+        let value = args.next().ok_or_else(|| error::Error::Parse(format!("missing value for {}", flag)))?;
+        match flag.as_str() {
+            "--day" => day = Some(value.parse()?),
+            "--runs" => runs = value.parse()?,
+            _ => return Err(error::Error::Parse(format!("unknown argument {:?}", flag))),
+        }
+    }
+
+    if strategies && day != Some(18) {
+        return Err(error::Error::Parse("--strategies only goes with --day 18".to_string()));
+    }
+    match (all, day) {
+        (true, None) | (false, Some(_)) => Ok(BenchArgs { day, runs, strategies }),
+        _ => Err(error::Error::Parse("expected either --all or --day".to_string())),
+    }
+}
+
+fn bench(args: &BenchArgs) -> Result<(), error::Error> {
+    let inputs = input::Inputs::from_env();
+    if args.strategies {
+        print!("{}", bench::strategy_summary(&bench::bench_day18_strategies(&inputs.load(18)?, args.runs)?));
+        return Ok(());
+    }
+    let mut benches = vec![];
+    match args.day {
+        Some(day) => benches.push(bench::bench_day(day, &inputs.load(day)?, args.runs)?),
+        None => {
+            for (day, result) in puzzle::DAYS.zip(bench::bench_all(&inputs, args.runs)) {
+                match result {
+                    Ok(bench) => benches.push(bench),
+                    Err(e) => eprintln!("day {} skipped: {:?}", day, e),
+                }
+            }
+        }
+    }
+    print!("{}", bench::summary(&benches));
+    Ok(())
+}
+
+struct VerifyArgs {
+    path: String,
+    no_cache: bool,
+}
+
+fn parse_verify_args(args: &[String]) -> Result<VerifyArgs, error::Error> {
+    let (args, no_cache) = match args.split_last() {
+        Some((last, rest)) if last == "--no-cache" => (rest, true),
+        _ => (args, false),
+    };
+    let path = match args {
+        [] => "answers.toml".to_string(),
+        [flag, path] if flag == "--answers" => path.clone(),
+        _ => return Err(error::Error::Parse(format!("unexpected arguments {:?}", args))),
+    };
+    Ok(VerifyArgs { path, no_cache })
+}
+
+fn verify(args: &VerifyArgs) -> Result<(), error::Error> {
+    let expected = verify::parse_answers(&input::read_path(&args.path)?)?;
+    report(&verify::verify(&expected, &input::Inputs::from_env(), answer_cache(args.no_cache).as_ref()), "answers")
+}
+
+// the answers of one day or of all of them, for after a solution changed
+fn parse_cache_args(args: &[String]) -> Result<Option<usize>, error::Error> {
+    match args {
+        [command] if command == "clear" => Ok(None),
+        [command, flag, day] if command == "clear" && flag == "--day" => Ok(Some(day.parse()?)),
+        _ => Err(error::Error::Parse(format!("unexpected arguments {:?}", args))),
+    }
+}
+
+fn clear_cache(day: Option<usize>) -> Result<(), error::Error> {
+    cache::AnswerCache::from_env().clear(day)
+}
+
+// prints the checks that didn't match and fails when there are any
+fn report(checks: &[verify::Check], what: &str) -> Result<(), error::Error> {
+    let mut num_bad = 0;
+    for check in checks {
+        let (day, part) = (check.expected.day, check.expected.part);
+        match &check.outcome {
+            verify::Outcome::Match => continue,
+            verify::Outcome::Mismatch(actual) => println!("day {} part {}: expected {:?}, got {:?}", day, part, check.expected.answer, actual.to_string()),
+            verify::Outcome::Failed(e) => println!("day {} part {}: {:?}", day, part, e),
+        }
+        num_bad += 1;
+    }
+    println!("{} of {} {} match", checks.len() - num_bad, checks.len(), what);
+    if num_bad > 0 {
+        return Err(error::Error::General(format!("{} {} don't match", num_bad, what)));
+    }
+    Ok(())
+}
+
+// RUST_LOG=debug shows what the solvers are doing, on stderr so the answers stay as they are
+#[cfg(feature = "trace")]
+fn init_tracing() {
+    tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .with_writer(std::io::stderr)
+        .init();
+}
+
+#[cfg(not(feature = "trace"))]
+fn init_tracing() {}
+
+fn main() {
+    init_tracing();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let result = match args.first().map(|s| s.as_str()) {
+        Some("run") => parse_run_args(&args[1..]).and_then(|args| run(&args)),
+        Some("verify") => parse_verify_args(&args[1..]).and_then(|args| verify(&args)),
+        Some("cache") => parse_cache_args(&args[1..]).and_then(clear_cache),
+        Some("bench") => parse_bench_args(&args[1..]).and_then(|args| bench(&args)),
+        _ => Err(error::Error::General("missing subcommand".to_string())),
+    };
+
+    if let Err(e) = result {
+        eprintln!("error: {:?}", e);
+        eprintln!("{}", USAGE);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,83 @@
+use crate::error;
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+pub const GLYPH_WIDTH: usize = 4;
+pub const GLYPH_HEIGHT: usize = 6;
+
+// the capitals the puzzles draw, as far as they have shown up, with '#' for a lit pixel
+const GLYPHS: [(char, [&str; GLYPH_HEIGHT]); 16] = [
+    ('A', [".##.", "#..#", "#..#", "####", "#..#", "#..#"]),
+    ('B', ["###.", "#..#", "###.", "#..#", "#..#", "###."]),
+    ('C', [".##.", "#..#", "#...", "#...", "#..#", ".##."]),
+    ('E', ["####", "#...", "###.", "#...", "#...", "####"]),
+    ('F', ["####", "#...", "###.", "#...", "#...", "#..."]),
+    ('G', [".##.", "#..#", "#...", "#.##", "#..#", ".###"]),
+    ('H', ["#..#", "#..#", "####", "#..#", "#..#", "#..#"]),
+    ('J', ["..##", "...#", "...#", "...#", "#..#", ".##."]),
+    ('K', ["#..#", "#.#.", "##..", "#.#.", "#.#.", "#..#"]),
+    ('L', ["#...", "#...", "#...", "#...", "#...", "####"]),
+    ('O', [".##.", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('P', ["###.", "#..#", "#..#", "###.", "#...", "#..."]),
+    ('R', ["###.", "#..#", "#..#", "###.", "#.#.", "#..#"]),
+    ('S', [".###", "#...", "#...", ".##.", "...#", "###."]),
+    ('U', ["#..#", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('Z', ["####", "...#", "..#.", ".#..", "#...", "####"]),
+];
+
+fn is_lit(c: char) -> bool {
+    !matches!(c, ' ' | '.')
+}
+
+// reads letters drawn next to each other with a column of space between them. anything but a
+// space or a dot is lit, so both '#'/'.' drawings and day13's 'X'/' ' work.
+pub fn decode(s: &str) -> Result<String, error::Error> {
+    let rows: Vec<Vec<bool>> = s.lines().filter(|l| !l.trim().is_empty()).map(|l| l.chars().map(is_lit).collect()).collect();
+    if rows.len() != GLYPH_HEIGHT {
+        return Err(error::Error::Parse(format!("letters are {} rows high, got {}", GLYPH_HEIGHT, rows.len())));
+    }
+    let lit = |x: usize, y: usize| rows[y].get(x).copied().unwrap_or(false);
+
+    let width = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+    let mut letters = String::new();
+    for left in (0..width).step_by(GLYPH_WIDTH + 1) {
+        if (0..GLYPH_HEIGHT).any(|y| lit(left + GLYPH_WIDTH, y)) {
+            return Err(error::Error::Parse(format!("no space after the letter at column {}", left)));
+        }
+        let glyph = GLYPHS
+            .iter()
+            .find(|(_, pixels)| pixels.iter().enumerate().all(|(y, row)| row.chars().enumerate().all(|(x, c)| is_lit(c) == lit(left + x, y))))
+            .ok_or_else(|| error::Error::Parse(format!("no letter looks like the one at column {}", left)))?;
+        letters.push(glyph.0);
+    }
+    Ok(letters)
+}
+
+#[test]
+fn test_decode() -> Result<(), error::Error> {
+    let drawing = r#"
+.##..###...##.
+#..#.#..#.#..#
+#..#.###..#...
+####.#..#.#...
+#..#.#..#.#..#
+#..#.###...##.
+"#;
+    assert_eq!(decode(drawing)?, "ABC");
+
+    // every glyph reads back as itself
+    for (c, pixels) in GLYPHS {
+        assert_eq!(decode(&pixels.join("\n"))?, c.to_string());
+    }
+
+    // trailing spaces are often gone, like after the last column of an R
+    assert_eq!(decode("X  X\nX  X\nXXXX\nX  X\nX  X\nX  X")?, "H");
+    assert_eq!(decode("XXX\nX  X\nX  X\nXXX\nX X\nX  X")?, "R");
+
+    assert!(decode("").is_err());
+    assert!(decode("####\n#...\n###.\n#...\n#...").is_err());
+    assert!(decode("#..#\n#..#\n#..#\n#..#\n#..#\n####").is_err());
+    assert!(decode("#####\n#....\n###..\n#....\n#....\n####.").is_err());
+    Ok(())
+}
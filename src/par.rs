@@ -0,0 +1,30 @@
+// rayon's parallel iterators when there are threads to run them on. without std the same calls
+// go through a plain iterator, one item after the other, so the solvers don't need two versions.
+
+#[cfg(feature = "std")]
+pub use rayon::prelude::*;
+
+#[cfg(not(feature = "std"))]
+pub trait IntoParallelIterator: IntoIterator + Sized {
+    fn into_par_iter(self) -> Self::IntoIter {
+        self.into_iter()
+    }
+}
+
+#[cfg(not(feature = "std"))]
+impl<I: IntoIterator> IntoParallelIterator for I {}
+
+// the rayon methods the solvers use that Iterator calls something else
+#[cfg(not(feature = "std"))]
+pub trait ParallelIterator: Iterator + Sized {
+    fn flat_map_iter<U: IntoIterator, F: FnMut(Self::Item) -> U>(self, f: F) -> core::iter::FlatMap<Self, U, F> {
+        self.flat_map(f)
+    }
+
+    fn find_map_first<B, F: FnMut(Self::Item) -> Option<B>>(mut self, f: F) -> Option<B> {
+        self.find_map(f)
+    }
+}
+
+#[cfg(not(feature = "std"))]
+impl<I: Iterator> ParallelIterator for I {}
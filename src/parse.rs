@@ -0,0 +1,151 @@
+use crate::error;
+
+use alloc::vec::Vec;
+
+// small helpers for the puzzle inputs. errors say where in the line things went wrong as
+// "column N: ...", and on_line puts the line in front of that for inputs with many lines.
+
+// the non-empty lines trimmed, with their line numbers counting from 1
+pub fn lines(s: &str) -> impl Iterator<Item = (usize, &str)> {
+    s.lines().enumerate().map(|(index, line)| (index + 1, line.trim())).filter(|(_, line)| !line.is_empty())
+}
+
+pub fn on_line<T>(number: usize, result: Result<T, error::Error>) -> Result<T, error::Error> {
+    result.map_err(|e| match e {
+        error::Error::Parse(message) => error::Error::Parse(format!("line {}, {}", number, message)),
+        e => e,
+    })
+}
+
+fn column(s: &str, rest: &str) -> usize {
+    s.len() - rest.len() + 1
+}
+
+// the signed integer at the start of s and what comes after it
+fn leading_int(s: &str) -> Option<(i64, &str)> {
+    let sign_len = if s.starts_with('-') || s.starts_with('+') { 1 } else { 0 };
+    let len = sign_len + s[sign_len..].bytes().take_while(|b| b.is_ascii_digit()).count();
+    if len == sign_len {
+        return None;
+    }
+    Some((s[..len].parse().ok()?, &s[len..]))
+}
+
+// the integers in s where the pattern has a {}, everything else has to match exactly, e.g.
+// scan::<2>("x=-3..7", "x={}..{}") is [-3, 7]
+pub fn scan<const N: usize>(s: &str, pattern: &str) -> Result<[i64; N], error::Error> {
+    let literals: Vec<&str> = pattern.split("{}").collect();
+    if literals.len() != N + 1 {
+        return Err(error::Error::General(format!("{:?} doesn't have {} numbers in it", pattern, N)));
+    }
+
+    let mut values = [0; N];
+    let mut rest = s;
+    for (index, literal) in literals.iter().enumerate() {
+        rest = rest
+            .strip_prefix(literal)
+            .ok_or_else(|| error::Error::Parse(format!("column {}: expected {:?} in {:?}", column(s, rest), literal, s)))?;
+        if index < N {
+            let (value, after) = leading_int(rest).ok_or_else(|| error::Error::Parse(format!("column {}: expected a number in {:?}", column(s, rest), s)))?;
+            values[index] = value;
+            rest = after;
+        }
+    }
+    if !rest.is_empty() {
+        return Err(error::Error::Parse(format!("column {}: unexpected {:?} in {:?}", column(s, rest), rest, s)));
+    }
+    Ok(values)
+}
+
+// every signed integer in s in order, whatever is around them
+pub fn ints(s: &str) -> Vec<i64> {
+    let mut values = vec![];
+    let mut rest = s;
+    while !rest.is_empty() {
+        // a dash only counts as a sign right in front of a digit
+        match leading_int(rest) {
+            Some((value, after)) => {
+                values.push(value);
+                rest = after;
+            }
+            None => rest = &rest[rest.chars().next().map_or(1, |c| c.len_utf8())..],
+        }
+    }
+    values
+}
+
+// s split at every separator with each piece parsed, e.g. split::<u64>("3,4,3", ",")
+pub fn split<T>(s: &str, separator: &str) -> Result<Vec<T>, error::Error>
+where
+    T: core::str::FromStr,
+    T::Err: core::fmt::Display,
+{
+    let mut values = vec![];
+    let mut start = 0;
+    for piece in s.split(separator) {
+        let value = piece.trim().parse().map_err(|e| error::Error::Parse(format!("column {}: {} in {:?}", start + 1, e, piece)))?;
+        values.push(value);
+        start += piece.len() + separator.len();
+    }
+    Ok(values)
+}
+
+// the value right after the label, up to the next whitespace or comma, e.g.
+// labeled::<u64>("Player 1 starting position: 4", "starting position:") is 4
+pub fn labeled<T>(s: &str, label: &str) -> Result<T, error::Error>
+where
+    T: core::str::FromStr,
+    T::Err: core::fmt::Display,
+{
+    let start = s.find(label).ok_or_else(|| error::Error::Parse(format!("column 1: expected {:?} in {:?}", label, s)))? + label.len();
+    let rest = &s[start..];
+    let value_start = start + (rest.len() - rest.trim_start().len());
+    let value = s[value_start..].split(|c: char| c.is_whitespace() || c == ',').next().unwrap_or("");
+    value.parse().map_err(|e| error::Error::Parse(format!("column {}: {} in {:?}", value_start + 1, e, s)))
+}
+
+#[test]
+fn test_scan() -> Result<(), error::Error> {
+    assert_eq!(scan::<3>("404,-588,-901", "{},{},{}")?, [404, -588, -901]);
+    assert_eq!(scan::<4>("target area: x=20..30, y=-10..-5", "target area: x={}..{}, y={}..{}")?, [20, 30, -10, -5]);
+    assert_eq!(scan::<0>("on", "on")?, [0i64; 0]);
+
+    let error = |result: Result<[i64; 2], error::Error>| match result {
+        Err(error::Error::Parse(message)) => message,
+        _ => panic!("expected a parse error"),
+    };
+    assert_eq!(error(scan("x=1..y", "x={}..{}")), "column 6: expected a number in \"x=1..y\"");
+    assert_eq!(error(scan("x=1,2", "x={}..{}")), "column 4: expected \"..\" in \"x=1,2\"");
+    assert_eq!(error(scan("x=1..2!", "x={}..{}")), "column 7: unexpected \"!\" in \"x=1..2!\"");
+    assert!(scan::<1>("99999999999999999999", "{}").is_err());
+    assert!(matches!(scan::<3>("1,2", "{},{}"), Err(error::Error::General(_))));
+    Ok(())
+}
+
+#[test]
+fn test_lines() -> Result<(), error::Error> {
+    let numbered: Vec<(usize, &str)> = lines("\n  a\n\nb  \n").collect();
+    assert_eq!(numbered, vec![(2, "a"), (4, "b")]);
+
+    let parsed: Result<Vec<[i64; 1]>, error::Error> = lines("1\n2\nthree").map(|(number, line)| on_line(number, scan(line, "{}"))).collect();
+    assert_eq!(parsed, Err(error::Error::Parse("line 3, column 1: expected a number in \"three\"".to_string())));
+    assert_eq!(on_line::<()>(1, Err(error::Error::Io("gone".to_string()))), Err(error::Error::Io("gone".to_string())));
+    Ok(())
+}
+
+#[test]
+fn test_ints_split_labeled() -> Result<(), error::Error> {
+    assert_eq!(ints("target area: x=20..30, y=-10..-5"), vec![20, 30, -10, -5]);
+    assert_eq!(ints("on x=-1..+2 - 3"), vec![-1, 2, 3]);
+    assert_eq!(ints("no numbers"), Vec::<i64>::new());
+
+    assert_eq!(split::<u64>("3,4, 3,1,2", ",")?, vec![3, 4, 3, 1, 2]);
+    assert_eq!(split::<u64>("3,4,x", ","), Err(error::Error::Parse("column 5: invalid digit found in string in \"x\"".to_string())));
+
+    assert_eq!(labeled::<u64>("Player 1 starting position: 4", "starting position:")?, 4);
+    assert_eq!(labeled::<i64>("x=-3, y=4", "y=")?, 4);
+    assert_eq!(labeled::<i64>("x=-3, y=4", "x=")?, -3);
+    assert!(labeled::<u64>("Player 1 starting position:", "starting position:").is_err());
+    assert!(labeled::<u64>("Player 1", "starting position:").is_err());
+    Ok(())
+}
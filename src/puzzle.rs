@@ -0,0 +1,367 @@
+use crate::error;
+use crate::*;
+
+#[cfg(feature = "std")]
+use crate::par::*;
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::time::{Duration, Instant};
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "json", derive(serde::Serialize), serde(untagged))]
+pub enum Answer {
+    Int(i128),
+    UInt(u128),
+    Text(String),
+    // several lines that spell out the answer, like day13's folded paper
+    Grid(String),
+}
+
+macro_rules! answer_from {
+    ($variant:ident, $wide:ty, $($from:ty),*) => {
+        $(impl From<$from> for Answer {
+            fn from(value: $from) -> Self {
+                Answer::$variant(value as $wide)
+            }
+        })*
+    };
+}
+
+answer_from!(Int, i128, i8, i16, i32, i64, i128, isize);
+answer_from!(UInt, u128, u8, u16, u32, u64, u128, usize);
+
+impl From<String> for Answer {
+    fn from(value: String) -> Self {
+        Answer::Text(value)
+    }
+}
+
+impl From<&str> for Answer {
+    fn from(value: &str) -> Self {
+        Answer::Text(value.to_string())
+    }
+}
+
+impl Answer {
+    pub fn is_multiline(&self) -> bool {
+        matches!(self, Answer::Grid(_))
+    }
+}
+
+impl core::fmt::Display for Answer {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Answer::Int(value) => write!(f, "{}", value),
+            Answer::UInt(value) => write!(f, "{}", value),
+            Answer::Text(s) | Answer::Grid(s) => write!(f, "{}", s),
+        }
+    }
+}
+
+// an input from the puzzle text with the answers it gives for part 1 and 2, None where the text
+// doesn't give one
+pub type Example = (&'static str, Option<&'static str>, Option<&'static str>);
+
+// one day of the calendar: parse the input once, then answer both parts from it
+pub trait Puzzle {
+    fn parse(input: &str) -> Result<Self, error::Error>
+    where
+        Self: Sized;
+
+    // parse for an input that isn't in a string yet, like one piped in on stdin
+    #[cfg(feature = "std")]
+    fn from_reader<R: std::io::Read>(reader: R) -> Result<Self, error::Error>
+    where
+        Self: Sized,
+    {
+        Self::parse(&input::read(reader)?)
+    }
+
+    fn part1(&self) -> Result<Answer, error::Error>;
+
+    fn part2(&self) -> Result<Answer, error::Error>;
+
+    fn examples() -> Vec<Example>
+    where
+        Self: Sized,
+    {
+        vec![]
+    }
+}
+
+pub const DAYS: core::ops::RangeInclusive<usize> = 1..=25;
+
+fn boxed<P: Puzzle + 'static>(input: &str) -> Result<Box<dyn Puzzle>, error::Error> {
+    Ok(Box::new(P::parse(input)?))
+}
+
+// what the registry knows about a day, without an input to parse yet
+struct Entry {
+    solver: fn(&str) -> Result<Box<dyn Puzzle>, error::Error>,
+    examples: fn() -> Vec<Example>,
+}
+
+fn entry<P: Puzzle + 'static>() -> Entry {
+    Entry {
+        solver: boxed::<P>,
+        examples: P::examples,
+    }
+}
+
+fn lookup(day: usize) -> Result<Entry, error::Error> {
+    match day {
+        1 => Ok(entry::<day1::Measurements>()),
+        2 => Ok(entry::<day2::Course>()),
+        3 => Ok(entry::<day3::Report>()),
+        4 => Ok(entry::<day4::Bingo>()),
+        5 => Ok(entry::<day5::Vents>()),
+        6 => Ok(entry::<day6::FishGame>()),
+        7 => Ok(entry::<day7::CrabGame>()),
+        8 => Ok(entry::<day8::Game>()),
+        9 => Ok(entry::<day9::HeightMap>()),
+        10 => Ok(entry::<day10::Lines>()),
+        11 => Ok(entry::<day11::GameState>()),
+        12 => Ok(entry::<day12::Graph>()),
+        13 => Ok(entry::<day13::Paper>()),
+        14 => Ok(entry::<day14::Game>()),
+        15 => Ok(entry::<day15::Board>()),
+        16 => Ok(entry::<day16::Packet>()),
+        17 => Ok(entry::<day17::TargetArea>()),
+        18 => Ok(entry::<day18::Homework>()),
+        19 => Ok(entry::<day19::Game>()),
+        20 => Ok(entry::<day20::Image>()),
+        21 => Ok(entry::<day21::Game>()),
+        22 => Ok(entry::<day22::Grid>()),
+        #[cfg(feature = "std")]
+        23 => Ok(entry::<day23::Burrow>()),
+        24 => Ok(entry::<day24::Monad>()),
+        25 => Ok(entry::<day25::SeaFloor>()),
+        _ => Err(error::Error::General(format!("no solver for day {}", day))),
+    }
+}
+
+pub fn solver(day: usize, input: &str) -> Result<Box<dyn Puzzle>, error::Error> {
+    let _span = tracing::info_span!("parse", day).entered();
+    (lookup(day)?.solver)(input)
+}
+
+pub fn examples(day: usize) -> Result<Vec<Example>, error::Error> {
+    Ok((lookup(day)?.examples)())
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "json", derive(serde::Serialize))]
+pub struct PartResult {
+    pub day: usize,
+    pub part: usize,
+    pub answer: Answer,
+    pub micros: u128,
+    // answered from the cache rather than solved
+    pub cached: bool,
+}
+
+pub fn answer(solver: &dyn Puzzle, part: usize) -> Result<Answer, error::Error> {
+    let answer = match part {
+        1 => solver.part1()?,
+        2 => solver.part2()?,
+        _ => return Err(error::Error::General(format!("no part {}, expected 1 or 2", part))),
+    };
+    tracing::debug!(%answer, "answered");
+    Ok(answer)
+}
+
+// parses the input and answers one part, with nothing but the input string going in. no
+// files and no clocks, so this is also what the wasm build calls.
+pub fn solve(day: usize, part: usize, input: &str) -> Result<Answer, error::Error> {
+    let solver = solver(day, input)?;
+    let _span = tracing::info_span!("part", day, part).entered();
+    answer(solver.as_ref(), part)
+}
+
+#[cfg(feature = "std")]
+pub fn solve_part(solver: &dyn Puzzle, day: usize, part: usize) -> Result<PartResult, error::Error> {
+    let _span = tracing::info_span!("part", day, part).entered();
+    let start = Instant::now();
+    let answer = answer(solver, part)?;
+    Ok(PartResult {
+        day,
+        part,
+        answer,
+        micros: start.elapsed().as_micros(),
+        cached: false,
+    })
+}
+
+// the parts of a day from the cache when it has all of them, otherwise the input is parsed, the
+// parts solved and the answers cached for next time
+#[cfg(feature = "std")]
+pub fn solve_parts(day: usize, input: &str, parts: &[usize], cache: Option<&cache::AnswerCache>) -> Result<Vec<PartResult>, error::Error> {
+    if let Some(results) = cache.and_then(|cache| cache.lookup(day, parts, input)) {
+        return Ok(results);
+    }
+    let solver = solver(day, input)?;
+    let results = parts.iter().map(|&part| solve_part(solver.as_ref(), day, part)).collect::<Result<Vec<_>, _>>()?;
+    if let Some(cache) = cache {
+        cache.store(input, &results);
+    }
+    Ok(results)
+}
+
+#[cfg(feature = "json")]
+pub fn to_json(results: &[PartResult]) -> Result<String, error::Error> {
+    Ok(serde_json::to_string(results)?)
+}
+
+#[cfg(feature = "std")]
+pub struct DayRun {
+    pub day: usize,
+    pub parts: Result<Vec<PartResult>, error::Error>,
+    // loading the input, parsing it and both parts
+    pub elapsed: Duration,
+}
+
+#[cfg(feature = "std")]
+pub struct RunAll {
+    pub days: Vec<DayRun>,
+    pub elapsed: Duration,
+}
+
+#[cfg(feature = "std")]
+impl RunAll {
+    // what running the days one after the other would have taken
+    pub fn serial_time(&self) -> Duration {
+        self.days.iter().map(|day| day.elapsed).sum()
+    }
+}
+
+#[cfg(feature = "std")]
+pub fn run_day(day: usize, inputs: &input::Inputs, cache: Option<&cache::AnswerCache>) -> DayRun {
+    let start = Instant::now();
+    let parts = inputs.load(day).and_then(|input| solve_parts(day, &input, &[1, 2], cache));
+    DayRun { day, parts, elapsed: start.elapsed() }
+}
+
+// every day on the rayon pool, in day order
+#[cfg(feature = "std")]
+pub fn run_all(inputs: &input::Inputs, cache: Option<&cache::AnswerCache>) -> RunAll {
+    let start = Instant::now();
+    let days = DAYS.into_par_iter().map(|day| run_day(day, inputs, cache)).collect();
+    RunAll { days, elapsed: start.elapsed() }
+}
+
+#[test]
+fn test_solver() -> Result<(), error::Error> {
+    let answers = [
+        (1, Answer::UInt(1759), Answer::UInt(1805)),
+        (6, Answer::UInt(396210), Answer::UInt(1770823541496)),
+        (17, Answer::Int(5151), Answer::UInt(968)),
+        (21, Answer::UInt(855624), Answer::UInt(187451244607486)),
+    ];
+    for (day, part1, part2) in answers {
+        let input = std::fs::read_to_string(format!("input_day{}", day))?;
+        let puzzle = solver(day, &input)?;
+        assert_eq!(puzzle.part1()?, part1);
+        assert_eq!(puzzle.part2()?, part2);
+    }
+
+    let puzzle = solver(13, &std::fs::read_to_string("input_day13")?)?;
+    assert_eq!(puzzle.part1()?, Answer::UInt(759));
+    assert_eq!(puzzle.part2()?, Answer::Text("HECRZKPR".to_string()));
+
+    assert!(solver(0, "").is_err());
+    assert!(solver(26, "").is_err());
+
+    assert_eq!(solve(1, 2, "1\n2\n3\n4")?, Answer::UInt(1));
+    assert!(solve(1, 3, "1").is_err());
+    assert!(solve(1, 1, "x").is_err());
+
+    assert_eq!(examples(1)?.len(), 1);
+    assert_eq!(examples(12)?.len(), 3);
+    assert!(examples(26).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_from_reader() -> Result<(), error::Error> {
+    let measurements = day1::Measurements::from_reader(std::fs::File::open("input_day1")?)?;
+    assert_eq!(measurements.part1()?, Answer::UInt(1759));
+
+    let target_area = day17::TargetArea::from_reader("target area: x=20..30, y=-10..-5".as_bytes())?;
+    assert_eq!(target_area.part1()?, Answer::Int(45));
+
+    assert!(matches!(day1::Measurements::from_reader(&[b'1', 0xff][..]), Err(error::Error::Io(_))));
+    assert!(matches!(day1::Measurements::from_reader("1\nx".as_bytes()), Err(error::Error::Parse(_))));
+    Ok(())
+}
+
+#[test]
+fn test_answer() {
+    assert_eq!(Answer::from(42u64), Answer::UInt(42));
+    assert_eq!(Answer::from(-42i32), Answer::Int(-42));
+    assert_ne!(Answer::from(42u64), Answer::from(42i64));
+    assert_eq!(Answer::from("abc").to_string(), "abc");
+}
+
+#[test]
+fn test_run_all() -> Result<(), error::Error> {
+    let cache_dir = std::env::temp_dir().join(format!("aoc2021-run-all-{}", std::process::id()));
+    std::fs::create_dir_all(&cache_dir)?;
+    for day in [1, 6] {
+        std::fs::copy(format!("input_day{}", day), cache_dir.join(format!("input_day{}", day)))?;
+    }
+
+    let run = run_all(&input::Inputs::new(&cache_dir), None);
+    assert_eq!(run.days.iter().map(|day| day.day).collect::<Vec<_>>(), DAYS.collect::<Vec<_>>());
+    let answers = |day: &DayRun| day.parts.as_ref().ok().map(|parts| parts.iter().map(|part| part.answer.clone()).collect::<Vec<_>>());
+    assert_eq!(answers(&run.days[0]), Some(vec![Answer::UInt(1759), Answer::UInt(1805)]));
+    assert_eq!(answers(&run.days[5]), Some(vec![Answer::UInt(396210), Answer::UInt(1770823541496)]));
+    assert_eq!(run.days.iter().filter(|day| day.parts.is_ok()).count(), 2);
+    assert_eq!(run.days[0].parts.as_ref().unwrap()[1].part, 2);
+    assert!(run.serial_time() >= run.days[0].elapsed);
+
+    // the second run with a cache answers from it
+    let answer_cache = cache::AnswerCache::new(&cache_dir);
+    for cached in [false, true] {
+        let run = run_all(&input::Inputs::new(&cache_dir), Some(&answer_cache));
+        assert_eq!(answers(&run.days[5]), Some(vec![Answer::UInt(396210), Answer::UInt(1770823541496)]));
+        assert!(run.days[5].parts.as_ref().unwrap().iter().all(|part| part.cached == cached));
+    }
+    answer_cache.clear(Some(6))?;
+    assert!(!run_day(6, &input::Inputs::new(&cache_dir), Some(&answer_cache)).parts?[0].cached);
+    assert!(run_day(1, &input::Inputs::new(&cache_dir), Some(&answer_cache)).parts?[0].cached);
+
+    std::fs::remove_dir_all(&cache_dir)?;
+    Ok(())
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn test_to_json() -> Result<(), error::Error> {
+    let results = [
+        PartResult {
+            day: 13,
+            part: 1,
+            answer: Answer::UInt(759),
+            micros: 12,
+            cached: false,
+        },
+        PartResult {
+            day: 13,
+            part: 2,
+            answer: Answer::Grid("X \n X".to_string()),
+            micros: 34,
+            cached: true,
+        },
+    ];
+    assert_eq!(
+        to_json(&results)?,
+        r#"[{"day":13,"part":1,"answer":759,"micros":12,"cached":false},{"day":13,"part":2,"answer":"X \n X","micros":34,"cached":true}]"#
+    );
+    // numbers as numbers, everything else as a string
+    assert_eq!(serde_json::to_string(&Answer::Int(-7))?, "-7");
+    assert_eq!(serde_json::to_string(&Answer::UInt(u128::MAX))?, u128::MAX.to_string());
+    assert_eq!(serde_json::to_string(&Answer::Grid("X \"\n X".to_string()))?, r#""X \"\n X""#);
+    Ok(())
+}
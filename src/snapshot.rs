@@ -0,0 +1,130 @@
+#[cfg(feature = "json")]
+use crate::error;
+use crate::collections::{HashMap, HashSet};
+use alloc::vec::Vec;
+
+// the puzzle states serialize with the serde feature, so a state can be written out between
+// steps and read back in later. hash maps and sets come out in a different order every run,
+// which makes two snapshots useless to diff, so these helpers write them sorted.
+
+pub fn sorted_set<T, S>(set: &HashSet<T>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: Ord + serde::Serialize,
+    S: serde::Serializer,
+{
+    let mut items: Vec<&T> = set.iter().collect();
+    items.sort();
+    serializer.collect_seq(items)
+}
+
+pub fn sorted_map<K, V, S>(map: &HashMap<K, V>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    K: Ord + serde::Serialize,
+    V: serde::Serialize,
+    S: serde::Serializer,
+{
+    let mut entries: Vec<(&K, &V)> = map.iter().collect();
+    entries.sort_by(|a, b| a.0.cmp(b.0));
+    serializer.collect_map(entries)
+}
+
+// json keys can only be strings, so a map keyed by e.g. a tuple goes out as a sorted list of
+// [key, value] pairs instead. use with #[serde(with = "crate::snapshot::map_as_list")].
+pub mod map_as_list {
+    use crate::collections::HashMap;
+    use alloc::vec::Vec;
+    use core::hash::Hash;
+
+    pub fn serialize<K, V, S>(map: &HashMap<K, V>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        K: Ord + serde::Serialize,
+        V: serde::Serialize,
+        S: serde::Serializer,
+    {
+        let mut entries: Vec<(&K, &V)> = map.iter().collect();
+        entries.sort_by(|a, b| a.0.cmp(b.0));
+        serializer.collect_seq(entries)
+    }
+
+    pub fn deserialize<'de, K, V, D>(deserializer: D) -> Result<HashMap<K, V>, D::Error>
+    where
+        K: Eq + Hash + serde::Deserialize<'de>,
+        V: serde::Deserialize<'de>,
+        D: serde::Deserializer<'de>,
+    {
+        let entries: Vec<(K, V)> = serde::Deserialize::deserialize(deserializer)?;
+        Ok(entries.into_iter().collect())
+    }
+}
+
+// pretty printed, one value per line, so two snapshots diff line by line
+#[cfg(feature = "json")]
+pub fn to_json<T: serde::Serialize>(state: &T) -> Result<String, error::Error> {
+    Ok(serde_json::to_string_pretty(state)?)
+}
+
+#[cfg(feature = "json")]
+pub fn from_json<T: serde::de::DeserializeOwned>(json: &str) -> Result<T, error::Error> {
+    serde_json::from_str(json).map_err(|e| error::Error::Parse(e.to_string()))
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn test_snapshot() -> Result<(), error::Error> {
+    use crate::{day13, day14, day15, day20, day22, day23, day25, grid, puzzle::Puzzle};
+
+    // every fold of the example as a snapshot, each one reloading to the same paper
+    let paper = day13::Paper::parse(day13::Paper::examples()[0].0)?;
+    let mut snapshots = vec![to_json(&paper)?];
+    for folded in paper.folds() {
+        let folded = folded?;
+        let json = to_json(&folded)?;
+        assert_eq!(from_json::<day13::Paper>(&json)?.render(), folded.render());
+        snapshots.push(json);
+    }
+    assert_eq!(snapshots.len(), 3);
+    assert_ne!(snapshots[1], snapshots[2]);
+    // the same state always writes the same json
+    assert_eq!(to_json(&paper)?, snapshots[0]);
+
+    let game = day14::Game::parse(day14::Game::examples()[0].0)?;
+    let json = to_json(&game)?;
+    assert!(json.contains("\"instructions\": [\n"));
+    assert_eq!(to_json(&from_json::<day14::Game>(&json)?)?, json);
+
+    let image = day20::Image::parse(day20::Image::examples()[0].0)?.enhance();
+    let reloaded: day20::Image = from_json(&to_json(&image)?)?;
+    assert_eq!(reloaded.render(), image.render());
+    assert_eq!(reloaded.enhance().num_lit_pixels(), 35);
+    // an enhancement is checked like it is when parsing
+    for enhancement in ["", "#.#", "x".repeat(512).as_str()] {
+        let mut json: serde_json::Value = serde_json::from_str(&to_json(&image)?)?;
+        json["enhancement"] = enhancement.into();
+        assert!(matches!(from_json::<day20::Image>(&json.to_string()), Err(error::Error::Parse(message)) if message.contains("enhancement")));
+    }
+
+    let grid = day22::Grid::parse(day22::Grid::examples()[0].0)?;
+    let reloaded: day22::Grid = from_json(&to_json(&grid)?)?;
+    assert_eq!(reloaded.part1()?, grid.part1()?);
+
+    let burrow = day23::Burrow::parse(day23::Burrow::examples()[0].0)?;
+    assert_eq!(from_json::<day23::Burrow>(&to_json(&burrow)?)?, burrow);
+
+    let floor = day25::SeaFloor::parse(day25::SeaFloor::examples()[0].0)?;
+    let (next, _) = floor.step();
+    assert_eq!(from_json::<day25::SeaFloor>(&to_json(&next)?)?, next);
+
+    // a custom movement is code and has no snapshot
+    let board = day15::Board::parse("12\n34")?;
+    assert!(to_json(&board).is_ok());
+    assert!(to_json(&board.with_movement(day15::Movement::Custom(|_| vec![]))).is_err());
+
+    // a grid has to have as many cells as its size says
+    assert!(from_json::<grid::Grid<u8>>(r#"{"width": 2, "height": 2, "cells": [1, 2, 3, 4]}"#).is_ok());
+    assert_eq!(
+        from_json::<grid::Grid<u8>>(r#"{"width": 2, "height": 2, "cells": [1, 2, 3]}"#),
+        Err(error::Error::Parse("3 cells don't make a 2x2 grid".to_string()))
+    );
+    assert!(from_json::<grid::Grid<u8>>(&format!(r#"{{"width": {}, "height": 2, "cells": [1, 2]}}"#, usize::MAX)).is_err());
+    Ok(())
+}
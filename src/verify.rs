@@ -0,0 +1,205 @@
+use crate::cache;
+use crate::error;
+use crate::input;
+use crate::puzzle;
+
+use rayon::prelude::*;
+
+// one expected answer; numbers too big for toml's integers can be written as strings
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Expected {
+    pub day: usize,
+    pub part: usize,
+    pub answer: String,
+}
+
+#[derive(PartialEq, Debug)]
+pub enum Outcome {
+    Match,
+    Mismatch(puzzle::Answer),
+    Failed(error::Error),
+}
+
+#[derive(PartialEq, Debug)]
+pub struct Check {
+    pub expected: Expected,
+    pub outcome: Outcome,
+}
+
+// tables named dayN with part1 and/or part2 in them, e.g.
+//
+//   [day1]
+//   part1 = 1759
+//   part2 = 1805
+pub fn parse_answers(s: &str) -> Result<Vec<Expected>, error::Error> {
+    let table: toml::Table = s.parse()?;
+    let mut expected = vec![];
+    for (name, parts) in &table {
+        let day = name
+            .strip_prefix("day")
+            .and_then(|day| day.parse().ok())
+            .ok_or_else(|| error::Error::Parse(format!("expected a table named dayN, got {:?}", name)))?;
+        let parts = parts.as_table().ok_or_else(|| error::Error::Parse(format!("{} must be a table", name)))?;
+        for (key, value) in parts {
+            let part = match key.as_str() {
+                "part1" => 1,
+                "part2" => 2,
+                _ => return Err(error::Error::Parse(format!("unknown key {}.{}, expected part1 or part2", name, key))),
+            };
+            let answer = match value {
+                toml::Value::Integer(n) => n.to_string(),
+                toml::Value::String(s) => s.clone(),
+                _ => return Err(error::Error::Parse(format!("{}.{} must be an integer or a string", name, key))),
+            };
+            expected.push(Expected { day, part, answer });
+        }
+    }
+    expected.sort_by_key(|e| (e.day, e.part));
+    Ok(expected)
+}
+
+// multi-line answers are compared line by line, ignoring trailing whitespace that editors like to strip
+pub fn matches(expected: &str, actual: &puzzle::Answer) -> bool {
+    let actual = actual.to_string();
+    if actual.contains('\n') || expected.contains('\n') {
+        expected.trim_end().lines().map(str::trim_end).eq(actual.trim_end().lines().map(str::trim_end))
+    } else {
+        expected.trim() == actual
+    }
+}
+
+fn outcome(expected: &Expected, answer: Result<puzzle::Answer, error::Error>) -> Outcome {
+    match answer {
+        Ok(answer) if matches(&expected.answer, &answer) => Outcome::Match,
+        Ok(answer) => Outcome::Mismatch(answer),
+        Err(e) => Outcome::Failed(e),
+    }
+}
+
+fn check(solver: &Result<Box<dyn puzzle::Puzzle>, error::Error>, expected: Expected) -> Check {
+    let answer = match solver {
+        Ok(solver) => puzzle::answer(solver.as_ref(), expected.part),
+        Err(e) => Err(e.clone()),
+    };
+    Check {
+        outcome: outcome(&expected, answer),
+        expected,
+    }
+}
+
+fn check_day(day: usize, expected: Vec<Expected>, inputs: &input::Inputs, cache: Option<&cache::AnswerCache>) -> Vec<Check> {
+    let parts: Vec<usize> = expected.iter().map(|e| e.part).collect();
+    let results = inputs.load(day).and_then(|input| puzzle::solve_parts(day, &input, &parts, cache));
+    expected
+        .into_iter()
+        .enumerate()
+        .map(|(index, expected)| {
+            let answer = results.as_ref().map(|results| results[index].answer.clone()).map_err(|e| e.clone());
+            Check {
+                outcome: outcome(&expected, answer),
+                expected,
+            }
+        })
+        .collect()
+}
+
+// solves every day that has expected answers, one day per rayon task
+pub fn verify(expected: &[Expected], inputs: &input::Inputs, cache: Option<&cache::AnswerCache>) -> Vec<Check> {
+    let mut days: Vec<usize> = expected.iter().map(|e| e.day).collect();
+    days.dedup();
+    days.into_par_iter()
+        .flat_map(|day| check_day(day, expected.iter().filter(|e| e.day == day).cloned().collect(), inputs, cache))
+        .collect()
+}
+
+// checks a day against the examples in its puzzle text, so it needs no input at all
+pub fn verify_examples(day: usize) -> Result<Vec<Check>, error::Error> {
+    let mut checks = vec![];
+    for (input, part1, part2) in puzzle::examples(day)? {
+        let solver = puzzle::solver(day, input);
+        for (part, answer) in [(1, part1), (2, part2)] {
+            if let Some(answer) = answer {
+                let expected = Expected {
+                    day,
+                    part,
+                    answer: answer.to_string(),
+                };
+                checks.push(check(&solver, expected));
+            }
+        }
+    }
+    Ok(checks)
+}
+
+#[test]
+fn test_parse_answers() -> Result<(), error::Error> {
+    let expected = parse_answers(
+        r#"
+[day13]
+part2 = '''
+X  X
+XXXX
+'''
+
+[day2]
+part2 = "1903644897"
+part1 = 2027977
+"#,
+    )?;
+    let expected: Vec<(usize, usize, &str)> = expected.iter().map(|e| (e.day, e.part, e.answer.as_str())).collect();
+    assert_eq!(expected, vec![(2, 1, "2027977"), (2, 2, "1903644897"), (13, 2, "X  X\nXXXX\n")]);
+
+    assert!(parse_answers("[day1]\npart3 = 1").is_err());
+    assert!(parse_answers("[one]\npart1 = 1").is_err());
+    assert!(parse_answers("[day1]\npart1 = 1.5").is_err());
+    assert!(parse_answers("[day1\npart1 = 1").is_err());
+    Ok(())
+}
+
+#[test]
+fn test_matches() {
+    assert!(matches("1759", &puzzle::Answer::UInt(1759)));
+    assert!(matches("-3", &puzzle::Answer::Int(-3)));
+    assert!(!matches("1760", &puzzle::Answer::UInt(1759)));
+    assert!(matches("X  X\nXXXX\n", &puzzle::Answer::Grid("X  X \nXXXX \n".to_string())));
+    assert!(!matches("X  X\nXXXX\n", &puzzle::Answer::Grid("X  X\nX  X\n".to_string())));
+}
+
+#[test]
+fn test_verify() -> Result<(), error::Error> {
+    let expected = parse_answers("[day1]\npart1 = 1759\npart2 = 1806\n\n[day6]\npart1 = 396210\n\n[day23]\npart1 = 1\n")?;
+    let checks = verify(&expected, &input::Inputs::new("."), None);
+    let outcomes: Vec<(usize, usize, &Outcome)> = checks.iter().map(|c| (c.expected.day, c.expected.part, &c.outcome)).collect();
+    assert_eq!(
+        outcomes[..3],
+        [(1, 1, &Outcome::Match), (1, 2, &Outcome::Mismatch(puzzle::Answer::UInt(1805))), (6, 1, &Outcome::Match)]
+    );
+    assert!(matches!(outcomes[3], (23, 1, Outcome::Failed(_))));
+    Ok(())
+}
+
+#[test]
+fn test_verify_answers_file() -> Result<(), error::Error> {
+    // only the quick days, the rest are covered by their own tests
+    let expected: Vec<Expected> = parse_answers(&std::fs::read_to_string("answers.toml")?)?
+        .into_iter()
+        .filter(|e| [1, 2, 6, 13, 16, 17, 21].contains(&e.day))
+        .collect();
+    assert_eq!(expected.len(), 14);
+    let checks = verify(&expected, &input::Inputs::new("."), None);
+    assert!(checks.iter().all(|check| check.outcome == Outcome::Match));
+    Ok(())
+}
+
+#[test]
+fn test_verify_examples() -> Result<(), error::Error> {
+    for day in puzzle::DAYS {
+        let checks = verify_examples(day)?;
+        assert!(!checks.is_empty(), "day {} has no examples", day);
+        for check in checks {
+            assert_eq!(check.outcome, Outcome::Match, "day {} part {}", day, check.expected.part);
+        }
+    }
+    assert!(verify_examples(26).is_err());
+    Ok(())
+}
@@ -0,0 +1,22 @@
+use crate::puzzle;
+use wasm_bindgen::prelude::*;
+
+// the browser playground's way in, built with `wasm-pack build --target web wasm`, which
+// wraps it in a cdylib.
+// the answer comes back as text, and so does an error, prefixed with "error: " so the page can
+// show whatever it gets.
+#[wasm_bindgen]
+pub fn solve(day: usize, part: usize, input: &str) -> String {
+    match puzzle::solve(day, part, input) {
+        Ok(answer) => answer.to_string(),
+        Err(e) => format!("error: {}", e),
+    }
+}
+
+#[test]
+fn test_solve() {
+    assert_eq!(solve(1, 1, "199\n200\n208\n210\n200\n207\n240\n269\n260\n263"), "7");
+    assert_eq!(solve(26, 1, ""), "error: no solver for day 26");
+    assert_eq!(solve(1, 3, "1"), "error: no part 3, expected 1 or 2");
+    assert_eq!(solve(6, 1, "x"), "error: invalid digit found in string");
+}
@@ -0,0 +1,2 @@
+// the exports live in the main crate's wasm module, this only links them into a cdylib
+pub use advent_of_code_2021::wasm::solve;